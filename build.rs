@@ -0,0 +1,88 @@
+use std::fmt::Write;
+use std::path::PathBuf;
+
+//#################################################################################################
+//
+//                                      EDGE TABLES
+//
+//#################################################################################################
+
+/*
+ * Returns the disks of the opponent flipped along an edge when the player puts a disk on the
+ * given square of it, the squares of the edge being the bits of the bytes.
+ */
+fn flips(own: u8, opp: u8, square: u8) -> u8 {
+    let mut flipped: u8 = 0;
+
+    for step in [1i8, -1] {
+        let mut run: u8 = 0;
+        let mut x: i8 = square as i8 + step;
+        while (0..8).contains(&x) && opp & 1 << x != 0 {
+            run |= 1 << x;
+            x += step;
+        }
+        if (0..8).contains(&x) && own & 1 << x != 0 {
+            flipped |= run;
+        }
+    }
+
+    flipped
+}
+
+/*
+ * Computes the disks of the player that can never be flipped along an edge, for every pair of
+ * bytes of his disks and of the opponent's, indexed by own | opp << 8. A disk on an edge can
+ * only be flipped along it, and any empty square of it may be filled by either player later,
+ * from the inside of the board, so a disk is stable if it stays the player's in every
+ * configuration reached by filling the empty squares one by one, flipping what each disk
+ * put flips along the edge. The configurations with fewer empty squares are done first, each
+ * one's stable disks being those of the player staying stable in all of its successors.
+ */
+fn edge_stability() -> Vec<u8> {
+    let mut table: Vec<u8> = vec![0; 1 << 16];
+
+    for empties in 0..=8 {
+        for own in 0..=255u8 {
+            for opp in (0..=255u8).filter(|&opp| opp & own == 0) {
+                let empty: u8 = !(own | opp);
+                if empty.count_ones() != empties {
+                    continue;
+                }
+
+                let mut stable: u8 = own;
+                for square in (0..8).filter(|&square| empty & 1 << square != 0) {
+                    let flipped: u8 = flips(own, opp, square);
+                    let (child_own, child_opp) = (own | 1 << square | flipped, opp & !flipped);
+                    stable &= table[child_own as usize | (child_opp as usize) << 8];
+                    let flipped: u8 = flips(opp, own, square);
+                    let (child_own, child_opp) = (own & !flipped, opp | 1 << square | flipped);
+                    stable &= table[child_own as usize | (child_opp as usize) << 8];
+                }
+                table[own as usize | (opp as usize) << 8] = stable;
+            }
+        }
+    }
+
+    table
+}
+
+/*
+ * Writes the tables into the edge_tables.rs file of the build directory, included by the
+ * features module, so that nothing is computed when the crate runs.
+ */
+fn main() {
+    let mut code: String = String::from("/*\n * The stable disks along an edge, generated by \
+        build.rs, see features::edge_stable.\n */\npub(crate) static EDGE_STABILITY: [u8; 65536] \
+        = [");
+    for (i, stable) in edge_stability().iter().enumerate() {
+        if i % 32 == 0 {
+            code += "\n   ";
+        }
+        write!(code, " {},", stable).unwrap();
+    }
+    code += "\n];\n";
+
+    let out: PathBuf = PathBuf::from(std::env::var_os("OUT_DIR").unwrap());
+    std::fs::write(out.join("edge_tables.rs"), code).unwrap();
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -0,0 +1,22 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use othello::types::*;
+use othello::wire::*;
+
+/*
+ * Feeds arbitrary bytes to both position decoders, and checks that whatever they accept is a
+ * well-formed position, encoded back to the same bytes.
+ */
+fuzz_target!(|data: &[u8]| {
+    for (decoded, packed) in [(decode(data), false), (decode_packed(data), true)] {
+        if let Some((oth, color)) = decoded {
+            assert!(oth.get_bitboard(Color::Black) & oth.get_bitboard(Color::White) == 0);
+            if packed {
+                assert_eq!(&encode_packed(oth, color)[..], data);
+            } else {
+                assert_eq!(&encode(oth, color)[..], data);
+            }
+        }
+    }
+});
@@ -0,0 +1,31 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use othello::types::*;
+use othello::record::*;
+
+/*
+ * Feeds arbitrary text to the parsers. Parsing must never panic, and whatever parses must be
+ * written back identically.
+ */
+fuzz_target!(|data: &[u8]| {
+    let text: &str = match std::str::from_utf8(data) {
+        Ok(text) => text,
+        Err(_) => return,
+    };
+
+    if let Some(mv) = parse_square(text) {
+        assert_eq!(mv.pop_cnt(), 1);
+        assert!(parse_square(&mv.square_name()) == Some(mv));
+    }
+
+    if let Ok(record) = GameRecord::from_text(text) {
+        let written: String = record.to_text();
+        let parsed: GameRecord = GameRecord::from_text(&written).unwrap();
+        assert_eq!(parsed.to_text(), written);
+
+        if let Ok(game) = record.to_game() {
+            assert!(game.history().len() >= record.moves.len());
+        }
+    }
+});
@@ -0,0 +1,47 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use othello::types::*;
+use othello::othello::*;
+
+/*
+ * Feeds arbitrary boards and moves to try_make_move and checks the invariants of the result:
+ * the error given matches the board, and a legal move puts a disk on the square and flips at
+ * least one of the opponent's disks, while leaving the other squares alone.
+ */
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 19 {
+        return;
+    }
+
+    let mut bytes: [u8; 8] = [0; 8];
+    bytes.copy_from_slice(&data[0..8]);
+    let black: BitBoard = u64::from_le_bytes(bytes);
+    bytes.copy_from_slice(&data[8..16]);
+    let white: BitBoard = u64::from_le_bytes(bytes) & !black;
+
+    let oth: Othello = Othello::create(black, white);
+    let color: Color = if data[16] & 1 == 0 { Color::Black } else { Color::White };
+    let (x, y): (u8, u8) = (data[17] % 10, data[18] % 10);
+
+    match oth.try_make_move(color, x, y) {
+        Err(MoveError::OutOfBounds) => assert!(x >= 8 || y >= 8),
+        Err(MoveError::Occupied) => assert!(oth.get_bitboard(Color::Black).contains(x, y)
+            || oth.get_bitboard(Color::White).contains(x, y)),
+        Err(MoveError::NoFlips) => assert!(oth.gen_moves(color) & (1u64 << (x + 8 * y)) == 0),
+        Err(err) => panic!("Unexpected error: {}", err),
+        Ok(new_oth) => {
+            let own: BitBoard = oth.get_bitboard(color);
+            let opp: BitBoard = oth.get_bitboard(color.invert());
+            let new_own: BitBoard = new_oth.get_bitboard(color);
+            let new_opp: BitBoard = new_oth.get_bitboard(color.invert());
+            let flipped: BitBoard = opp & !new_opp;
+
+            assert!(new_own & new_opp == 0);
+            assert!(new_own.contains(x, y));
+            assert!(flipped != 0);
+            assert!(new_opp & !opp == 0);
+            assert_eq!(new_own, own | flipped | (1u64 << (x + 8 * y)));
+        },
+    }
+});
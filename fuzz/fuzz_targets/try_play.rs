@@ -0,0 +1,30 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use othello::types::*;
+use othello::game::*;
+
+/*
+ * Plays arbitrary sequences of moves, two bytes each, through Game::try_play, resigning on a
+ * zero byte. The game must never panic and the disk count must stay consistent.
+ */
+fuzz_target!(|data: &[u8]| {
+    let mut game: Game = Game::new();
+
+    for pair in data.chunks_exact(2) {
+        if pair[0] == 0 {
+            game.resign(game.color());
+        }
+
+        let color: Color = if pair[0] & 0x80 == 0 { game.color() } else { game.color().invert() };
+        let plies: usize = game.history().len();
+
+        match game.try_play(color, pair[0] % 10, pair[1] % 10) {
+            Ok(()) => assert!(game.history().len() > plies),
+            Err(_) => assert_eq!(game.history().len(), plies),
+        }
+
+        let score: Score = game.board().score();
+        assert_eq!(score.get(Color::Black) + score.get(Color::White) + game.board().empties(), 64);
+    }
+});
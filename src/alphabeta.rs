@@ -1,5 +1,21 @@
+use std::cell::Cell;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
 use crate::types::*;
 use crate::othello::*;
+use crate::eval::*;
+use crate::phase::*;
+use crate::game::*;
+use crate::player::*;
+use crate::searcher::{SearchLimits, SearchResult};
+use crate::tablebase::*;
+use crate::book::*;
+use crate::ordering::*;
+use crate::executor::*;
+use crate::opponent::*;
+use crate::journal::*;
 
 //#################################################################################################
 //
@@ -12,6 +28,30 @@ use crate::othello::*;
  */
 pub struct AlphaBetaPlayer {
     max_depth: u8,
+    personality: Personality,
+    weights: EvalWeights,
+    resign: Option<(i32, u8)>,
+    target_elo: Option<u16>,
+    time_manager: Option<TimeManager>,
+    contempt: i32,
+    tablebase: Option<Arc<Tablebase>>,
+    book: Option<Arc<Book>>,
+    book_variety: Option<(i32, f64)>,
+    journal: Option<Arc<Journal>>,
+    ordering: MoveOrdering,
+    reductions: bool,
+    pass_pruning: bool,
+    params: SearchParams,
+    root_moves: BitBoard,
+    excluded_moves: BitBoard,
+    executor: Arc<dyn Executor>,
+    node_limit: Option<u64>,
+    opponent_model: Option<OpponentModel>,
+    rng: Cell<u64>,
+    streak: Cell<u8>,
+    last_value: Cell<i32>,
+    last_stats: Cell<SearchStats>,
+    stop: Arc<AtomicBool>,
 }
 
 impl AlphaBetaPlayer {
@@ -19,159 +59,2018 @@ impl AlphaBetaPlayer {
      * Creates a new AlphaBetaPlayer AI.
      */
     pub fn new(max_depth: u8) -> AlphaBetaPlayer {
-        AlphaBetaPlayer { max_depth, }
+        AlphaBetaPlayer {
+            max_depth,
+            personality: Personality::Balanced,
+            weights: Personality::Balanced.weights(),
+            resign: None,
+            target_elo: None,
+            time_manager: None,
+            contempt: 0,
+            tablebase: None,
+            book: None,
+            book_variety: None,
+            journal: None,
+            ordering: MoveOrdering::default(),
+            reductions: true,
+            pass_pruning: true,
+            params: SearchParams::default(),
+            root_moves: !0,
+            excluded_moves: 0,
+            executor: Arc::new(ThreadPerJob),
+            node_limit: None,
+            opponent_model: None,
+            rng: Cell::new(0x9E3779B97F4A7C15),
+            streak: Cell::new(0),
+            last_value: Cell::new(0),
+            last_stats: Cell::new(SearchStats::default()),
+            stop: Arc::new(AtomicBool::new(false)),
+        }
     }
-}
 
-/*
- * The evaluation function, using masks to do a weighted sum of the board and the move generation
- * algorithm to measure mobility for both players.
- */
-#[inline(always)]
-fn evaluate(oth: Othello) -> i32 {
-    let mut res: i32 = 0;
+    /*
+     * Gives a personality to the AI, replacing its evaluation weights with those of the
+     * personality.
+     */
+    pub fn with_personality(mut self, personality: Personality) -> AlphaBetaPlayer {
+        self.personality = personality;
+        self.weights = personality.weights();
+        self
+    }
+
+    /*
+     * Replaces the evaluation weights of the AI with custom ones, keeping the search options
+     * of its personality.
+     */
+    pub fn with_weights(mut self, weights: EvalWeights) -> AlphaBetaPlayer {
+        self.weights = weights;
+        self
+    }
+
+    /*
+     * Makes the AI resign once its evaluation, from its own point of view, has been below
+     * -threshold for the given number of consecutive moves. A threshold or a number of moves
+     * that isn't positive, which would make it resign in even or won positions, never does.
+     */
+    pub fn with_resign(mut self, threshold: i32, moves: u8) -> AlphaBetaPlayer {
+        self.resign = Some((threshold, moves)).filter(|_| threshold > 0 && moves > 0);
+        self
+    }
+
+    /*
+     * Makes the AI play like a player of the given rating, by mixing its best moves with
+     * inferior ones, see pick_move. At MAX_ELO and above, it always plays its best move.
+     */
+    pub fn with_target_elo(mut self, elo: u16) -> AlphaBetaPlayer {
+        self.target_elo = Some(elo);
+        self
+    }
+
+    /*
+     * Lets the AI manage its time when the clock is limited: it then searches deeper and
+     * deeper, up to its maximum depth, as long as the time manager allows it, see TimeManager.
+     * Otherwise, it always searches to its maximum depth.
+     */
+    pub fn with_time_manager(mut self, time_manager: TimeManager) -> AlphaBetaPlayer {
+        self.time_manager = Some(time_manager);
+        self
+    }
+
+    /*
+     * Values drawn games at -contempt from the AI's point of view, instead of 0. A positive
+     * contempt makes it press for a win against weaker opponents, a negative one makes it
+     * steer for a draw against stronger ones.
+     */
+    pub fn with_contempt(mut self, contempt: i32) -> AlphaBetaPlayer {
+        self.contempt = contempt;
+        self
+    }
+
+    /*
+     * Makes the AI consult the given tablebase before searching, and play a perfect move when
+     * the position is in it.
+     */
+    pub fn with_tablebase(mut self, tablebase: Arc<Tablebase>) -> AlphaBetaPlayer {
+        self.tablebase = Some(tablebase);
+        self
+    }
+
+    /*
+     * Makes the AI play the best move of the given opening book, when the position is in it,
+     * instead of searching.
+     */
+    pub fn with_book(mut self, book: Arc<Book>) -> AlphaBetaPlayer {
+        self.book = Some(book);
+        self
+    }
+
+    /*
+     * Makes the AI vary its openings: among the book moves within the margin of the best one,
+     * it picks each with a probability proportional to exp(-gap / temperature), where the gap is
+     * how much worse than the best move it is, so that it doesn't play the same lines game after
+     * game. A temperature of 0 always picks the best move, an infinite one any of them equally.
+     * Proven results are only picked among equal ones. A margin that isn't positive, or a
+     * negative or NaN temperature, which would favor the worse moves, disables the variety.
+     */
+    pub fn with_book_variety(mut self, margin: i32, temperature: f64) -> AlphaBetaPlayer {
+        self.book_variety =
+            Some((margin, temperature)).filter(|_| margin > 0 && temperature >= 0.0);
+        self
+    }
+
+    /*
+     * Makes the AI append every completed search of a position, to choose a move or to analyze
+     * it, to the given journal. The journal can be shared by several AIs.
+     */
+    pub fn with_journal(mut self, journal: Arc<Journal>) -> AlphaBetaPlayer {
+        self.journal = Some(journal);
+        self
+    }
+
+    /*
+     * Appends the search of the position to the journal of the AI, if it has one, its value
+     * being from black's point of view. Failing to write to it doesn't stop the AI.
+     */
+    fn journal(&self, oth: Othello, color: Color, best: BitBoard, value: i32, stats: SearchStats) {
+        if let Some(journal) = &self.journal {
+            let score: i32 = if color == Color::Black { value } else { value.saturating_neg() };
+            let _ = journal.append(oth, color, JournalEntry { depth: stats.depth, score, best, });
+        }
+    }
+
+    /*
+     * Makes the AI model its opponent over the games it plays, and prefer among its book moves
+     * and its best moves after searching, those within TIE_MARGIN of the best one, the moves
+     * leading to the situations where the opponent did worst so far, see OpponentModel. The
+     * model is kept until this is called again.
+     */
+    pub fn with_opponent_model(mut self, enabled: bool) -> AlphaBetaPlayer {
+        self.opponent_model = Some(OpponentModel::new()).filter(|_| enabled);
+        self
+    }
+
+    /*
+     * Returns the model of the opponent of the AI, if it keeps one.
+     */
+    pub fn opponent_model(&self) -> Option<&OpponentModel> {
+        self.opponent_model.as_ref()
+    }
+
+    /*
+     * Changes the order in which the search tries moves, to compare orderings.
+     */
+    pub fn with_ordering(mut self, ordering: MoveOrdering) -> AlphaBetaPlayer {
+        self.ordering = ordering;
+        self
+    }
+
+    /*
+     * Enables or disables the late move reductions, enabled by default.
+     */
+    pub fn with_reductions(mut self, reductions: bool) -> AlphaBetaPlayer {
+        self.reductions = reductions;
+        self
+    }
+
+    /*
+     * Enables or disables the pass search pruning, enabled by default.
+     */
+    pub fn with_pass_pruning(mut self, pass_pruning: bool) -> AlphaBetaPlayer {
+        self.pass_pruning = pass_pruning;
+        self
+    }
+
+    /*
+     * Searches with the given constants instead of the default ones, see SearchParams.
+     */
+    pub fn with_search_params(mut self, params: SearchParams) -> AlphaBetaPlayer {
+        self.params = params;
+        self
+    }
+
+    /*
+     * Restricts the moves the AI searches, and plays, to the given ones, like the searchmoves
+     * of the go command of engine protocols. Moves that aren't legal are ignored, and the
+     * restriction too when none of its moves is.
+     */
+    pub fn with_search_moves(mut self, moves: BitBoard) -> AlphaBetaPlayer {
+        self.root_moves = moves;
+        self
+    }
+
+    /*
+     * Excludes the given moves from the search, to find the best move other than the one
+     * played when annotating a game for example. Hints and analyses then ignore them, and none
+     * is given if every move is excluded. The AI still plays an excluded move if it has no
+     * other.
+     */
+    pub fn with_excluded_moves(mut self, moves: BitBoard) -> AlphaBetaPlayer {
+        self.excluded_moves = moves;
+        self
+    }
+
+    /*
+     * Runs the searches of the root moves, in parallel, with the given executor instead of one
+     * thread per move.
+     */
+    pub fn with_executor(mut self, executor: Arc<dyn Executor>) -> AlphaBetaPlayer {
+        self.executor = executor;
+        self
+    }
+
+    /*
+     * Limits the number of nodes the AI searches for each move, all threads together, for
+     * matches limited by nodes rather than time to be fair whatever the number of threads. The
+     * AI deepens its search until the budget is spent, and keeps the result of the last
+     * iteration it completed, the first one always being.
+     */
+    pub fn with_node_limit(mut self, nodes: u64) -> AlphaBetaPlayer {
+        self.node_limit = Some(nodes);
+        self
+    }
 
-    let black: BitBoard = oth.get_bitboard(Color::Black);
-    let white: BitBoard = oth.get_bitboard(Color::White);
+    /*
+     * Returns the moves of the given player the AI may search, see with_search_moves and
+     * with_excluded_moves.
+     */
+    fn root_moves(&self, oth: Othello, color: Color) -> BitBoard {
+        let moves: BitBoard = oth.gen_moves(color);
+        let allowed: BitBoard = match moves & self.root_moves {
+            0 => moves,
+            allowed => allowed,
+        };
+        allowed & !self.excluded_moves
+    }
 
-    macro_rules! delta_mask {
-        ($mask: expr, $val: expr) => {
-            res += $val * ((black & $mask).pop_cnt() as i32 - (white & $mask).pop_cnt() as i32);
+    /*
+     * Returns the moves of the given player the AI may play: those it may search, or all of
+     * them if every one is excluded.
+     */
+    fn playable_moves(&self, oth: Othello, color: Color) -> BitBoard {
+        match self.root_moves(oth, color) {
+            0 => oth.gen_moves(color),
+            moves => moves,
         }
     }
 
-    delta_mask!(0x8100000000000081, 100);
-    delta_mask!(0x2400810000810024, 10);
-    delta_mask!(0x1800008181000018, 5);
-    delta_mask!(0x00003C3C3C3C0000, -1);
-    delta_mask!(0x003C424242423C00, -2);
-    delta_mask!(0x4281000000008142, -20);
-    delta_mask!(0x0042000000004200, -50);
+    /*
+     * Returns the statistics of the search of the last chosen move.
+     */
+    pub fn last_stats(&self) -> SearchStats {
+        self.last_stats.get()
+    }
+}
+
+//#################################################################################################
+//
+//                                     SEARCH STATISTICS
+//
+//#################################################################################################
 
-    let black_mobility: i32 = oth.gen_moves(Color::Black).pop_cnt() as i32;
-    let white_mobility: i32 = oth.gen_moves(Color::White).pop_cnt() as i32;
-    res += 5 * (black_mobility - white_mobility);
+/*
+ * Statistics about a search: the number of nodes visited, the number of transposition table
+ * hits, the number of beta cutoffs, the number of reduced moves searched again at full depth,
+ * the deepest ply reached, passes included, the depth of the deepest complete search of the
+ * root, and the time it took. The search keeps no transposition table, so tt_hits stays 0; it
+ * is there for tools tracking it across engines.
+ */
+#[derive(Clone, Copy, Default)]
+pub struct SearchStats {
+    pub nodes: u64,
+    pub tt_hits: u64,
+    pub cutoffs: u64,
+    pub researches: u64,
+    pub max_depth_reached: u8,
+    pub depth: u8,
+    pub time: Duration,
+}
 
-    res
+impl SearchStats {
+    /*
+     * Adds the statistics of another search, run in parallel, to these ones.
+     */
+    fn merge(&mut self, other: SearchStats) {
+        self.nodes += other.nodes;
+        self.tt_hits += other.tt_hits;
+        self.cutoffs += other.cutoffs;
+        self.researches += other.researches;
+        self.max_depth_reached = std::cmp::max(self.max_depth_reached, other.max_depth_reached);
+        self.depth = std::cmp::max(self.depth, other.depth);
+    }
 }
 
+//#################################################################################################
+//
+//                                         SEARCH
+//
+//#################################################################################################
+
 /*
- * Another evaluation function that specializes in ended games, returns the max value of i32 if
- * black wins, the min value if white wins or 0 if it's a draw. The 0 encourages the AI to
- * seek a draw if it's already loosing and can't manage to win.
+ * Another evaluation function that specializes in ended games, returns the value of a win at
+ * the given ply, see WIN, if black wins, its opposite if white wins or the given value if it's
+ * a draw. A value of 0 encourages the AI to seek a draw if it's already loosing and can't
+ * manage to win.
  */
 #[inline(always)]
-fn evaluate_end(oth: Othello) -> i32 {
+fn evaluate_end(oth: Othello, draw: i32, ply: u8) -> i32 {
     let black_score = oth.get_bitboard(Color::Black).pop_cnt();
     let white_score = oth.get_bitboard(Color::White).pop_cnt();
 
     if black_score > white_score {
-        std::i32::MAX
+        WIN - ply as i32
     } else if black_score < white_score {
-        std::i32::MIN
+        ply as i32 - WIN
     } else {
-        0
+        draw
     }
 }
 
 /*
- * Standard recursive minimax with alpha-beta prunig algorithm.
+ * The constants of the search, which can be tuned, see the tuning module.
+ *
+ * The late move reductions reduce the moves tried from the reduction_index-th one on,
+ * counting from 0, when at least reduction_depth plies remain after them: the later a move is
+ * tried, the less likely it is to be best, and the shallower it is searched first. They come
+ * from a table by remaining depth and move number, see ReductionTable, reducing by
+ * reduction_scale hundredths of ln(depth) * ln(index), rounded down, and by at least one ply,
+ * so that the deeper the search and the later the move, the more it is reduced. The defaults
+ * are a starting point for the tuning module rather than tuned values.
+ *
+ * The pass search, see Search::pass_search, is tried with at least pass_depth plies remaining
+ * and pass_mobility moves for the player to move, pass_reduction plies shallower than the
+ * search of the moves, and must beat the bound by pass_margin, a free pass being worth more
+ * than a move in many Othello positions.
  */
-fn alphabeta(oth: Othello, mut alpha: i32, mut beta: i32, mut color: Color, mut depth: u8) -> i32 {
-    if depth == 0 {
-        return evaluate(oth);
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SearchParams {
+    pub reduction_depth: u8,
+    pub reduction_index: u8,
+    pub reduction_scale: u8,
+    pub pass_depth: u8,
+    pub pass_reduction: u8,
+    pub pass_margin: i32,
+    pub pass_mobility: u8,
+}
+
+impl Default for SearchParams {
+    fn default() -> SearchParams {
+        SearchParams {
+            reduction_depth: 3,
+            reduction_index: 4,
+            reduction_scale: 40,
+            pass_depth: 4,
+            pass_reduction: 2,
+            pass_margin: 40,
+            pass_mobility: 4,
+        }
     }
+}
+
+/*
+ * The plies the late move reductions take from the moves, by remaining depth and index of the
+ * move, see SearchParams. Depths and indices beyond the table are reduced like its last row
+ * and column, and no reduction leaves less than a ply to search, unless reduction_depth lets
+ * moves with a single ply remaining be reduced.
+ */
+struct ReductionTable {
+    plies: [[u8; ReductionTable::MOVES]; ReductionTable::DEPTHS],
+}
 
-    let mut moves = oth.gen_moves(color);
+impl ReductionTable {
+    const DEPTHS: usize = 64;
+    const MOVES: usize = 32;
 
-    if moves == 0 {
-        color = color.invert();
-        depth -= 1;
-        if depth == 0 {
-            return evaluate(oth);
+    /*
+     * Fills the table with the reductions of the given constants.
+     */
+    fn new(params: SearchParams) -> ReductionTable {
+        let mut plies: [[u8; ReductionTable::MOVES]; ReductionTable::DEPTHS] =
+            [[0; ReductionTable::MOVES]; ReductionTable::DEPTHS];
+        let scale: f64 = params.reduction_scale as f64 / 100.0;
+        for (depth, row) in plies.iter_mut().enumerate().skip(params.reduction_depth as usize) {
+            for (index, cell) in row.iter_mut().enumerate().skip(params.reduction_index as usize) {
+                let reduction: f64 = scale * (depth as f64).ln() * (index as f64).ln();
+                *cell = (reduction as u8).clamp(1, (depth as u8).saturating_sub(1).max(1));
+            }
         }
-        moves = oth.gen_moves(color);
-        if moves == 0 {
-            return evaluate_end(oth);
+        ReductionTable { plies, }
+    }
+
+    /*
+     * Returns the plies taken from the index-th move tried with the given depth remaining.
+     */
+    fn get(&self, depth: u8, index: usize) -> u8 {
+        let row: usize = (depth as usize).min(ReductionTable::DEPTHS - 1);
+        self.plies[row][index.min(ReductionTable::MOVES - 1)]
+    }
+}
+
+/*
+ * How many nodes a search goes through between two checks of its limits, and takes from its
+ * node budget at once.
+ */
+const NODE_CHUNK: u64 = 1024;
+
+/*
+ * Takes the given number of nodes from the budget, returning false, and leaving the budget as
+ * it was, if there are not enough left.
+ */
+fn spend(budget: &AtomicU64, nodes: u64) -> bool {
+    budget.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| left.checked_sub(nodes))
+        .is_ok()
+}
+
+/*
+ * The state of a search running in a thread: the weights of the evaluation, the value of drawn
+ * games, the statistics gathered so far, the deadline after which the search is aborted and
+ * the flag telling it to stop, if any. The principal variation found from each ply is kept
+ * too, passes being empty BitBoards, as well as the way moves are ordered and the principal
+ * variation of the previous iteration, followed as long as the search is on it.
+ */
+struct Search {
+    weights: EvalWeights,
+    draw: i32,
+    stats: SearchStats,
+    deadline: Option<Instant>,
+    stop: Option<Arc<AtomicBool>>,
+    budget: Option<Arc<AtomicU64>>,
+    reserved: u64,
+    aborted: bool,
+    pv: Vec<Vec<BitBoard>>,
+    ordering: MoveOrdering,
+    previous_pv: Vec<BitBoard>,
+    follow_pv: bool,
+    reductions: bool,
+    reduction_table: ReductionTable,
+    pass_pruning: bool,
+    params: SearchParams,
+    after_pass_search: bool,
+}
+
+impl Search {
+    /*
+     * Creates a new search with the given weights.
+     */
+    fn new(weights: EvalWeights) -> Search {
+        Search {
+            weights,
+            draw: 0,
+            stats: SearchStats::default(),
+            deadline: None,
+            stop: None,
+            budget: None,
+            reserved: 0,
+            aborted: false,
+            pv: Vec::new(),
+            ordering: MoveOrdering::default(),
+            previous_pv: Vec::new(),
+            follow_pv: false,
+            reductions: false,
+            reduction_table: ReductionTable::new(SearchParams::default()),
+            pass_pruning: false,
+            params: SearchParams::default(),
+            after_pass_search: false,
         }
     }
 
-    let mut value: i32;
-    let new_color = color.invert();
-    depth -= 1;
+    /*
+     * Searches with the given constants.
+     */
+    fn with_params(mut self, params: SearchParams) -> Search {
+        self.params = params;
+        self.reduction_table = ReductionTable::new(params);
+        self
+    }
 
-    match color {
-        Color::Black => {
-            value = std::i32::MIN;
-            while moves != 0 {
-                let new_oth = oth.make_move(color, moves.pop_lsb());
-                value = std::cmp::max(value, alphabeta(new_oth, alpha, beta, new_color, depth));
-                alpha = std::cmp::max(alpha, value);
-                if alpha >= beta {
-                    break;
-                }
-            }
-        },
-        Color::White => {
-            value = std::i32::MAX;
-            while moves != 0 {
-                let new_oth = oth.make_move(color, moves.pop_lsb());
-                value = std::cmp::min(value, alphabeta(new_oth, alpha, beta, new_color, depth));
-                beta = std::cmp::min(beta, value);
-                if alpha >= beta {
-                    break;
-                }
-            }
-        },
+    /*
+     * Reduces the depth of late moves, see SearchParams.
+     */
+    fn with_reductions(mut self, reductions: bool) -> Search {
+        self.reductions = reductions;
+        self
     }
 
-    value
-}
+    /*
+     * Prunes the positions where passing is already good enough, see Search::pass_search.
+     */
+    fn with_pass_pruning(mut self, pass_pruning: bool) -> Search {
+        self.pass_pruning = pass_pruning;
+        self
+    }
 
-impl Player for AlphaBetaPlayer {
     /*
-     * Launches one thread per possible moves and wait for them to complete their tree search
-     * using the above alpha-beta algorithm and selects the best move, according to the color
-     * of the player.
+     * Orders the moves with the given ordering, trying first those of the principal variation
+     * of the previous iteration, given from the first ply.
      */
-    fn chose_move(&self, oth: Othello, mut moves: BitBoard, color: Color) -> BitBoard {
-        let mut handles = vec![];
+    fn with_ordering(mut self, ordering: MoveOrdering, previous_pv: Vec<BitBoard>) -> Search {
+        self.ordering = ordering;
+        self.follow_pv = !previous_pv.is_empty();
+        self.previous_pv = previous_pv;
+        self
+    }
 
-        while moves != 0 {
-            let mv = moves.pop_lsb();
-            let oth = oth.make_move(color, mv);
-            let color = color.invert();
-            let depth = self.max_depth;
-            handles.push(std::thread::spawn(move || -> (i32, BitBoard) {
-                (alphabeta(oth, std::i32::MIN, std::i32::MAX, color, depth), mv)
-            }));
+    /*
+     * Values drawn games at -contempt for the given player, who is searching. Contempt is kept
+     * short of the values of won and lost games.
+     */
+    fn with_contempt(mut self, contempt: i32, color: Color) -> Search {
+        let contempt: i32 = contempt.clamp(1 - PROVEN, PROVEN - 1);
+        self.draw = match color {
+            Color::Black => -contempt,
+            Color::White => contempt,
+        };
+        self
+    }
+
+    /*
+     * Aborts the search at the given deadline, its result being meaningless from then on.
+     */
+    fn with_deadline(mut self, deadline: Option<Instant>) -> Search {
+        self.deadline = deadline;
+        self
+    }
+
+    /*
+     * Aborts the search as soon as the given flag is set, its result being meaningless then.
+     */
+    fn with_stop(mut self, stop: Option<Arc<AtomicBool>>) -> Search {
+        self.stop = stop;
+        self
+    }
+
+    /*
+     * Takes the nodes of the search from the given budget, shared by all the threads searching
+     * the same move, the search being aborted when it is spent.
+     */
+    fn with_budget(mut self, budget: Option<Arc<AtomicU64>>) -> Search {
+        self.budget = budget;
+        self
+    }
+
+    /*
+     * Returns true if the search should be aborted, because of the deadline, the flag or the
+     * node budget. The next NODE_CHUNK nodes are taken from the budget at the same time.
+     */
+    fn should_abort(&mut self) -> bool {
+        if self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+            || self.stop.as_ref().is_some_and(|stop| stop.load(Ordering::Relaxed))
+        {
+            return true;
+        }
+
+        match &self.budget {
+            Some(budget) if !spend(budget, NODE_CHUNK) => true,
+            Some(_) => {
+                self.reserved += NODE_CHUNK;
+                false
+            },
+            None => false,
+        }
+    }
+
+    /*
+     * Gives the nodes taken from the budget but not searched back to it, once the search is
+     * over.
+     */
+    fn release_nodes(&mut self) {
+        if let Some(budget) = &self.budget {
+            budget.fetch_add(self.reserved.saturating_sub(self.stats.nodes), Ordering::Relaxed);
+            self.reserved = 0;
+        }
+    }
+
+    /*
+     * Makes the principal variation from the given ply the given move, after a pass if the
+     * player had to, followed by the principal variation from the child ply.
+     */
+    fn update_pv(&mut self, ply: usize, passed: bool, mv: BitBoard, child: usize) {
+        let (parents, children) = self.pv.split_at_mut(child);
+        parents[ply].clear();
+        if passed {
+            parents[ply].push(0);
+        }
+        parents[ply].push(mv);
+        parents[ply].extend_from_slice(&children[0]);
+    }
+
+    /*
+     * Returns by how many plies the search of the given move, the index-th one tried in the
+     * position, can be reduced, the remaining depth after it being given. Neither the moves of
+     * the principal variation nor corners are reduced, and neither are moves close enough to
+     * the end of the game for the search to reach it.
+     */
+    fn reduction(&self, oth: Othello, mv: BitBoard, depth: u8, index: usize) -> u8 {
+        if !self.reductions || self.follow_pv || mv & CORNERS != 0 || oth.empties() <= depth + 1 {
+            return 0;
+        }
+        self.reduction_table.get(depth, index)
+    }
+
+    /*
+     * The pass search, the null move of chess adapted to Othello, where passing is a move of its
+     * own when there is no other. If the player to move still beats the bound beyond which
+     * the position is pruned by some margin after giving his opponent a free move, searched
+     * shallower with a null window, his actual moves are assumed to do well enough too and are
+     * not searched. The bound is returned then, None otherwise.
+     * It is not tried twice in a row, on the principal variation, with few moves to choose
+     * from, or close to the end of the game, whose exact values it would spoil and where
+     * passing can be the best thing to do.
+     */
+    fn pass_search(
+        &mut self, oth: Othello, alpha: i32, beta: i32, color: Color, depth: u8, ply: u8,
+    ) -> Option<i32> {
+        let SearchParams { pass_depth, pass_reduction, pass_margin, .. } = self.params;
+        if !self.pass_pruning || self.follow_pv || depth < pass_depth
+            || oth.empties() <= depth + pass_depth
+        {
+            return None;
+        }
+
+        let (depth, ply): (u8, u8) = ((depth - 1).saturating_sub(pass_reduction), ply + 1);
+        match color {
+            Color::Black if beta < i32::MAX - pass_margin => {
+                let bound: i32 = beta + pass_margin;
+                self.after_pass_search = true;
+                let value: i32 = self.alphabeta(oth, bound - 1, bound, Color::White, depth, ply);
+                (value >= bound).then_some(beta)
+            },
+            Color::White if alpha > pass_margin - i32::MAX => {
+                let bound: i32 = alpha - pass_margin;
+                self.after_pass_search = true;
+                let value: i32 = self.alphabeta(oth, bound, bound + 1, Color::Black, depth, ply);
+                (value <= bound).then_some(alpha)
+            },
+            _ => None,
+        }
+    }
+
+    /*
+     * Standard recursive minimax with alpha-beta prunig algorithm, ply being the distance to the
+     * root of the search.
+     */
+    fn alphabeta(
+        &mut self, oth: Othello, mut alpha: i32, mut beta: i32, mut color: Color, mut depth: u8,
+        mut ply: u8,
+    ) -> i32 {
+        self.stats.nodes += 1;
+        self.stats.max_depth_reached = std::cmp::max(self.stats.max_depth_reached, ply);
+        let after_pass_search: bool = std::mem::take(&mut self.after_pass_search);
+
+        // Looking at the time is slow, so it is only done every NODE_CHUNK nodes, at the first
+        // of them so that they can be taken from the budget before they are searched.
+        if self.stats.nodes % NODE_CHUNK == 1 && self.should_abort() {
+            self.aborted = true;
+        }
+        if self.aborted {
+            return 0;
+        }
+
+        // Room is made for the principal variations of this node and of its children, passes
+        // included.
+        let node: usize = ply as usize;
+        if self.pv.len() < node + 3 {
+            self.pv.resize(node + 3, Vec::new());
+        }
+        self.pv[node].clear();
+
+        if depth == 0 {
+            return evaluate(oth, &self.weights);
+        }
+
+        let mut moves = oth.gen_moves(color);
+
+        if moves == 0 {
+            color = color.invert();
+            depth -= 1;
+            ply += 1;
+            self.stats.max_depth_reached = std::cmp::max(self.stats.max_depth_reached, ply);
+            if depth == 0 {
+                return evaluate(oth, &self.weights);
+            }
+            moves = oth.gen_moves(color);
+            if moves == 0 {
+                // The game ended before the pass just counted.
+                return evaluate_end(oth, self.draw, ply - 1);
+            }
         }
 
-        let mut res: (i32, BitBoard) = handles.remove(0).join().unwrap();
-        let mut val: i32 = res.0;
-        let mut best: BitBoard = res.1;
+        let mut value: i32;
+        let new_color = color.invert();
+        let passed: bool = ply as usize > node;
+        if !passed && !after_pass_search && moves.pop_cnt() >= self.params.pass_mobility {
+            if let Some(bound) = self.pass_search(oth, alpha, beta, color, depth, ply) {
+                return bound;
+            }
+        }
+        let following: bool = self.follow_pv;
+        let first: BitBoard = match (following, (ply as usize).checked_sub(1)) {
+            (true, Some(index)) => self.previous_pv.get(index).copied().unwrap_or(0),
+            _ => 0,
+        };
+        let ordered: MoveList = self.ordering.order(oth, color, moves, first, depth);
+        depth -= 1;
+        ply += 1;
 
         match color {
             Color::Black => {
-                for handle in handles {
-                    res = handle.join().unwrap();
-                    if res.0 > val {
-                        val = res.0;
-                        best = res.1;
+                value = i32::MIN;
+                for (i, mv) in ordered.enumerate() {
+                    self.follow_pv = following && mv == first;
+                    let new_oth = oth.make_move(color, mv);
+                    let reduced: u8 = depth - self.reduction(oth, mv, depth, i);
+                    let mut new_value: i32 =
+                        self.alphabeta(new_oth, alpha, beta, new_color, reduced, ply);
+                    // A reduced move that looks better than the best one so far is searched
+                    // again at full depth.
+                    if reduced < depth && new_value > alpha {
+                        self.stats.researches += 1;
+                        new_value = self.alphabeta(new_oth, alpha, beta, new_color, depth, ply);
+                    }
+                    if new_value > value || self.pv[node].is_empty() {
+                        self.update_pv(node, passed, mv, ply as usize);
+                    }
+                    value = std::cmp::max(value, new_value);
+                    alpha = std::cmp::max(alpha, value);
+                    if alpha >= beta {
+                        self.stats.cutoffs += 1;
+                        break;
                     }
                 }
             },
             Color::White => {
-                for handle in handles {
-                    res = handle.join().unwrap();
-                    if res.0 < val {
-                        val = res.0;
-                        best = res.1;
+                value = i32::MAX;
+                for (i, mv) in ordered.enumerate() {
+                    self.follow_pv = following && mv == first;
+                    let new_oth = oth.make_move(color, mv);
+                    let reduced: u8 = depth - self.reduction(oth, mv, depth, i);
+                    let mut new_value: i32 =
+                        self.alphabeta(new_oth, alpha, beta, new_color, reduced, ply);
+                    if reduced < depth && new_value < beta {
+                        self.stats.researches += 1;
+                        new_value = self.alphabeta(new_oth, alpha, beta, new_color, depth, ply);
+                    }
+                    if new_value < value || self.pv[node].is_empty() {
+                        self.update_pv(node, passed, mv, ply as usize);
+                    }
+                    value = std::cmp::min(value, new_value);
+                    beta = std::cmp::min(beta, value);
+                    if alpha >= beta {
+                        self.stats.cutoffs += 1;
+                        break;
                     }
                 }
             },
         }
 
-        best
+        value
+    }
+}
+
+//#################################################################################################
+//
+//                                          HINTS
+//
+//#################################################################################################
+
+/*
+ * A move suggested by the AI, with its evaluation from the point of view of the player to move
+ * and a one-line explanation.
+ */
+pub struct Hint {
+    pub mv: BitBoard,
+    pub value: i32,
+    pub reason: String,
+}
+
+/*
+ * Explains a move in one line, using the variation of the evaluation terms it causes from the
+ * point of view of the player making it. The biggest gain is given as a reason, or the biggest
+ * concession if the move gains nothing.
+ */
+fn explain(oth: Othello, color: Color, mv: BitBoard, value: i32, weights: &EvalWeights) -> String {
+    let new_oth: Othello = oth.make_move(color, mv);
+
+    if value >= PROVEN {
+        return "Wins the game by force.".to_string();
+    } else if value <= -PROVEN {
+        return "Every move loses by force, this one too.".to_string();
+    } else if new_oth.gen_moves(color.invert()) == 0 && new_oth.gen_moves(color) != 0 {
+        return "Forces the opponent to pass.".to_string();
+    }
+
+    let before: EvalBreakdown = EvalBreakdown::new(oth, weights);
+    let after: EvalBreakdown = EvalBreakdown::new(new_oth, weights);
+    let sign: i32 = if color == Color::Black { 1 } else { -1 };
+    let own_mobility: u8 = new_oth.gen_moves(color).pop_cnt();
+    let opp_mobility: u8 = new_oth.gen_moves(color.invert()).pop_cnt();
+
+    // Each term comes with the reasons given when it is the biggest gain or concession.
+    let terms: [(i32, &str, &str); 7] = [
+        (after.corners - before.corners,
+            "Secures a corner.", "Gives up a corner."),
+        (after.edges - before.edges,
+            "Strengthens the edges.", "Gives up edge disks."),
+        (after.center - before.center,
+            "Keeps few disks in the center.", "Takes many central disks."),
+        (after.c_squares - before.c_squares,
+            "Gets rid of C-square disks.", "Plays next to a corner on a C-square."),
+        (after.x_squares - before.x_squares,
+            "Avoids the X-squares.", "Plays an X-square, giving away a corner."),
+        (after.mobility - before.mobility,
+            "Gains mobility", "Loses some mobility"),
+        (after.disks - before.disks,
+            "Flips many disks.", "Flips few disks."),
+    ];
+
+    let deltas = terms.iter().map(|term| sign * term.0).enumerate();
+    let (best, gain) = deltas.clone().max_by_key(|&(_, delta)| delta).unwrap();
+    let (worst, _) = deltas.min_by_key(|&(_, delta)| delta).unwrap();
+
+    let reason: &str = if gain > 0 { terms[best].1 } else { terms[worst].2 };
+
+    if reason.ends_with('.') {
+        reason.to_string()
+    } else {
+        format!("{} ({} moves against {}).", reason, own_mobility, opp_mobility)
+    }
+}
+
+impl AlphaBetaPlayer {
+    /*
+     * Searches every possible move to the maximum depth. Returns the evaluation of every move,
+     * from black's point of view, in the order they were given, and the statistics of the
+     * whole search.
+     */
+    fn search_moves(&self, oth: Othello, moves: BitBoard, color: Color)
+        -> (Vec<(i32, BitBoard)>, SearchStats)
+    {
+        let depth: u8 = self.personality.depth(self.max_depth);
+        if self.node_limit.is_some() {
+            return self.search_deepening(oth, moves, color, depth, None);
+        }
+        let (results, stats) = self.search_depth(oth, moves, color, depth, None);
+        (results.unwrap(), stats)
+    }
+
+    /*
+     * Searches every possible move in parallel, with the executor of the AI, one job per move,
+     * to the given depth using the above alpha-beta algorithm. Returns the evaluation of every
+     * move, like search_moves, or None if the deadline was reached before the end of the
+     * search, and the statistics of the whole search.
+     */
+    fn search_depth(
+        &self, oth: Othello, moves: BitBoard, color: Color, depth: u8,
+        deadline: Option<Instant>,
+    ) -> (Option<Vec<(i32, BitBoard)>>, SearchStats) {
+        let limits: Limits = Limits { deadline, ..Limits::default() };
+        let (results, stats) = self.search_depth_pv(oth, moves, color, depth, limits, &[]);
+        (results.map(|results| values(&results)), stats)
+    }
+
+    /*
+     * Searches every move like search_depth, also aborting when the given flag is set, and
+     * returns the principal variation following each move with its evaluation. The principal
+     * variations of the previous iteration, if given, are tried first.
+     */
+    fn search_depth_pv(
+        &self, oth: Othello, mut moves: BitBoard, color: Color, depth: u8,
+        limits: Limits, previous: &[RootResult],
+    ) -> (Option<Vec<RootResult>>, SearchStats) {
+        let start: Instant = Instant::now();
+        let mut searches: Vec<(Search, BitBoard)> = Vec::new();
+
+        while moves != 0 {
+            let mv = moves.pop_lsb();
+            let previous_pv: Vec<BitBoard> = previous.iter()
+                .find(|result| result.1 == mv)
+                .map_or_else(Vec::new, |result| result.2.clone());
+            let search: Search = Search::new(self.weights)
+                .with_contempt(self.contempt, color)
+                .with_ordering(self.ordering, previous_pv)
+                .with_reductions(self.reductions)
+                .with_pass_pruning(self.pass_pruning)
+                .with_params(self.params)
+                .with_deadline(limits.deadline)
+                .with_stop(limits.stop.clone())
+                .with_budget(limits.budget.clone());
+            searches.push((search, mv));
+        }
+
+        let outcomes = map(&*self.executor, searches, |(mut search, mv)| {
+            let (oth, color) = (oth.make_move(color, mv), color.invert());
+            let value: i32 = search.alphabeta(oth, i32::MIN, i32::MAX, color, depth, 1);
+            search.release_nodes();
+            let pv: Vec<BitBoard> = search.pv.get(1).cloned().unwrap_or_default();
+            (Some(value).filter(|_| !search.aborted), mv, pv, search.stats)
+        });
+
+        let mut results: Option<Vec<RootResult>> = Some(Vec::with_capacity(outcomes.len()));
+        let mut stats: SearchStats = SearchStats { nodes: 1, ..SearchStats::default() };
+
+        for (value, mv, pv, thread_stats) in outcomes {
+            match (&mut results, value) {
+                (Some(results), Some(value)) => results.push((value, mv, pv)),
+                _ => results = None,
+            }
+            stats.merge(thread_stats);
+        }
+        if results.is_some() {
+            stats.depth = depth;
+        }
+        stats.time = start.elapsed();
+
+        (results, stats)
+    }
+
+    /*
+     * Searches every possible move deeper and deeper, from one ply to the given maximum depth,
+     * while the time manager, if any, and the node limit of the AI allow it, and returns the
+     * evaluations of the last complete iteration, like search_moves. The first iteration is
+     * always completed, its nodes being taken from the budget afterwards.
+     */
+    fn search_deepening(
+        &self, oth: Othello, moves: BitBoard, color: Color, max_depth: u8,
+        timing: Option<(TimeManager, Duration)>,
+    ) -> (Vec<(i32, BitBoard)>, SearchStats) {
+        let start: Instant = Instant::now();
+        let budget: Option<Arc<AtomicU64>> = self.node_limit.map(|nodes| Arc::new(nodes.into()));
+        let (results, mut stats) =
+            self.search_depth_pv(oth, moves, color, max_depth.min(1), Limits::default(), &[]);
+        if let Some(budget) = &budget {
+            let nodes: u64 = budget.load(Ordering::Relaxed).min(stats.nodes);
+            spend(budget, nodes);
+        }
+        let mut pvs: Vec<RootResult> = results.unwrap();
+        let mut results: Vec<(i32, BitBoard)> = values(&pvs);
+        let mut best: BitBoard = best_result(&results, color).1;
+        let mut stable: u8 = 1;
+
+        for depth in 2..=max_depth {
+            if let Some((time_manager, time)) = timing {
+                if start.elapsed() >= time / 2 || time_manager.is_easy(&results, color, stable) {
+                    break;
+                }
+            }
+
+            let limits: Limits = Limits {
+                deadline: timing.map(|(_, time)| start + time),
+                stop: None,
+                budget: budget.clone(),
+            };
+            let (new_results, new_stats) =
+                self.search_depth_pv(oth, moves, color, depth, limits, &pvs);
+            stats.merge(new_stats);
+
+            match new_results {
+                Some(new_results) => {
+                    results = values(&new_results);
+                    pvs = new_results;
+                },
+                None => break,
+            }
+
+            let new_best: BitBoard = best_result(&results, color).1;
+            stable = if new_best == best { stable.saturating_add(1) } else { 1 };
+            best = new_best;
+        }
+        stats.time = start.elapsed();
+
+        (results, stats)
+    }
+
+    /*
+     * Searches every possible move and returns the best one, according to the color of the
+     * player, with its evaluation from black's point of view and the statistics of the search.
+     * If the player has no legal move, he has to pass, and an empty BitBoard is returned with
+     * the evaluation of the position.
+     */
+    pub fn best_move(&self, oth: Othello, color: Color) -> (BitBoard, i32, SearchStats) {
+        let moves: BitBoard = self.playable_moves(oth, color);
+        if moves == 0 {
+            let start: Instant = Instant::now();
+            let mut search: Search = Search::new(self.weights)
+                .with_contempt(self.contempt, color)
+                .with_ordering(self.ordering, Vec::new())
+                .with_reductions(self.reductions)
+                .with_pass_pruning(self.pass_pruning)
+                .with_params(self.params);
+            let depth: u8 = self.personality.depth(self.max_depth);
+            let value: i32 = search.alphabeta(oth, i32::MIN, i32::MAX, color, depth, 0);
+            search.stats.depth = depth;
+            search.stats.time = start.elapsed();
+            self.journal(oth, color, 0, value, search.stats);
+            return (0, value, search.stats);
+        }
+
+        let (results, stats) = self.search_moves(oth, moves, color);
+        let (val, best) = best_result(&results, color);
+        self.journal(oth, color, best, val, stats);
+
+        (best, val, stats)
+    }
+
+    /*
+     * Returns the n best moves for the given player, best first, each with a short reason
+     * explaining it, intended for teaching front-ends.
+     */
+    pub fn hint(&self, oth: Othello, color: Color, n: usize) -> Vec<Hint> {
+        self.hint_moves(oth, color, self.root_moves(oth, color), n)
+    }
+
+    /*
+     * Returns the best move for the given player other than the one he played, which is what
+     * blunder checks compare it with, or None if he had no other.
+     */
+    pub fn best_alternative(&self, oth: Othello, color: Color, played: BitBoard) -> Option<Hint> {
+        self.hint_moves(oth, color, self.root_moves(oth, color) & !played, 1).pop()
+    }
+
+    /*
+     * Returns the n best of the given moves, like hint.
+     */
+    fn hint_moves(&self, oth: Othello, color: Color, moves: BitBoard, n: usize) -> Vec<Hint> {
+        if moves == 0 {
+            return Vec::new();
+        }
+
+        let (mut results, _) = self.search_moves(oth, moves, color);
+        match color {
+            Color::Black => results.sort_by_key(|&(value, _)| std::cmp::Reverse(value)),
+            Color::White => results.sort_by_key(|&(value, _)| value),
+        }
+
+        results.into_iter().take(n).map(|(value, mv)| {
+            let value: i32 = if color == Color::Black { value } else { value.saturating_neg() };
+            Hint { mv, value, reason: explain(oth, color, mv, value, &self.weights), }
+        }).collect()
+    }
+}
+
+impl AlphaBetaPlayer {
+    /*
+     * Turns the final disc differential of a solved position, from the point of view of the
+     * given player, into an evaluation from black's point of view, as the search would give.
+     */
+    fn exact_value(&self, score: i32, color: Color) -> i32 {
+        let own: i32 = match score.signum() {
+            0 => -self.contempt.clamp(1 - PROVEN, PROVEN - 1),
+            _ => Value::Exact(score as i8).to_search(),
+        };
+        match color {
+            Color::Black => own,
+            Color::White => -own,
+        }
+    }
+}
+
+/*
+ * What aborts a search: a deadline, a flag being set, a node budget being spent, several of
+ * them or none.
+ */
+#[derive(Clone, Default)]
+struct Limits {
+    deadline: Option<Instant>,
+    stop: Option<Arc<AtomicBool>>,
+    budget: Option<Arc<AtomicU64>>,
+}
+
+/*
+ * The evaluation of a move from black's point of view, the move and the principal variation
+ * following it.
+ */
+type RootResult = (i32, BitBoard, Vec<BitBoard>);
+
+/*
+ * Returns the evaluations of the moves, without their principal variations.
+ */
+fn values(results: &[RootResult]) -> Vec<(i32, BitBoard)> {
+    results.iter().map(|&(value, mv, _)| (value, mv)).collect()
+}
+
+/*
+ * Returns the best of the evaluated moves, given from black's point of view, for the given
+ * player, the first one in case of a tie.
+ */
+fn best_result(results: &[(i32, BitBoard)], color: Color) -> (i32, BitBoard) {
+    let (mut val, mut best): (i32, BitBoard) = results[0];
+
+    for &(res, mv) in &results[1..] {
+        let better: bool = match color {
+            Color::Black => res > val,
+            Color::White => res < val,
+        };
+        if better {
+            val = res;
+            best = mv;
+        }
+    }
+
+    (val, best)
+}
+
+//#################################################################################################
+//
+//                                    INFINITE ANALYSIS
+//
+//#################################################################################################
+
+/*
+ * One iteration of an analysis: the depth searched, the best move with its evaluation from the
+ * point of view of the player to move, the principal variation starting with it, passes being
+ * empty BitBoards, and the statistics of the iteration.
+ */
+#[derive(Clone)]
+pub struct Iteration {
+    pub depth: u8,
+    pub mv: BitBoard,
+    pub value: i32,
+    pub pv: Vec<BitBoard>,
+    pub stats: SearchStats,
+}
+
+impl AlphaBetaPlayer {
+    /*
+     * Analyzes the position deeper and deeper, one ply at a time and whatever the depth of the
+     * AI, until the given flag is set or the whole game tree has been searched. Each iteration
+     * is reported as soon as it is complete, and the last one is returned. Setting the flag
+     * aborts the search within a few thousand nodes, the unfinished iteration being dropped,
+     * which is what GUIs need for "go infinite". Returns None if the player has to pass, if all
+     * his moves are excluded, or if the flag was set before the end of the first iteration.
+     */
+    pub fn analyze(
+        &self, oth: Othello, color: Color, stop: Arc<AtomicBool>,
+        report: impl FnMut(&Iteration),
+    ) -> Option<Iteration> {
+        let limits: Limits = Limits { stop: Some(stop), ..Limits::default() };
+        self.analyze_within(oth, color, limits, u8::MAX, report).0
+    }
+
+    /*
+     * Analyzes the position like analyze, within the given limits and up to the given depth,
+     * also returning the nodes of all the iterations, the unfinished one included.
+     */
+    fn analyze_within(
+        &self, oth: Othello, color: Color, limits: Limits, max_depth: u8,
+        mut report: impl FnMut(&Iteration),
+    ) -> (Option<Iteration>, u64) {
+        let moves: BitBoard = self.root_moves(oth, color);
+        if moves == 0 {
+            return (None, 0);
+        }
+
+        let mut last: Option<Iteration> = None;
+        let mut nodes: u64 = 0;
+        let mut previous: Vec<RootResult> = Vec::new();
+        for depth in 1..=max_depth {
+            let (results, stats) =
+                self.search_depth_pv(oth, moves, color, depth, limits.clone(), &previous);
+            nodes += stats.nodes;
+            previous = match results {
+                Some(results) => results,
+                None => break,
+            };
+
+            let (value, mv) = best_result(&values(&previous), color);
+            let mut pv: Vec<BitBoard> = vec![mv];
+            pv.extend_from_slice(&previous.iter().find(|result| result.1 == mv).unwrap().2);
+
+            let value: i32 = if color == Color::Black { value } else { value.saturating_neg() };
+            let iteration: Iteration = Iteration { depth, mv, value, pv, stats, };
+            report(&iteration);
+            last = Some(iteration);
+
+            // No leaf was cut by the depth, so searching deeper would change nothing.
+            if stats.max_depth_reached <= depth {
+                break;
+            }
+        }
+
+        if let Some(last) = &last {
+            let value: i32 =
+                if color == Color::Black { last.value } else { last.value.saturating_neg() };
+            self.journal(oth, color, last.mv, value, last.stats);
+        }
+        (last, nodes)
+    }
+}
+
+impl AlphaBetaPlayer {
+    /*
+     * Returns the flag stopping the searches of the AI as a Searcher, so that another thread
+     * can stop them, see Searcher::stop.
+     */
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+}
+
+impl crate::searcher::Searcher for AlphaBetaPlayer {
+    /*
+     * Searches the position deeper and deeper like analyze, up to the depth of the limits or
+     * of the AI, and within their nodes and time, without the book or the tablebase. The last
+     * complete iteration gives the result, an iteration one ply deep being completed anyway,
+     * and the nodes are those of every iteration. The stop flag is cleared once the search is
+     * over, so that a stop sent while it starts isn't lost.
+     */
+    fn best_move(&mut self, (oth, color): (Othello, Color), limits: &SearchLimits)
+        -> SearchResult
+    {
+        let start: Instant = Instant::now();
+        let bounds: Limits = Limits {
+            deadline: limits.time.map(|time| start + time),
+            stop: Some(self.stop.clone()),
+            budget: limits.nodes.map(|nodes| Arc::new(nodes.into())),
+        };
+        let depth: u8 = limits.depth.unwrap_or(self.max_depth);
+        let (mut iteration, mut nodes): (Option<Iteration>, u64) =
+            self.analyze_within(oth, color, bounds, depth, |_| ());
+        if iteration.is_none() {
+            let (fallback, fallback_nodes) =
+                self.analyze_within(oth, color, Limits::default(), 1, |_| ());
+            iteration = fallback;
+            nodes += fallback_nodes;
+        }
+        self.stop.store(false, Ordering::Relaxed);
+
+        let (best, value, pv, depth) = match iteration {
+            Some(iteration) => (iteration.mv, iteration.value, iteration.pv, iteration.depth),
+            None => {
+                let (mv, value, stats) = AlphaBetaPlayer::best_move(self, oth, color);
+                let value: i32 = if color == Color::Black { value } else { value.saturating_neg() };
+                nodes += stats.nodes;
+                (mv, value, vec![mv], stats.depth)
+            },
+        };
+        SearchResult {
+            best,
+            value: Some(Value::from_search(value)),
+            win_probability: win_probability(value, oth.empties()),
+            pv,
+            depth,
+            nodes,
+            time: start.elapsed(),
+        }
+    }
+
+    fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    fn name(&self) -> String {
+        Player::name(self)
+    }
+}
+
+//#################################################################################################
+//
+//                                   DETERMINISM AUDIT
+//
+//#################################################################################################
+
+impl AlphaBetaPlayer {
+    /*
+     * Searches each position twice on the thread of the caller, both to choose a move and to
+     * analyze it up to the depth of the AI, and checks that the searches went the same way:
+     * the same move and value, and for each iteration the same nodes, cutoffs and principal
+     * variation. This catches the nondeterminism of state left uninitialized or carried from a
+     * search to the next, and of iteration orders changing from run to run. Returns the nodes
+     * of the first searches, or the first difference found.
+     */
+    pub fn audit_determinism(self, positions: &[(Othello, Color)]) -> Result<u64, String> {
+        let player: AlphaBetaPlayer = self.with_executor(Arc::new(Inline));
+        let mut nodes: u64 = 0;
+
+        for (i, &(oth, color)) in positions.iter().enumerate() {
+            let (first, second) = (player.best_move(oth, color), player.best_move(oth, color));
+            if (first.0, first.1, first.2.nodes) != (second.0, second.1, second.2.nodes) {
+                return Err(format!("position {}: {} {:+} in {} nodes, then {} {:+} in {} nodes",
+                    i + 1, first.0.square_name(), first.1, first.2.nodes,
+                    second.0.square_name(), second.1, second.2.nodes));
+            }
+            nodes += first.2.nodes;
+
+            let (first, second) = (player.iterations(oth, color), player.iterations(oth, color));
+            for (a, b) in first.iter().zip(&second) {
+                let describe = |iteration: &Iteration| -> String {
+                    format!("{} {:+} in {} nodes and {} cutoffs", format_pv(&iteration.pv),
+                        iteration.value, iteration.stats.nodes, iteration.stats.cutoffs)
+                };
+                if describe(a) != describe(b) {
+                    return Err(format!("position {}, depth {}: {}, then {}", i + 1, a.depth,
+                        describe(a), describe(b)));
+                }
+            }
+            if first.len() != second.len() {
+                return Err(format!("position {}: {} iterations, then {}", i + 1, first.len(),
+                    second.len()));
+            }
+        }
+
+        Ok(nodes)
+    }
+
+    /*
+     * Returns the iterations of an analysis of the position up to the depth of the AI.
+     */
+    fn iterations(&self, oth: Othello, color: Color) -> Vec<Iteration> {
+        let stop: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+        let mut iterations: Vec<Iteration> = Vec::new();
+        self.analyze(oth, color, stop.clone(), |iteration| {
+            if iteration.depth <= self.max_depth {
+                iterations.push(iteration.clone());
+            }
+            if iteration.depth >= self.max_depth {
+                stop.store(true, Ordering::Relaxed);
+            }
+        });
+        iterations
+    }
+}
+
+//#################################################################################################
+//
+//                                     TIME MANAGEMENT
+//
+//#################################################################################################
+
+/*
+ * The time manager of the AI, deciding how long it thinks when its time is limited. The time
+ * left is shared between the moves the AI will play, every other move until the board is full,
+ * in proportion to the weight of the phase of each, see Phase, and each move gets its share
+ * plus most of the increment. By default, the midgame and the pre-endgame, from 40 down to 15
+ * empty squares, where games are decided, get the most time, the opening less, its moves often
+ * coming from the book, and the endgame little, the exact search solving it quickly. The
+ * search is deepened iteration by iteration, and no new iteration is started once half of
+ * that time is spent. An iteration still running when the time is up is aborted and its result
+ * discarded.
+ *
+ * With the easy move option, the search also stops as soon as the best move has stayed the
+ * same for the given number of iterations, and beats every other move by the given margin.
+ * Either way, a move that is the only legal one is played after a one ply search.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TimeManager {
+    easy_move: Option<(u8, i32)>,
+    phases: PhaseThresholds,
+    weights: [u32; 4],
+}
+
+/*
+ * The default weights of the phases in the time manager, in the order of Phase::ALL, in
+ * percents of the time of an average move.
+ */
+pub const PHASE_WEIGHTS: [u32; 4] = [60, 150, 150, 20];
+
+impl Default for TimeManager {
+    fn default() -> TimeManager {
+        TimeManager { easy_move: None, phases: PhaseThresholds::default(), weights: PHASE_WEIGHTS, }
+    }
+}
+
+impl TimeManager {
+    /*
+     * Creates a new TimeManager, without the easy move option.
+     */
+    pub fn new() -> TimeManager {
+        TimeManager::default()
+    }
+
+    /*
+     * Stops the search early when the best move has been stable and dominant, as described
+     * above.
+     */
+    pub fn with_easy_move(mut self, iterations: u8, margin: i32) -> TimeManager {
+        self.easy_move = Some((iterations, margin));
+        self
+    }
+
+    /*
+     * Uses the given thresholds to tell the phases of the game apart.
+     */
+    pub fn with_phases(mut self, phases: PhaseThresholds) -> TimeManager {
+        self.phases = phases;
+        self
+    }
+
+    /*
+     * Shares the time between the phases with the given weights, see PHASE_WEIGHTS.
+     */
+    pub fn with_phase_weights(mut self, weights: [u32; 4]) -> TimeManager {
+        self.weights = weights;
+        self
+    }
+
+    /*
+     * Returns the time the given player can spend on his move, or None if his time is
+     * unlimited.
+     */
+    fn budget(&self, clock: &Clock, color: Color, empties: u8) -> Option<Duration> {
+        let remaining: Duration = clock.remaining(color)?;
+        let weight = |empties: u8| self.weights[self.phases.classify(empties).index()] as f64;
+        let total: f64 = (1..=empties).rev().step_by(2).map(weight).sum();
+        let share: Duration = remaining.mul_f64(weight(empties) / total.max(1.0))
+            + clock.increment() * 3 / 4;
+        Some(share.min(remaining / 2))
+    }
+
+    /*
+     * Returns true if the search can stop, given the evaluations of the last iteration and the
+     * number of iterations the best move has been the same for.
+     */
+    fn is_easy(&self, results: &[(i32, BitBoard)], color: Color, stable: u8) -> bool {
+        if results.len() == 1 {
+            return true;
+        }
+
+        let (iterations, margin) = match self.easy_move {
+            Some(easy_move) => easy_move,
+            None => return false,
+        };
+
+        let mut values: Vec<i64> = results.iter().map(|&(value, _)| match color {
+            Color::Black => value as i64,
+            Color::White => -(value as i64),
+        }).collect();
+        values.sort_unstable_by(|a, b| b.cmp(a));
+
+        stable >= iterations && values[0] - values[1] >= margin as i64
+    }
+}
+
+//#################################################################################################
+//
+//                                      TARGET RATING
+//
+//#################################################################################################
+
+/*
+ * The rating at and above which the AI always plays its best move.
+ */
+pub const MAX_ELO: u16 = 2000;
+
+/*
+ * The temperatures used by pick_move for a few rating deficits below MAX_ELO, interpolated in
+ * between. They are hand-picked estimates, not measurements: `othello calibrate` plays the AI
+ * at each of these ratings against itself at full strength, 3 plies deep by default, to check
+ * them. Weaker ratings use the last temperature.
+ */
+pub const TEMPERATURES: [(f64, f64); 6] = [
+    (0.0, 0.0), (75.0, 5.0), (200.0, 10.0), (380.0, 20.0), (450.0, 40.0), (600.0, 100.0),
+];
+
+/*
+ * Returns the temperature modelling the given rating.
+ */
+fn temperature(elo: u16) -> f64 {
+    interpolate(&TEMPERATURES, MAX_ELO.saturating_sub(elo) as f64)
+}
+
+impl AlphaBetaPlayer {
+    /*
+     * Chooses among the evaluated moves, given from the point of view of the player, the first
+     * best one, or the one the opponent model prefers among those within TIE_MARGIN of it.
+     */
+    fn tie_break(&self, oth: Othello, color: Color, moves: &[(i32, BitBoard)])
+        -> Option<BitBoard>
+    {
+        let best: i32 = moves.iter().map(|&(value, _)| value).max()?;
+        // Proven results are only tied with equal ones, the fastest win being played.
+        let margin: i64 = if is_proven(best) { 0 } else { TIE_MARGIN as i64 };
+        let mut close: Vec<(i32, BitBoard)> = moves.iter().copied()
+            .filter(|&(value, _)| value as i64 >= best as i64 - margin)
+            .collect();
+        close.sort_by_key(|&(value, _)| std::cmp::Reverse(value));
+
+        match &self.opponent_model {
+            Some(model) => {
+                let close: Vec<BitBoard> = close.iter().map(|&(_, mv)| mv).collect();
+                model.choose(oth, color, &close)
+            },
+            None => close.first().map(|&(_, mv)| mv),
+        }
+    }
+
+    /*
+     * Picks a book move among the evaluated ones, given from the point of view of the player,
+     * within the margin of the best one, see with_book_variety.
+     */
+    fn pick_book_move(&self, moves: &[(i32, BitBoard)], margin: i32, temperature: f64)
+        -> Option<BitBoard>
+    {
+        let best: i64 = moves.iter().map(|&(value, _)| value as i64).max()?;
+        let margin: i64 = if is_proven(best as i32) { 0 } else { margin as i64 };
+        let weights: Vec<f64> = moves.iter().map(|&(value, _)| {
+            let gap: i64 = best - value as i64;
+            if gap == 0 {
+                1.0
+            } else if gap > margin || temperature == 0.0 {
+                0.0
+            } else {
+                (-(gap as f64) / temperature).exp()
+            }
+        }).collect();
+
+        let mut rng: u64 = self.rng.get();
+        let mut target: f64 = next_random(&mut rng) as f64 / u64::MAX as f64
+            * weights.iter().sum::<f64>();
+        self.rng.set(rng);
+
+        for (&(_, mv), &weight) in moves.iter().zip(&weights) {
+            if target < weight {
+                return Some(mv);
+            }
+            target -= weight;
+        }
+
+        moves.iter().find(|&&(value, _)| value as i64 == best).map(|&(_, mv)| mv)
+    }
+
+    /*
+     * Picks a move among the evaluated ones, given from black's point of view, to play like a
+     * player of the given rating. Each move is chosen with a probability proportional to
+     * exp(-gap / temperature), where the gap is how much worse than the best move it is and
+     * the temperature grows as the rating drops below MAX_ELO. Small inaccuracies
+     * are thus frequent, and big blunders, like giving away a corner, rare until the rating
+     * gets low. Moves losing by force are never chosen over anything better.
+     */
+    fn pick_move(&self, results: &[(i32, BitBoard)], color: Color, elo: u16) -> (i32, BitBoard) {
+        let own = |value: i32| -> i64 {
+            match color {
+                Color::Black => value as i64,
+                Color::White => -(value as i64),
+            }
+        };
+
+        let best: i64 = results.iter().map(|&(value, _)| own(value)).max().unwrap();
+        let temperature: f64 = temperature(elo);
+        let weights: Vec<f64> = results.iter().map(|&(value, _)| {
+            if temperature == 0.0 || own(value) <= -(PROVEN as i64) {
+                if own(value) == best { 1.0 } else { 0.0 }
+            } else {
+                (-((best - own(value)) as f64) / temperature).exp()
+            }
+        }).collect();
+
+        let mut rng: u64 = self.rng.get();
+        let mut target: f64 = next_random(&mut rng) as f64 / u64::MAX as f64
+            * weights.iter().sum::<f64>();
+        self.rng.set(rng);
+
+        for (&result, &weight) in results.iter().zip(&weights) {
+            if target < weight {
+                return result;
+            }
+            target -= weight;
+        }
+
+        *results.iter().max_by_key(|&&(value, _)| own(value)).unwrap()
+    }
+}
+
+impl Player for AlphaBetaPlayer {
+    /*
+     * Searches every possible move and selects the best one, according to the color of the
+     * player, or a worse one from time to time if it has a target rating. Resigns instead if
+     * the position has been hopeless for too long.
+     */
+    fn choose_move(&mut self, game: &Game, clock: &Clock) -> Action {
+        let (oth, color) = (game.board(), game.color());
+        let moves: BitBoard = self.playable_moves(oth, color);
+        if let Some(model) = &mut self.opponent_model {
+            model.before_move(game, clock, color);
+        }
+        let booked = self.book.as_ref().and_then(|book| {
+            let mut booked: Vec<(BitBoard, BookEntry)> = book.moves(oth, color);
+            booked.retain(|&(mv, _)| mv & moves != 0);
+            let candidates: Vec<(i32, BitBoard)> = booked.iter()
+                .map(|&(mv, entry)| (entry.score, mv))
+                .collect();
+            let chosen: Option<BitBoard> = match self.book_variety {
+                Some((margin, temperature)) => {
+                    self.pick_book_move(&candidates, margin, temperature)
+                },
+                None => self.tie_break(oth, color, &candidates),
+            };
+            booked.into_iter().find(|&(mv, _)| Some(mv) == chosen)
+        });
+        let perfect = self.tablebase.as_ref()
+            .and_then(|tablebase| tablebase.best_move(oth, color))
+            .filter(|&(mv, _)| mv & moves != 0 || moves == 0);
+        let (best, val, stats) = if let Some((mv, entry)) = booked {
+            let value: i32 = if color == Color::Black { entry.score } else { -entry.score };
+            (mv, value, SearchStats::default())
+        } else if let Some((mv, score)) = perfect {
+            (mv, self.exact_value(score, color), SearchStats::default())
+        } else if moves == 0 {
+            self.best_move(oth, color)
+        } else {
+            let budget = self.time_manager
+                .and_then(|manager| Some((manager, manager.budget(clock, color, oth.empties())?)));
+            let (results, stats) = match budget {
+                Some(timing) => {
+                    let depth: u8 = self.personality.depth(self.max_depth);
+                    self.search_deepening(oth, moves, color, depth, Some(timing))
+                },
+                None => self.search_moves(oth, moves, color),
+            };
+            let (value, mv) = best_result(&results, color);
+            self.journal(oth, color, mv, value, stats);
+            let (val, best) = match self.target_elo {
+                Some(elo) => self.pick_move(&results, color, elo),
+                None => {
+                    let candidates: Vec<(i32, BitBoard)> = results.iter()
+                        .map(|&(res, mv)| match color {
+                            Color::Black => (res, mv),
+                            Color::White => (res.saturating_neg(), mv),
+                        })
+                        .collect();
+                    let chosen: Option<BitBoard> = self.tie_break(oth, color, &candidates);
+                    results.iter().copied().find(|&(_, mv)| Some(mv) == chosen)
+                        .unwrap_or_else(|| best_result(&results, color))
+                },
+            };
+            (best, val, stats)
+        };
+        self.last_stats.set(stats);
+
+        let value: i32 = match color {
+            Color::Black => val,
+            Color::White => val.saturating_neg(),
+        };
+        self.last_value.set(value);
+        if let Some(model) = &mut self.opponent_model {
+            model.after_move(game, clock, color, best, value);
+        }
+
+        if let Some((threshold, moves)) = self.resign {
+            if value < -threshold {
+                self.streak.set(self.streak.get().saturating_add(1));
+            } else {
+                self.streak.set(0);
+            }
+            if self.streak.get() >= moves {
+                return Action::Resign;
+            }
+        }
+
+        Action::Play(best)
+    }
+
+    /*
+     * Accepts a draw whenever the AI thought it was worse on its last move, unless its
+     * personality never does.
+     */
+    fn accept_draw(&mut self, _game: &Game, _color: Color) -> bool {
+        self.personality.accepts_draws() && self.last_value.get() < 0
+    }
+
+    /*
+     * Returns the evaluation of the last chosen move, from the AI's point of view.
+     */
+    fn evaluation(&self) -> Option<i32> {
+        Some(self.last_value.get())
+    }
+
+    /*
+     * Returns the depth and nodes of the search of the last chosen move, None when it came
+     * from the book or the tablebase without a search.
+     */
+    fn search_stats(&self) -> Option<(u8, u64)> {
+        let stats: SearchStats = self.last_stats.get();
+        (stats.nodes > 0).then_some((stats.depth, stats.nodes))
+    }
+
+    /*
+     * Names the AI after its depth, and its personality unless it is balanced.
+     */
+    fn name(&self) -> String {
+        match self.personality {
+            Personality::Balanced => format!("AlphaBeta {}", self.max_depth),
+            personality => format!("AlphaBeta {} {}", self.max_depth, personality.name()),
+        }
+    }
+}
+
+//#################################################################################################
+//
+//                                     ALPHABETA TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that wins are valued by their distance and written as such, that searching a
+ * position twice gives the same search, that principal variations show the passes in them,
+ * that the time manager gives the midgame more time per move than the opening and the endgame,
+ * that book moves are varied within their margin, that the pass search keeps proven values
+ * and saves nodes, that late moves are reduced more the deeper and the later they are without
+ * changing the best moves, that drawn games are valued by the contempt of the AI, that easy
+ * moves are played before the end of their time share, that weaker target ratings play worse
+ * moves, that excluded moves are neither searched nor suggested, that the statistics of a
+ * search are reproducible, that the personalities evaluate and play differently, and that the
+ * AI resigns after enough bad moves.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsuite::*;
+
+    #[test]
+    fn proven_values() {
+        // Black fills the board with h8 and wins, which white can't prevent when moving first.
+        let oth: Othello = Othello::create((1 << 61) - 1, 0b11 << 61);
+        let player: AlphaBetaPlayer = AlphaBetaPlayer::new(4);
+        let (mv, value, _) = player.best_move(oth, Color::Black);
+        assert_eq!((mv, Value::from_search(value)), (1 << 63, Value::Win(1)));
+        let (mv, value, _) = player.best_move(oth, Color::White);
+        assert_eq!((mv, Value::from_search(-value)), (0, Value::Loss(2)));
+
+        for value in [Value::Heuristic(-12), Value::Win(7), Value::Loss(0)] {
+            assert_eq!(Value::from_search(value.to_search()), value);
+        }
+        assert_eq!(Value::from_search(Value::Exact(-6).to_search()), Value::Loss(0));
+        let values: [Value; 4] =
+            [Value::Heuristic(12), Value::Exact(6), Value::Win(7), Value::Win(0)];
+        let names: Vec<String> = values.iter().map(Value::to_string).collect();
+        assert_eq!(names, ["+12", "+6 exact", "win in 7", "win"]);
+    }
+
+    #[test]
+    fn determinism() {
+        let mut suite: Vec<(Othello, Color)> = positions().iter().take(3)
+            .map(|position| (position.oth, position.color))
+            .collect();
+        suite.push((Othello::new(), Color::Black));
+        assert!(AlphaBetaPlayer::new(6).audit_determinism(&suite).unwrap() > 0);
+        let positional: AlphaBetaPlayer =
+            AlphaBetaPlayer::new(5).with_personality(Personality::Positional);
+        assert!(positional.audit_determinism(&suite).is_ok());
+    }
+
+    #[test]
+    fn pass_in_pv() {
+        // Black has to pass after h6.
+        let game: Game = Game::from_line("f5f6f7g7c4f8h8").unwrap();
+        let player: AlphaBetaPlayer = AlphaBetaPlayer::new(3).with_search_moves(1 << 47);
+        let pv: Vec<BitBoard> = player.iterations(game.board(), game.color()).pop().unwrap().pv;
+        assert!(pv[..2] == [1 << 47, 0] && format_pv(&pv).starts_with("h6 PA "));
+        let mut replayed: Game = game.clone();
+        replayed.apply_pv(&pv).unwrap();
+        assert_eq!(replayed.history().len(), game.history().len() + pv.len());
+    }
+
+    #[test]
+    fn pass_search() {
+        let mut proven: usize = 0;
+        for position in positions().iter().take(10) {
+            let (oth, color) = (position.oth, position.color);
+            let depth: u8 = oth.empties() + 2;
+            let (_, value, stats) =
+                AlphaBetaPlayer::new(depth).with_pass_pruning(false).best_move(oth, color);
+            let (_, pruned, pruned_stats) = AlphaBetaPlayer::new(depth).best_move(oth, color);
+            assert_eq!(value, pruned);
+            assert!(pruned_stats.nodes <= stats.nodes);
+            proven += Value::from_search(value).is_proven() as usize;
+        }
+        assert!(proven > 5);
+
+        let mut saved: bool = false;
+        for position in positions().iter().skip(20).step_by(10).take(5) {
+            let (oth, color) = (position.oth, position.color);
+            let unpruned: AlphaBetaPlayer = AlphaBetaPlayer::new(6).with_pass_pruning(false);
+            let (_, _, stats) = unpruned.best_move(oth, color);
+            let (_, _, pruned) = AlphaBetaPlayer::new(6).best_move(oth, color);
+            assert!(pruned.nodes <= stats.nodes);
+            saved |= pruned.nodes < stats.nodes;
+        }
+        assert!(saved);
+    }
+
+    #[test]
+    fn reductions() {
+        let table: ReductionTable = ReductionTable::new(SearchParams::default());
+        assert!(table.get(2, 10) == 0 && table.get(3, 3) == 0 && table.get(3, 4) == 1);
+        assert!(table.get(20, 31) > table.get(20, 4) && table.get(20, 8) > table.get(8, 8));
+        assert!((1..80).all(|depth| (0..40).all(|index| {
+            table.get(depth, index) <= table.get(depth, index + 1)
+                && table.get(depth, index) <= table.get(depth + 1, index)
+                && table.get(depth, index) < depth.max(2)
+        })));
+
+        let (mut nodes, mut reduced_nodes, mut researches): (u64, u64, u64) = (0, 0, 0);
+        for position in positions().iter().step_by(7) {
+            let (oth, color) = (position.oth, position.color);
+            let full: AlphaBetaPlayer = AlphaBetaPlayer::new(5).with_reductions(false);
+            let (mv, value, stats) = full.best_move(oth, color);
+            let (reduced_mv, reduced_value, reduced) =
+                AlphaBetaPlayer::new(5).best_move(oth, color);
+            assert_eq!(mv, reduced_mv);
+            assert!(oth.empties() > 12 || value == reduced_value);
+            assert_eq!(stats.researches, 0);
+            (nodes, reduced_nodes) = (nodes + stats.nodes, reduced_nodes + reduced.nodes);
+            researches += reduced.researches;
+        }
+        assert!(reduced_nodes < nodes && researches > 0);
+    }
+
+    #[test]
+    fn easy_move() {
+        // The best move of #65 stays ahead of the others by more than 100 from the first ply,
+        // while those of #41 change.
+        let clock: Clock = Clock::new(Duration::from_secs(600), Duration::ZERO);
+        let depth = |manager: TimeManager, position: usize| -> u8 {
+            let position: &TestPosition = &positions()[position];
+            let game: Game = Game::from_position(position.oth, position.color);
+            let mut player: AlphaBetaPlayer = AlphaBetaPlayer::new(6).with_time_manager(manager);
+            player.choose_move(&game, &clock);
+            player.last_stats().depth
+        };
+        let easy: TimeManager = TimeManager::new().with_easy_move(2, 100);
+        assert_eq!(depth(easy, 64), 2);
+        assert_eq!(depth(TimeManager::new(), 64), 6);
+        assert_eq!(depth(easy, 40), 6);
+    }
+
+    #[test]
+    fn target_elo() {
+        // Values are from black's point of view: a1 is the best move for black, c1 a worse one
+        // and b1 loses by force, while for white, b1 wins by force.
+        let results: [(i32, BitBoard); 3] = [(120, 1), (-WIN, 2), (60, 4)];
+        let picks = |results: &[(i32, BitBoard)], elo: u16, color: Color| -> Vec<BitBoard> {
+            (1..=50u64).map(|seed| {
+                let player: AlphaBetaPlayer = AlphaBetaPlayer::new(1).with_target_elo(elo);
+                player.rng.set(seed.wrapping_mul(0x9E3779B97F4A7C15));
+                player.pick_move(results, color, elo).1
+            }).collect()
+        };
+        assert!(picks(&results, MAX_ELO, Color::Black).iter().all(|&mv| mv == 1));
+        assert!(picks(&results, MAX_ELO + 100, Color::Black).iter().all(|&mv| mv == 1));
+        let weak: Vec<BitBoard> = picks(&results, 1400, Color::Black);
+        assert!(weak.contains(&1) && weak.contains(&4) && !weak.contains(&2));
+        assert!(picks(&results, 1900, Color::Black).iter().filter(|&&mv| mv == 1).count()
+            > weak.iter().filter(|&&mv| mv == 1).count());
+        assert!(picks(&results, 1400, Color::White).iter().all(|&mv| mv == 2));
+
+        let white: [(i32, BitBoard); 2] = [(-120, 1), (-60, 4)];
+        assert!(picks(&white, MAX_ELO, Color::White).iter().all(|&mv| mv == 1));
+        assert!(picks(&white, 1400, Color::White).contains(&4));
+    }
+
+    #[test]
+    fn excluded_moves() {
+        let game: Game = Game::from_line("f5d6c3d3c4").unwrap();
+        let (oth, color) = (game.board(), game.color());
+        let (best, _, _) = AlphaBetaPlayer::new(4).best_move(oth, color);
+        let mut excluding: AlphaBetaPlayer = AlphaBetaPlayer::new(4).with_excluded_moves(best);
+        let (mv, _, _) = excluding.best_move(oth, color);
+        assert!(mv != best && mv & oth.gen_moves(color) != 0);
+        let hints: Vec<Hint> = excluding.hint(oth, color, 10);
+        assert!(hints.iter().all(|hint| hint.mv != best));
+        assert_eq!(hints.len(), oth.gen_moves(color).count_ones() as usize - 1);
+        let alternative: Option<Hint> = excluding.best_alternative(oth, color, mv);
+        assert!(alternative.is_some_and(|hint| hint.mv != best && hint.mv != mv));
+        assert!(excluding.choose_move(&game, &Clock::unlimited()) != Action::Play(best));
+
+        let mut excluding: AlphaBetaPlayer =
+            AlphaBetaPlayer::new(4).with_excluded_moves(oth.gen_moves(color));
+        assert!(excluding.hint(oth, color, 3).is_empty());
+        assert!(excluding.best_alternative(oth, color, best).is_none());
+        match excluding.choose_move(&game, &Clock::unlimited()) {
+            Action::Play(mv) => assert!(mv & oth.gen_moves(color) != 0),
+            action => panic!("Unexpected action {:?}.", action),
+        }
+        excluding.best_move(oth, color);
+    }
+
+    #[test]
+    fn search_stats() {
+        for position in positions().iter().step_by(25) {
+            let (oth, color) = (position.oth, position.color);
+            let player: AlphaBetaPlayer = AlphaBetaPlayer::new(5);
+            let (mv, value, stats) = player.best_move(oth, color);
+            let (replayed_mv, replayed_value, replayed) = player.best_move(oth, color);
+            assert!(stats.nodes > 0 && stats.cutoffs > 0);
+            assert_eq!((mv, value, stats.nodes), (replayed_mv, replayed_value, replayed.nodes));
+            assert_eq!((stats.cutoffs, stats.researches), (replayed.cutoffs, replayed.researches));
+            assert_eq!(stats.depth, 5);
+            assert!(stats.max_depth_reached >= 5);
+            assert_eq!(stats.tt_hits, 0);
+        }
+
+        let mut player: AlphaBetaPlayer = AlphaBetaPlayer::new(3);
+        player.choose_move(&Game::new(), &Clock::unlimited());
+        assert_eq!(player.last_stats().depth, 3);
+        assert!(player.last_stats().nodes > 0);
+    }
+
+    #[test]
+    fn personalities() {
+        let suite: &[TestPosition] = &positions()[..40];
+        let best = |personality: Personality, position: &TestPosition| -> BitBoard {
+            let player: AlphaBetaPlayer = AlphaBetaPlayer::new(4).with_personality(personality);
+            player.best_move(position.oth, position.color).0
+        };
+        let balanced: EvalWeights = Personality::Balanced.weights();
+        for personality in [Personality::Aggressive, Personality::Greedy, Personality::Positional] {
+            let weights: EvalWeights = personality.weights();
+            assert!(suite.iter().any(|p| weights.evaluate(p.oth) != balanced.evaluate(p.oth)));
+            assert!(suite.iter().step_by(4)
+                .any(|p| best(personality, p) != best(Personality::Balanced, p)));
+            assert_eq!(Personality::from_name(&personality.name().to_uppercase()),
+                Some(personality));
+        }
+        assert_eq!(Personality::Greedy.depth(6), 3);
+        assert!(!Personality::Aggressive.accepts_draws() && Personality::Greedy.accepts_draws());
+    }
+
+    #[test]
+    fn contempt() {
+        // White draws by playing g4 or a8.
+        let position: &TestPosition = &positions()[12];
+        assert_eq!((position.score, position.color), (0, Color::White));
+        let (oth, depth): (Othello, u8) = (position.oth, position.oth.empties() + 2);
+        let replied: Othello = oth.make_move(Color::White, 1 << 30);
+        let value = |contempt: i32, oth: Othello, color: Color| -> i32 {
+            AlphaBetaPlayer::new(depth).with_contempt(contempt).best_move(oth, color).1
+        };
+
+        // Values are from black's point of view: the AI sees the draw at -contempt and its
+        // opponent at +contempt.
+        assert_eq!(value(0, oth, Color::White), 0);
+        assert_eq!(value(30, oth, Color::White), 30);
+        assert_eq!(value(-30, oth, Color::White), -30);
+        assert_eq!(value(0, replied, Color::Black), 0);
+        assert_eq!(value(30, replied, Color::Black), -30);
+    }
+
+    #[test]
+    fn resign() {
+        // Black loses by 36 disks.
+        let game: Game = Game::from_position(positions()[5].oth, Color::Black);
+        let actions = |player: &mut AlphaBetaPlayer| -> Vec<bool> {
+            (0..3).map(|_| player.choose_move(&game, &Clock::unlimited()) == Action::Resign)
+                .collect()
+        };
+        assert_eq!(actions(&mut AlphaBetaPlayer::new(2).with_resign(1, 2)), [false, true, true]);
+        assert_eq!(actions(&mut AlphaBetaPlayer::new(2).with_resign(1, 1)), [true, true, true]);
+        assert_eq!(actions(&mut AlphaBetaPlayer::new(2).with_resign(i32::MAX, 1)), [false; 3]);
+        assert_eq!(actions(&mut AlphaBetaPlayer::new(2).with_resign(0, 1)), [false; 3]);
+        assert_eq!(actions(&mut AlphaBetaPlayer::new(2).with_resign(1, 0)), [false; 3]);
+    }
+
+    #[test]
+    fn book_variety() {
+        let game: Game = Game::from_line("f5d6").unwrap();
+        let (oth, color) = (game.board(), game.color());
+        let mut moves: BitBoard = oth.gen_moves(color);
+        let mut entries: Vec<(Othello, Color, BookEntry)> = Vec::new();
+        while moves != 0 {
+            let entry: BookEntry = BookEntry { score: 4 * entries.len() as i32 - 20, depth: 10, };
+            entries.push((oth.make_move(color, moves.pop_lsb()), color.invert(), entry));
+        }
+        let book: Arc<Book> = Arc::new(Book::from_entries(entries));
+        let booked: Vec<(BitBoard, BookEntry)> = book.moves(oth, color);
+        let best: i32 = booked[0].1.score;
+        let close: BitBoard = booked.iter()
+            .filter(|&&(_, entry)| entry.score >= best - 8)
+            .fold(0, |close, &(mv, _)| close | mv);
+        assert!(close.count_ones() > 1);
+
+        let played = |player: &mut AlphaBetaPlayer| -> BitBoard {
+            (0..40).fold(0, |played, _| match player.choose_move(&game, &Clock::unlimited()) {
+                Action::Play(mv) => played | mv,
+                action => panic!("Unexpected action {:?}.", action),
+            })
+        };
+        let mut varied: AlphaBetaPlayer = AlphaBetaPlayer::new(2)
+            .with_book(Arc::clone(&book))
+            .with_book_variety(8, f64::INFINITY);
+        assert_eq!(played(&mut varied), close);
+        let mut greedy: AlphaBetaPlayer =
+            AlphaBetaPlayer::new(2).with_book(Arc::clone(&book)).with_book_variety(8, 0.0);
+        assert_eq!(played(&mut greedy), booked[0].0);
+        let mut inverted: AlphaBetaPlayer =
+            AlphaBetaPlayer::new(2).with_book(Arc::clone(&book)).with_book_variety(8, -4.0);
+        assert_eq!(played(&mut inverted), booked[0].0);
+        assert_eq!(played(&mut AlphaBetaPlayer::new(2).with_book(book)), booked[0].0);
+    }
+
+    #[test]
+    fn time_shares() {
+        let clock: Clock = Clock::new(Duration::from_secs(90), Duration::ZERO);
+        let manager: TimeManager = TimeManager::new();
+        let budget = |manager: TimeManager, empties: u8| {
+            manager.budget(&clock, Color::Black, empties).unwrap()
+        };
+        assert!(budget(manager, 30) > budget(manager, 60) * 4);
+        let uniform: TimeManager = manager.with_phase_weights([100; 4]);
+        assert!(budget(uniform, 60).abs_diff(Duration::from_secs(3)) < Duration::from_millis(1));
+
+        // With a third of the clock left for a third of the moves, uniform weights give the
+        // endgame the same share as the pre-endgame, and the default ones far less.
+        let endgame: Clock = Clock::new(Duration::from_secs(30), Duration::ZERO);
+        let late = |manager: TimeManager| manager.budget(&endgame, Color::Black, 10).unwrap();
+        assert!(late(uniform).abs_diff(budget(uniform, 30)) < Duration::from_millis(1));
+        assert!(late(manager) * 3 < budget(manager, 30) * 2);
+        assert_eq!(manager.budget(&Clock::unlimited(), Color::Black, 30), None);
     }
 }
@@ -0,0 +1,1311 @@
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+#[cfg(feature = "spectate")]
+use std::sync::Arc;
+
+use crate::types::*;
+use crate::othello::*;
+use crate::game::*;
+use crate::player::*;
+use crate::record::*;
+use crate::endgame::*;
+use crate::stats::*;
+use crate::error::*;
+#[cfg(feature = "spectate")]
+use crate::spectate::*;
+
+//#################################################################################################
+//
+//                                     STANDINGS TYPE
+//
+//#################################################################################################
+
+/*
+ * The games a player forfeited, by choosing an illegal move or by running out of time.
+ */
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Forfeits {
+    pub illegal_moves: u32,
+    pub timeouts: u32,
+}
+
+impl Forfeits {
+    /*
+     * Returns the total number of games forfeited.
+     */
+    pub fn total(&self) -> u32 {
+        self.illegal_moves + self.timeouts
+    }
+}
+
+/*
+ * The standings of a head-to-head match, from the point of view of the first player, with the
+ * games forfeited by each player, counted among the losses of the first player and his wins,
+ * and the games repeating an earlier one of the match move for move, counted among the others
+ * unless the arena discards them.
+ */
+#[derive(Clone, Copy, Default)]
+pub struct Standings {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    pub forfeits: Forfeits,
+    pub opponent_forfeits: Forfeits,
+    pub duplicates: u32,
+}
+
+impl Standings {
+    /*
+     * Returns the score of the first player, a win counting 1 and a draw half, over the number
+     * of games, 0.5 before any.
+     */
+    pub fn score(&self) -> f64 {
+        let games: u32 = self.wins + self.draws + self.losses;
+        if games == 0 {
+            0.5
+        } else {
+            (self.wins as f64 + self.draws as f64 / 2.0) / games as f64
+        }
+    }
+
+    /*
+     * Returns the Elo difference between the first player and the second measured by the
+     * games, with its error bars and the likelihood of superiority of the first player.
+     */
+    pub fn elo(&self) -> EloEstimate {
+        EloEstimate::new(self.wins, self.draws, self.losses)
+    }
+
+    /*
+     * Counts the outcome of a finished game in which the first player had the given color.
+     */
+    fn record(&mut self, outcome: Outcome, first: Color) {
+        let forfeits: &mut Forfeits = match outcome.winner {
+            Some(color) if color == first => {
+                self.wins += 1;
+                &mut self.opponent_forfeits
+            },
+            Some(_) => {
+                self.losses += 1;
+                &mut self.forfeits
+            },
+            None => {
+                self.draws += 1;
+                return;
+            },
+        };
+
+        match outcome.reason {
+            Reason::IllegalMove => forfeits.illegal_moves += 1,
+            Reason::Timeout => forfeits.timeouts += 1,
+            _ => (),
+        }
+    }
+}
+
+//#################################################################################################
+//
+//                                       RULES TYPE
+//
+//#################################################################################################
+
+/*
+ * A custom adjudicator, returning the winner of the game if it should end.
+ */
+pub type Adjudicator = fn(&Game) -> Option<Option<Color>>;
+
+/*
+ * The most empty squares the solver adjudicates games from. Beyond it, solving a position
+ * between two moves can take longer than playing the rest of the game.
+ */
+pub const MAX_SOLVE_EMPTIES: u8 = 16;
+
+/*
+ * The rules of a match, deciding when its game can be adjudicated early: either as soon as the
+ * endgame solver can prove the result, once both players have agreed on a large score for a
+ * given number of consecutive moves, or whenever a custom adjudicator, called after each move,
+ * returns the winner, Some(None) standing for a draw. They also give the handicap of Black, the
+ * number of corners he starts with.
+ */
+#[derive(Clone, Copy, Default)]
+pub struct Rules {
+    handicap: u8,
+    solve_empties: u8,
+    agreement: Option<(i32, u8)>,
+    adjudicator: Option<Adjudicator>,
+}
+
+impl Rules {
+    /*
+     * Creates new Rules, without adjudication.
+     */
+    pub fn new() -> Rules {
+        Rules::default()
+    }
+
+    /*
+     * Gives Black the given number of corners, up to 4, before the first move, see
+     * Othello::with_handicap.
+     */
+    pub fn with_handicap(mut self, corners: u8) -> Rules {
+        self.handicap = corners;
+        self
+    }
+
+    /*
+     * Adjudicates games with the endgame solver once there are at most the given number of
+     * empty squares left on the board, up to MAX_SOLVE_EMPTIES.
+     */
+    pub fn with_solver(mut self, empties: u8) -> Rules {
+        self.solve_empties = empties.min(MAX_SOLVE_EMPTIES);
+        self
+    }
+
+    /*
+     * Adjudicates games once both players' evaluations have favored the same side by at least
+     * the given threshold for the given number of consecutive moves. A threshold or a number of
+     * moves that isn't positive disables the adjudication by agreement.
+     */
+    pub fn with_agreement(mut self, threshold: i32, moves: u8) -> Rules {
+        self.agreement = Some((threshold, moves)).filter(|_| threshold > 0 && moves > 0);
+        self
+    }
+
+    /*
+     * Adjudicates games with the given function, called after each move.
+     */
+    pub fn with_adjudicator(mut self, adjudicator: Adjudicator) -> Rules {
+        self.adjudicator = Some(adjudicator);
+        self
+    }
+}
+
+/*
+ * The time control of a match: either unlimited, or a base time per player, plus an increment
+ * added after each of his moves.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TimeControl {
+    Unlimited,
+    Increment { base: Duration, increment: Duration },
+}
+
+impl TimeControl {
+    /*
+     * Returns the clock of a game starting with this time control.
+     */
+    pub fn clock(&self) -> Clock {
+        match *self {
+            TimeControl::Unlimited => Clock::unlimited(),
+            TimeControl::Increment { base, increment, } => Clock::new(base, increment),
+        }
+    }
+}
+
+//#################################################################################################
+//
+//                                       MATCH TYPE
+//
+//#################################################################################################
+
+/*
+ * A game between two players, played under the given rules and time control. It can be played
+ * one step at a time, for GUIs showing the board between the moves, or in one go, returning
+ * its record.
+ *
+ * The evaluations of the players can be recorded after each ply, so that GUIs can draw the
+ * advantage graph under the board. The stats of every move, the time it took, the depth and
+ * nodes of its search and the evaluation of its player, are always recorded, and written in
+ * its record.
+ *
+ * A player choosing an illegal move, or running out of time, forfeits the game, its outcome
+ * telling why, see Reason. With the spectate feature, the state of the game can be broadcast to
+ * spectators after each step.
+ */
+pub struct Match {
+    rules: Rules,
+    game: Game,
+    clock: Clock,
+    values: [Option<i32>; 2],
+    streak: Option<(Color, u8)>,
+    record_evaluations: bool,
+    evaluations: Vec<Option<i32>>,
+    stats: Vec<MoveStats>,
+    #[cfg(feature = "spectate")]
+    spectators: Option<Arc<Spectators>>,
+}
+
+impl Match {
+    /*
+     * Creates a new Match, in the starting position with the handicap of the rules.
+     */
+    pub fn new(rules: Rules, time_control: TimeControl) -> Match {
+        Match {
+            rules,
+            game: Game::with_handicap(rules.handicap),
+            clock: time_control.clock(),
+            values: [None, None],
+            streak: None,
+            record_evaluations: false,
+            evaluations: Vec::new(),
+            stats: Vec::new(),
+            #[cfg(feature = "spectate")]
+            spectators: None,
+        }
+    }
+
+    /*
+     * Starts the match from the given game, an opening for example, rather than from the
+     * starting position. The moves already played have no stats.
+     */
+    pub fn with_game(mut self, game: Game) -> Match {
+        self.stats = vec![MoveStats::default(); game.history().len()];
+        self.game = game;
+        self
+    }
+
+    /*
+     * Records the evaluation of the players after each ply.
+     */
+    pub fn with_evaluations(mut self) -> Match {
+        self.record_evaluations = true;
+        self
+    }
+
+    /*
+     * Broadcasts the state of the game to the given spectators after each step, see
+     * state_json.
+     */
+    #[cfg(feature = "spectate")]
+    pub fn with_spectators(mut self, spectators: Arc<Spectators>) -> Match {
+        self.spectators = Some(spectators);
+        self
+    }
+
+    /*
+     * Returns the game played so far.
+     */
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /*
+     * Returns the clock of the game.
+     */
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /*
+     * Returns the recorded evaluations, one per ply of the history of the game, from black's
+     * point of view. An evaluation is None for passes and for the moves of players without an
+     * evaluation. The series is empty if evaluations aren't recorded.
+     */
+    pub fn evaluations(&self) -> &[Option<i32>] {
+        &self.evaluations
+    }
+
+    /*
+     * Returns the stats of the moves, one per ply of the history of the game, those of passes
+     * and of moves played before the match started being unknown.
+     */
+    pub fn stats(&self) -> &[MoveStats] {
+        &self.stats
+    }
+
+    /*
+     * Consumes the match and returns its game.
+     */
+    pub fn into_game(self) -> Game {
+        self.game
+    }
+
+    /*
+     * Plays one move, forced passes, resignations, draw offers and forfeits included, then
+     * adjudicates the game if the rules allow it. Does nothing if the game is over.
+     */
+    pub fn step(
+        &mut self,
+        black: &mut dyn Player,
+        white: &mut dyn Player,
+    ) -> Result<(), OthelloError> {
+        if self.game.is_over() {
+            return Ok(());
+        }
+
+        #[cfg(feature = "spectate")]
+        let color: Color = self.game.color();
+        self.advance(black, white)?;
+
+        // The evaluation sent is that of the player who just moved.
+        #[cfg(feature = "spectate")]
+        if let Some(spectators) = &self.spectators {
+            let value: Option<i32> = self.values[color as usize];
+            let (black, white) = (black.name(), white.name());
+            spectators.broadcast(&state_json(&self.game, &self.clock, &black, &white, value));
+        }
+
+        Ok(())
+    }
+
+    /*
+     * Does the work of step, the game not being over.
+     */
+    fn advance(
+        &mut self,
+        black: &mut dyn Player,
+        white: &mut dyn Player,
+    ) -> Result<(), OthelloError> {
+        if self.game.board().empties() <= self.rules.solve_empties {
+            self.game.adjudicate(solve_winner(self.game.board(), self.game.color()));
+            return Ok(());
+        }
+
+        let color: Color = self.game.color();
+        let plies: usize = self.game.history().len();
+        let start: Instant = Instant::now();
+        match self.game.step(black, white, &mut self.clock) {
+            Err(OthelloError::IllegalMove(_)) => {
+                self.game.forfeit(color, Reason::IllegalMove);
+                return Ok(());
+            },
+            result => result?,
+        }
+
+        // Evaluations are stored from black's point of view.
+        self.values[color as usize] = match color {
+            Color::Black => black.evaluation(),
+            Color::White => white.evaluation().map(i32::saturating_neg),
+        };
+
+        let player: &dyn Player = if color == Color::Black { black } else { white };
+        let search: Option<(u8, u64)> = player.search_stats();
+        let stats: MoveStats = MoveStats {
+            time: Some(start.elapsed()),
+            depth: search.map(|(depth, _)| depth),
+            nodes: search.map(|(_, nodes)| nodes),
+            score: player.evaluation(),
+        };
+
+        // The first new ply is the move, the others forced passes.
+        for ply in plies..self.game.history().len() {
+            if self.record_evaluations {
+                self.evaluations.push(self.values[color as usize].filter(|_| ply == plies));
+            }
+            self.stats.push(if ply == plies { stats } else { MoveStats::default() });
+        }
+
+        if let Some((threshold, moves)) = self.rules.agreement {
+            let favored: Option<Color> = match self.values {
+                [Some(b), Some(w)] if b >= threshold && w >= threshold => Some(Color::Black),
+                [Some(b), Some(w)] if b <= -threshold && w <= -threshold => Some(Color::White),
+                _ => None,
+            };
+            // The streak starts over when the players agree on the other side.
+            self.streak = favored.map(|side| match self.streak {
+                Some((streak_side, streak)) if streak_side == side => (side, streak + 1),
+                _ => (side, 1),
+            });
+
+            if let Some((winner, streak)) = self.streak {
+                if streak >= moves {
+                    self.game.adjudicate(Some(winner));
+                }
+            }
+        }
+
+        if let Some(adjudicator) = self.rules.adjudicator {
+            if let Some(winner) = adjudicator(&self.game) {
+                self.game.adjudicate(winner);
+            }
+        }
+
+        Ok(())
+    }
+
+    /*
+     * Plays a whole game between the two players and returns its record, dated of today.
+     */
+    pub fn play(
+        mut black: impl Player,
+        mut white: impl Player,
+        rules: Rules,
+        time_control: TimeControl,
+    ) -> Result<GameRecord, OthelloError> {
+        let mut current: Match = Match::new(rules, time_control);
+        while !current.game().is_over() {
+            current.step(&mut black, &mut white)?;
+        }
+
+        let record: GameRecord =
+            GameRecord::from_game(current.game(), &black.name(), &white.name(), &today());
+        Ok(record.with_stats(current.stats().to_vec()))
+    }
+}
+
+//#################################################################################################
+//
+//                                       ARENA TYPE
+//
+//#################################################################################################
+
+/*
+ * The tournament runner, playing series of games between two players, alternating colors, with
+ * unlimited time unless given a time control. Games can be adjudicated early, and forfeited,
+ * as in matches. The records of the games played can be checkpointed to a file after each
+ * game, so that a long run stopped by a crash can be resumed. Beyond head-to-head matches, it
+ * plays gauntlets and round-robins between more players, see Schedule.
+ */
+#[derive(Clone)]
+pub struct Arena {
+    games: u32,
+    rules: Rules,
+    time_control: TimeControl,
+    opening_plies: usize,
+    seed: u64,
+    openings: Vec<Game>,
+    checkpoint: Option<(PathBuf, bool)>,
+    discard_duplicates: bool,
+    #[cfg(feature = "spectate")]
+    spectators: Option<Arc<Spectators>>,
+}
+
+impl Arena {
+    /*
+     * Creates a new Arena playing the given number of games per match, without adjudication.
+     */
+    pub fn new(games: u32) -> Arena {
+        Arena {
+            games,
+            rules: Rules::new(),
+            time_control: TimeControl::Unlimited,
+            opening_plies: 0,
+            seed: 0,
+            openings: Vec::new(),
+            checkpoint: None,
+            discard_duplicates: false,
+            #[cfg(feature = "spectate")]
+            spectators: None,
+        }
+    }
+
+    /*
+     * Starts each pair of games from an opening of the given number of random plies, drawn
+     * with the given seed, each player having both sides of it. Deterministic players would
+     * otherwise play the same two games over and over.
+     */
+    pub fn with_random_openings(mut self, plies: usize, seed: u64) -> Arena {
+        self.opening_plies = plies;
+        self.seed = seed;
+        self
+    }
+
+    /*
+     * Starts each pair of games from an opening of the suite, in turn, each player having both
+     * sides of it, rather than from random plies. See parse_openings and balanced_openings.
+     */
+    pub fn with_openings(mut self, openings: Vec<Game>) -> Arena {
+        self.openings = openings;
+        self
+    }
+
+    /*
+     * Appends the record of each game to the given file as soon as it is over, as a line of
+     * JSON. With resume, the games already in the file count as played, and the match goes on
+     * from the next one, the file having to be the checkpoint of a match between the same
+     * players. Otherwise the file is started over.
+     */
+    pub fn with_checkpoint(mut self, path: impl Into<PathBuf>, resume: bool) -> Arena {
+        self.checkpoint = Some((path.into(), resume));
+        self
+    }
+
+    /*
+     * Leaves the games repeating an earlier one of the match out of the standings, so that
+     * deterministic players replaying the same games don't weigh them several times. They are
+     * still counted as duplicates, and recorded.
+     */
+    pub fn with_duplicates_discarded(mut self, discard: bool) -> Arena {
+        self.discard_duplicates = discard;
+        self
+    }
+
+    /*
+     * Plays every game with the given time control, the players losing on time forfeiting.
+     */
+    pub fn with_time_control(mut self, time_control: TimeControl) -> Arena {
+        self.time_control = time_control;
+        self
+    }
+
+    /*
+     * Broadcasts the state of every game to the given spectators after each move, see
+     * Match::with_spectators.
+     */
+    #[cfg(feature = "spectate")]
+    pub fn with_spectators(mut self, spectators: Arc<Spectators>) -> Arena {
+        self.spectators = Some(spectators);
+        self
+    }
+
+    /*
+     * Gives the given number of corners to Black in every game, that is to each player in
+     * turn, see Rules::with_handicap.
+     */
+    pub fn with_handicap(mut self, corners: u8) -> Arena {
+        self.rules = self.rules.with_handicap(corners);
+        self
+    }
+
+    /*
+     * Adjudicates games with the endgame solver once there are at most the given number of
+     * empty squares left on the board, up to MAX_SOLVE_EMPTIES, see Rules::with_solver.
+     */
+    pub fn with_solver(mut self, empties: u8) -> Arena {
+        self.rules = self.rules.with_solver(empties);
+        self
+    }
+
+    /*
+     * Adjudicates games once both players' evaluations have favored the same side by at least
+     * the given threshold for the given number of consecutive moves, see Rules::with_agreement.
+     */
+    pub fn with_agreement(mut self, threshold: i32, moves: u8) -> Arena {
+        self.rules = self.rules.with_agreement(threshold, moves);
+        self
+    }
+
+    /*
+     * Plays one game between the two players until it is over, resignations, draw offers,
+     * forfeits and adjudication included, and returns it.
+     */
+    pub fn play_game(
+        &self,
+        black: &mut dyn Player,
+        white: &mut dyn Player,
+    ) -> Result<Game, OthelloError> {
+        let opening: Game = Game::with_handicap(self.rules.handicap);
+        Ok(self.play_opening(opening, black, white)?.into_game())
+    }
+
+    /*
+     * Plays one match like play_game, from the given opening, and returns it.
+     */
+    fn play_opening(
+        &self,
+        opening: Game,
+        black: &mut dyn Player,
+        white: &mut dyn Player,
+    ) -> Result<Match, OthelloError> {
+        let mut current: Match = Match::new(self.rules, self.time_control).with_game(opening);
+        #[cfg(feature = "spectate")]
+        if let Some(spectators) = &self.spectators {
+            current = current.with_spectators(Arc::clone(spectators));
+        }
+        while !current.game().is_over() {
+            current.step(black, white)?;
+        }
+
+        Ok(current)
+    }
+
+    /*
+     * Plays a match between the two players, the first one playing black in even games and
+     * white in odd games, and returns the standings.
+     */
+    pub fn run(
+        &self,
+        first: &mut dyn Player,
+        second: &mut dyn Player,
+    ) -> Result<Standings, OthelloError> {
+        Ok(self.run_recorded(first, second)?.0)
+    }
+
+    /*
+     * Plays a match like run, and returns the standings along with the records of the games,
+     * with the stats of their moves, duplicates included.
+     */
+    pub fn run_recorded(
+        &self,
+        first: &mut dyn Player,
+        second: &mut dyn Player,
+    ) -> Result<(Standings, Vec<GameRecord>), OthelloError> {
+        let mut records: Vec<GameRecord> = match &self.checkpoint {
+            Some((path, resume)) => {
+                read_checkpoint(path, *resume, self.games, [first.name(), second.name()])?
+            },
+            None => Vec::new(),
+        };
+
+        for i in records.len() as u32..self.games {
+            let opening: Game = self.opening(i / 2)?;
+            let (played, black, white) = if i % 2 == 0 {
+                (self.play_opening(opening, first, second)?, first.name(), second.name())
+            } else {
+                (self.play_opening(opening, second, first)?, second.name(), first.name())
+            };
+            let record: GameRecord = GameRecord::from_game(played.game(), &black, &white, &today())
+                .with_stats(played.stats().to_vec());
+            if let Some((path, _)) = &self.checkpoint {
+                append_checkpoint(path, &record)?;
+            }
+            records.push(record);
+        }
+
+        let mut standings: Standings = Standings::default();
+        let duplicates: Vec<usize> = find_duplicates(&records);
+        for (i, record) in records.iter().enumerate() {
+            if duplicates.binary_search(&i).is_ok() {
+                standings.duplicates += 1;
+                if self.discard_duplicates {
+                    continue;
+                }
+            }
+            let color: Color = if i % 2 == 0 { Color::Black } else { Color::White };
+            standings.record(record.outcome.unwrap(), color);
+        }
+
+        Ok((standings, records))
+    }
+
+    /*
+     * Returns the opening of the given pair of games, from the suite if there is one, or else
+     * of random plies drawn from the seed of the arena and the number of the pair.
+     */
+    fn opening(&self, pair: u32) -> Result<Game, OthelloError> {
+        if !self.openings.is_empty() {
+            return Ok(self.openings[pair as usize % self.openings.len()].clone());
+        }
+
+        let mut game: Game = Game::with_handicap(self.rules.handicap);
+        let mut random: RandomPlayer = RandomPlayer::new(self.seed.wrapping_add(pair as u64));
+        while game.history().len() < self.opening_plies && !game.is_over() {
+            if let Action::Play(mv) = random.choose_move(&game, &Clock::unlimited()) {
+                game.play(mv)?;
+            }
+        }
+        Ok(game)
+    }
+}
+
+/*
+ * Returns the indices, in increasing order, of the games repeating an earlier one: the same
+ * players with the same colors playing the same moves. Deterministic engines replay the very
+ * same games from the same openings, which then weigh several times in the statistics of a
+ * match.
+ */
+pub fn find_duplicates(records: &[GameRecord]) -> Vec<usize> {
+    let mut seen: HashSet<(&str, &str, u8, &[BitBoard])> = HashSet::new();
+    (0..records.len())
+        .filter(|&i| {
+            let record: &GameRecord = &records[i];
+            !seen.insert((&record.black, &record.white, record.handicap, &record.moves))
+        })
+        .collect()
+}
+
+//#################################################################################################
+//
+//                                     OPENING SUITES
+//
+//#################################################################################################
+
+/*
+ * Reads a suite of openings, one transcript per line, empty lines and lines starting with #
+ * being skipped.
+ */
+pub fn parse_openings(text: &str) -> Result<Vec<Game>, OthelloError> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(i, line)| Game::from_transcript(line)
+            .map_err(|err| parse_error(format!("At line {} of the openings: {}", i + 1, err))))
+        .collect()
+}
+
+/*
+ * Writes a suite of openings, one transcript per line, see parse_openings.
+ */
+pub fn write_openings(openings: &[Game]) -> String {
+    openings.iter().map(|game| format_pv(game.history()) + "\n").collect()
+}
+
+/*
+ * Generates openings of the given number of random plies, drawn from the seed, that the AI
+ * finds balanced: a shallow search, to the depth of the AI, must value the position within
+ * the margin of a draw, in the units of its evaluation, so that neither side of an opening
+ * starts with a decisive advantage. Openings reaching the same position are only kept once.
+ * Returns fewer openings than asked for if too few random ones are balanced.
+ */
+#[cfg(feature = "alphabeta")]
+pub fn balanced_openings(
+    player: &crate::alphabeta::AlphaBetaPlayer, count: usize, plies: usize, margin: i32,
+    seed: u64,
+) -> Vec<Game> {
+    let mut openings: Vec<Game> = Vec::new();
+    let arena: Arena = Arena::new(0).with_random_openings(plies, seed);
+
+    for pair in 0..100 * count as u32 {
+        if openings.len() == count {
+            break;
+        }
+        let Ok(game) = arena.opening(pair) else { continue };
+        if game.is_over() || openings.iter().any(|opening| opening.board() == game.board()) {
+            continue;
+        }
+        let (_, value, _) = player.best_move(game.board(), game.color());
+        if value.abs() <= margin {
+            openings.push(game);
+        }
+    }
+
+    openings
+}
+
+//#################################################################################################
+//
+//                                       SCHEDULES
+//
+//#################################################################################################
+
+/*
+ * The pairings of a tournament between more than two players: a gauntlet pits the first
+ * player, the candidate, against each of the others, the references, and a round-robin every
+ * player against every other one. Each pairing is a match of the games of the arena.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Schedule {
+    Gauntlet,
+    RoundRobin,
+}
+
+impl Schedule {
+    /*
+     * Returns the pairings of the given number of players, as their indices.
+     */
+    pub fn pairings(self, players: usize) -> Vec<(usize, usize)> {
+        match self {
+            Schedule::Gauntlet => (1..players).map(|j| (0, j)).collect(),
+            Schedule::RoundRobin => (0..players)
+                .flat_map(|i| (i + 1..players).map(move |j| (i, j)))
+                .collect(),
+        }
+    }
+}
+
+/*
+ * The results of a tournament between several players: the standings of each player against
+ * each other one, None if they didn't meet.
+ */
+#[derive(Clone)]
+pub struct CrossTable {
+    pub names: Vec<String>,
+    pub results: Vec<Vec<Option<Standings>>>,
+}
+
+impl CrossTable {
+    /*
+     * Returns the points of the player, a win counting 1 and a draw half, and the number of
+     * games he played.
+     */
+    pub fn points(&self, player: usize) -> (f64, u32) {
+        self.results[player].iter().flatten().fold((0.0, 0), |(points, games), standings| {
+            let played: u32 = standings.wins + standings.draws + standings.losses;
+            (points + standings.wins as f64 + standings.draws as f64 / 2.0, games + played)
+        })
+    }
+
+    /*
+     * Writes the cross-table, one row per player, the best first, with his points against each
+     * opponent in the columns and his total, for instance:
+     *
+     * #  Player        1    2    3    Points
+     * 1  AlphaBeta 9   .    3.5  4    7.5/8
+     * 2  MCTS 20000    0.5  .    2    2.5/8
+     * 3  Random        0    2    .    2/8
+     */
+    pub fn to_text(&self) -> String {
+        let mut order: Vec<usize> = (0..self.names.len()).collect();
+        order.sort_by(|&a, &b| self.points(b).0.total_cmp(&self.points(a).0));
+        let width: usize = self.names.iter().map(String::len).max().unwrap_or(0).max(6) + 2;
+
+        let mut text: String = format!("{:<3}{:<width$}", "#", "Player");
+        for rank in 1..=order.len() {
+            text.push_str(&format!("{:<5}", rank));
+        }
+        text.push_str("Points\n");
+        for (rank, &i) in order.iter().enumerate() {
+            text.push_str(&format!("{:<3}{:<width$}", rank + 1, self.names[i]));
+            for &j in &order {
+                let cell: String = match self.results[i][j] {
+                    _ if i == j => ".".to_string(),
+                    Some(standings) => {
+                        (standings.wins as f64 + standings.draws as f64 / 2.0).to_string()
+                    },
+                    None => "-".to_string(),
+                };
+                text.push_str(&format!("{:<5}", cell));
+            }
+            let (points, games): (f64, u32) = self.points(i);
+            text.push_str(&format!("{}/{}\n", points, games));
+        }
+
+        text
+    }
+}
+
+impl Arena {
+    /*
+     * Plays a tournament between the players with the given schedule, each pairing being a
+     * match like run, the first player of the pairing playing black in its even games, and
+     * returns the cross-table. With a checkpoint, each pairing has its own, named after the
+     * checkpoint and the numbers of its players, from 1.
+     */
+    pub fn run_schedule(
+        &self,
+        schedule: Schedule,
+        players: &mut [&mut dyn Player],
+    ) -> Result<CrossTable, OthelloError> {
+        let names: Vec<String> = players.iter().map(|player| player.name()).collect();
+        let mut results: Vec<Vec<Option<Standings>>> = vec![vec![None; names.len()]; names.len()];
+
+        for (i, j) in schedule.pairings(players.len()) {
+            let mut arena: Arena = self.clone();
+            if let Some((path, resume)) = &self.checkpoint {
+                let stem: String = path.file_stem().unwrap_or_default().to_string_lossy().into();
+                let mut name: String = format!("{}-{}-{}", stem, i + 1, j + 1);
+                if let Some(extension) = path.extension() {
+                    name = format!("{}.{}", name, extension.to_string_lossy());
+                }
+                arena.checkpoint = Some((path.with_file_name(name), *resume));
+            }
+            let (left, right) = players.split_at_mut(j);
+            let standings: Standings = arena.run(&mut *left[i], &mut *right[0])?;
+            results[i][j] = Some(standings);
+            results[j][i] = Some(Standings {
+                wins: standings.losses,
+                draws: standings.draws,
+                losses: standings.wins,
+                forfeits: standings.opponent_forfeits,
+                opponent_forfeits: standings.forfeits,
+                duplicates: standings.duplicates,
+            });
+        }
+
+        Ok(CrossTable { names, results, })
+    }
+}
+
+//#################################################################################################
+//
+//                                      CHECKPOINTS
+//
+//#################################################################################################
+
+/*
+ * Returns the records of the games of a checkpoint, at most the given number, checking that
+ * they were played between the given players, the first one having black in even games, and
+ * that they are over. A line left unfinished by a crash is dropped, and the file is rewritten
+ * without it. Without resume, the file is emptied and no record returned.
+ */
+fn read_checkpoint(
+    path: &Path, resume: bool, games: u32, players: [String; 2],
+) -> Result<Vec<GameRecord>, OthelloError> {
+    let mut text: String = String::new();
+    if resume && path.exists() {
+        text = std::fs::read_to_string(path)?;
+        text.truncate(text.rfind('\n').map_or(0, |end| end + 1));
+    }
+
+    let mut records: Vec<GameRecord> = Vec::new();
+    for (i, line) in text.lines().enumerate().take(games as usize) {
+        let record: GameRecord = GameRecord::from_json(line)
+            .map_err(|err| parse_error(format!("At line {} of the checkpoint: {}", i + 1, err)))?;
+        let (black, white) = if i % 2 == 0 { (0, 1) } else { (1, 0) };
+        if record.black != players[black] || record.white != players[white] {
+            return Err(parse_error(format!("Game {} of the checkpoint is between {} and {}, \
+                not between {} and {}.", i + 1, record.black, record.white, players[black],
+                players[white])));
+        }
+        if record.outcome.is_none() {
+            return Err(parse_error(format!("Game {} of the checkpoint is unfinished.", i + 1)));
+        }
+        records.push(record);
+    }
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let lines: String = records.iter().map(|record| record.to_json() + "\n").collect();
+    let temp: PathBuf = path.with_extension("tmp");
+    std::fs::write(&temp, lines)?;
+    std::fs::rename(&temp, path)?;
+    Ok(records)
+}
+
+/*
+ * Appends the record of a game to a checkpoint, and waits for it to reach the disk.
+ */
+fn append_checkpoint(path: &Path, record: &GameRecord) -> Result<(), OthelloError> {
+    let mut file: std::fs::File = std::fs::OpenOptions::new().append(true).open(path)?;
+    writeln!(file, "{}", record.to_json())?;
+    file.sync_all()?;
+    Ok(())
+}
+
+//#################################################################################################
+//
+//                                       TIME USAGE
+//
+//#################################################################################################
+
+/*
+ * Writes the stats of every move of the games as CSV, one row per ply, to audit how players use
+ * their time: the number of the game, its players, the ply, the color and the name of the player
+ * on move, the empty squares before the move and the move, then the seconds it took and the
+ * total he has taken so far in the game, the depth and nodes of his search and his evaluation,
+ * empty when unknown.
+ */
+pub fn write_time_usage(records: &[GameRecord]) -> String {
+    let mut csv: String = String::from(
+        "game,black,white,ply,color,player,empties,move,time,total,depth,nodes,score\n");
+    let field = |value: Option<String>| value.unwrap_or_default();
+
+    for (game, record) in records.iter().enumerate() {
+        let mut empties: u8 = Othello::with_handicap(record.handicap).empties();
+        let mut totals: [Duration; 2] = [Duration::ZERO; 2];
+        for (ply, &mv) in record.moves.iter().enumerate() {
+            let stats: MoveStats = record.stats.get(ply).copied().unwrap_or_default();
+            let color: Color = if ply % 2 == 0 { Color::Black } else { Color::White };
+            let player: &str = if color == Color::Black { &record.black } else { &record.white };
+            totals[color as usize] += stats.time.unwrap_or_default();
+            csv.push_str(&format!("{},{},{},{},{},{},{},{},{},{:.3},{},{},{}\n", game + 1,
+                csv_field(&record.black), csv_field(&record.white), ply + 1, color,
+                csv_field(player), empties, format_pv(&[mv]),
+                field(stats.time.map(|time| format!("{:.3}", time.as_secs_f64()))),
+                totals[color as usize].as_secs_f64(),
+                field(stats.depth.map(|depth| depth.to_string())),
+                field(stats.nodes.map(|nodes| nodes.to_string())),
+                field(stats.score.map(|score| score.to_string()))));
+            if mv != 0 {
+                empties -= 1;
+            }
+        }
+    }
+
+    csv
+}
+
+/*
+ * Quotes a field of a CSV file if it holds a comma or a quote.
+ */
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+//#################################################################################################
+//
+//                                       MATCH TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that matches are recorded, adjudicated by the rules and forfeited by illegal moves and
+ * time losses, and that the tournament runner counts the forfeits, plays random openings,
+ * records the stats of the moves, written as CSV, resumes from its checkpoints, even one cut
+ * short in the middle of a line, finds and discards duplicate games, plays gauntlets and
+ * round-robins, and deals balanced openings from suites.
+ * Also plays pairs of games mirrored by each symmetry of the board, checking that the AI, its
+ * book and the analysis cache see mirrored positions as the same one: the moves played must be
+ * mirrored and the evaluations identical.
+ */
+#[cfg(all(test, feature = "alphabeta"))]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::alphabeta::*;
+    use crate::eval::*;
+    use crate::symmetry::*;
+    use crate::book::*;
+    use crate::cache::*;
+
+    #[test]
+    fn play() {
+        let time_control: TimeControl = TimeControl::Increment {
+            base: Duration::from_secs(60),
+            increment: Duration::from_secs(1),
+        };
+        let record: GameRecord = Match::play(
+            AlphaBetaPlayer::new(2),
+            RandomPlayer::new(1),
+            Rules::new(),
+            time_control,
+        ).unwrap();
+        assert_eq!((record.black.as_str(), record.white.as_str()), ("AlphaBeta 2", "Random"));
+        assert_eq!(record.date.len(), 10);
+        assert!(record.outcome.unwrap().reason == Reason::Finished);
+        assert!(record.to_game().unwrap().history() == record.moves.as_slice());
+
+        // Adjudicates a draw as soon as a corner is taken.
+        let rules: Rules = Rules::new().with_adjudicator(|game| {
+            let board: Othello = game.board();
+            let disks: BitBoard =
+                board.get_bitboard(Color::Black) | board.get_bitboard(Color::White);
+            (disks & 0x8100000000000081 != 0).then_some(None)
+        });
+        let record: GameRecord =
+            Match::play(RandomPlayer::new(2), RandomPlayer::new(3), rules, time_control).unwrap();
+        let outcome: Outcome = record.outcome.unwrap();
+        assert!(outcome == Outcome { winner: None, reason: Reason::Adjudication, });
+
+        // The solver takes over from 14 empty squares on, but not from more than 16.
+        assert_eq!(Rules::new().with_solver(60).solve_empties, MAX_SOLVE_EMPTIES);
+        let rules: Rules = Rules::new().with_solver(14);
+        let record: GameRecord =
+            Match::play(RandomPlayer::new(2), RandomPlayer::new(3), rules, time_control).unwrap();
+        assert!(record.outcome.unwrap().reason == Reason::Adjudication);
+        let game: Game = record.to_game().unwrap();
+        assert_eq!(game.board().empties(), 14);
+
+        // The random player has no evaluation to agree on.
+        assert!(Rules::new().with_agreement(0, 3).agreement.is_none());
+        assert!(Rules::new().with_agreement(100, 0).agreement.is_none());
+        let rules: Rules = Rules::new().with_agreement(1, 1);
+        let record: GameRecord =
+            Match::play(AlphaBetaPlayer::new(2), RandomPlayer::new(5), rules, time_control)
+                .unwrap();
+        assert!(record.outcome.unwrap().reason == Reason::Finished);
+
+        let mut current: Match = Match::new(Rules::new(), time_control).with_evaluations();
+        let (mut black, mut white) = (AlphaBetaPlayer::new(2), RandomPlayer::new(4));
+        while !current.game().is_over() {
+            current.step(&mut black, &mut white).unwrap();
+        }
+        let history: &[BitBoard] = current.game().history();
+        assert_eq!(current.evaluations().len(), history.len());
+        // Only the moves of the alpha-beta AI, playing the even plies, are evaluated.
+        for (ply, (&mv, &value)) in history.iter().zip(current.evaluations()).enumerate() {
+            assert_eq!(value.is_some(), mv != 0 && ply % 2 == 0);
+        }
+    }
+
+    /*
+     * A player thinking for a millisecond before playing his first legal move.
+     */
+    struct SlowPlayer;
+
+    impl Player for SlowPlayer {
+        fn choose_move(&mut self, game: &Game, _clock: &Clock) -> Action {
+            std::thread::sleep(Duration::from_millis(1));
+            Action::Play(game.moves() & game.moves().wrapping_neg())
+        }
+    }
+
+    #[test]
+    fn forfeits() {
+        // Black plays an occupied square.
+        let illegal: ScriptedPlayer = ScriptedPlayer::new(vec![1 << 27]);
+        let record: GameRecord =
+            Match::play(illegal, RandomPlayer::new(5), Rules::new(), TimeControl::Unlimited)
+                .unwrap();
+        let outcome: Outcome = record.outcome.unwrap();
+        assert!(outcome == Outcome { winner: Some(Color::White), reason: Reason::IllegalMove, });
+        assert!(record.to_game().unwrap().outcome() == Some(outcome));
+
+        let time_control: TimeControl = TimeControl::Increment {
+            base: Duration::from_micros(100),
+            increment: Duration::ZERO,
+        };
+        let arena: Arena = Arena::new(2).with_time_control(time_control);
+        let standings: Standings = arena.run(&mut SlowPlayer, &mut RandomPlayer::new(6)).unwrap();
+        assert_eq!((standings.wins, standings.losses), (0, 2));
+        assert_eq!(standings.forfeits, Forfeits { illegal_moves: 0, timeouts: 2, });
+        assert_eq!(standings.opponent_forfeits.total(), 0);
+    }
+
+    #[test]
+    fn openings() {
+        let arena: Arena = Arena::new(4).with_random_openings(6, 1);
+        let opening: Game = arena.opening(0).unwrap();
+        assert_eq!(opening.history().len(), 6);
+        assert!(arena.opening(0).unwrap().board() == opening.board());
+        assert!(arena.opening(1).unwrap().board() != opening.board());
+
+        let mut a: AlphaBetaPlayer = AlphaBetaPlayer::new(2);
+        let mut b: AlphaBetaPlayer =
+            AlphaBetaPlayer::new(2).with_weights(Personality::Greedy.weights());
+        let standings: Standings = arena.run(&mut a, &mut b).unwrap();
+        assert_eq!(standings.wins + standings.draws + standings.losses, 4);
+        assert!((0.0..=1.0).contains(&standings.score()));
+
+        let (_, records): (Standings, Vec<GameRecord>) = Arena::new(2)
+            .with_random_openings(6, 1)
+            .run_recorded(&mut a, &mut RandomPlayer::new(7))
+            .unwrap();
+        assert_eq!((records[0].black.as_str(), records[1].black.as_str()),
+            ("AlphaBeta 2", "Random"));
+        assert!(records[0].stats[..6].iter().all(|stats| stats.time.is_none()));
+        assert!(records[0].stats[6].nodes.is_some());
+        let csv: String = write_time_usage(&records);
+        let plies: usize = records.iter().map(|record| record.moves.len()).sum();
+        assert_eq!(csv.lines().count(), plies + 1);
+        let row: &str = csv.lines().nth(7).unwrap();
+        assert!(row.starts_with("1,AlphaBeta 2,Random,7,Black,AlphaBeta 2,54,"));
+    }
+
+    #[test]
+    fn duplicates() {
+        let (mut a, mut b): (AlphaBetaPlayer, AlphaBetaPlayer) =
+            (AlphaBetaPlayer::new(1), AlphaBetaPlayer::new(2));
+        let (standings, records): (Standings, Vec<GameRecord>) =
+            Arena::new(5).run_recorded(&mut a, &mut b).unwrap();
+        assert_eq!(find_duplicates(&records), [2, 3, 4]);
+        assert_eq!((standings.duplicates, standings.wins + standings.draws + standings.losses),
+            (3, 5));
+
+        let standings: Standings = Arena::new(5).with_duplicates_discarded(true)
+            .run(&mut a, &mut b).unwrap();
+        assert_eq!((standings.duplicates, standings.wins + standings.draws + standings.losses),
+            (3, 2));
+        let standings: Standings = Arena::new(4).with_random_openings(4, 1)
+            .with_duplicates_discarded(true).run(&mut a, &mut b).unwrap();
+        assert_eq!((standings.duplicates, standings.wins + standings.draws + standings.losses),
+            (0, 4));
+    }
+
+    #[test]
+    fn checkpoints() {
+        let path: PathBuf =
+            std::env::temp_dir().join(format!("othello-{}.checkpoint", std::process::id()));
+        let (mut a, mut b): (AlphaBetaPlayer, RandomPlayer) =
+            (AlphaBetaPlayer::new(1), RandomPlayer::new(8));
+        let (_, played): (Standings, Vec<GameRecord>) =
+            Arena::new(2).with_checkpoint(&path, false).run_recorded(&mut a, &mut b).unwrap();
+        let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "{{\"black\":\"AlphaBeta 1\",\"moves\":[").unwrap();
+
+        let (standings, records): (Standings, Vec<GameRecord>) =
+            Arena::new(4).with_checkpoint(&path, true).run_recorded(&mut a, &mut b).unwrap();
+        assert_eq!(standings.wins + standings.draws + standings.losses, 4);
+        assert_eq!(records.len(), 4);
+        assert!(records[1].moves == played[1].moves);
+        assert!(records[1].stats.iter().zip(&played[1].stats)
+            .all(|(read, written)| (read.nodes, read.score) == (written.nodes, written.score)));
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 4);
+
+        let mut other: AlphaBetaPlayer = AlphaBetaPlayer::new(2);
+        assert!(Arena::new(4).with_checkpoint(&path, true).run(&mut other, &mut b).is_err());
+        Arena::new(1).with_checkpoint(&path, false).run(&mut other, &mut b).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn suites() {
+        let player: AlphaBetaPlayer = AlphaBetaPlayer::new(2);
+        let openings: Vec<Game> = balanced_openings(&player, 3, 6, 40, 1);
+        assert_eq!(openings.len(), 3);
+        assert!(openings.iter().all(|game| game.history().len() == 6));
+        assert!(openings.iter()
+            .all(|game| player.best_move(game.board(), game.color()).1.abs() <= 40));
+
+        let text: String = format!("# Balanced openings\n\n{}", write_openings(&openings));
+        let read: Vec<Game> = parse_openings(&text).unwrap();
+        assert!(read.iter().zip(&openings).all(|(a, b)| a.history() == b.history()));
+        assert!(parse_openings("f5d6\nf5f5\n").is_err());
+
+        let arena: Arena = Arena::new(8).with_openings(read);
+        assert!(arena.opening(1).unwrap().board() == openings[1].board());
+        assert!(arena.opening(3).unwrap().board() == openings[0].board());
+    }
+
+    #[test]
+    fn schedules() {
+        assert_eq!(Schedule::Gauntlet.pairings(3), [(0, 1), (0, 2)]);
+        assert_eq!(Schedule::RoundRobin.pairings(3), [(0, 1), (0, 2), (1, 2)]);
+
+        let (mut a, mut b, mut c) = (AlphaBetaPlayer::new(1), RandomPlayer::new(9), SlowPlayer);
+        let arena: Arena = Arena::new(2);
+        let table: CrossTable = arena.run_schedule(Schedule::RoundRobin, &mut [&mut a, &mut b,
+            &mut c]).unwrap();
+        assert!(table.results.iter().enumerate().all(|(i, row)| row[i].is_none()));
+        assert_eq!(table.points(0).1 + table.points(1).1 + table.points(2).1, 12);
+        let against: [Standings; 2] = [table.results[0][1].unwrap(), table.results[1][0].unwrap()];
+        assert_eq!((against[0].wins, against[0].losses), (against[1].losses, against[1].wins));
+        let text: String = table.to_text();
+        assert!(text.starts_with("#  Player       1    2    3    Points\n"));
+        assert_eq!(text.lines().count(), 4);
+
+        let table: CrossTable =
+            arena.run_schedule(Schedule::Gauntlet, &mut [&mut a, &mut b, &mut c]).unwrap();
+        assert!(table.results[1][2].is_none() && table.results[0][2].is_some());
+        assert!(table.to_text().contains("-    "));
+    }
+
+    /*
+     * Plays a game with the AI from the given position, and the same game mirrored by the
+     * symmetry, the player of the mirrored game being made to follow the first one when it
+     * picks another move of equal value.
+     */
+    fn play_mirrored(start: Game, symmetry: Symmetry, book: &Arc<Book>) {
+        let mirrored: Game = Game::from_position(start.board().transform(symmetry), start.color());
+        let mut games: [Game; 2] = [start, mirrored];
+        let new_player = || AlphaBetaPlayer::new(3).with_book(Arc::clone(book));
+        let mut players: [AlphaBetaPlayer; 2] = [new_player(), new_player()];
+        let mut cache: AnalysisCache = AnalysisCache::new(64);
+        let clock: Clock = Clock::unlimited();
+
+        while !games[0].is_over() {
+            let (oth, color) = (games[0].board(), games[0].color());
+            let [a, b] = [0, 1].map(|i| match players[i].choose_move(&games[i], &clock) {
+                Action::Play(mv) => mv,
+                action => panic!("Unexpected action {:?}.", action),
+            });
+            assert_eq!(players[0].evaluation(), players[1].evaluation(), "At {:?}.", symmetry);
+
+            if b != symmetry.apply(a) {
+                let value = |mv: BitBoard| players[0].hint(oth, color, 64).into_iter()
+                    .find(|hint| hint.mv == mv)
+                    .map(|hint| hint.value);
+                assert_eq!(value(a), value(symmetry.inverse().apply(b)), "At {:?}.", symmetry);
+            }
+
+            let analysis: Analysis = Analysis { score: 0, pv: vec![a], depth: 3, };
+            cache.insert(oth, color, analysis);
+            let mirrored: Option<Analysis> = cache.get(games[1].board(), color);
+            assert_eq!(mirrored.map(|analysis| analysis.pv), Some(vec![symmetry.apply(a)]));
+
+            games[0].play(a).unwrap();
+            games[1].play(symmetry.apply(a)).unwrap();
+            assert!(games[1].board() == games[0].board().transform(symmetry));
+        }
+        assert!(games[1].outcome() == games[0].outcome());
+    }
+
+    #[test]
+    fn mirrored_games() {
+        let start: Game = Game::from_line("f5d6").unwrap();
+        let (oth, color) = (start.board(), start.color());
+
+        // Book moves with distinct scores, given in the orientation of the first game only.
+        let mut moves: BitBoard = oth.gen_moves(color);
+        let mut entries: Vec<(Othello, Color, BookEntry)> = Vec::new();
+        while moves != 0 {
+            let score: i32 = 3 * entries.len() as i32 - 4;
+            let entry: BookEntry = BookEntry { score, depth: 10, };
+            entries.push((oth.make_move(color, moves.pop_lsb()), color.invert(), entry));
+        }
+        let book: Arc<Book> = Arc::new(Book::from_entries(entries));
+
+        for &symmetry in &Symmetry::ALL[1..] {
+            play_mirrored(start.clone(), symmetry, &book);
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use std::path::Path;
+
+use crate::types::*;
+use crate::othello::*;
+use crate::wire::*;
+use crate::mapped::*;
+use crate::eval::{WIN, PROVEN};
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                       BOOK TYPE
+//
+//#################################################################################################
+
+/*
+ * The magic number and version of opening book files. Their payload is a list of entries of 16
+ * bytes: the packed wire encoding of the canonical position and the player to move, its score
+ * from the point of view of that player, as a little-endian i16, and the depth it was searched
+ * to. Entries are sorted by encoding, so that they can be found by binary search right in the
+ * mapped file.
+ */
+const MAGIC: &[u8; 8] = b"OTHELLBK";
+const VERSION: u32 = 1;
+const ENTRY_SIZE: usize = 16;
+
+/*
+ * Scores of won and lost positions, proven by the search, are stored as the largest values of
+ * i16, other scores being clamped short of them, and read back as wins and losses at an
+ * unknown distance, see WIN.
+ */
+const MAX_SCORE: i16 = i16::MAX;
+
+/*
+ * The evaluation of a position of the book, from the point of view of the player to move.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BookEntry {
+    pub score: i32,
+    pub depth: u8,
+}
+
+/*
+ * Where the entries of a book are: in memory for a book being built, or in a mapped file.
+ */
+enum Storage {
+    Owned(Vec<u8>),
+    Mapped(MappedFile),
+}
+
+/*
+ * An opening book, holding the evaluations of positions, in canonical form so that a position
+ * is found whatever the orientation of the board.
+ */
+pub struct Book {
+    storage: Storage,
+}
+
+impl Book {
+    /*
+     * Creates a book from the given positions and their evaluations. A position given several
+     * times keeps its deepest evaluation.
+     */
+    pub fn from_entries(entries: impl IntoIterator<Item = (Othello, Color, BookEntry)>) -> Book {
+        let mut entries: Vec<[u8; ENTRY_SIZE]> = entries.into_iter()
+            .map(|(oth, color, entry)| encode_entry(oth, color, entry))
+            .collect();
+        // Deeper entries come first among equal positions, and are the ones kept.
+        entries.sort_unstable_by(|a, b| {
+            a[..PACKED_SIZE].cmp(&b[..PACKED_SIZE]).then(b[15].cmp(&a[15]))
+        });
+        entries.dedup_by(|a, b| a[..PACKED_SIZE] == b[..PACKED_SIZE]);
+
+        Book { storage: Storage::Owned(entries.concat()), }
+    }
+
+    /*
+     * Opens a book file, mapping it into memory. Returns an error if the file can't be read or
+     * isn't a valid book.
+     */
+    pub fn open(path: impl AsRef<Path>) -> Result<Book, OthelloError> {
+        let file: MappedFile = MappedFile::open(path, MAGIC, VERSION)?;
+        if !file.payload().len().is_multiple_of(ENTRY_SIZE) {
+            return Err(parse_error("Truncated book file."));
+        }
+        Ok(Book { storage: Storage::Mapped(file), })
+    }
+
+    /*
+     * Returns the book with the given positions and evaluations added, those already in it
+     * keeping their deepest evaluation, like in from_entries.
+     */
+    pub fn with_entries(&self, entries: impl IntoIterator<Item = (Othello, Color, BookEntry)>)
+        -> Book
+    {
+        Book::from_entries(self.entries().into_iter().chain(entries))
+    }
+
+    /*
+     * Returns all the positions of the book, in canonical form, with their evaluations.
+     */
+    pub fn entries(&self) -> Vec<(Othello, Color, BookEntry)> {
+        self.bytes().chunks(ENTRY_SIZE)
+            .filter_map(|entry| {
+                let (oth, color) = decode_packed(&entry[..PACKED_SIZE])?;
+                Some((oth, color, decode_entry(entry)))
+            })
+            .collect()
+    }
+
+    /*
+     * Writes the book to a file.
+     */
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), OthelloError> {
+        write_file(path, MAGIC, VERSION, self.bytes())
+    }
+
+    /*
+     * Returns the number of positions in the book.
+     */
+    pub fn len(&self) -> usize {
+        self.bytes().len() / ENTRY_SIZE
+    }
+
+    /*
+     * Returns true if the book holds no position.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /*
+     * Returns the evaluation of the position, if it is in the book.
+     */
+    pub fn probe(&self, oth: Othello, color: Color) -> Option<BookEntry> {
+        let key: [u8; PACKED_SIZE] = encode_packed(oth.canonical().0, color);
+        let bytes: &[u8] = self.bytes();
+        let (mut low, mut high): (usize, usize) = (0, self.len());
+
+        while low < high {
+            let mid: usize = (low + high) / 2;
+            let entry: &[u8] = &bytes[mid * ENTRY_SIZE..(mid + 1) * ENTRY_SIZE];
+            match entry[..PACKED_SIZE].cmp(&key) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => return Some(decode_entry(entry)),
+            }
+        }
+
+        None
+    }
+
+    /*
+     * Returns the legal moves of the position leading to positions of the book, with their
+     * evaluations from the point of view of the player making them, best first.
+     */
+    pub fn moves(&self, oth: Othello, color: Color) -> Vec<(BitBoard, BookEntry)> {
+        let mut moves: BitBoard = oth.gen_moves(color);
+        let mut result: Vec<(BitBoard, BookEntry)> = Vec::new();
+
+        while moves != 0 {
+            let mv: BitBoard = moves.pop_lsb();
+            let new_oth: Othello = oth.make_move(color, mv);
+            // The opponent may have to pass, the position being then stored with us to move.
+            let entry: Option<BookEntry> = if new_oth.gen_moves(color.invert()) == 0 {
+                self.probe(new_oth, color)
+            } else {
+                self.probe(new_oth, color.invert())
+                    .map(|entry| BookEntry { score: entry.score.saturating_neg(), ..entry })
+            };
+            if let Some(entry) = entry {
+                result.push((mv, entry));
+            }
+        }
+
+        result.sort_by_key(|&(_, entry)| std::cmp::Reverse(entry.score));
+        result
+    }
+
+    /*
+     * Returns the entries of the book, wherever they are.
+     */
+    fn bytes(&self) -> &[u8] {
+        match &self.storage {
+            Storage::Owned(bytes) => bytes,
+            Storage::Mapped(file) => file.payload(),
+        }
+    }
+}
+
+/*
+ * Encodes an entry of the book.
+ */
+fn encode_entry(oth: Othello, color: Color, entry: BookEntry) -> [u8; ENTRY_SIZE] {
+    let score: i16 = if entry.score >= PROVEN {
+        MAX_SCORE
+    } else if entry.score <= -PROVEN {
+        -MAX_SCORE
+    } else {
+        entry.score.clamp(-(MAX_SCORE as i32) + 1, MAX_SCORE as i32 - 1) as i16
+    };
+
+    let mut bytes: [u8; ENTRY_SIZE] = [0; ENTRY_SIZE];
+    bytes[..PACKED_SIZE].copy_from_slice(&encode_packed(oth.canonical().0, color));
+    bytes[13..15].copy_from_slice(&score.to_le_bytes());
+    bytes[15] = entry.depth;
+    bytes
+}
+
+/*
+ * Decodes the evaluation of an entry of the book.
+ */
+fn decode_entry(bytes: &[u8]) -> BookEntry {
+    let score: i32 = match i16::from_le_bytes([bytes[13], bytes[14]]) {
+        MAX_SCORE => WIN,
+        score if score == -MAX_SCORE => -WIN,
+        score => score as i32,
+    };
+    BookEntry { score, depth: bytes[15], }
+}
+
+//#################################################################################################
+//
+//                                        BOOK TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that books survive a round trip through a mapped file, that entries can be added to
+ * them, and that damaged files are rejected.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let start: Othello = Othello::new();
+        let f5: Othello = start.make_move(Color::Black, 1 << 37);
+        let d3: Othello = start.make_move(Color::Black, 1 << 19);
+        let book: Book = Book::from_entries(vec![
+            (start, Color::Black, BookEntry { score: 5, depth: 4, }),
+            (f5, Color::White, BookEntry { score: -5, depth: 3, }),
+            (f5, Color::White, BookEntry { score: -7, depth: 6, }),
+            (d3, Color::White, BookEntry { score: i32::MAX, depth: 60, }),
+        ]);
+        // f5 and d3 are symmetric, so only the deepest entry is kept.
+        assert_eq!(book.len(), 2);
+
+        let path = std::env::temp_dir().join(format!("othello-book-{}", std::process::id()));
+        book.save(&path).unwrap();
+        let opened: Book = Book::open(&path).unwrap();
+        assert_eq!(opened.probe(start, Color::Black), Some(BookEntry { score: 5, depth: 4, }));
+        let won: BookEntry = BookEntry { score: i32::MAX, depth: 60, };
+        assert_eq!(opened.probe(f5, Color::White), Some(won));
+        assert_eq!(opened.moves(start, Color::Black).len(), 4);
+        assert_eq!(opened.moves(start, Color::Black)[0].1.score, -i32::MAX);
+        let grown: Book = opened.with_entries(vec![
+            (start, Color::Black, BookEntry { score: 3, depth: 2, }),
+            (d3.make_move(Color::White, 1 << 18), Color::Black, BookEntry { score: 1, depth: 2, }),
+        ]);
+        assert_eq!(grown.len(), 3);
+        assert_eq!(grown.probe(start, Color::Black), Some(BookEntry { score: 5, depth: 4, }));
+        drop(opened);
+
+        let mut bytes: Vec<u8> = std::fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(Book::open(&path).is_err());
+        std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(Book::open(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
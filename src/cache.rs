@@ -0,0 +1,255 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+use crate::types::*;
+use crate::othello::*;
+use crate::symmetry::*;
+
+//#################################################################################################
+//
+//                                      ANALYSIS TYPE
+//
+//#################################################################################################
+
+/*
+ * A completed analysis of a position: its score from the point of view of the player to move,
+ * the principal variation, passes being empty BitBoards, and the depth it was searched to.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Analysis {
+    pub score: i32,
+    pub pv: Vec<BitBoard>,
+    pub depth: u8,
+}
+
+impl Analysis {
+    /*
+     * Applies the given symmetry to the moves of the principal variation.
+     */
+    pub(crate) fn transform(&self, symmetry: Symmetry) -> Analysis {
+        Analysis {
+            score: self.score,
+            pv: self.pv.iter().map(|&mv| symmetry.apply(mv)).collect(),
+            depth: self.depth,
+        }
+    }
+
+    /*
+     * Writes the analysis on one line: the depth, the score and the square indices of the
+     * principal variation, 64 standing for a pass.
+     */
+    fn to_line(&self) -> String {
+        let mut line: String = format!("{} {}", self.depth, self.score);
+        for &mv in &self.pv {
+            line += &format!(" {}", if mv == 0 { 64 } else { mv.trailing_zeros() });
+        }
+        line
+    }
+
+    /*
+     * Reads an analysis written by to_line.
+     */
+    fn from_line(line: &str) -> Option<Analysis> {
+        let mut words = line.split_whitespace();
+        let depth: u8 = words.next()?.parse().ok()?;
+        let score: i32 = words.next()?.parse().ok()?;
+        let pv: Option<Vec<BitBoard>> = words.map(|word| match word.parse::<u32>().ok()? {
+            64 => Some(0),
+            index if index < 64 => Some(1u64 << index),
+            _ => None,
+        }).collect();
+
+        Some(Analysis { score, pv: pv?, depth, })
+    }
+}
+
+//#################################################################################################
+//
+//                                   ANALYSIS CACHE TYPE
+//
+//#################################################################################################
+
+/*
+ * The key of a position in the cache: its canonical board and the player to move.
+ */
+type Key = (Othello, Color);
+
+/*
+ * A cache of completed analyses, for front-ends going back and forth in a game not to search
+ * the same positions again. Positions are stored in canonical form, so that an analysis is
+ * found for all the symmetric positions, its principal variation being transformed back.
+ *
+ * The cache holds at most a given number of analyses, the least recently used ones being
+ * evicted first. Evicted analyses can optionally be spilled to a directory on disk, one file
+ * per position, and are read back from there when needed. Spilling is done on a best effort
+ * basis: an analysis that can't be written is simply dropped.
+ */
+pub struct AnalysisCache {
+    capacity: usize,
+    spill: Option<PathBuf>,
+    entries: HashMap<Key, (Analysis, u64)>,
+    recency: BTreeMap<u64, Key>,
+    clock: u64,
+}
+
+impl AnalysisCache {
+    /*
+     * Creates a new empty cache holding at most capacity analyses in memory.
+     */
+    pub fn new(capacity: usize) -> AnalysisCache {
+        AnalysisCache {
+            capacity: capacity.max(1),
+            spill: None,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            clock: 0,
+        }
+    }
+
+    /*
+     * Spills evicted analyses to the given directory, creating it if needed.
+     */
+    pub fn with_spill(mut self, dir: impl Into<PathBuf>) -> AnalysisCache {
+        let dir: PathBuf = dir.into();
+        let _ = std::fs::create_dir_all(&dir);
+        self.spill = Some(dir);
+        self
+    }
+
+    /*
+     * Returns the number of analyses held in memory.
+     */
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /*
+     * Returns true if no analysis is held in memory.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /*
+     * Returns the analysis of the given position, if there is one in memory or on disk, making
+     * it the most recently used.
+     */
+    pub fn get(&mut self, oth: Othello, color: Color) -> Option<Analysis> {
+        let (canonical, symmetry) = oth.canonical();
+        let key: Key = (canonical, color);
+
+        if !self.entries.contains_key(&key) {
+            let analysis: Analysis = self.read_spilled(key)?;
+            self.store(key, analysis);
+        }
+
+        self.clock += 1;
+        let (analysis, used) = self.entries.get_mut(&key)?;
+        self.recency.remove(used);
+        self.recency.insert(self.clock, key);
+        *used = self.clock;
+
+        Some(analysis.transform(symmetry.inverse()))
+    }
+
+    /*
+     * Stores the analysis of the given position, unless a deeper one is already known.
+     */
+    pub fn insert(&mut self, oth: Othello, color: Color, analysis: Analysis) {
+        let (canonical, symmetry) = oth.canonical();
+        let key: Key = (canonical, color);
+
+        let known: Option<u8> = match self.entries.get(&key) {
+            Some((known, _)) => Some(known.depth),
+            None => self.read_spilled(key).map(|known| known.depth),
+        };
+        if known.is_some_and(|depth| depth > analysis.depth) {
+            return;
+        }
+
+        self.store(key, analysis.transform(symmetry));
+    }
+
+    /*
+     * Stores an analysis, already in canonical form, as the most recently used one, evicting
+     * the least recently used one if the cache is full.
+     */
+    fn store(&mut self, key: Key, analysis: Analysis) {
+        self.clock += 1;
+        if let Some((_, used)) = self.entries.insert(key, (analysis, self.clock)) {
+            self.recency.remove(&used);
+        }
+        self.recency.insert(self.clock, key);
+
+        while self.entries.len() > self.capacity {
+            let (_, evicted) = self.recency.pop_first().unwrap();
+            let (analysis, _) = self.entries.remove(&evicted).unwrap();
+            self.write_spilled(evicted, &analysis);
+        }
+    }
+
+    /*
+     * Returns the path of the file of the given position in the spill directory, if any.
+     */
+    fn spill_path(&self, (oth, color): Key) -> Option<PathBuf> {
+        let (black, white) = (oth.get_bitboard(Color::Black), oth.get_bitboard(Color::White));
+        let side: char = if color == Color::Black { 'b' } else { 'w' };
+        let name: String = format!("{:016x}{:016x}{}", black, white, side);
+        self.spill.as_ref().map(|dir| dir.join(name))
+    }
+
+    /*
+     * Reads the analysis of the given position from the spill directory.
+     */
+    fn read_spilled(&self, key: Key) -> Option<Analysis> {
+        let text: String = std::fs::read_to_string(self.spill_path(key)?).ok()?;
+        Analysis::from_line(text.trim())
+    }
+
+    /*
+     * Writes the analysis of the given position to the spill directory, if there is one.
+     */
+    fn write_spilled(&self, key: Key, analysis: &Analysis) {
+        if let Some(path) = self.spill_path(key) {
+            let _ = std::fs::write(path, analysis.to_line());
+        }
+    }
+}
+
+//#################################################################################################
+//
+//                                    ANALYSIS CACHE TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that symmetric positions share their analysis and that the least recently used
+ * analyses are evicted first.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lru() {
+        let oth: Othello = Othello::new().make_move(Color::Black, 1u64 << 37);
+        let mut cache: AnalysisCache = AnalysisCache::new(2);
+        cache.insert(oth, Color::White, Analysis { score: 3, pv: vec![1u64 << 45, 0], depth: 4, });
+
+        for &symmetry in &Symmetry::ALL {
+            let analysis: Analysis = cache.get(oth.transform(symmetry), Color::White).unwrap();
+            assert_eq!(analysis.pv, vec![symmetry.apply(1u64 << 45), 0]);
+        }
+        assert_eq!(cache.get(oth, Color::Black), None);
+
+        let other: Othello = oth.make_move(Color::White, 1u64 << 43);
+        let another: Othello = Othello::new();
+        cache.insert(other, Color::Black, Analysis { score: 0, pv: vec![], depth: 1, });
+        cache.get(oth, Color::White);
+        cache.insert(another, Color::Black, Analysis { score: 0, pv: vec![], depth: 1, });
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(oth, Color::White).is_some());
+        assert!(cache.get(other, Color::Black).is_none());
+    }
+}
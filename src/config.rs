@@ -0,0 +1,507 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::alphabeta::*;
+use crate::eval::*;
+use crate::ordering::*;
+use crate::book::*;
+use crate::journal::*;
+use crate::executor::*;
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                       CONFIG TYPE
+//
+//#################################################################################################
+
+/*
+ * The options of the engine, shared by the command line and the protocols. They are read from
+ * the config file, then overridden one by one by command line flags or by the setoption
+ * commands of the protocols, through set_option. Besides the options of the alpha-beta AI and
+ * the paths of its book and weight files, they hold the number of threads of the searches able
+ * to use several, and the size of their hash tables, in megabytes. A node limit of 0 means
+ * none, and the adaptive option makes the AI model its opponent, see OpponentModel. The
+ * searches of the AI are appended to the journal file, if one is given, and the analyses of
+ * the shell reaching the autobook depth are added to the autobook file, if one is given. When
+ * its time is limited, the AI shares it between the phases of the game with the time weights,
+ * see TimeManager. With a book margin, the AI varies its book moves within it, with the book
+ * temperature, see AlphaBetaPlayer::with_book_variety.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Config {
+    pub depth: u8,
+    pub personality: Personality,
+    pub contempt: i32,
+    pub ordering: MoveOrdering,
+    pub nodes: u64,
+    pub adaptive: bool,
+    pub threads: usize,
+    pub hash: usize,
+    pub book: Option<PathBuf>,
+    pub book_margin: i32,
+    pub book_temperature: f64,
+    pub weights: Option<PathBuf>,
+    pub journal: Option<PathBuf>,
+    pub autobook: Option<PathBuf>,
+    pub autobook_depth: u8,
+    pub time_weights: [u32; 4],
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            depth: 9,
+            personality: Personality::Balanced,
+            contempt: 0,
+            ordering: MoveOrdering::default(),
+            nodes: 0,
+            adaptive: false,
+            threads: 1,
+            hash: 16,
+            book: None,
+            book_margin: 0,
+            book_temperature: 4.0,
+            weights: None,
+            journal: None,
+            autobook: None,
+            autobook_depth: 14,
+            time_weights: PHASE_WEIGHTS,
+        }
+    }
+}
+
+impl Config {
+    /*
+     * Returns the path of the config file: rust-othello/config.toml in the config directory of
+     * the user, that is $XDG_CONFIG_HOME or ~/.config on Unix, and %APPDATA% on Windows.
+     */
+    pub fn default_path() -> Option<PathBuf> {
+        let xdg: Option<OsString> = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty());
+        let dir: PathBuf = if cfg!(windows) {
+            PathBuf::from(std::env::var_os("APPDATA")?)
+        } else if let Some(dir) = xdg {
+            PathBuf::from(dir)
+        } else {
+            PathBuf::from(std::env::var_os("HOME")?).join(".config")
+        };
+        Some(dir.join("rust-othello").join("config.toml"))
+    }
+
+    /*
+     * Reads the config file at its default path, or returns the default options if there is
+     * none.
+     */
+    pub fn load() -> Result<Config, OthelloError> {
+        match Config::default_path() {
+            Some(path) if path.exists() => Config::from_file(path),
+            _ => Ok(Config::default()),
+        }
+    }
+
+    /*
+     * Reads the config file at the given path.
+     */
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Config, OthelloError> {
+        Config::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /*
+     * Reads a config file. Only the subset of TOML needed here is understood: one key = value
+     * pair per line, values being integers or double-quoted strings, and # comments. Options
+     * may also be put in an [engine] table.
+     */
+    pub fn parse(text: &str) -> Result<Config, OthelloError> {
+        let mut config: Config = Config::default();
+
+        for (i, line) in text.lines().enumerate() {
+            let line: &str = strip_comment(line).trim();
+            if line.is_empty() || line == "[engine]" {
+                continue;
+            }
+
+            let (key, value) = line.split_once('=')
+                .ok_or_else(|| parse_error(format!("Expected key = value at line {}.", i + 1)))?;
+            let value: String = parse_value(value.trim())
+                .ok_or_else(|| parse_error(format!("Invalid value at line {}.", i + 1)))?;
+            config.set_option(key.trim(), &value)?;
+        }
+
+        Ok(config)
+    }
+
+    /*
+     * Sets the option of the given name, case insensitive, from its value written as text.
+     */
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), OthelloError> {
+        let invalid = || parse_error(format!("Invalid value for option {}: {}", name, value));
+
+        match name.to_ascii_lowercase().as_str() {
+            "depth" => self.depth = parse_positive(value).ok_or_else(invalid)?,
+            "personality" => self.personality = Personality::from_name(value).ok_or_else(invalid)?,
+            "contempt" => self.contempt = value.parse().map_err(|_| invalid())?,
+            "ordering" => self.ordering = MoveOrdering::from_name(value).ok_or_else(invalid)?,
+            "nodes" => self.nodes = value.parse().map_err(|_| invalid())?,
+            "adaptive" => self.adaptive = value.parse().map_err(|_| invalid())?,
+            "threads" => self.threads = parse_positive(value).ok_or_else(invalid)?,
+            "hash" => self.hash = value.parse().map_err(|_| invalid())?,
+            "book" => self.book = Some(PathBuf::from(value)).filter(|_| !value.is_empty()),
+            "book_margin" => self.book_margin = value.parse().map_err(|_| invalid())?,
+            "book_temperature" => {
+                self.book_temperature = value.parse().ok().filter(|t: &f64| *t >= 0.0)
+                    .ok_or_else(invalid)?
+            },
+            "weights" | "evalfile" => {
+                self.weights = Some(PathBuf::from(value)).filter(|_| !value.is_empty())
+            },
+            "journal" => self.journal = Some(PathBuf::from(value)).filter(|_| !value.is_empty()),
+            "autobook" => self.autobook = Some(PathBuf::from(value)).filter(|_| !value.is_empty()),
+            "autobook_depth" => self.autobook_depth = parse_positive(value).ok_or_else(invalid)?,
+            "time_opening" => self.time_weights[0] = parse_positive(value).ok_or_else(invalid)?,
+            "time_midgame" => self.time_weights[1] = parse_positive(value).ok_or_else(invalid)?,
+            "time_pre_endgame" => {
+                self.time_weights[2] = parse_positive(value).ok_or_else(invalid)?
+            },
+            "time_endgame" => self.time_weights[3] = parse_positive(value).ok_or_else(invalid)?,
+            _ => return Err(parse_error(format!("Unknown option: {}", name))),
+        }
+
+        Ok(())
+    }
+
+    /*
+     * Returns the warnings about the options: the files of the book and of the weights given
+     * but missing, which the AI does without.
+     */
+    pub fn warnings(&self) -> Vec<Warning> {
+        let mut warnings: Vec<Warning> = Vec::new();
+        if let Some(path) = self.weights.as_ref().filter(|path| !path.exists()) {
+            warnings.push(Warning::MissingWeights(path.clone()));
+        }
+        if let Some(path) = self.book.as_ref().filter(|path| !path.exists()) {
+            warnings.push(Warning::MissingBook(path.clone()));
+        }
+        warnings
+    }
+
+    /*
+     * Creates the alpha-beta AI described by the options, loading its book and evaluation
+     * weights if they are given, the balanced personality using the embedded weights otherwise.
+     * When their files are missing, the AI plays with its built-in evaluation, from the disks
+     * and the mobility, and without a book, as told by warnings, rather than not at all. Files
+     * that can't be read or are malformed are still errors. The journal is created if missing.
+     */
+    pub fn player(&self) -> Result<AlphaBetaPlayer, OthelloError> {
+        let mut player: AlphaBetaPlayer = AlphaBetaPlayer::new(self.depth)
+            .with_personality(self.personality)
+            .with_contempt(self.contempt)
+            .with_ordering(self.ordering)
+            .with_opponent_model(self.adaptive)
+            .with_time_manager(TimeManager::new().with_phase_weights(self.time_weights));
+        if self.nodes > 0 {
+            player = player.with_node_limit(self.nodes);
+        }
+        if let Some(path) = self.weights.as_ref().filter(|path| path.exists()) {
+            player = player.with_weights(EvalWeights::load(path)?);
+        } else if self.personality == Personality::Balanced {
+            player = player.with_weights(EvalWeights::embedded());
+        }
+        if let Some(path) = self.book.as_ref().filter(|path| path.exists()) {
+            player = player.with_book(Arc::new(Book::open(path)?))
+                .with_book_variety(self.book_margin, self.book_temperature);
+        }
+        if let Some(path) = &self.journal {
+            player = player.with_journal(Arc::new(Journal::open(path)?));
+        }
+        Ok(player)
+    }
+}
+
+/*
+ * A problem with the options that doesn't keep the AI from playing, only weakens it.
+ */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Warning {
+    MissingWeights(PathBuf),
+    MissingBook(PathBuf),
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Warning::MissingWeights(path) => write!(f, "The weights file {} is missing, using \
+                the built-in evaluation", path.display()),
+            Warning::MissingBook(path) => write!(f, "The book {} is missing, playing without \
+                one", path.display()),
+        }
+    }
+}
+
+/*
+ * Parses a number, which must be positive.
+ */
+fn parse_positive<T: FromStr + Default + PartialOrd>(value: &str) -> Option<T> {
+    value.parse().ok().filter(|n| *n > T::default())
+}
+
+/*
+ * Removes the comment at the end of a line, if any, ignoring # inside strings.
+ */
+fn strip_comment(line: &str) -> &str {
+    let mut in_string: bool = false;
+    let mut escaped: bool = false;
+
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => (),
+        }
+    }
+
+    line
+}
+
+/*
+ * Reads a value, an integer or a double-quoted string with the \" and \\ escapes, as text.
+ */
+fn parse_value(value: &str) -> Option<String> {
+    if let Some(inner) = value.strip_prefix('"') {
+        let inner: &str = inner.strip_suffix('"')?;
+        let mut text: String = String::new();
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next()? {
+                    c @ ('"' | '\\') => text.push(c),
+                    _ => return None,
+                },
+                '"' => return None,
+                c => text.push(c),
+            }
+        }
+        Some(text)
+    } else {
+        value.parse::<i64>().ok().map(|n| n.to_string())
+    }
+}
+
+//#################################################################################################
+//
+//                                     ENGINE BUILDER
+//
+//#################################################################################################
+
+/*
+ * The engine of the crate, the alpha-beta AI, under the name front-ends know it by.
+ */
+pub type Engine = AlphaBetaPlayer;
+
+/*
+ * Builds an engine from its options, those of the config, in one expression, the book and the
+ * evaluation weights being loaded and the options checked by build:
+ * `Engine::builder().threads(4).hash_mb(256).book(path).eval(path).build()?`.
+ */
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct EngineBuilder {
+    config: Config,
+}
+
+impl AlphaBetaPlayer {
+    /*
+     * Returns a builder of the engine, with the default options.
+     */
+    pub fn builder() -> EngineBuilder {
+        EngineBuilder::default()
+    }
+}
+
+impl EngineBuilder {
+    /*
+     * Returns a builder starting from the options of the config.
+     */
+    pub fn from_config(config: &Config) -> EngineBuilder {
+        EngineBuilder { config: config.clone(), }
+    }
+
+    pub fn depth(mut self, depth: u8) -> EngineBuilder {
+        self.config.depth = depth;
+        self
+    }
+
+    pub fn personality(mut self, personality: Personality) -> EngineBuilder {
+        self.config.personality = personality;
+        self
+    }
+
+    pub fn contempt(mut self, contempt: i32) -> EngineBuilder {
+        self.config.contempt = contempt;
+        self
+    }
+
+    pub fn ordering(mut self, ordering: MoveOrdering) -> EngineBuilder {
+        self.config.ordering = ordering;
+        self
+    }
+
+    /*
+     * Limits the nodes searched for each move, see AlphaBetaPlayer::with_node_limit.
+     */
+    pub fn nodes(mut self, nodes: u64) -> EngineBuilder {
+        self.config.nodes = nodes;
+        self
+    }
+
+    /*
+     * Makes the engine model its opponent, see OpponentModel.
+     */
+    pub fn adaptive(mut self, adaptive: bool) -> EngineBuilder {
+        self.config.adaptive = adaptive;
+        self
+    }
+
+    /*
+     * Searches the root moves on a pool of the given number of threads.
+     */
+    pub fn threads(mut self, threads: usize) -> EngineBuilder {
+        self.config.threads = threads;
+        self
+    }
+
+    /*
+     * Sets the size of the hash tables of the searches using them, in megabytes.
+     */
+    pub fn hash_mb(mut self, hash: usize) -> EngineBuilder {
+        self.config.hash = hash;
+        self
+    }
+
+    /*
+     * Plays the moves of the opening book in the given file.
+     */
+    pub fn book(mut self, path: impl AsRef<Path>) -> EngineBuilder {
+        self.config.book = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /*
+     * Varies the book moves within the margin of the best one, with the given temperature, see
+     * AlphaBetaPlayer::with_book_variety.
+     */
+    pub fn book_variety(mut self, margin: i32, temperature: f64) -> EngineBuilder {
+        self.config.book_margin = margin;
+        self.config.book_temperature = temperature;
+        self
+    }
+
+    /*
+     * Evaluates positions with the weights in the given file, see EvalWeights::load.
+     */
+    pub fn eval(mut self, path: impl AsRef<Path>) -> EngineBuilder {
+        self.config.weights = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /*
+     * Appends the searches of the engine to the journal in the given file, see Journal.
+     */
+    pub fn journal(mut self, path: impl AsRef<Path>) -> EngineBuilder {
+        self.config.journal = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /*
+     * Shares the time of the engine between the phases of the game with the given weights, see
+     * TimeManager::with_phase_weights.
+     */
+    pub fn time_weights(mut self, weights: [u32; 4]) -> EngineBuilder {
+        self.config.time_weights = weights;
+        self
+    }
+
+    /*
+     * Returns the options of the engine being built.
+     */
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /*
+     * Builds the engine, failing if the depth or the threads are 0, or if the book or the
+     * weights can't be read. Missing files are only warned about, see Config::player.
+     */
+    pub fn build(self) -> Result<Engine, OthelloError> {
+        let invalid = |name: &str| parse_error(format!("Invalid value for option {}: 0", name));
+        if self.config.depth == 0 {
+            return Err(invalid("depth"));
+        }
+        if self.config.threads == 0 {
+            return Err(invalid("threads"));
+        }
+
+        let player: AlphaBetaPlayer = self.config.player()?;
+        Ok(match self.config.threads {
+            1 => player,
+            threads => player.with_executor(Arc::new(ThreadPool::new(threads))),
+        })
+    }
+}
+
+//#################################################################################################
+//
+//                                       CONFIG TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that config files are read, that options can be overridden afterwards, and that
+ * engines are built from them, even when their files are missing.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let text: &str = "# Engine options\n\
+                          [engine]\n\
+                          depth = 12\n\
+                          personality = \"positional\" # the best one\n\
+                          threads = 4\n\
+                          book = \"C:\\\\books\\\\#1.book\"\n";
+        let mut config: Config = Config::parse(text).unwrap();
+        assert_eq!(config.depth, 12);
+        assert_eq!(config.personality, Personality::Positional);
+        assert_eq!(config.threads, 4);
+        assert_eq!(config.hash, 16);
+        assert_eq!(config.book, Some(PathBuf::from("C:\\books\\#1.book")));
+
+        config.set_option("Depth", "5").unwrap();
+        assert_eq!(config.depth, 5);
+        assert!(config.set_option("threads", "0").is_err());
+        config.set_option("time_midgame", "200").unwrap();
+        assert_eq!(config.time_weights, [60, 200, 150, 20]);
+        config.set_option("book_margin", "6").unwrap();
+        config.set_option("book_temperature", "inf").unwrap();
+        assert_eq!((config.book_margin, config.book_temperature), (6, f64::INFINITY));
+        assert!(config.set_option("book_temperature", "-1").is_err());
+        assert!(Config::parse("colour = 1").is_err());
+        assert!(Config::parse("depth = \"deep").is_err());
+
+        let builder: EngineBuilder = Engine::builder().threads(2).hash_mb(256).depth(4);
+        assert_eq!((builder.config().threads, builder.config().hash), (2, 256));
+        assert!(builder.clone().build().is_ok());
+        assert!(builder.clone().threads(0).build().is_err());
+        let builder: EngineBuilder = builder.book("missing.book").eval("missing.weights");
+        assert_eq!(builder.config().warnings(), [
+            Warning::MissingWeights(PathBuf::from("missing.weights")),
+            Warning::MissingBook(PathBuf::from("missing.book")),
+        ]);
+        assert!(builder.build().is_ok());
+    }
+}
@@ -0,0 +1,220 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread::JoinHandle;
+
+use crate::game::*;
+use crate::player::*;
+use crate::shell::*;
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                        MOCK GUI
+//
+//#################################################################################################
+
+/*
+ * A protocol handler driven by the mock GUI: it is sent one command at a time, and returns
+ * the lines answered, or the message of the error the command failed with.
+ */
+trait Handler {
+    fn send(&mut self, command: &str) -> Result<String, String>;
+}
+
+/*
+ * The shell, as driven by a GUI through its commands.
+ */
+impl Handler for Shell {
+    fn send(&mut self, command: &str) -> Result<String, String> {
+        let mut out: Vec<u8> = Vec::new();
+        match self.execute(command, &mut out) {
+            Ok(_) => Ok(String::from_utf8_lossy(&out).into_owned()),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+}
+
+/*
+ * A connection to player::serve answering with the given player on another thread. A command
+ * gets the line answered, and when the connection is closed instead, the error serve stopped
+ * with, if any.
+ */
+struct Served {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    server: Option<JoinHandle<Result<(), OthelloError>>>,
+}
+
+impl Served {
+    fn new(mut player: impl Player + Send + 'static) -> Served {
+        let listener: TcpListener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || serve(listener.accept()?.0, &mut player));
+        let writer: TcpStream = TcpStream::connect(addr).unwrap();
+        let reader: BufReader<TcpStream> = BufReader::new(writer.try_clone().unwrap());
+        Served { reader, writer, server: Some(server), }
+    }
+}
+
+impl Handler for Served {
+    fn send(&mut self, command: &str) -> Result<String, String> {
+        let mut answer: String = String::new();
+        let answered: bool = writeln!(self.writer, "{}", command).is_ok()
+            && self.reader.read_line(&mut answer).is_ok_and(|n| n > 0);
+        if answered {
+            return Ok(answer);
+        }
+        match self.server.take().map(|server| server.join().unwrap()) {
+            Some(Err(err)) => Err(err.to_string()),
+            _ => Err("Connection closed".to_string()),
+        }
+    }
+}
+
+/*
+ * Plays a scripted conversation with the handler, panicking at the first step not going as
+ * expected, with the conversation so far. A script has one step per line, blank lines being
+ * skipped:
+ *   - "> <command>" sends a command, in which any line of moves between braces, like {f5d6},
+ *     is replaced with the hexadecimal position reached by it, as sent by a NetworkPlayer,
+ *   - "< <text>" expects a line answered to the last command to start with the text, leading
+ *     spaces aside, after the lines already expected, any answer matching an empty text,
+ *   - "! <text>" expects the last command to have failed with an error containing the text.
+ * The conversation is strict: a command failing when no error is expected fails it, and so
+ * does a command succeeding when one is.
+ */
+fn converse(handler: &mut dyn Handler, script: &str) {
+    let mut transcript: String = String::new();
+    let mut last: Option<Result<String, String>> = None;
+    let mut expected: usize = 0;
+
+    for step in script.lines().map(str::trim).filter(|step| !step.is_empty()) {
+        transcript += &format!("{}\n", step);
+        let fail = |reason: &str| panic!("{}\nafter the conversation:\n{}", reason, transcript);
+        let (kind, text) = step.split_at(1);
+        let text: &str = text.trim();
+
+        match (kind, &last) {
+            (">", Some(Err(err))) => fail(&format!("Unexpected error: {}", err)),
+            (">", _) => {
+                last = Some(handler.send(&with_positions(text)));
+                expected = 0;
+            },
+            ("<", Some(Ok(answer))) => {
+                let lines: Vec<&str> = answer.lines().map(str::trim_start).collect();
+                match lines[expected..].iter().position(|line| line.starts_with(text)) {
+                    Some(i) => expected += i + 1,
+                    None => fail(&format!("Expected \"{}\" in the answer:\n{}", text, answer)),
+                }
+            },
+            ("!", Some(Err(err))) if err.contains(text) => last = None,
+            ("!", Some(Err(err))) => {
+                fail(&format!("Expected \"{}\" in the error: {}", text, err))
+            },
+            ("!", Some(Ok(_))) => fail("Expected an error"),
+            _ => fail("Invalid step"),
+        }
+    }
+    if let Some(Err(err)) = last {
+        panic!("Unexpected error: {}\nafter the conversation:\n{}", err, transcript);
+    }
+}
+
+/*
+ * Replaces the lines of moves between braces of a command with the positions they reach.
+ */
+fn with_positions(command: &str) -> String {
+    let mut result: String = String::new();
+    let mut rest: &str = command;
+    while let Some((before, after)) = rest.split_once('{') {
+        let (line, after) = after.split_once('}').expect("Unclosed brace");
+        let game: Game = Game::from_line(line).expect("Invalid line of moves");
+        result += before;
+        result += &to_hex(game.board(), game.color());
+        rest = after;
+    }
+    result + rest
+}
+
+//#################################################################################################
+//
+//                                    CONFORMANCE TEST
+//
+//#################################################################################################
+
+/*
+ * Checks the conversations of GUIs with the shell and with servers of remote players,
+ * malformed commands and commands sent during an analysis included.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::*;
+
+    #[test]
+    fn shell() {
+        let mut shell: Shell = Shell::new(Config { depth: 2, ..Config::default() });
+        converse(&mut shell, "
+            > help
+            < Commands:
+            < stop
+            > load f5d6
+            < Black to move.
+            > hint 2
+            < 1.
+            < 2.
+            > frobnicate
+            ! Unknown command: frobnicate
+            > play a1
+            ! Illegal
+            > undo many
+            ! Invalid count: many
+            > sync ----
+            ! Differing squares: d5, e5, f5, d6.
+            > go
+            > go
+            > board
+            < Black to move.
+            > set depth 3
+            > set depth 0
+            ! Invalid value for option depth
+            > set
+            ! Usage: set <option> <value>
+            > c3
+            < White to move.
+            > go excludemoves c4 searchmoves
+            ! Usage: go
+            > go excludemoves c4
+            > undo 2
+            < White to move.
+            > stop
+            > stop
+            > quit
+        ");
+    }
+
+    #[test]
+    fn serve() {
+        converse(&mut Served::new(RandomPlayer::new(1)), "
+            > play {f5} 1000
+            < f6
+            > draw {f5d6} -
+            ! Invalid request
+        ");
+        converse(&mut Served::new(RandomPlayer::new(1)), "
+            > play {f5d6c3} -
+            <
+            > play {f5d6c3d3c4} nan
+            ! Invalid request
+        ");
+        converse(&mut Served::new(RandomPlayer::new(1)), "
+            > play {f5} -
+            > play {f5f6} -
+            ! doesn't follow the last move played
+        ");
+        converse(&mut Served::new(RandomPlayer::new(1)), "
+            > play 0123 -
+            ! Invalid position: 0123
+        ");
+    }
+}
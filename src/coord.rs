@@ -0,0 +1,174 @@
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use crate::types::*;
+
+//#################################################################################################
+//
+//                                     CONVENTION TYPE
+//
+//#################################################################################################
+
+/*
+ * The two conventions used to name the squares: a1 in the top-left corner, which is the usual
+ * one in Othello, or a1 in the bottom-left corner, like in chess. Columns are always lettered
+ * from a to h from left to right.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Convention {
+    TopLeft, BottomLeft,
+}
+
+/*
+ * The convention used by default, shared by the whole program.
+ */
+static CONVENTION: AtomicU8 = AtomicU8::new(0);
+
+/*
+ * Changes the convention used by default when parsing and printing squares.
+ */
+pub fn set_convention(convention: Convention) {
+    CONVENTION.store(convention as u8, Ordering::Relaxed);
+}
+
+/*
+ * Returns the convention used by default when parsing and printing squares.
+ */
+pub fn convention() -> Convention {
+    match CONVENTION.load(Ordering::Relaxed) {
+        0 => Convention::TopLeft,
+        _ => Convention::BottomLeft,
+    }
+}
+
+//#################################################################################################
+//
+//                                       COORD TYPE
+//
+//#################################################################################################
+
+/*
+ * The coordinates of a square on the board as it is displayed, x being the column from the
+ * left and y the row from the top, both in 0..8. They map directly to the bits of BitBoards,
+ * whatever the convention used to name them.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Coord {
+    pub x: u8,
+    pub y: u8,
+}
+
+impl Coord {
+    /*
+     * Creates the Coord at (x, y), or returns None if it is outside of the board.
+     */
+    pub fn new(x: u8, y: u8) -> Option<Coord> {
+        if x < 8 && y < 8 {
+            Some(Coord { x, y, })
+        } else {
+            None
+        }
+    }
+
+    /*
+     * Returns the Coord of the square of a single-bit BitBoard.
+     */
+    pub fn from_bitboard(mv: BitBoard) -> Coord {
+        let index: u8 = mv.trailing_zeros() as u8;
+        Coord { x: index % 8, y: index / 8, }
+    }
+
+    /*
+     * Returns the single-bit BitBoard of the square.
+     */
+    #[inline(always)]
+    pub fn to_bitboard(self) -> BitBoard {
+        1u64 << (self.x + 8 * self.y)
+    }
+
+    /*
+     * Returns the number of the row of the square, in 1..=8, in the given convention.
+     */
+    pub fn row(self, convention: Convention) -> u8 {
+        match convention {
+            Convention::TopLeft => self.y + 1,
+            Convention::BottomLeft => 8 - self.y,
+        }
+    }
+
+    /*
+     * Returns the name of the square, of the form "[a-h][1-8]", in the default convention.
+     */
+    #[cfg(feature = "std")]
+    pub fn name(self) -> String {
+        self.name_with(convention())
+    }
+
+    /*
+     * Returns the name of the square, of the form "[a-h][1-8]", in the given convention.
+     */
+    #[cfg(feature = "std")]
+    pub fn name_with(self, convention: Convention) -> String {
+        format!("{}{}", (b'a' + self.x) as char, self.row(convention))
+    }
+
+    /*
+     * Parses a square name of the form "[a-h][1-8]", case insensitive, in the default
+     * convention.
+     */
+    pub fn parse(name: &str) -> Option<Coord> {
+        Coord::parse_with(name, convention())
+    }
+
+    /*
+     * Parses a square name of the form "[a-h][1-8]", case insensitive, in the given convention.
+     */
+    pub fn parse_with(name: &str, convention: Convention) -> Option<Coord> {
+        match name.as_bytes() {
+            &[col, row] => {
+                let col: u8 = col.to_ascii_lowercase();
+                if !(b'a'..=b'h').contains(&col) || !(b'1'..=b'8').contains(&row) {
+                    return None;
+                }
+                let y: u8 = match convention {
+                    Convention::TopLeft => row - b'1',
+                    Convention::BottomLeft => b'8' - row,
+                };
+                Some(Coord { x: col - b'a', y, })
+            },
+            _ => None,
+        }
+    }
+}
+
+//#################################################################################################
+//
+//                                     CONVENTION TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that both conventions name the squares as expected. Only the per call functions are
+ * used, as tests run concurrently and the default convention is shared.
+ */
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conventions() {
+        let a1: Coord = Coord { x: 0, y: 0, };
+        assert_eq!(a1.name_with(Convention::TopLeft), "a1");
+        assert_eq!(a1.name_with(Convention::BottomLeft), "a8");
+        assert_eq!(Coord::parse_with("D3", Convention::TopLeft), Coord::new(3, 2));
+        assert_eq!(Coord::parse_with("d3", Convention::BottomLeft), Coord::new(3, 5));
+        assert_eq!(Coord::parse_with("i3", Convention::TopLeft), None);
+
+        for index in 0..64 {
+            let coord: Coord = Coord::from_bitboard(1u64 << index);
+            for &convention in &[Convention::TopLeft, Convention::BottomLeft] {
+                let name: String = coord.name_with(convention);
+                assert_eq!(Coord::parse_with(&name, convention), Some(coord));
+            }
+        }
+    }
+}
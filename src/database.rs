@@ -0,0 +1,566 @@
+use std::collections::VecDeque;
+use std::path::Path;
+
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+use rusqlite::types::Value;
+
+use crate::types::*;
+use crate::othello::*;
+use crate::game::*;
+use crate::record::*;
+use crate::cache::*;
+use crate::symmetry::*;
+use crate::wire::*;
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                      DATABASE TYPE
+//
+//#################################################################################################
+
+/*
+ * The schema of the database. Games have their players, date, result token and termination, as
+ * in the text format of records, and the final disk differential for black. Their moves are
+ * stored one per row, encoded as bytes like in the wire module, and as a blob of those bytes,
+ * the line, for queries. Lines are normalized to start with f5, the symmetry applied being
+ * stored with them. Analyses are keyed by canonical position and player to move, like in the
+ * analysis cache, their principal variation being a blob of square indices.
+ */
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS games (
+        id INTEGER PRIMARY KEY,
+        black TEXT NOT NULL,
+        white TEXT NOT NULL,
+        date TEXT NOT NULL,
+        result TEXT NOT NULL,
+        termination TEXT,
+        score INTEGER NOT NULL,
+        line BLOB NOT NULL,
+        symmetry INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS games_black ON games (black);
+    CREATE INDEX IF NOT EXISTS games_white ON games (white);
+    CREATE INDEX IF NOT EXISTS games_score ON games (score);
+    CREATE INDEX IF NOT EXISTS games_line ON games (line);
+    CREATE TABLE IF NOT EXISTS moves (
+        game INTEGER NOT NULL REFERENCES games(id) ON DELETE CASCADE,
+        ply INTEGER NOT NULL,
+        square INTEGER NOT NULL,
+        PRIMARY KEY (game, ply)
+    );
+    CREATE TABLE IF NOT EXISTS analyses (
+        black INTEGER NOT NULL,
+        white INTEGER NOT NULL,
+        color INTEGER NOT NULL,
+        score INTEGER NOT NULL,
+        depth INTEGER NOT NULL,
+        pv BLOB NOT NULL,
+        PRIMARY KEY (black, white, color)
+    );
+";
+
+/*
+ * A database of games and analyses stored with SQLite, into which game collections can be
+ * imported to be queried later on.
+ */
+pub struct Database {
+    conn: Connection,
+}
+
+/*
+ * Turns an SQLite error into an I/O error.
+ */
+fn sql_error(err: rusqlite::Error) -> OthelloError {
+    OthelloError::Io(std::io::Error::other(format!("Database error: {}", err)))
+}
+
+impl Database {
+    /*
+     * Opens the database in the given file, creating it if needed.
+     */
+    pub fn open(path: impl AsRef<Path>) -> Result<Database, OthelloError> {
+        Database::init(Connection::open(path).map_err(sql_error)?)
+    }
+
+    /*
+     * Opens a new database living in memory only.
+     */
+    pub fn open_in_memory() -> Result<Database, OthelloError> {
+        Database::init(Connection::open_in_memory().map_err(sql_error)?)
+    }
+
+    /*
+     * Creates the tables of a newly opened database, if they don't exist yet.
+     */
+    fn init(conn: Connection) -> Result<Database, OthelloError> {
+        conn.execute_batch(SCHEMA).map_err(sql_error)?;
+        Ok(Database { conn, })
+    }
+
+//#################################################################################################
+//
+//                                          GAMES
+//
+//#################################################################################################
+
+    /*
+     * Adds a game to the database and returns its id. Handicap games can't be stored, their
+     * positions being out of the symmetries the lines are stored under.
+     */
+    pub fn insert_game(&mut self, record: &GameRecord) -> Result<i64, OthelloError> {
+        if record.handicap > 0 {
+            return Err(parse_error("Handicap games can't be stored in the database."));
+        }
+        let game: Game = record.to_game()?;
+        let tx = self.conn.transaction().map_err(sql_error)?;
+        let id: i64 = insert_game(&tx, record, &game)?;
+        tx.commit().map_err(sql_error)?;
+        Ok(id)
+    }
+
+    /*
+     * Adds several games to the database at once. Handicap games and games whose moves are
+     * illegal are skipped, the number of games added is returned.
+     */
+    pub fn insert_games(&mut self, records: &[GameRecord]) -> Result<usize, OthelloError> {
+        let tx = self.conn.transaction().map_err(sql_error)?;
+        let mut count: usize = 0;
+
+        for record in records {
+            if let Some(game) = record.to_game().ok().filter(|_| record.handicap == 0) {
+                insert_game(&tx, record, &game)?;
+                count += 1;
+            }
+        }
+
+        tx.commit().map_err(sql_error)?;
+        Ok(count)
+    }
+
+    /*
+     * Returns the game with the given id, if any.
+     */
+    pub fn game(&self, id: i64) -> Result<Option<GameRecord>, OthelloError> {
+        self.conn
+            .query_row(
+                &format!("SELECT {} FROM games WHERE id = ?1", GAME_COLUMNS), [id], read_game,
+            )
+            .optional()
+            .map_err(sql_error)?
+            .map(|row| row.map(|(_, record)| record))
+            .transpose()
+    }
+
+    /*
+     * Returns the number of games in the database.
+     */
+    pub fn game_count(&self) -> Result<usize, OthelloError> {
+        self.conn
+            .query_row("SELECT COUNT(*) FROM games", [], |row| row.get::<_, i64>(0))
+            .map(|count| count as usize)
+            .map_err(sql_error)
+    }
+
+    /*
+     * Imports a WTHOR games file, see the wthor module. The names of the players are read from
+     * the given players file, if any. Returns the number of games added.
+     */
+    pub fn import_wthor(&mut self, games: impl AsRef<Path>, players: Option<&Path>)
+        -> Result<usize, OthelloError>
+    {
+        let names: Vec<String> = match players {
+            Some(path) => crate::wthor::parse_players(&read_file(path)?)?,
+            None => Vec::new(),
+        };
+        let records: Vec<GameRecord> = crate::wthor::parse_games(&read_file(games)?, &names)?;
+        self.insert_games(&records)
+    }
+
+    /*
+     * Imports a GGF collection, see the ggf module. Returns the number of games added.
+     */
+    pub fn import_ggf(&mut self, path: impl AsRef<Path>) -> Result<usize, OthelloError> {
+        let text: String = String::from_utf8_lossy(&read_file(path)?).into_owned();
+        let records: Vec<GameRecord> = crate::ggf::parse_games(&text)?;
+        self.insert_games(&records)
+    }
+
+    /*
+     * Imports the JSON or CSV export of an online app, see the online module. Returns the
+     * number of games added.
+     */
+    pub fn import_online(&mut self, path: impl AsRef<Path>) -> Result<usize, OthelloError> {
+        let text: String = String::from_utf8_lossy(&read_file(path)?).into_owned();
+        let records: Vec<GameRecord> = crate::online::parse_games(&text)?;
+        self.insert_games(&records)
+    }
+
+//#################################################################################################
+//
+//                                         ANALYSES
+//
+//#################################################################################################
+
+    /*
+     * Stores the analysis of the given position, unless a deeper one is already known.
+     */
+    pub fn insert_analysis(&self, oth: Othello, color: Color, analysis: &Analysis)
+        -> Result<(), OthelloError>
+    {
+        let (canonical, symmetry) = oth.canonical();
+        let pv: Vec<u8> = analysis.transform(symmetry).pv.into_iter().map(move_to_byte).collect();
+
+        self.conn
+            .execute(
+                "INSERT INTO analyses (black, white, color, score, depth, pv)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT (black, white, color) DO UPDATE
+                 SET score = excluded.score, depth = excluded.depth, pv = excluded.pv
+                 WHERE excluded.depth >= analyses.depth",
+                params![
+                    canonical.get_bitboard(Color::Black) as i64,
+                    canonical.get_bitboard(Color::White) as i64,
+                    color as u8,
+                    analysis.score,
+                    analysis.depth,
+                    pv,
+                ],
+            )
+            .map(|_| ())
+            .map_err(sql_error)
+    }
+
+    /*
+     * Returns the analysis of the given position, if any.
+     */
+    pub fn analysis(&self, oth: Othello, color: Color) -> Result<Option<Analysis>, OthelloError> {
+        let (canonical, symmetry) = oth.canonical();
+
+        let row = self.conn
+            .query_row(
+                "SELECT score, depth, pv FROM analyses
+                 WHERE black = ?1 AND white = ?2 AND color = ?3",
+                params![
+                    canonical.get_bitboard(Color::Black) as i64,
+                    canonical.get_bitboard(Color::White) as i64,
+                    color as u8,
+                ],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get::<_, Vec<u8>>(2)?)),
+            )
+            .optional()
+            .map_err(sql_error)?;
+
+        Ok(row.map(|(score, depth, pv)| {
+            let pv: Vec<BitBoard> = pv.into_iter().map(square_to_move).collect();
+            Analysis { score, pv, depth, }.transform(symmetry.inverse())
+        }))
+    }
+}
+
+//#################################################################################################
+//
+//                                         QUERIES
+//
+//#################################################################################################
+
+/*
+ * A query over the games of the database, keeping the games matching all of its filters: the
+ * opening line they start with, whatever its orientation, the range of their final disk
+ * differential for black, and the name of one of their players. A query without filters
+ * matches every game.
+ */
+#[derive(Clone, Default)]
+pub struct GameQuery {
+    opening: Vec<BitBoard>,
+    score: Option<(i32, i32)>,
+    player: Option<String>,
+}
+
+impl GameQuery {
+    /*
+     * Creates a new query matching every game.
+     */
+    pub fn new() -> GameQuery {
+        GameQuery::default()
+    }
+
+    /*
+     * Keeps the games starting with the given moves, or with a symmetric line.
+     */
+    pub fn with_opening(mut self, moves: &[BitBoard]) -> GameQuery {
+        self.opening = moves.to_vec();
+        self
+    }
+
+    /*
+     * Keeps the games whose final disk differential for black is within min..=max.
+     */
+    pub fn with_score(mut self, min: i32, max: i32) -> GameQuery {
+        self.score = Some((min, max));
+        self
+    }
+
+    /*
+     * Keeps the games played by the given player, with either color.
+     */
+    pub fn with_player(mut self, name: &str) -> GameQuery {
+        self.player = Some(name.to_string());
+        self
+    }
+}
+
+/*
+ * The number of games read from the database at once by a query.
+ */
+const BATCH_SIZE: usize = 1024;
+
+/*
+ * An iterator over the games matching a query, yielding their ids and records in the order
+ * they were added. Games are read in batches, so that queries over millions of games don't
+ * hold them all in memory.
+ */
+pub struct Games<'a> {
+    db: &'a Database,
+    sql: String,
+    params: Vec<Value>,
+    last_id: i64,
+    batch: VecDeque<(i64, GameRecord)>,
+    done: bool,
+}
+
+impl Database {
+    /*
+     * Returns an iterator over the games matching the query.
+     */
+    pub fn query(&self, query: &GameQuery) -> Games<'_> {
+        let mut conditions: String = String::new();
+        let mut params: Vec<Value> = Vec::new();
+
+        if !query.opening.is_empty() {
+            let symmetry: Symmetry = normalizing_symmetry(&query.opening);
+            let start: Vec<u8> = query.opening.iter()
+                .map(|&mv| move_to_byte(symmetry.apply(mv)))
+                .collect();
+            let mut end: Vec<u8> = start.clone();
+            *end.last_mut().unwrap() += 1;
+
+            let n: usize = params.len() + 2;
+            conditions += &format!(" AND line >= ?{} AND line < ?{}", n, n + 1);
+            params.push(Value::Blob(start));
+            params.push(Value::Blob(end));
+        }
+        if let Some((min, max)) = query.score {
+            let n: usize = params.len() + 2;
+            conditions += &format!(" AND score BETWEEN ?{} AND ?{}", n, n + 1);
+            params.push(Value::Integer(min as i64));
+            params.push(Value::Integer(max as i64));
+        }
+        if let Some(player) = &query.player {
+            conditions += &format!(" AND (black = ?{0} OR white = ?{0})", params.len() + 2);
+            params.push(Value::Text(player.clone()));
+        }
+
+        Games {
+            db: self,
+            sql: format!(
+                "SELECT {} FROM games WHERE id > ?1{} ORDER BY id LIMIT {}",
+                GAME_COLUMNS, conditions, BATCH_SIZE,
+            ),
+            params,
+            last_id: 0,
+            batch: VecDeque::new(),
+            done: false,
+        }
+    }
+}
+
+impl Games<'_> {
+    /*
+     * Reads the next batch of games.
+     */
+    fn fetch(&mut self) -> Result<(), OthelloError> {
+        let mut stmt = self.db.conn.prepare_cached(&self.sql).map_err(sql_error)?;
+        let params = std::iter::once(Value::Integer(self.last_id))
+            .chain(self.params.iter().cloned());
+        let rows = stmt.query_map(params_from_iter(params), read_game).map_err(sql_error)?;
+
+        for row in rows {
+            self.batch.push_back(row.map_err(sql_error)??);
+        }
+        if self.batch.len() < BATCH_SIZE {
+            self.done = true;
+        }
+        if let Some(&(id, _)) = self.batch.back() {
+            self.last_id = id;
+        }
+
+        Ok(())
+    }
+}
+
+impl Iterator for Games<'_> {
+    type Item = Result<(i64, GameRecord), OthelloError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.batch.is_empty() && !self.done {
+            if let Err(err) = self.fetch() {
+                self.done = true;
+                return Some(Err(err));
+            }
+        }
+
+        self.batch.pop_front().map(Ok)
+    }
+}
+
+/*
+ * Adds a game to the database within a transaction and returns its id. The game is the one of
+ * the record, replayed, whose history has every pass.
+ */
+fn insert_game(tx: &rusqlite::Transaction, record: &GameRecord, game: &Game)
+    -> Result<i64, OthelloError>
+{
+    let score: Score = game.board().score();
+    let symmetry: Symmetry = normalizing_symmetry(game.history());
+    let line: Vec<u8> = game.history().iter()
+        .map(|&mv| move_to_byte(symmetry.apply(mv)))
+        .collect();
+
+    tx.execute(
+        "INSERT INTO games (black, white, date, result, termination, score, line, symmetry)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            record.black,
+            record.white,
+            record.date,
+            result_token(record.outcome),
+            record.outcome.and_then(|outcome| termination(outcome.reason)),
+            score.get(Color::Black) as i32 - score.get(Color::White) as i32,
+            line,
+            symmetry as u8,
+        ],
+    ).map_err(sql_error)?;
+    let id: i64 = tx.last_insert_rowid();
+
+    let mut stmt = tx
+        .prepare_cached("INSERT INTO moves (game, ply, square) VALUES (?1, ?2, ?3)")
+        .map_err(sql_error)?;
+    for (ply, &mv) in game.history().iter().enumerate() {
+        stmt.execute(params![id, ply as i64, move_to_byte(mv)]).map_err(sql_error)?;
+    }
+
+    Ok(id)
+}
+
+/*
+ * The columns read by read_game.
+ */
+const GAME_COLUMNS: &str = "id, black, white, date, result, termination, line, symmetry";
+
+/*
+ * Reads a game from a row of GAME_COLUMNS, returning its id and its record.
+ */
+fn read_game(row: &Row) -> rusqlite::Result<Result<(i64, GameRecord), OthelloError>> {
+    let termination: Option<String> = row.get(5)?;
+    let line: Vec<u8> = row.get(6)?;
+    let symmetry: Symmetry = Symmetry::ALL[row.get::<_, u8>(7)? as usize % 8];
+    let result: String = row.get(4)?;
+    let (id, black, white, date) = (row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?);
+
+    Ok((|| {
+        let reason: Reason = match termination {
+            Some(name) => parse_termination(&name)?,
+            None => Reason::Finished,
+        };
+        let outcome: Option<Outcome> = parse_result(&result)?
+            .map(|winner| Outcome { winner, reason, });
+        let moves: Vec<BitBoard> = line.into_iter()
+            .map(|square| symmetry.inverse().apply(square_to_move(square)))
+            .collect();
+
+        Ok((id, GameRecord {
+            black, white, date, outcome, handicap: 0, moves, stats: Vec::new(),
+        }))
+    })())
+}
+
+/*
+ * Returns the symmetry of the starting position turning the first move of the given line into
+ * f5, or the identity if there is none.
+ */
+fn normalizing_symmetry(line: &[BitBoard]) -> Symmetry {
+    let f5: BitBoard = 1u64 << 37;
+    [Symmetry::Identity, Symmetry::Transpose, Symmetry::Rotate180, Symmetry::AntiTranspose]
+        .iter()
+        .copied()
+        .find(|symmetry| line.first().map(|&mv| symmetry.apply(mv)) == Some(f5))
+        .unwrap_or(Symmetry::Identity)
+}
+
+/*
+ * Turns a square index read from the database into a move, invalid ones standing for a pass.
+ */
+fn square_to_move(square: u8) -> BitBoard {
+    byte_to_move(square).unwrap_or(0)
+}
+
+/*
+ * Reads a whole file.
+ */
+fn read_file(path: impl AsRef<Path>) -> Result<Vec<u8>, OthelloError> {
+    let path: &Path = path.as_ref();
+    std::fs::read(path).map_err(|err| {
+        let msg: String = format!("Can't read {}: {}", path.display(), err);
+        OthelloError::Io(std::io::Error::new(err.kind(), msg))
+    })
+}
+
+//#################################################################################################
+//
+//                                      DATABASE TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that games and analyses come back out of the database as they went in, and that
+ * queries find symmetric openings.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut db: Database = Database::open_in_memory().unwrap();
+
+        let ggf: &str = "(;GM[Othello]PB[alice]PW[bob]DT[2003.08.22]TY[8]RE[+2.000:r]
+            BO[8 -------- -------- -------- ---O*--- ---*O--- -------- -------- -------- *]
+            B[f5//0.01]W[d6]B[c3/1.5/2]W[d3];)
+            (;GM[Othello]TY[10]B[f5];)
+            (;GM[Othello]PB[carol]PW[bob]TY[8]RE[?]B[d3]W[c5];)";
+        let records: Vec<GameRecord> = crate::ggf::parse_games(ggf).unwrap();
+        assert_eq!(db.insert_games(&records).unwrap(), 2);
+
+        let count = |query: GameQuery| db.query(&query).map(Result::unwrap).count();
+        let opening: Vec<BitBoard> = records[0].moves.clone();
+        assert_eq!(count(GameQuery::new()), 2);
+        assert_eq!(count(GameQuery::new().with_opening(&opening[..2])), 2);
+        assert_eq!(count(GameQuery::new().with_opening(&opening[..3])), 1);
+        assert_eq!(count(GameQuery::new().with_player("bob").with_score(0, 0)), 2);
+        assert_eq!(count(GameQuery::new().with_score(1, 64)), 0);
+        assert_eq!(count(GameQuery::new().with_player("alice")), 1);
+
+        let record: GameRecord = db.game(1).unwrap().unwrap();
+        assert_eq!(record.moves, records[0].moves);
+        assert_eq!(record.to_text(), records[0].to_text());
+        assert!(record.outcome.unwrap().reason == Reason::Resignation);
+
+        let oth: Othello = Othello::new();
+        let analysis: Analysis = Analysis { score: 2, pv: vec![1u64 << 37, 0], depth: 6, };
+        db.insert_analysis(oth, Color::Black, &analysis).unwrap();
+        db.insert_analysis(oth, Color::Black, &Analysis { depth: 2, ..analysis.clone() }).unwrap();
+        assert_eq!(db.analysis(oth, Color::Black).unwrap(), Some(analysis));
+        assert_eq!(db.analysis(oth, Color::White).unwrap(), None);
+    }
+}
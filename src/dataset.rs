@@ -0,0 +1,343 @@
+use std::convert::TryInto;
+use std::path::Path;
+
+use crate::types::*;
+use crate::othello::*;
+use crate::game::*;
+use crate::player::*;
+use crate::mcts::MctsPlayer;
+use crate::features::*;
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                      DATASET TYPE
+//
+//#################################################################################################
+
+/*
+ * A dataset of positions for training evaluation functions or networks, each with its
+ * features from the point of view of the player to move, see features::extract, the policy
+ * target over the 64 squares and the outcome of the game for that player: 1 for a win, 0 for
+ * a draw and -1 for a loss. It is written as a NumPy .npz archive that Python training code
+ * can load with numpy.load.
+ */
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Dataset {
+    pub features: Vec<[f32; FEATURES]>,
+    pub policies: Vec<[f32; 64]>,
+    pub outcomes: Vec<f32>,
+}
+
+impl Dataset {
+    /*
+     * Returns the number of positions in the dataset.
+     */
+    pub fn len(&self) -> usize {
+        self.outcomes.len()
+    }
+
+    /*
+     * Returns true if the dataset has no position.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.outcomes.is_empty()
+    }
+
+    /*
+     * Adds a position, with the given player to move, its policy and outcome for him.
+     */
+    pub fn add(&mut self, oth: Othello, color: Color, policy: [f32; 64], outcome: f32) {
+        self.features.push(extract(oth, color));
+        self.policies.push(policy);
+        self.outcomes.push(outcome);
+    }
+
+    /*
+     * Adds every position of a finished game from the given ply on, where the player to move
+     * had a choice, the policy being the move he played. Does nothing if the game isn't over.
+     */
+    pub fn add_game(&mut self, game: &Game, from_ply: usize) {
+        let winner: Option<Color> = match game.outcome() {
+            Some(outcome) => outcome.winner,
+            None => return,
+        };
+        let mut oth: Othello = Othello::with_handicap(game.handicap());
+        let mut color: Color = Color::Black;
+
+        for (ply, &mv) in game.history().iter().enumerate() {
+            if mv != 0 && ply >= from_ply {
+                let mut policy: [f32; 64] = [0.0; 64];
+                policy[mv.trailing_zeros() as usize] = 1.0;
+                self.add(oth, color, policy, outcome(winner, color));
+            }
+            if mv != 0 {
+                oth = oth.make_move(color, mv);
+            }
+            color = color.invert();
+        }
+    }
+
+    /*
+     * Plays the given number of games of the player against himself and returns their
+     * positions. The first plies of each game are played at random, with the given seed, so
+     * that the games differ, and are left out of the dataset.
+     */
+    pub fn self_play(
+        player: &mut dyn Player, games: usize, random_plies: usize, seed: u64,
+    ) -> Result<Dataset, OthelloError> {
+        let mut dataset: Dataset = Dataset::default();
+        let mut random: RandomPlayer = RandomPlayer::new(seed);
+        let clock: Clock = Clock::unlimited();
+
+        for _ in 0..games {
+            let mut game: Game = Game::new();
+            while !game.is_over() {
+                let chooser: &mut dyn Player = if game.history().len() < random_plies {
+                    &mut random
+                } else {
+                    player
+                };
+                match chooser.choose_move(&game, &clock) {
+                    Action::Play(mv) | Action::OfferDraw(mv) => game.play(mv)?,
+                    Action::Resign => game.resign(game.color()),
+                }
+            }
+            dataset.add_game(&game, random_plies);
+        }
+
+        Ok(dataset)
+    }
+
+    /*
+     * Plays the given number of games of the Monte-Carlo tree search AI against itself, the
+     * policy of each position being the visits of the moves at the root of its search,
+     * normalized, as in AlphaZero. For the given number of plies, moves are drawn in
+     * proportion to their visits raised to the power of 1 / temperature, then the most visited
+     * one is played. Root noise, see MctsPlayer::with_root_noise, makes the games explore
+     * more.
+     */
+    pub fn mcts_self_play(
+        player: &MctsPlayer, games: usize, temperature: f64, temperature_plies: usize,
+        seed: u64,
+    ) -> Result<Dataset, OthelloError> {
+        let mut dataset: Dataset = Dataset::default();
+        let mut rng: u64 = (seed ^ 0x9E3779B97F4A7C15).max(1);
+
+        for _ in 0..games {
+            let mut game: Game = Game::new();
+            let mut positions: Vec<(Othello, Color, [f32; 64])> = Vec::new();
+
+            while !game.is_over() {
+                let visits: Vec<(BitBoard, u32)> = player.root_visits(game.board(), game.color());
+                let total: f32 = visits.iter().map(|&(_, n)| n as f32).sum::<f32>().max(1.0);
+                let mut policy: [f32; 64] = [0.0; 64];
+                for &(mv, n) in visits.iter().filter(|&&(mv, _)| mv != 0) {
+                    policy[mv.trailing_zeros() as usize] = n as f32 / total;
+                }
+                positions.push((game.board(), game.color(), policy));
+
+                let hot: bool = game.history().len() < temperature_plies && temperature > 0.0;
+                let mv: BitBoard = if hot {
+                    sample(&visits, temperature, &mut rng)
+                } else {
+                    visits.iter().max_by_key(|&&(_, n)| n).map_or(0, |&(mv, _)| mv)
+                };
+                game.play(mv)?;
+            }
+
+            let winner: Option<Color> = game.outcome().and_then(|outcome| outcome.winner);
+            for (oth, color, policy) in positions {
+                dataset.add(oth, color, policy, outcome(winner, color));
+            }
+        }
+
+        Ok(dataset)
+    }
+
+    /*
+     * Writes the dataset as an uncompressed NumPy .npz archive holding three float32 arrays:
+     * features of shape (n, FEATURES), policies of shape (n, 64) and outcomes of shape (n,).
+     */
+    pub fn write_npz(&self, path: impl AsRef<Path>) -> Result<(), OthelloError> {
+        let n: usize = self.len();
+        let features: Vec<f32> = self.features.iter().flatten().copied().collect();
+        let policies: Vec<f32> = self.policies.iter().flatten().copied().collect();
+
+        let arrays: [(&str, Vec<u8>); 3] = [
+            ("features.npy", npy(&[n, FEATURES], &features)),
+            ("policies.npy", npy(&[n, 64], &policies)),
+            ("outcomes.npy", npy(&[n], &self.outcomes)),
+        ];
+        std::fs::write(path, zip(&arrays)?)?;
+        Ok(())
+    }
+}
+
+/*
+ * Returns the outcome of a game with the given winner for the given player.
+ */
+fn outcome(winner: Option<Color>, color: Color) -> f32 {
+    match winner {
+        Some(winner) if winner == color => 1.0,
+        Some(_) => -1.0,
+        None => 0.0,
+    }
+}
+
+/*
+ * Draws one of the moves in proportion to its visits raised to the power of 1 / temperature.
+ */
+fn sample(visits: &[(BitBoard, u32)], temperature: f64, rng: &mut u64) -> BitBoard {
+    let weights: Vec<f64> = visits.iter()
+        .map(|&(_, n)| (n as f64).powf(1.0 / temperature))
+        .collect();
+    let mut target: f64 = next_random(rng) as f64 / u64::MAX as f64 * weights.iter().sum::<f64>();
+
+    for (&(mv, _), weight) in visits.iter().zip(&weights) {
+        if target < *weight {
+            return mv;
+        }
+        target -= weight;
+    }
+    visits.last().map_or(0, |&(mv, _)| mv)
+}
+
+//#################################################################################################
+//
+//                                      NPZ ENCODING
+//
+//#################################################################################################
+
+/*
+ * Encodes an array of little-endian float32 in the .npy format, version 1.0: a magic string,
+ * the length of the header, the header itself, a Python dict padded with spaces so that the
+ * data is aligned on 64 bytes, then the data.
+ */
+fn npy(shape: &[usize], data: &[f32]) -> Vec<u8> {
+    let dims: Vec<String> = shape.iter().map(|dim| dim.to_string()).collect();
+    let shape: String = match dims.as_slice() {
+        [dim] => format!("({},)", dim),
+        dims => format!("({})", dims.join(", ")),
+    };
+    let mut header: String =
+        format!("{{'descr': '<f4', 'fortran_order': False, 'shape': {}, }}", shape);
+    while !(10 + header.len() + 1).is_multiple_of(64) {
+        header.push(' ');
+    }
+    header.push('\n');
+
+    let mut bytes: Vec<u8> = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    for value in data {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes
+}
+
+/*
+ * Bundles the given files in a zip archive, stored without compression as NumPy reads them
+ * fine this way. Returns an error if the archive would be too large for the zip format
+ * without its 64-bit extensions.
+ */
+fn zip(files: &[(&str, Vec<u8>)]) -> Result<Vec<u8>, OthelloError> {
+    let too_large = || parse_error("The dataset is too large for an npz archive.".to_string());
+    let mut archive: Vec<u8> = Vec::new();
+    let mut directory: Vec<u8> = Vec::new();
+
+    for (name, data) in files {
+        let offset: u32 = archive.len().try_into().map_err(|_| too_large())?;
+        let size: u32 = data.len().try_into().map_err(|_| too_large())?;
+
+        // The fields common to the local header and the central directory entry: versions,
+        // flags, method, time and date, checksum, sizes and length of the name.
+        let mut common: Vec<u8> = Vec::new();
+        for field in [20u16, 0, 0, 0, 0x21] {
+            common.extend_from_slice(&field.to_le_bytes());
+        }
+        common.extend_from_slice(&crc32(data).to_le_bytes());
+        common.extend_from_slice(&size.to_le_bytes());
+        common.extend_from_slice(&size.to_le_bytes());
+        common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+
+        archive.extend_from_slice(&0x04034B50u32.to_le_bytes());
+        archive.extend_from_slice(&common);
+        archive.extend_from_slice(&0u16.to_le_bytes());
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(data);
+
+        directory.extend_from_slice(&0x02014B50u32.to_le_bytes());
+        directory.extend_from_slice(&20u16.to_le_bytes());
+        directory.extend_from_slice(&common);
+        directory.extend_from_slice(&[0; 12]);
+        directory.extend_from_slice(&offset.to_le_bytes());
+        directory.extend_from_slice(name.as_bytes());
+    }
+
+    let offset: u32 = archive.len().try_into().map_err(|_| too_large())?;
+    let entries: u16 = files.len() as u16;
+    archive.extend_from_slice(&directory);
+    archive.extend_from_slice(&0x06054B50u32.to_le_bytes());
+    archive.extend_from_slice(&[0; 4]);
+    archive.extend_from_slice(&entries.to_le_bytes());
+    archive.extend_from_slice(&entries.to_le_bytes());
+    archive.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+    archive.extend_from_slice(&offset.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes());
+    Ok(archive)
+}
+
+/*
+ * The CRC-32 checksum of zip archives, computed bit by bit, fast enough for datasets.
+ */
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc: u32 = !0;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB88320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+//#################################################################################################
+//
+//                                      DATASET TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that a game gives one position per move and the right outcomes, with the moves played
+ * or the visits of the MCTS as policies, and the encodings against known values.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dataset() {
+        let mut dataset: Dataset = Dataset::self_play(&mut RandomPlayer::new(7), 2, 4, 1).unwrap();
+        assert!(dataset.len() >= 2 * 50 && dataset.len() <= 2 * 56);
+        assert!(dataset.policies.iter().all(|policy| policy.iter().sum::<f32>() == 1.0));
+        assert!(dataset.outcomes.iter().all(|outcome| [-1.0, 0.0, 1.0].contains(outcome)));
+
+        let len: usize = dataset.len();
+        dataset.add_game(&Game::new(), 0);
+        assert_eq!(dataset.len(), len);
+
+        let player: MctsPlayer = MctsPlayer::new(50).with_root_noise(0.3, 0.25);
+        let dataset: Dataset = Dataset::mcts_self_play(&player, 1, 1.0, 10, 1).unwrap();
+        assert!(dataset.len() >= 50 && dataset.len() <= 60);
+        assert!(dataset.policies.iter()
+            .all(|policy| (policy.iter().sum::<f32>() - 1.0).abs() < 1e-4));
+        let noise: Vec<f64> = crate::mcts::dirichlet(0.3, 10, &mut 12345);
+        assert!(noise.iter().all(|&x| x >= 0.0) && (noise.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+
+        assert_eq!(crc32(b"123456789"), 0xCBF43926);
+        let array: Vec<u8> = npy(&[2], &[1.0, 2.0]);
+        assert_eq!(array.len(), 128 + 8);
+        assert!(array.starts_with(b"\x93NUMPY\x01\x00\x76\x00{'descr': '<f4'"));
+    }
+}
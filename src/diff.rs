@@ -0,0 +1,90 @@
+use crate::types::*;
+use crate::othello::*;
+
+//#################################################################################################
+//
+//                                       BOARD DIFF
+//
+//#################################################################################################
+
+/*
+ * The space between the two boards drawn side by side.
+ */
+const GAP: &str = "    ";
+
+/*
+ * Returns the squares that differ between the two boards.
+ */
+pub fn differing_squares(left: Othello, right: Othello) -> BitBoard {
+    let black: BitBoard = left.get_bitboard(Color::Black) ^ right.get_bitboard(Color::Black);
+    black | (left.get_bitboard(Color::White) ^ right.get_bitboard(Color::White))
+}
+
+/*
+ * Draws the two boards side by side as plain text, under the given titles, the squares that
+ * differ between them being put between brackets, and lists these squares below. This is what
+ * goes in protocol errors when the position of a GUI or another program gets out of sync with
+ * the one of the engine, to see at a glance where they parted.
+ */
+pub fn board_diff(left: Othello, right: Othello, titles: (&str, &str)) -> String {
+    let differing: BitBoard = differing_squares(left, right);
+    let width: usize = 2 + 3 * 8;
+    let header: String = format!("  {}", (b'a'..=b'h').map(|c| format!(" {} ", c as char))
+        .collect::<String>());
+
+    let mut text: String = format!("{:width$}{}{}\n", titles.0, GAP, titles.1, width = width);
+    text += &format!("{}{}{}\n", header, GAP, header.trim_end());
+    for y in 0..8 {
+        for (i, oth) in [left, right].iter().enumerate() {
+            text += if i == 0 { "" } else { GAP };
+            text += &format!("{} ", y + 1);
+            for x in 0..8 {
+                let c: char = oth.get_square(x, y).to_char();
+                let highlighted: bool = differing.contains(x, y);
+                text += &if highlighted { format!("[{}]", c) } else { format!(" {} ", c) };
+            }
+        }
+        text.truncate(text.trim_end().len());
+        text.push('\n');
+    }
+
+    let mut squares: Vec<String> = Vec::new();
+    let mut rest: BitBoard = differing;
+    while rest != 0 {
+        squares.push(rest.pop_lsb().square_name());
+    }
+    text += &match squares.len() {
+        0 => "The boards are the same.\n".to_string(),
+        _ => format!("Differing squares: {}.\n", squares.join(", ")),
+    };
+    text
+}
+
+//#################################################################################################
+//
+//                                     BOARD DIFF TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that the differing squares are highlighted on both boards and listed.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn board_diff() {
+        let left: Othello = Othello::new();
+        let right: Othello = left.make_move(Color::Black, 1 << 37);
+        let diff: String = super::board_diff(left, right, ("Engine", "GUI"));
+        let lines: Vec<&str> = diff.lines().collect();
+
+        assert_eq!(lines.len(), 11);
+        assert!(lines[0].starts_with("Engine") && lines[0].ends_with("GUI"));
+        assert_eq!(lines[5], "4  -  -  -  O  X  -  -  -     4  -  -  -  O  X  -  -  -");
+        assert_eq!(lines[6], "5  -  -  -  X [O][-] -  -     5  -  -  -  X [X][X] -  -");
+        assert_eq!(lines[10], "Differing squares: e5, f5.");
+        assert!(super::board_diff(left, left, ("", "")).ends_with("The boards are the same.\n"));
+    }
+}
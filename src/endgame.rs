@@ -0,0 +1,318 @@
+use crate::types::*;
+use crate::othello::*;
+
+//#################################################################################################
+//
+//                                     ENDGAME SOLVER
+//
+//#################################################################################################
+
+/*
+ * Returns the final disc differential of a finished game from the point of view of the given
+ * color, empty squares being awarded to the winner as usual.
+ */
+pub(crate) fn final_score(oth: Othello, color: Color) -> i32 {
+    let own: i32 = oth.get_bitboard(color).pop_cnt() as i32;
+    let opp: i32 = oth.get_bitboard(color.invert()).pop_cnt() as i32;
+    let empties: i32 = oth.empties() as i32;
+
+    if own > opp {
+        own - opp + empties
+    } else if own < opp {
+        own - opp - empties
+    } else {
+        0
+    }
+}
+
+/*
+ * Exhaustive negamax with alpha-beta pruning, searching until the end of the game.
+ */
+pub(crate) fn negamax(oth: Othello, color: Color, mut alpha: i32, beta: i32, passed: bool)
+    -> i32
+{
+    let mut moves: BitBoard = oth.gen_moves(color);
+
+    if moves == 0 {
+        if passed {
+            return final_score(oth, color);
+        }
+        return -negamax(oth, color.invert(), -beta, -alpha, true);
+    }
+
+    let mut best: i32 = -64;
+
+    while moves != 0 {
+        let new_oth: Othello = oth.make_move(color, moves.pop_lsb());
+        let value: i32 = -negamax(new_oth, color.invert(), -beta, -alpha, false);
+        if value > best {
+            best = value;
+            if value > alpha {
+                alpha = value;
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+    }
+
+    best
+}
+
+/*
+ * Solves the position exactly and returns the final disc differential with perfect play from
+ * the point of view of the given color. Only practical with few empty squares left.
+ */
+pub fn solve(oth: Othello, color: Color) -> i32 {
+    negamax(oth, color, -64, 64, false)
+}
+
+/*
+ * Only solves the position for a win, a draw or a loss of the given color, which is much faster
+ * than an exact solve. Returns 1, 0 or -1 accordingly.
+ */
+pub fn solve_wld(oth: Othello, color: Color) -> i32 {
+    negamax(oth, color, -1, 1, false).signum()
+}
+
+/*
+ * Returns the winner of the position with perfect play, or None if it is a draw.
+ */
+pub fn solve_winner(oth: Othello, color: Color) -> Option<Color> {
+    match solve_wld(oth, color) {
+        1 => Some(color),
+        -1 => Some(color.invert()),
+        _ => None,
+    }
+}
+
+//#################################################################################################
+//
+//                                      ADJUDICATION
+//
+//#################################################################################################
+
+/*
+ * The number of nodes searched by solve_bounded between two calls to its expiry check.
+ */
+const CHECK_INTERVAL: u64 = 4096;
+
+/*
+ * The result of a position with perfect play: the winner, None for a draw, and by how many
+ * disks he wins. It is displayed like "Black wins by 4 with perfect play".
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ExactResult {
+    pub winner: Option<Color>,
+    pub margin: u8,
+}
+
+impl ExactResult {
+    /*
+     * Returns the result of a final disc differential from the point of view of the given
+     * color.
+     */
+    pub fn from_score(score: i32, color: Color) -> ExactResult {
+        let winner: Option<Color> = match score.signum() {
+            1 => Some(color),
+            -1 => Some(color.invert()),
+            _ => None,
+        };
+        ExactResult { winner, margin: score.unsigned_abs() as u8, }
+    }
+
+    /*
+     * Returns the final disc differential from the point of view of the given color.
+     */
+    pub fn score(&self, color: Color) -> i32 {
+        match self.winner {
+            Some(winner) if winner == color => self.margin as i32,
+            Some(_) => -(self.margin as i32),
+            None => 0,
+        }
+    }
+}
+
+impl core::fmt::Display for ExactResult {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self.winner {
+            Some(winner) => write!(f, "{} wins by {} with perfect play", winner, self.margin),
+            None => write!(f, "Draw with perfect play"),
+        }
+    }
+}
+
+/*
+ * The negamax of the solver, giving up once the expiry check, called every CHECK_INTERVAL
+ * nodes, returns true.
+ */
+fn negamax_bounded(
+    oth: Othello,
+    color: Color,
+    mut alpha: i32,
+    beta: i32,
+    passed: bool,
+    nodes: &mut u64,
+    expired: &mut dyn FnMut() -> bool,
+) -> Option<i32> {
+    *nodes += 1;
+    if nodes.is_multiple_of(CHECK_INTERVAL) && expired() {
+        return None;
+    }
+
+    let mut moves: BitBoard = oth.gen_moves(color);
+
+    if moves == 0 {
+        if passed {
+            return Some(final_score(oth, color));
+        }
+        return Some(-negamax_bounded(oth, color.invert(), -beta, -alpha, true, nodes, expired)?);
+    }
+
+    let mut best: i32 = -64;
+
+    while moves != 0 {
+        let new_oth: Othello = oth.make_move(color, moves.pop_lsb());
+        let value: i32 =
+            -negamax_bounded(new_oth, color.invert(), -beta, -alpha, false, nodes, expired)?;
+        if value > best {
+            best = value;
+            if value > alpha {
+                alpha = value;
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+    }
+
+    Some(best)
+}
+
+/*
+ * Solves the position exactly like solve, unless the expiry check, called every few thousand
+ * nodes, returns true first, in which case None is returned.
+ */
+pub fn solve_bounded(oth: Othello, color: Color, expired: &mut dyn FnMut() -> bool)
+    -> Option<i32>
+{
+    negamax_bounded(oth, color, -64, 64, false, &mut 0, expired)
+}
+
+/*
+ * The time adjudicate gives the solver to prove the result.
+ */
+#[cfg(feature = "std")]
+pub const ADJUDICATION_TIME: std::time::Duration = std::time::Duration::from_millis(250);
+
+/*
+ * Returns the result of the position with perfect play, the given player being to move, for
+ * GUIs showing it, but only when there are at most the given number of empty squares and the
+ * solver proves it within ADJUDICATION_TIME. Otherwise, returns None.
+ */
+#[cfg(feature = "std")]
+pub fn adjudicate(oth: &Othello, to_move: Color, max_empties: u8) -> Option<ExactResult> {
+    adjudicate_within(oth, to_move, max_empties, ADJUDICATION_TIME)
+}
+
+/*
+ * Like adjudicate, with the given time instead of ADJUDICATION_TIME.
+ */
+#[cfg(feature = "std")]
+pub fn adjudicate_within(
+    oth: &Othello,
+    to_move: Color,
+    max_empties: u8,
+    time: std::time::Duration,
+) -> Option<ExactResult> {
+    if oth.empties() > max_empties {
+        return None;
+    }
+
+    let deadline: std::time::Instant = std::time::Instant::now() + time;
+    let score: i32 = solve_bounded(*oth, to_move, &mut || std::time::Instant::now() >= deadline)?;
+    Some(ExactResult::from_score(score, to_move))
+}
+
+//#################################################################################################
+//
+//                                      SOLVER TEST
+//
+//#################################################################################################
+
+/*
+ * Checks the solver against a plain minimax without any pruning, and that adjudication only
+ * answers within its number of empty squares and its time.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /*
+     * Minimax without pruning, returning the final disc differential for the given color.
+     */
+    fn minimax(oth: Othello, color: Color, passed: bool) -> i32 {
+        let mut moves: BitBoard = oth.gen_moves(color);
+
+        if moves == 0 {
+            if passed { return final_score(oth, color); }
+            return -minimax(oth, color.invert(), true);
+        }
+
+        let mut best: i32 = -64;
+        while moves != 0 {
+            best = best.max(-minimax(oth.make_move(color, moves.pop_lsb()), color.invert(), false));
+        }
+
+        best
+    }
+
+    /*
+     * Plays the first legal move until only a few empty squares are left, then compares.
+     */
+    #[test]
+    fn correctness() {
+        let mut oth: Othello = Othello::new();
+        let mut color: Color = Color::Black;
+
+        while oth.empties() > 9 {
+            let mut moves: BitBoard = oth.gen_moves(color);
+            if moves == 0 {
+                color = color.invert();
+                moves = oth.gen_moves(color);
+            }
+            oth = oth.make_move(color, moves.pop_lsb());
+            color = color.invert();
+
+            if oth.empties() <= 12 {
+                let exact: i32 = minimax(oth, color, false);
+                assert_eq!(solve(oth, color), exact);
+                assert_eq!(solve_wld(oth, color), exact.signum());
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn adjudication() {
+        let mut oth: Othello = Othello::new();
+        let zero: std::time::Duration = std::time::Duration::ZERO;
+        assert_eq!(adjudicate(&oth, Color::Black, 40), None);
+        assert_eq!(adjudicate_within(&oth, Color::Black, 60, zero), None);
+
+        let mut color: Color = Color::Black;
+        while oth.empties() > 12 || oth.gen_moves(color) == 0 {
+            if oth.gen_moves(color) != 0 {
+                oth = oth.make_move(color, oth.gen_moves(color).pop_lsb());
+            }
+            color = color.invert();
+        }
+        let result: Option<ExactResult> = adjudicate(&oth, color, 12);
+        assert_eq!(result.map(|result| result.score(color)), Some(solve(oth, color)));
+
+        let result: ExactResult = ExactResult::from_score(-4, Color::White);
+        assert_eq!(result.score(Color::Black), 4);
+        assert_eq!(result.to_string(), "Black wins by 4 with perfect play");
+        assert_eq!(ExactResult::from_score(0, Color::Black).to_string(), "Draw with perfect play");
+    }
+}
@@ -0,0 +1,63 @@
+use crate::types::*;
+
+//#################################################################################################
+//
+//                                       ERROR TYPE
+//
+//#################################################################################################
+
+/*
+ * The errors returned by the fallible functions of the crate:
+ *   - ParseError when a text or a file, a game record or a collection for example, is malformed.
+ *   - IllegalMove when a move breaks the rules, with the reason why.
+ *   - Io when reading or writing a file, or a database, fails.
+ *   - ProtocolError when another program talking to this one, a GUI or an engine, misbehaves.
+ * The board logic of the no_std core, like Othello::try_make_move, only returns MoveErrors.
+ */
+#[derive(Debug)]
+pub enum OthelloError {
+    ParseError(String),
+    IllegalMove(MoveError),
+    Io(std::io::Error),
+    ProtocolError(String),
+}
+
+impl std::fmt::Display for OthelloError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            OthelloError::ParseError(msg) => write!(f, "Parse error: {}", msg),
+            OthelloError::IllegalMove(err) => write!(f, "Illegal move: {}", err),
+            OthelloError::Io(err) => write!(f, "I/O error: {}", err),
+            OthelloError::ProtocolError(msg) => write!(f, "Protocol error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for OthelloError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OthelloError::IllegalMove(err) => Some(err),
+            OthelloError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<MoveError> for OthelloError {
+    fn from(err: MoveError) -> OthelloError {
+        OthelloError::IllegalMove(err)
+    }
+}
+
+impl From<std::io::Error> for OthelloError {
+    fn from(err: std::io::Error) -> OthelloError {
+        OthelloError::Io(err)
+    }
+}
+
+/*
+ * Shorthand to build a ParseError from a message.
+ */
+pub(crate) fn parse_error(msg: impl Into<String>) -> OthelloError {
+    OthelloError::ParseError(msg.into())
+}
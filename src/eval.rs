@@ -0,0 +1,539 @@
+use std::path::Path;
+
+use crate::types::*;
+use crate::othello::*;
+use crate::mapped::*;
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                        EVALUATION
+//
+//#################################################################################################
+
+/*
+ * The masks of the squares the evaluation function gives weight to.
+ */
+pub(crate) const CORNERS: BitBoard = 0x8100000000000081;
+pub(crate) const A_SQUARES: BitBoard = 0x2400810000810024;
+pub(crate) const B_SQUARES: BitBoard = 0x1800008181000018;
+const CENTER: BitBoard = 0x00003C3C3C3C0000;
+const RING: BitBoard = 0x003C424242423C00;
+pub(crate) const C_SQUARES: BitBoard = 0x4281000000008142;
+pub(crate) const X_SQUARES: BitBoard = 0x0042000000004200;
+
+/*
+ * The weights of the evaluation function: one per mask, one per move of mobility and one per
+ * disk, whatever its square.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct EvalWeights {
+    pub corners: i32,
+    pub a_squares: i32,
+    pub b_squares: i32,
+    pub center: i32,
+    pub ring: i32,
+    pub c_squares: i32,
+    pub x_squares: i32,
+    pub mobility: i32,
+    pub disks: i32,
+}
+
+impl Default for EvalWeights {
+    fn default() -> EvalWeights {
+        Personality::Balanced.weights()
+    }
+}
+
+/*
+ * The magic number and version of evaluation weight files, whose payload is the weights in the
+ * order of the fields above, as little-endian i32s.
+ */
+const WEIGHTS_MAGIC: &[u8; 8] = b"OTHELLEV";
+const WEIGHTS_VERSION: u32 = 1;
+
+/*
+ * The default weight file. The weights are those of the balanced personality, adjusted by hand
+ * to value the A and B squares and mobility a little more: 12, 7 and 7 instead of 10, 5 and 5.
+ */
+#[cfg(feature = "default-weights")]
+const EMBEDDED_WEIGHTS: &[u8] = include_bytes!("../weights/default.weights");
+
+impl EvalWeights {
+    /*
+     * Returns the weights in the order of the fields.
+     */
+    fn to_array(self) -> [i32; 9] {
+        [
+            self.corners, self.a_squares, self.b_squares, self.center, self.ring,
+            self.c_squares, self.x_squares, self.mobility, self.disks,
+        ]
+    }
+
+    /*
+     * Reads weights from a file, mapping it into memory. Returns an error if the file can't be
+     * read or isn't a valid weight file.
+     */
+    pub fn load(path: impl AsRef<Path>) -> Result<EvalWeights, OthelloError> {
+        let file: MappedFile = MappedFile::open(path, WEIGHTS_MAGIC, WEIGHTS_VERSION)?;
+        EvalWeights::from_payload(file.payload())
+    }
+
+    /*
+     * Reads weights from the bytes of a weight file.
+     */
+    pub fn from_bytes(bytes: &[u8]) -> Result<EvalWeights, OthelloError> {
+        EvalWeights::from_payload(payload(bytes, WEIGHTS_MAGIC, WEIGHTS_VERSION)?)
+    }
+
+    /*
+     * Reads weights from the payload of a weight file.
+     */
+    fn from_payload(payload: &[u8]) -> Result<EvalWeights, OthelloError> {
+        if payload.len() != 36 {
+            return Err(parse_error("Invalid weight file."));
+        }
+
+        let w: Vec<i32> = payload.chunks_exact(4)
+            .map(|chunk| i32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+        Ok(EvalWeights {
+            corners: w[0], a_squares: w[1], b_squares: w[2], center: w[3], ring: w[4],
+            c_squares: w[5], x_squares: w[6], mobility: w[7], disks: w[8],
+        })
+    }
+
+    /*
+     * Returns the default weights embedded in the executable by the "default-weights" feature,
+     * hand-adjusted estimates nudged from those of the balanced personality, or the latter
+     * without the feature. Only the config uses them, for the AI of balanced personality
+     * without weights of its own.
+     */
+    #[cfg(feature = "default-weights")]
+    pub fn embedded() -> EvalWeights {
+        EvalWeights::from_bytes(EMBEDDED_WEIGHTS).expect("The embedded weights are valid.")
+    }
+
+    #[cfg(not(feature = "default-weights"))]
+    pub fn embedded() -> EvalWeights {
+        Personality::Balanced.weights()
+    }
+
+    /*
+     * Writes the weights to a file.
+     */
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), OthelloError> {
+        let payload: Vec<u8> = self.to_array().iter().flat_map(|w| w.to_le_bytes()).collect();
+        write_file(path, WEIGHTS_MAGIC, WEIGHTS_VERSION, &payload)
+    }
+}
+
+/*
+ * The evaluation function, using masks to do a weighted sum of the board and the move generation
+ * algorithm to measure mobility for both players.
+ */
+#[inline(always)]
+pub(crate) fn evaluate(oth: Othello, weights: &EvalWeights) -> i32 {
+    EvalBreakdown::new(oth, weights).total()
+}
+
+impl EvalWeights {
+    /*
+     * Evaluates the given board with the weights, from black's point of view.
+     */
+    pub fn evaluate(&self, oth: Othello) -> i32 {
+        evaluate(oth, self)
+    }
+
+    /*
+     * Evaluates every board, like evaluate, but BATCH_LANES boards at a time: their disks are
+     * laid out lane by lane and each term is computed for all the lanes before the next, loops
+     * the compiler can vectorize. The results are the same as evaluate's, in the same order.
+     */
+    pub fn evaluate_batch(&self, positions: &[Othello]) -> Vec<i32> {
+        let terms: [(BitBoard, i32); 8] = [
+            (CORNERS, self.corners), (A_SQUARES, self.a_squares), (B_SQUARES, self.b_squares),
+            (CENTER, self.center), (RING, self.ring), (C_SQUARES, self.c_squares),
+            (X_SQUARES, self.x_squares), (!0, self.disks),
+        ];
+        let mut values: Vec<i32> = Vec::with_capacity(positions.len());
+
+        for chunk in positions.chunks(BATCH_LANES) {
+            let mut black: [BitBoard; BATCH_LANES] = [0; BATCH_LANES];
+            let mut white: [BitBoard; BATCH_LANES] = [0; BATCH_LANES];
+            let mut lanes: [i32; BATCH_LANES] = [0; BATCH_LANES];
+            for (i, oth) in chunk.iter().enumerate() {
+                black[i] = oth.get_bitboard(Color::Black);
+                white[i] = oth.get_bitboard(Color::White);
+            }
+
+            for (mask, weight) in terms {
+                for lane in 0..BATCH_LANES {
+                    let delta: i32 = (black[lane] & mask).pop_cnt() as i32
+                        - (white[lane] & mask).pop_cnt() as i32;
+                    lanes[lane] += weight * delta;
+                }
+            }
+
+            for (i, oth) in chunk.iter().enumerate() {
+                let mobility: i32 = oth.gen_moves(Color::Black).pop_cnt() as i32
+                    - oth.gen_moves(Color::White).pop_cnt() as i32;
+                values.push(lanes[i] + self.mobility * mobility);
+            }
+        }
+
+        values
+    }
+}
+
+/*
+ * The number of boards evaluated together by EvalWeights::evaluate_batch.
+ */
+const BATCH_LANES: usize = 8;
+
+/*
+ * The terms of the evaluation function taken separately, so that they can be explained to the
+ * user. Like the evaluation, they are from black's point of view and sum up to it.
+ */
+#[derive(Clone, Copy, Default)]
+pub struct EvalBreakdown {
+    pub corners: i32,
+    pub edges: i32,
+    pub center: i32,
+    pub c_squares: i32,
+    pub x_squares: i32,
+    pub mobility: i32,
+    pub disks: i32,
+}
+
+impl EvalBreakdown {
+    /*
+     * Computes the breakdown of the evaluation of the given board with the given weights.
+     */
+    #[inline(always)]
+    pub fn new(oth: Othello, weights: &EvalWeights) -> EvalBreakdown {
+        let black: BitBoard = oth.get_bitboard(Color::Black);
+        let white: BitBoard = oth.get_bitboard(Color::White);
+
+        macro_rules! delta_mask {
+            ($mask: expr, $val: expr) => {
+                $val * ((black & $mask).pop_cnt() as i32 - (white & $mask).pop_cnt() as i32)
+            }
+        }
+
+        let black_mobility: i32 = oth.gen_moves(Color::Black).pop_cnt() as i32;
+        let white_mobility: i32 = oth.gen_moves(Color::White).pop_cnt() as i32;
+
+        EvalBreakdown {
+            corners: delta_mask!(CORNERS, weights.corners),
+            edges: delta_mask!(A_SQUARES, weights.a_squares)
+                + delta_mask!(B_SQUARES, weights.b_squares),
+            center: delta_mask!(CENTER, weights.center) + delta_mask!(RING, weights.ring),
+            c_squares: delta_mask!(C_SQUARES, weights.c_squares),
+            x_squares: delta_mask!(X_SQUARES, weights.x_squares),
+            mobility: weights.mobility * (black_mobility - white_mobility),
+            disks: delta_mask!(!0u64, weights.disks),
+        }
+    }
+
+    /*
+     * Returns the evaluation, that is the sum of all the terms.
+     */
+    pub fn total(&self) -> i32 {
+        self.corners + self.edges + self.center + self.c_squares + self.x_squares
+            + self.mobility + self.disks
+    }
+}
+
+//#################################################################################################
+//
+//                                      PERSONALITIES
+//
+//#################################################################################################
+
+/*
+ * The styles of play of the AI, to give varied opponents. Each one comes with its own weights
+ * for the evaluation and a few search options:
+ *   - Balanced is the default, weighting squares and mobility evenly.
+ *   - Aggressive loves mobility and fights for it, never accepting a draw.
+ *   - Greedy grabs as many disks as it can, looking only half as far ahead.
+ *   - Positional cares about good and bad squares above all.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Personality {
+    Balanced, Aggressive, Greedy, Positional,
+}
+
+impl Personality {
+    /*
+     * All the personalities.
+     */
+    pub const ALL: [Personality; 4] = [
+        Personality::Balanced, Personality::Aggressive, Personality::Greedy,
+        Personality::Positional,
+    ];
+
+    /*
+     * Returns the name of the personality, in lowercase.
+     */
+    pub fn name(self) -> &'static str {
+        match self {
+            Personality::Balanced => "balanced",
+            Personality::Aggressive => "aggressive",
+            Personality::Greedy => "greedy",
+            Personality::Positional => "positional",
+        }
+    }
+
+    /*
+     * Returns the personality with the given name, case insensitive.
+     */
+    pub fn from_name(name: &str) -> Option<Personality> {
+        Personality::ALL.iter().copied().find(|p| p.name().eq_ignore_ascii_case(name))
+    }
+
+    /*
+     * Returns the weights of the evaluation of the personality.
+     */
+    pub fn weights(self) -> EvalWeights {
+        match self {
+            Personality::Balanced => EvalWeights {
+                corners: 100, a_squares: 10, b_squares: 5, center: -1, ring: -2,
+                c_squares: -20, x_squares: -50, mobility: 5, disks: 0,
+            },
+            Personality::Aggressive => EvalWeights {
+                corners: 80, a_squares: 5, b_squares: 2, center: -1, ring: -1,
+                c_squares: -10, x_squares: -30, mobility: 15, disks: 0,
+            },
+            Personality::Greedy => EvalWeights {
+                corners: 20, a_squares: 2, b_squares: 2, center: 0, ring: 0,
+                c_squares: 0, x_squares: -5, mobility: 0, disks: 10,
+            },
+            Personality::Positional => EvalWeights {
+                corners: 150, a_squares: 20, b_squares: 10, center: -2, ring: -4,
+                c_squares: -40, x_squares: -80, mobility: 2, disks: 0,
+            },
+        }
+    }
+
+    /*
+     * Returns the depth searched by the personality, given the depth of the AI.
+     */
+    pub fn depth(self, max_depth: u8) -> u8 {
+        match self {
+            Personality::Greedy => std::cmp::max(max_depth / 2, 1),
+            _ => max_depth,
+        }
+    }
+
+    /*
+     * Returns true if the personality accepts draws when it thinks it is worse.
+     */
+    pub fn accepts_draws(self) -> bool {
+        self != Personality::Aggressive
+    }
+}
+
+//#################################################################################################
+//
+//                                       VALUE TYPE
+//
+//#################################################################################################
+
+/*
+ * The value of a won game in the search, from the point of view of the winner: a win in n
+ * plies from the root is worth WIN - n, so that the AI goes for the fastest wins and holds out
+ * the longest when it loses. Every value from PROVEN up is a proven win, and every value down
+ * from -PROVEN a proven loss, a value of WIN itself meaning a win at an unknown distance, as
+ * found in a book or a tablebase.
+ */
+pub const WIN: i32 = i32::MAX;
+pub const PROVEN: i32 = WIN - 256;
+
+/*
+ * Returns true if the value, from either point of view, is a proven win or loss.
+ */
+pub fn is_proven(value: i32) -> bool {
+    value >= PROVEN || value <= -PROVEN
+}
+
+/*
+ * A value shown to users, from the point of view of the player to move: the heuristic
+ * evaluation of the AI, in the units of its weights, the exact final disc differential of a
+ * solved position, or a game won or lost by force in the given number of plies, 0 when the
+ * distance isn't known.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Value {
+    Heuristic(i32),
+    Exact(i8),
+    Win(u8),
+    Loss(u8),
+}
+
+impl Value {
+    /*
+     * Returns the value of a result of the search, given from the point of view of the player.
+     */
+    pub fn from_search(value: i32) -> Value {
+        if value >= PROVEN {
+            Value::Win((WIN - value) as u8)
+        } else if value <= -PROVEN {
+            Value::Loss((WIN - value.saturating_neg()) as u8)
+        } else {
+            Value::Heuristic(value)
+        }
+    }
+
+    /*
+     * Returns the value as the search gives it, exact scores becoming wins and losses at an
+     * unknown distance, and draws 0.
+     */
+    pub fn to_search(self) -> i32 {
+        match self {
+            Value::Heuristic(value) => value.clamp(1 - PROVEN, PROVEN - 1),
+            Value::Exact(score) => WIN * score.signum() as i32,
+            Value::Win(plies) => WIN - plies as i32,
+            Value::Loss(plies) => plies as i32 - WIN,
+        }
+    }
+
+    /*
+     * Returns true if the value is known for sure.
+     */
+    pub fn is_proven(self) -> bool {
+        !matches!(self, Value::Heuristic(_))
+    }
+}
+
+/*
+ * Writes the value for front-ends: "+12" for heuristic ones, "+6 exact" for solved scores,
+ * and "win in 7" or "loss in 4" for proven results, or just "win" and "loss" at an unknown
+ * distance.
+ */
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Value::Heuristic(value) => write!(f, "{:+}", value),
+            Value::Exact(score) => write!(f, "{:+} exact", score),
+            Value::Win(0) => write!(f, "win"),
+            Value::Loss(0) => write!(f, "loss"),
+            Value::Win(plies) => write!(f, "win in {}", plies),
+            Value::Loss(plies) => write!(f, "loss in {}", plies),
+        }
+    }
+}
+
+/*
+ * Writes an evaluation of the search for front-ends, see Value.
+ */
+pub fn value_name(value: i32) -> String {
+    Value::from_search(value).to_string()
+}
+
+//#################################################################################################
+//
+//                                     WIN PROBABILITY
+//
+//#################################################################################################
+
+/*
+ * The scales of the logistic curve turning an evaluation into a probability of winning, for a
+ * few numbers of empty squares, interpolated in between. They are hand-picked estimates for the
+ * default weights: an evaluation is worth less and less as the board fills up, the disks and
+ * the squares it counts being about to change hands fewer times.
+ */
+const WIN_SCALES: [(f64, f64); 7] = [
+    (0.0, 170.0), (10.0, 130.0), (20.0, 90.0), (30.0, 63.0), (40.0, 42.0), (50.0, 34.0),
+    (60.0, 40.0),
+];
+
+/*
+ * Returns the probability that the player wins the game, counting draws as half wins, given
+ * the evaluation of the position from his point of view and the number of empty squares. Won
+ * and lost games are certain.
+ */
+pub fn win_probability(value: i32, empties: u8) -> f64 {
+    if value >= PROVEN {
+        1.0
+    } else if value <= -PROVEN {
+        0.0
+    } else {
+        1.0 / (1.0 + (-(value as f64) / interpolate(&WIN_SCALES, empties as f64)).exp())
+    }
+}
+
+/*
+ * Interpolates linearly between the points of a table sorted by abscissa, clamping outside.
+ */
+pub(crate) fn interpolate(table: &[(f64, f64)], x: f64) -> f64 {
+    for window in table.windows(2) {
+        let ((x0, y0), (x1, y1)) = (window[0], window[1]);
+        if x <= x1 {
+            return y0 + (y1 - y0) * (x - x0).max(0.0) / (x1 - x0);
+        }
+    }
+
+    table[table.len() - 1].1
+}
+
+//#################################################################################################
+//
+//                                     EVALUATION TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that evaluating boards in batches gives the same values as one by one, including for
+ * a last batch that isn't full, and that the embedded weights are valid, differ from the balanced
+ * ones and round-trip through a weight file, and that the probabilities of winning are
+ * symmetric, increasing and certain for proven values.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsuite::positions;
+
+    #[test]
+    fn evaluate_batch() {
+        let weights: EvalWeights = Personality::Aggressive.weights();
+        let boards: Vec<Othello> = positions().iter().map(|position| position.oth).collect();
+        assert_ne!(boards.len() % BATCH_LANES, 0);
+
+        let values: Vec<i32> = boards.iter().map(|&oth| weights.evaluate(oth)).collect();
+        assert_eq!(weights.evaluate_batch(&boards), values);
+        assert!(weights.evaluate_batch(&[]).is_empty());
+    }
+
+    #[cfg(feature = "default-weights")]
+    #[test]
+    fn embedded_weights() {
+        let embedded: EvalWeights = EvalWeights::embedded();
+        assert_ne!(embedded, Personality::Balanced.weights());
+        assert!(EvalWeights::from_bytes(&EMBEDDED_WEIGHTS[..40]).is_err());
+
+        let path: std::path::PathBuf =
+            std::env::temp_dir().join(format!("othello-{}.weights", std::process::id()));
+        embedded.save(&path).unwrap();
+        assert_eq!(EvalWeights::load(&path).unwrap(), embedded);
+        assert_eq!(std::fs::read(&path).unwrap(), EMBEDDED_WEIGHTS);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn win_probabilities() {
+        for empties in [0, 5, 20, 45, 60, 64] {
+            assert!((win_probability(0, empties) - 0.5).abs() < 1e-12);
+            let mut last: f64 = 0.0;
+            for value in (-2000..=2000).step_by(50) {
+                let p: f64 = win_probability(value, empties);
+                assert!((0.0..=1.0).contains(&p) && p >= last);
+                assert!((p + win_probability(-value, empties) - 1.0).abs() < 1e-12);
+                last = p;
+            }
+            assert!(win_probability(100, empties) > win_probability(10, empties));
+            assert_eq!(win_probability(PROVEN, empties), 1.0);
+            assert_eq!(win_probability(-WIN, empties), 0.0);
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::types::*;
+use crate::othello::*;
+use crate::game::*;
+use crate::endgame::*;
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                      EXECUTOR TRAIT
+//
+//#################################################################################################
+
+/*
+ * A job given to an executor, which may borrow from the caller.
+ */
+pub type Job<'a> = Box<dyn FnOnce() + Send + 'a>;
+
+/*
+ * What runs the jobs of the parallel parts of the crate: the searches of the root moves by the
+ * alpha-beta AI, the threads of the Monte-Carlo tree search and batch solving. The executor
+ * decides where they run, so that the crate can be embedded in a server without spawning more
+ * threads than it has cores: a rayon scope, a custom pool or the runtime of the caller can be
+ * plugged in by implementing this trait. The jobs must all be done when execute returns, and
+ * they must be allowed to run at the same time, the MCTS threads sharing the count of their
+ * playouts for instance, though running them one after the other is correct too.
+ */
+pub trait Executor: Send + Sync {
+    fn execute<'a>(&self, jobs: Vec<Job<'a>>);
+}
+
+/*
+ * Runs every job in a thread of its own, which is the default.
+ */
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ThreadPerJob;
+
+impl Executor for ThreadPerJob {
+    fn execute<'a>(&self, jobs: Vec<Job<'a>>) {
+        std::thread::scope(|scope| {
+            for job in jobs {
+                scope.spawn(job);
+            }
+        });
+    }
+}
+
+/*
+ * Runs the jobs on at most the given number of threads, each taking the next job left once done
+ * with the previous one.
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct ThreadPool {
+    threads: usize,
+}
+
+impl ThreadPool {
+    /*
+     * Creates a new pool of the given number of threads, at least one.
+     */
+    pub fn new(threads: usize) -> ThreadPool {
+        ThreadPool { threads: threads.max(1), }
+    }
+}
+
+impl Executor for ThreadPool {
+    fn execute<'a>(&self, jobs: Vec<Job<'a>>) {
+        let threads: usize = self.threads.min(jobs.len());
+        let queue: Mutex<std::vec::IntoIter<Job<'a>>> = Mutex::new(jobs.into_iter());
+
+        std::thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| loop {
+                    // The lock is released before running the job.
+                    let job: Option<Job<'a>> = queue.lock().unwrap().next();
+                    match job {
+                        Some(job) => job(),
+                        None => break,
+                    }
+                });
+            }
+        });
+    }
+}
+
+/*
+ * Runs the jobs one after the other in the thread of the caller, never spawning any.
+ */
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Inline;
+
+impl Executor for Inline {
+    fn execute<'a>(&self, jobs: Vec<Job<'a>>) {
+        for job in jobs {
+            job();
+        }
+    }
+}
+
+/*
+ * Calls the function on every input with the executor, and returns the results in the order
+ * of the inputs.
+ */
+pub fn map<T: Send, R: Send>(
+    executor: &dyn Executor, inputs: Vec<T>, f: impl Fn(T) -> R + Sync,
+) -> Vec<R> {
+    let mut results: Vec<Option<R>> = inputs.iter().map(|_| None).collect();
+    let f = &f;
+    let jobs: Vec<Job> = inputs.into_iter()
+        .zip(results.iter_mut())
+        .map(|(input, result)| Box::new(move || *result = Some(f(input))) as Job)
+        .collect();
+    executor.execute(jobs);
+
+    results.into_iter().map(|result| result.expect("The executor didn't run every job.")).collect()
+}
+
+//#################################################################################################
+//
+//                                      BATCH SOLVING
+//
+//#################################################################################################
+
+/*
+ * Solves every position with the endgame solver, the player to move being given with it, and
+ * returns their scores in order. Positions are solved in parallel by the executor.
+ */
+pub fn solve_batch(executor: &dyn Executor, positions: &[(Othello, Color)]) -> Vec<i32> {
+    map(executor, positions.to_vec(), |(oth, color)| solve(oth, color))
+}
+
+/*
+ * What solve_file did: the number of positions it solved, and of those it skipped because the
+ * output already held them.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SolveSummary {
+    pub solved: usize,
+    pub skipped: usize,
+}
+
+/*
+ * Solves every position of the input file, one per line as read by parse_position, empty lines
+ * and lines starting with # being ignored, and writes their scores to the output file as CSV:
+ * the number of the line, the position as written and the score for the player to move. The
+ * positions are solved by the executor in chunks of the given size, the output being flushed
+ * after each, so that an interrupted run loses at most one chunk. With resume, the lines
+ * already in the output are skipped and the new results appended to it, an unfinished last
+ * line being dropped. Otherwise, the output is overwritten.
+ */
+pub fn solve_file(
+    executor: &dyn Executor, input: &Path, output: &Path, chunk: usize, resume: bool,
+) -> Result<SolveSummary, OthelloError> {
+    let text: String = std::fs::read_to_string(input)?;
+    let mut positions: Vec<(usize, &str, Othello, Color)> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line: &str = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (oth, color) = parse_position(line)
+            .map_err(|err| parse_error(format!("At line {}: {}", i + 1, err)))?;
+        positions.push((i + 1, line, oth, color));
+    }
+
+    let mut done: String = String::new();
+    if resume && output.exists() {
+        done = std::fs::read_to_string(output)?;
+        done.truncate(done.rfind('\n').map_or(0, |end| end + 1));
+    }
+    let solved: Vec<usize> = done.lines()
+        .filter_map(|line| line.split(',').next()?.parse().ok())
+        .collect();
+    if done.is_empty() {
+        done.push_str("line,position,score\n");
+    }
+    std::fs::write(output, &done)?;
+
+    let total: usize = positions.len();
+    positions.retain(|&(line, ..)| !solved.contains(&line));
+    let summary: SolveSummary = SolveSummary {
+        solved: positions.len(),
+        skipped: total - positions.len(),
+    };
+
+    let mut file: std::fs::File = std::fs::OpenOptions::new().append(true).open(output)?;
+    for chunk in positions.chunks(chunk.max(1)) {
+        let batch: Vec<(Othello, Color)> = chunk.iter()
+            .map(|&(_, _, oth, color)| (oth, color))
+            .collect();
+        let mut rows: String = String::new();
+        for (&(line, position, ..), score) in chunk.iter().zip(solve_batch(executor, &batch)) {
+            rows += &format!("{},\"{}\",{}\n", line, position.replace('"', "\"\""), score);
+        }
+        file.write_all(rows.as_bytes())?;
+        file.flush()?;
+    }
+
+    Ok(summary)
+}
+
+//#################################################################################################
+//
+//                                      EXECUTOR TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that every executor runs every job, and keeps the results in order, that solving a file
+ * resumes where it stopped, and that the node limit of the alpha-beta AI holds whether its root
+ * moves are searched at the same time or not.
+ */
+#[cfg(all(test, feature = "alphabeta"))]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::alphabeta::*;
+
+    #[test]
+    fn executors() {
+        let executors: [&dyn Executor; 3] = [&ThreadPerJob, &ThreadPool::new(3), &Inline];
+        let inputs: Vec<u64> = (0..20).collect();
+
+        for executor in executors {
+            let squares: Vec<u64> = map(executor, inputs.clone(), |n| n * n);
+            assert!(squares.iter().enumerate().all(|(i, &square)| square == (i * i) as u64));
+        }
+    }
+
+    #[test]
+    fn solve_from_file() {
+        let dir: std::path::PathBuf = std::env::temp_dir().join("othello-solve-file-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let (input, output) = (dir.join("positions.txt"), dir.join("results.csv"));
+        let board: &str = "XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXO- X";
+        std::fs::write(&input, format!("# positions\n{}\n\n{} ; one empty\n", board, board))
+            .unwrap();
+
+        // A run interrupted in the middle of its second line.
+        std::fs::write(&output, format!("line,position,score\n2,\"{}\",64\n4,\"", board))
+            .unwrap();
+        let summary: SolveSummary = solve_file(&Inline, &input, &output, 1, true).unwrap();
+        assert_eq!(summary, SolveSummary { solved: 1, skipped: 1, });
+        let results: String = std::fs::read_to_string(&output).unwrap();
+        assert_eq!(results.lines().collect::<Vec<&str>>(), [
+            "line,position,score",
+            &format!("2,\"{}\",64", board),
+            &format!("4,\"{} ; one empty\",64", board),
+        ]);
+
+        assert!(solve_file(&Inline, &input, &output, 1, false).unwrap().solved == 2);
+        std::fs::write(&input, "f5 zz\n").unwrap();
+        assert!(solve_file(&Inline, &input, &output, 1, false).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn node_limit() {
+        let executors: [Arc<dyn Executor>; 2] = [Arc::new(ThreadPerJob), Arc::new(Inline)];
+
+        for executor in executors {
+            let player: AlphaBetaPlayer = AlphaBetaPlayer::new(30)
+                .with_node_limit(50_000)
+                .with_executor(executor);
+            let (mv, _, stats) = player.best_move(Othello::new(), Color::Black);
+            assert_ne!(mv, 0);
+            assert!(stats.nodes > 25_000 && stats.nodes <= 50_100);
+        }
+    }
+}
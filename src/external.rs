@@ -0,0 +1,293 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::types::*;
+use crate::coord::*;
+use crate::othello::*;
+use crate::game::*;
+use crate::player::*;
+use crate::searcher::*;
+use crate::ggf::*;
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                     EXTERNAL ENGINE
+//
+//#################################################################################################
+
+/*
+ * The disk differential worth odds of e to 1 of winning, to turn the scores of external
+ * engines, which estimate the final disk differential, into probabilities of winning.
+ */
+const DISK_SCALE: f64 = 8.0;
+
+/*
+ * The time an engine is given to quit before it is killed.
+ */
+const QUIT_TIME: Duration = Duration::from_secs(1);
+
+/*
+ * An external engine, run as a child process speaking the NBoard protocol on its standard
+ * input and output, like Edax started with its NBoard interface, so that our AIs can be
+ * measured against it in the arena. Only the commands every NBoard engine understands are
+ * used:
+ *   - "nboard 2" starts the conversation, the engine possibly answering "set myname <name>".
+ *   - "set depth <n>" sets the depth it searches to.
+ *   - "set game <ggf>" gives it the position, as a GGF game starting from it.
+ *   - "ping <n>" waits for it to be ready, the answer being "pong <n>".
+ *   - "go" asks for a move, the answer being "=== <move>[/<score>[/<time>]]", "PA" for a pass.
+ * NBoard has no way to interrupt a search, so the engine always searches to its depth and
+ * the time limits of searches are ignored. The engine is told to quit when dropped.
+ */
+pub struct ExternalEngine {
+    name: String,
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    depth: u8,
+    sent_depth: Option<u8>,
+    pings: u32,
+}
+
+impl ExternalEngine {
+    /*
+     * Starts the given program with the given arguments and greets it, searching 12 plies deep
+     * by default. The engine is named after its answer, or else after the program.
+     */
+    pub fn spawn(program: &str, args: &[&str]) -> Result<ExternalEngine, OthelloError> {
+        let mut child: Child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin: ChildStdin = child.stdin.take().unwrap();
+        let stdout: BufReader<ChildStdout> = BufReader::new(child.stdout.take().unwrap());
+        let name: String = std::path::Path::new(program)
+            .file_name()
+            .map_or(program.to_string(), |name| name.to_string_lossy().into_owned());
+
+        let mut engine: ExternalEngine = ExternalEngine {
+            name,
+            child,
+            stdin,
+            stdout,
+            depth: 12,
+            sent_depth: None,
+            pings: 0,
+        };
+        engine.send("nboard 2")?;
+        engine.sync()?;
+        Ok(engine)
+    }
+
+    /*
+     * Starts an engine from a command line, the program followed by its arguments, separated
+     * by spaces.
+     */
+    pub fn from_command_line(line: &str) -> Result<ExternalEngine, OthelloError> {
+        let mut words = line.split_whitespace();
+        let program: &str = words.next().ok_or_else(|| {
+            OthelloError::ProtocolError("Empty engine command line.".to_string())
+        })?;
+        ExternalEngine::spawn(program, &words.collect::<Vec<&str>>())
+    }
+
+    /*
+     * Sets the depth the engine searches to when no limit is given.
+     */
+    pub fn with_depth(mut self, depth: u8) -> ExternalEngine {
+        self.depth = depth;
+        self
+    }
+
+    /*
+     * Sends a command to the engine.
+     */
+    fn send(&mut self, command: &str) -> Result<(), OthelloError> {
+        writeln!(self.stdin, "{}", command)?;
+        self.stdin.flush()?;
+        Ok(())
+    }
+
+    /*
+     * Reads the next line of the engine, noting its name if it gives it, and failing if it
+     * has exited.
+     */
+    fn read(&mut self) -> Result<String, OthelloError> {
+        let mut line: String = String::new();
+        if self.stdout.read_line(&mut line)? == 0 {
+            return Err(OthelloError::ProtocolError(format!("{} exited.", self.name)));
+        }
+        if let Some(name) = line.trim().strip_prefix("set myname ") {
+            self.name = name.trim().to_string();
+        }
+        Ok(line.trim().to_string())
+    }
+
+    /*
+     * Pings the engine and waits for its answer, skipping everything it says in between.
+     */
+    fn sync(&mut self) -> Result<(), OthelloError> {
+        self.pings += 1;
+        let pong: String = format!("pong {}", self.pings);
+        self.send(&format!("ping {}", self.pings))?;
+        while self.read()? != pong {}
+        Ok(())
+    }
+
+    /*
+     * Asks the engine for its move in the position, searching to the given depth, and returns
+     * it along with its score, the final disk differential it expects, if it gives one.
+     */
+    fn go(&mut self, oth: Othello, color: Color, depth: u8)
+        -> Result<(BitBoard, Option<f64>), OthelloError>
+    {
+        if self.sent_depth != Some(depth) {
+            self.send(&format!("set depth {}", depth))?;
+            self.sent_depth = Some(depth);
+        }
+        self.send(&format!("set game {}", to_ggf(oth, color)))?;
+        self.sync()?;
+        self.send("go")?;
+
+        loop {
+            let line: String = self.read()?;
+            let Some(answer) = line.strip_prefix("===") else { continue };
+            let mut fields = answer.trim().split('/');
+            let name: &str = fields.next().unwrap_or("");
+            let mv: BitBoard = match Coord::parse_with(name, Convention::TopLeft) {
+                _ if name.eq_ignore_ascii_case("pa") => 0,
+                Some(coord) => coord.to_bitboard(),
+                None => {
+                    let error: String = format!("Invalid move: {}", answer.trim());
+                    return Err(OthelloError::ProtocolError(error));
+                },
+            };
+            let score: Option<f64> = fields.next().and_then(|score| score.trim().parse().ok());
+            return Ok((mv, score));
+        }
+    }
+}
+
+/*
+ * Writes a position as a GGF game starting from it, without moves.
+ */
+fn to_ggf(oth: Othello, color: Color) -> String {
+    format!("(;GM[Othello]PC[Rust-Othello]TY[8]{};)", write_board(oth, color))
+}
+
+impl Searcher for ExternalEngine {
+    /*
+     * Asks the engine for its move, searching to the depth of the limits or else its own.
+     * Positions without a move are passed without asking, and an engine which exits or answers
+     * something else than a move passes. Nodes and the principal variation beyond the move
+     * aren't known.
+     */
+    fn best_move(&mut self, (oth, color): (Othello, Color), limits: &SearchLimits)
+        -> SearchResult
+    {
+        let start: Instant = Instant::now();
+        let depth: u8 = limits.depth.unwrap_or(self.depth);
+        let (best, score): (BitBoard, Option<f64>) = if oth.gen_moves(color) == 0 {
+            (0, None)
+        } else {
+            self.go(oth, color, depth).unwrap_or((0, None))
+        };
+        let win_probability: f64 =
+            score.map_or(0.5, |score| 1.0 / (1.0 + (-score / DISK_SCALE).exp()));
+
+        SearchResult {
+            best,
+            value: None,
+            win_probability,
+            pv: vec![best],
+            depth,
+            nodes: 0,
+            time: start.elapsed(),
+        }
+    }
+
+    /*
+     * Does nothing, NBoard engines can't be interrupted.
+     */
+    fn stop(&self) {}
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+impl Player for ExternalEngine {
+    /*
+     * Asks the engine for its move, searching to its depth. An engine which exits or answers
+     * something else than a move resigns.
+     */
+    fn choose_move(&mut self, game: &Game, _clock: &Clock) -> Action {
+        if game.board().gen_moves(game.color()) == 0 {
+            return Action::Play(0);
+        }
+        match self.go(game.board(), game.color(), self.depth) {
+            Ok((mv, _)) => Action::Play(mv),
+            Err(_) => Action::Resign,
+        }
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+impl Drop for ExternalEngine {
+    /*
+     * Tells the engine to quit, and makes sure it did.
+     */
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let deadline: Instant = Instant::now() + QUIT_TIME;
+        while matches!(self.child.try_wait(), Ok(None)) && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+//#################################################################################################
+//
+//                                  EXTERNAL ENGINE TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that an engine is greeted, named, given positions and asked for moves, against a mock
+ * engine written in shell, which always plays f5 and expects to be 2 disks ahead.
+ */
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    const MOCK: &str = "while read command argument; do case $command in \
+        nboard) echo 'set myname Mock';; ping) echo \"pong $argument\";; \
+        go) echo 'status thinking'; echo '=== F5/2.00/0.1';; quit) exit;; esac; done";
+
+    #[test]
+    fn external_engine() {
+        let mut engine: ExternalEngine = ExternalEngine::spawn("sh", &["-c", MOCK]).unwrap();
+        assert_eq!(Searcher::name(&engine), "Mock");
+
+        let (oth, color): (Othello, Color) = (Othello::new(), Color::Black);
+        let limits: SearchLimits = SearchLimits { depth: Some(4), ..SearchLimits::default() };
+        let result: SearchResult = engine.best_move((oth, color), &limits);
+        assert!(result.best == parse_square("f5").unwrap() && result.depth == 4);
+        assert!(result.win_probability > 0.5);
+        assert!(to_ggf(oth, color).contains("BO[8 -------- -------- -------- ---O*--- ---*O--- "));
+
+        let game: Game = Game::new();
+        assert!(matches!(engine.choose_move(&game, &Clock::unlimited()), Action::Play(mv)
+            if mv == parse_square("f5").unwrap()));
+        assert!(ExternalEngine::from_command_line(" ").is_err());
+    }
+}
@@ -0,0 +1,253 @@
+use crate::types::*;
+use crate::othello::*;
+
+//#################################################################################################
+//
+//                                     FEATURE LAYOUT
+//
+//#################################################################################################
+
+/*
+ * The features of a position, from the point of view of the player to move, as a vector of
+ * FEATURES floats for machine learning frameworks. Squares are numbered like the bits of
+ * BitBoards, a1 being 0, b1 1 and h8 63, and counts are divided by 64 to stay within [0, 1]:
+ *   - 0..64 are the disks of the player, 1 where he has one and 0 elsewhere.
+ *   - 64..128 are the disks of the opponent.
+ *   - 128..192 are the legal moves of the player.
+ *   - 192 and 193 are the number of legal moves of the player and of the opponent.
+ *   - 194 and 195 are their numbers of stable disks, see stable.
+ *   - 196 and 197 are their numbers of frontier disks, see frontier.
+ *   - 198 is the parity, 1 if an odd number of squares is empty and 0 otherwise.
+ *   - 199 is the phase, from 0 at the start of the game to 1 when the board is full.
+ */
+pub const FEATURES: usize = 200;
+
+pub const OWN_DISKS: usize = 0;
+pub const OPP_DISKS: usize = 64;
+pub const OWN_MOVES: usize = 128;
+pub const MOBILITY: usize = 192;
+pub const STABILITY: usize = 194;
+pub const FRONTIER: usize = 196;
+pub const PARITY: usize = 198;
+pub const PHASE: usize = 199;
+
+/*
+ * Returns the features of the position for the given player to move, laid out as described
+ * above.
+ */
+pub fn extract(oth: Othello, color: Color) -> [f32; FEATURES] {
+    let mut features: [f32; FEATURES] = [0.0; FEATURES];
+    let own: BitBoard = oth.get_bitboard(color);
+    let opp: BitBoard = oth.get_bitboard(color.invert());
+    let moves: BitBoard = oth.gen_moves(color);
+
+    for square in 0..64 {
+        let bit: BitBoard = 1 << square;
+        features[OWN_DISKS + square] = (own & bit != 0) as u8 as f32;
+        features[OPP_DISKS + square] = (opp & bit != 0) as u8 as f32;
+        features[OWN_MOVES + square] = (moves & bit != 0) as u8 as f32;
+    }
+
+    let count = |board: BitBoard| board.pop_cnt() as f32 / 64.0;
+    features[MOBILITY] = count(moves);
+    features[MOBILITY + 1] = count(oth.gen_moves(color.invert()));
+    features[STABILITY] = count(stable(oth, color));
+    features[STABILITY + 1] = count(stable(oth, color.invert()));
+    features[FRONTIER] = count(frontier(oth, color));
+    features[FRONTIER + 1] = count(frontier(oth, color.invert()));
+    features[PARITY] = (oth.empties() % 2) as f32;
+    features[PHASE] = (60 - oth.empties().min(60)) as f32 / 60.0;
+
+    features
+}
+
+//#################################################################################################
+//
+//                                   STABILITY AND FRONTIER
+//
+//#################################################################################################
+
+/*
+ * The eight directions, by pairs of opposite ones along the same line: each is given by the
+ * squares having a neighbour in that direction, and the shift from them to their neighbour,
+ * positive toward h8.
+ */
+const DIRECTIONS: [(BitBoard, i8); 8] = [
+    (0x7F7F7F7F7F7F7F7F, 1), (0xFEFEFEFEFEFEFEFE, -1),
+    (0x00FFFFFFFFFFFFFF, 8), (0xFFFFFFFFFFFFFF00, -8),
+    (0x007F7F7F7F7F7F7F, 9), (0xFEFEFEFEFEFEFE00, -9),
+    (0x00FEFEFEFEFEFEFE, 7), (0x7F7F7F7F7F7F7F00, -7),
+];
+
+/*
+ * Returns the neighbours in the given direction of the squares of the board.
+ */
+fn neighbours(board: BitBoard, (mask, shift): (BitBoard, i8)) -> BitBoard {
+    if shift > 0 {
+        (board & mask) << shift
+    } else {
+        (board & mask) >> -shift
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/edge_tables.rs"));
+
+/*
+ * Returns the disks of the player that can never be flipped along an edge, given his disks and
+ * the opponent's on it as bytes, a1 to h1 being bits 0 to 7 for the first row. The table is
+ * computed by build.rs.
+ */
+pub const fn edge_stable(own: u8, opp: u8) -> u8 {
+    EDGE_STABILITY[own as usize | (opp as usize) << 8]
+}
+
+/*
+ * The first square of each edge of the board, in the order of the bits of its byte, and the
+ * shift from one square of the edge to the next.
+ */
+const EDGES: [(u8, u8); 4] = [(0, 1), (56, 1), (0, 8), (7, 8)];
+
+/*
+ * Returns the disks of the player on the edges of the board that can never be flipped, see
+ * edge_stable.
+ */
+fn stable_edges(own: BitBoard, opp: BitBoard) -> BitBoard {
+    let mut stable: BitBoard = 0;
+
+    for &(first, shift) in EDGES.iter() {
+        let byte = |board: BitBoard| (0..8)
+            .fold(0u8, |byte, i| byte | ((board >> (first + i * shift) & 1) as u8) << i);
+        let edge: u8 = edge_stable(byte(own), byte(opp));
+        for i in (0..8).filter(|i| edge & 1 << i != 0) {
+            stable |= 1 << (first + i * shift);
+        }
+    }
+
+    stable
+}
+
+/*
+ * Returns the disks of the player that can never be flipped, or rather a part of them found
+ * the usual way: the disks of the edges stable along them, see edge_stable, and the disks
+ * such that, along each of the four lines through them, the line is full or one of their two
+ * neighbours on the line is the edge of the board or a stable disk of the same color. They are
+ * found by growing the set of stable disks until it stops changing.
+ */
+pub fn stable(oth: Othello, color: Color) -> BitBoard {
+    let own: BitBoard = oth.get_bitboard(color);
+    let filled: BitBoard = own | oth.get_bitboard(color.invert());
+
+    // The squares from which the board is filled up to the edge in each direction.
+    let mut filled_up_to: [BitBoard; 8] = [filled; 8];
+    for (dir, filled_up_to) in filled_up_to.iter_mut().enumerate() {
+        for _ in 0..7 {
+            *filled_up_to = filled
+                & (!DIRECTIONS[dir].0 | neighbours(*filled_up_to, DIRECTIONS[dir ^ 1]));
+        }
+    }
+
+    let edges: BitBoard = stable_edges(own, oth.get_bitboard(color.invert()));
+    let mut stable: BitBoard = edges;
+    loop {
+        let mut new_stable: BitBoard = own;
+        for line in 0..4 {
+            let (forward, backward) = (2 * line, 2 * line + 1);
+            new_stable &= (filled_up_to[forward] & filled_up_to[backward])
+                | !DIRECTIONS[forward].0
+                | !DIRECTIONS[backward].0
+                | neighbours(stable, DIRECTIONS[forward])
+                | neighbours(stable, DIRECTIONS[backward]);
+        }
+
+        new_stable |= edges;
+        if new_stable == stable {
+            return stable;
+        }
+        stable = new_stable;
+    }
+}
+
+/*
+ * Returns the frontier disks of the player, those next to an empty square.
+ */
+pub fn frontier(oth: Othello, color: Color) -> BitBoard {
+    let empty: BitBoard = !(oth.get_bitboard(Color::Black) | oth.get_bitboard(Color::White));
+    let next_to_empty: BitBoard = DIRECTIONS.iter()
+        .fold(0, |acc, &dir| acc | neighbours(empty, dir));
+    oth.get_bitboard(color) & next_to_empty
+}
+
+//#################################################################################################
+//
+//                                      FEATURES TEST
+//
+//#################################################################################################
+
+/*
+ * Checks the features of the starting position, the stable and frontier disks of a position
+ * where black holds a corner and the disks next to it, the stable disks along edges, and that
+ * stable disks are never flipped in random games.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_features() {
+        let features: [f32; FEATURES] = extract(Othello::new(), Color::Black);
+        assert_eq!(features[OWN_DISKS..OPP_DISKS].iter().sum::<f32>(), 2.0);
+        assert_eq!(features[OPP_DISKS..OWN_MOVES].iter().sum::<f32>(), 2.0);
+        assert_eq!(features[OWN_MOVES..MOBILITY].iter().sum::<f32>(), 4.0);
+        assert_eq!(features[MOBILITY], 4.0 / 64.0);
+        assert_eq!(features[STABILITY], 0.0);
+        assert_eq!(features[FRONTIER], 2.0 / 64.0);
+        assert_eq!((features[PARITY], features[PHASE]), (0.0, 0.0));
+
+        let oth: Othello = crate::pos!("
+            X X X - - - - -
+            X X - - - - - -
+            X O - - - - - -
+            - - - O X - - -
+            - - - X O - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - - - - - -
+        ");
+        let stable_squares: BitBoard = 0b111 | 0b11 << 8 | 1 << 16;
+        assert_eq!(stable(oth, Color::Black), stable_squares);
+        assert_eq!(stable(oth, Color::White), 0);
+        assert_eq!(frontier(oth, Color::Black), 0b110 | 1 << 9 | 1 << 16 | 1 << 28 | 1 << 35);
+    }
+
+    #[test]
+    fn stability() {
+        assert_eq!((edge_stable(0xFF, 0), edge_stable(0b1, 0), edge_stable(0b10, 0)), (0xFF, 1, 0));
+        // Black can flip d1, then white the whole row from a1 to e1 by playing f1.
+        assert_eq!(edge_stable(0b0110, 0b1001), 0);
+        assert_eq!(edge_stable(0b1000_0111, 0b0111_1000), 0b1000_0111);
+
+        let mut seed: u64 = 7;
+        for _ in 0..20 {
+            let mut positions: Vec<Othello> = Vec::new();
+            let (mut oth, mut color): (Othello, Color) = (Othello::new(), Color::Black);
+            while oth.gen_moves(color) | oth.gen_moves(color.invert()) != 0 {
+                let mut moves: BitBoard = oth.gen_moves(color);
+                if moves != 0 {
+                    seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                    for _ in 0..(seed >> 33) % moves.pop_cnt() as u64 {
+                        moves.pop_lsb();
+                    }
+                    oth = oth.make_move(color, moves.pop_lsb());
+                    positions.push(oth);
+                }
+                color = color.invert();
+            }
+            for position in positions {
+                for color in Color::iter() {
+                    let stable: BitBoard = stable(position, color);
+                    assert_eq!(stable & oth.get_bitboard(color), stable);
+                }
+            }
+        }
+    }
+}
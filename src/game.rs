@@ -0,0 +1,611 @@
+use crate::types::*;
+use crate::othello::*;
+use crate::player::*;
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                      OUTCOME TYPE
+//
+//#################################################################################################
+
+/*
+ * The reason why a game came to an end: either because no player can move anymore, because
+ * one of the players resigned, because both agreed on a draw, because the game was
+ * adjudicated by an external arbiter, or because one of the players forfeited it by choosing
+ * an illegal move or by running out of time.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Reason {
+    Finished, Resignation, Agreement, Adjudication, IllegalMove, Timeout,
+}
+
+impl Reason {
+    /*
+     * Returns true if the game was forfeited by the loser.
+     */
+    pub fn is_forfeit(self) -> bool {
+        matches!(self, Reason::IllegalMove | Reason::Timeout)
+    }
+}
+
+/*
+ * The outcome of a finished game, a winner of None meaning the game is a draw.
+ */
+#[derive(Clone, Copy, PartialEq)]
+pub struct Outcome {
+    pub winner: Option<Color>,
+    pub reason: Reason,
+}
+
+//#################################################################################################
+//
+//                                        GAME TYPE
+//
+//#################################################################################################
+
+/*
+ * A game of Othello, keeping track of the board, of the player to move and of every ply played
+ * so far. Passes are forced and made automatically, they are recorded in the history as empty
+ * BitBoards.
+ */
+#[derive(Clone)]
+pub struct Game {
+    oth: Othello,
+    color: Color,
+    history: Vec<BitBoard>,
+    handicap: u8,
+    draw_offer: Option<Color>,
+    outcome: Option<Outcome>,
+}
+
+impl Game {
+    /*
+     * Creates a new game in the starting position, black to play.
+     */
+    pub fn new() -> Game {
+        Game::from_position(Othello::new(), Color::Black)
+    }
+
+    /*
+     * Creates a new game in the starting position with the given number of corners, up to 4,
+     * given to Black as a handicap, see Othello::with_handicap. Black plays first all the same.
+     */
+    pub fn with_handicap(corners: u8) -> Game {
+        let mut game: Game = Game::from_position(Othello::with_handicap(corners), Color::Black);
+        game.handicap = corners.min(HANDICAP_CORNERS.len() as u8);
+        game
+    }
+
+    /*
+     * Creates a new game from the given position with the given player to move.
+     */
+    pub fn from_position(oth: Othello, color: Color) -> Game {
+        let mut game = Game {
+            oth,
+            color,
+            history: Vec::new(),
+            handicap: 0,
+            draw_offer: None,
+            outcome: None,
+        };
+        game.update();
+        game
+    }
+
+    /*
+     * Plays a line of moves, like "f5d6c3", from the starting position, and returns the game.
+     * Returns an error if a square is invalid or a move illegal.
+     */
+    pub fn from_line(line: &str) -> Result<Game, OthelloError> {
+        let mut game: Game = Game::new();
+        game.play_line(line)?;
+        Ok(game)
+    }
+
+    /*
+     * Plays a transcript, like "F5D6C3" or "f5 d6 c3 pass", from the starting position, and
+     * returns the game, see play_transcript.
+     */
+    pub fn from_transcript(transcript: &str) -> Result<Game, OthelloError> {
+        let mut game: Game = Game::new();
+        game.play_transcript(transcript)?;
+        Ok(game)
+    }
+
+//#################################################################################################
+//
+//                                        ACCESSERS
+//
+//#################################################################################################
+
+    /*
+     * Returns the current board.
+     */
+    #[inline(always)]
+    pub fn board(&self) -> Othello {
+        self.oth
+    }
+
+    /*
+     * Returns the color of the player to move.
+     */
+    #[inline(always)]
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /*
+     * Returns the legal moves of the player to move, or an empty BitBoard if the game is over.
+     */
+    pub fn moves(&self) -> BitBoard {
+        if self.is_over() {
+            0
+        } else {
+            self.oth.gen_moves(self.color)
+        }
+    }
+
+    /*
+     * Returns every ply played so far, passes being empty BitBoards.
+     */
+    pub fn history(&self) -> &[BitBoard] {
+        &self.history
+    }
+
+    /*
+     * Returns the number of corners given to Black as a handicap, 0 if none was.
+     */
+    pub fn handicap(&self) -> u8 {
+        self.handicap
+    }
+
+    /*
+     * Returns the last move played, skipping passes, or an empty BitBoard if there is none.
+     */
+    pub fn last_move(&self) -> BitBoard {
+        self.history.iter().rev().copied().find(|&mv| mv != 0).unwrap_or(0)
+    }
+
+    /*
+     * Returns the color of the player currently offering a draw, if any.
+     */
+    pub fn draw_offer(&self) -> Option<Color> {
+        self.draw_offer
+    }
+
+    /*
+     * Returns the outcome of the game, or None if it is still ongoing.
+     */
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    /*
+     * Returns true if the game is over.
+     */
+    #[inline(always)]
+    pub fn is_over(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+//#################################################################################################
+//
+//                                        GAME FLOW
+//
+//#################################################################################################
+
+    /*
+     * Plays the given move for the player to move, then makes the opponent pass if he has to.
+     * Playing a move implicitly declines any draw offer made by the opponent. Returns an error,
+     * leaving the game untouched, if the move is illegal.
+     */
+    pub fn play(&mut self, mv: BitBoard) -> Result<(), OthelloError> {
+        if self.is_over() {
+            return Err(MoveError::GameOver.into());
+        } else if mv == 0 {
+            return Err(MoveError::IllegalPass.into());
+        } else if mv.pop_cnt() != 1 {
+            return Err(MoveError::OutOfBounds.into());
+        } else if mv & self.moves() == 0 {
+            let occupied: BitBoard =
+                self.oth.get_bitboard(Color::Black) | self.oth.get_bitboard(Color::White);
+            let err: MoveError = if occupied & mv != 0 {
+                MoveError::Occupied
+            } else {
+                MoveError::NoFlips
+            };
+            return Err(err.into());
+        }
+
+        if self.draw_offer == Some(self.color.invert()) {
+            self.draw_offer = None;
+        }
+
+        self.oth = self.oth.make_move(self.color, mv);
+        self.history.push(mv);
+        self.color = self.color.invert();
+        self.update();
+        Ok(())
+    }
+
+    /*
+     * Plays a line of moves, like "f5d6c3", from the current position. Spaces are ignored and
+     * passes are made automatically. Returns an error if a square is invalid, before playing
+     * anything, or at the first illegal move, the moves before it staying played.
+     */
+    pub fn play_line(&mut self, line: &str) -> Result<(), OthelloError> {
+        for mv in parse_line(line)? {
+            self.play(mv)?;
+        }
+        Ok(())
+    }
+
+    /*
+     * Plays a transcript of moves from the current position, see parse_transcript. Passes
+     * are made automatically, and the pass markers of the transcript are only checked to be
+     * where the player to move had to pass. Returns an error at the first illegal move or
+     * misplaced pass, the moves before it staying played.
+     */
+    pub fn play_transcript(&mut self, transcript: &str) -> Result<(), OthelloError> {
+        self.apply_pv(&parse_transcript(transcript)?)
+    }
+
+    /*
+     * Plays a principal variation from the current position, passes being empty BitBoards, as
+     * found by the search or in the history of a game. Passes are made automatically, so those
+     * of the variation are only checked to be where the player to move had to pass, like the
+     * pass markers of play_transcript. Returns an error at the first illegal move or misplaced
+     * pass, the moves before it staying played.
+     */
+    pub fn apply_pv(&mut self, pv: &[BitBoard]) -> Result<(), OthelloError> {
+        // Whether the player of the last move plays again, his opponent having passed.
+        let mut passed: bool = false;
+
+        for (i, &mv) in pv.iter().enumerate() {
+            if mv == 0 {
+                if !passed && !self.is_over() {
+                    return Err(parse_error(format!(
+                        "Pass at move {}, while {} has legal moves.", i + 1, self.color,
+                    )));
+                }
+                passed = false;
+                continue;
+            }
+            let color: Color = self.color;
+            self.play(mv)?;
+            passed = self.color == color;
+        }
+        Ok(())
+    }
+
+    /*
+     * Plays the move at (x, y) for the given player if it is legal, or returns the reason why
+     * it is not.
+     */
+    pub fn try_play(&mut self, color: Color, x: u8, y: u8) -> Result<(), OthelloError> {
+        if self.is_over() {
+            return Err(MoveError::GameOver.into());
+        }
+        if color != self.color {
+            return Err(MoveError::WrongTurn.into());
+        }
+
+        self.oth.try_make_move(color, x, y)?;
+        self.play(1u64 << (x + 8 * y))
+    }
+
+    /*
+     * Makes the given player resign, his opponent wins the game.
+     */
+    pub fn resign(&mut self, color: Color) {
+        self.end(Some(color.invert()), Reason::Resignation);
+    }
+
+    /*
+     * Registers a draw offer from the given player. It stands until the opponent accepts it
+     * or plays a move.
+     */
+    pub fn offer_draw(&mut self, color: Color) {
+        if !self.is_over() {
+            self.draw_offer = Some(color);
+        }
+    }
+
+    /*
+     * Accepts the draw offered by the opponent of the given player. Returns false if no such
+     * offer was standing.
+     */
+    pub fn accept_draw(&mut self, color: Color) -> bool {
+        if self.is_over() || self.draw_offer != Some(color.invert()) {
+            return false;
+        }
+
+        self.end(None, Reason::Agreement);
+        true
+    }
+
+    /*
+     * Makes the given player forfeit the game for the given reason, an illegal move or a time
+     * loss, his opponent wins the game.
+     */
+    pub fn forfeit(&mut self, color: Color, reason: Reason) {
+        self.end(Some(color.invert()), reason);
+    }
+
+    /*
+     * Ends the game with the given winner, as decided by an external arbiter.
+     */
+    pub fn adjudicate(&mut self, winner: Option<Color>) {
+        self.end(winner, Reason::Adjudication);
+    }
+
+    /*
+     * Asks the player to move what he wants to do and does it, charging the time he took to
+     * the clock. When a draw is offered, the opponent is asked whether he accepts it right
+     * away. The player forfeits the game if he ran out of time, whatever he chose. Returns an
+     * error if the player chooses an illegal move.
+     */
+    pub fn step(
+        &mut self,
+        black: &mut dyn Player,
+        white: &mut dyn Player,
+        clock: &mut Clock,
+    ) -> Result<(), OthelloError> {
+        if self.is_over() {
+            return Ok(());
+        }
+
+        let color: Color = self.color;
+        let (player, opponent): (&mut dyn Player, &mut dyn Player) = match color {
+            Color::Black => (black, white),
+            Color::White => (white, black),
+        };
+
+        let start: std::time::Instant = std::time::Instant::now();
+        let action: Action = player.choose_move(self, clock);
+        clock.charge(color, start.elapsed());
+
+        if clock.is_flagged(color) {
+            self.forfeit(color, Reason::Timeout);
+            return Ok(());
+        }
+
+        match action {
+            Action::Play(mv) => self.play(mv)?,
+            Action::OfferDraw(mv) => {
+                self.play(mv)?;
+                self.offer_draw(color);
+                if !self.is_over() && opponent.accept_draw(self, color.invert()) {
+                    self.accept_draw(color.invert());
+                }
+            },
+            Action::Resign => self.resign(color),
+        }
+
+        Ok(())
+    }
+
+    /*
+     * Makes the player to move pass if he can't play, and ends the game if neither player can.
+     */
+    fn update(&mut self) {
+        if self.oth.gen_moves(self.color) != 0 {
+            return;
+        }
+
+        if self.oth.gen_moves(self.color.invert()) == 0 {
+            self.end(self.oth.score().winner(), Reason::Finished);
+        } else {
+            self.history.push(0);
+            self.color = self.color.invert();
+        }
+    }
+
+    /*
+     * Ends the game, if it isn't already.
+     */
+    fn end(&mut self, winner: Option<Color>, reason: Reason) {
+        if !self.is_over() {
+            self.outcome = Some(Outcome { winner, reason, });
+            self.draw_offer = None;
+        }
+    }
+}
+
+impl Default for Game {
+    fn default() -> Game {
+        Game::new()
+    }
+}
+
+/*
+ * Parses a line of moves, like "f5d6c3", spaces being ignored, into its squares in order.
+ */
+pub fn parse_line(line: &str) -> Result<Vec<BitBoard>, OthelloError> {
+    let squares: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+
+    squares.chunks(2).map(|chunk| {
+        let name: String = chunk.iter().collect();
+        parse_square(&name).ok_or_else(|| parse_error(format!("Invalid square: {}", name)))
+    }).collect()
+}
+
+/*
+ * Parses a transcript of moves, as exported by online sites, "F5D6C3" for example: squares in
+ * either case, with or without separators, spaces, commas, dots or dashes, between them, and
+ * passes written "pass", "pa", "ps" or "@@". Passes are returned as 0.
+ */
+pub fn parse_transcript(transcript: &str) -> Result<Vec<BitBoard>, OthelloError> {
+    let text: String = transcript.to_ascii_lowercase();
+    let bytes: &[u8] = text.as_bytes();
+    let mut moves: Vec<BitBoard> = Vec::new();
+    let mut i: usize = 0;
+
+    while i < bytes.len() {
+        if bytes[i].is_ascii_whitespace() || b",.;-".contains(&bytes[i]) {
+            i += 1;
+        } else if bytes[i..].starts_with(b"pass") {
+            moves.push(0);
+            i += 4;
+        } else if let Some(token) = text.get(i..i + 2) {
+            match token {
+                "pa" | "ps" | "@@" => moves.push(0),
+                _ => moves.push(parse_square(token).ok_or_else(|| {
+                    parse_error(format!("Invalid move in the transcript: {}", token))
+                })?),
+            }
+            i += 2;
+        } else {
+            return Err(parse_error(format!("Invalid move in the transcript: {}", &text[i..])));
+        }
+    }
+
+    Ok(moves)
+}
+
+/*
+ * Writes a principal variation, or any sequence of moves, the way GUIs show them: square
+ * names separated by spaces, passes being written PA, like "d3 c3 PA c4". The text is read
+ * back by parse_transcript.
+ */
+pub fn format_pv(pv: &[BitBoard]) -> String {
+    let moves: Vec<String> = pv.iter()
+        .map(|&mv| if mv == 0 { "PA".to_string() } else { mv.square_name() })
+        .collect();
+    moves.join(" ")
+}
+
+/*
+ * Parses a set of moves written like a line, "d3c4" for example, into one BitBoard.
+ */
+pub fn parse_moves(line: &str) -> Result<BitBoard, OthelloError> {
+    Ok(parse_line(line)?.into_iter().fold(0, |acc, mv| acc | mv))
+}
+
+/*
+ * Parses a position, written either as a line of moves from the starting position, or any
+ * transcript read by parse_transcript, or as its 64 squares from a1 to h8, row by row, with
+ * the characters of Square::from_char, followed by the player to move, X or O, as in the OBF
+ * files of Edax. Spaces are ignored, and a semicolon starts a comment.
+ */
+pub fn parse_position(text: &str) -> Result<(Othello, Color), OthelloError> {
+    let text: &str = text.split(';').next().unwrap_or("");
+    let chars: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+
+    if chars.len() == 65 && chars[..64].iter().all(|&c| Square::from_char(c).is_some()) {
+        let (mut black, mut white): (BitBoard, BitBoard) = (0, 0);
+        for (i, &c) in chars[..64].iter().enumerate() {
+            match Square::from_char(c) {
+                Some(Square::Black) => black |= 1 << i,
+                Some(Square::White) => white |= 1 << i,
+                _ => (),
+            }
+        }
+        let color: Color = match chars[64] {
+            'X' => Color::Black,
+            'O' => Color::White,
+            c => return Err(parse_error(format!("Invalid player to move: {}", c))),
+        };
+        return Ok((Othello::create(black, white), color));
+    }
+
+    let game: Game = Game::from_transcript(text)?;
+    Ok((game.board(), game.color()))
+}
+
+//#################################################################################################
+//
+//                                       GAME TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that transcripts are read whatever their case and separators, that their pass
+ * markers are checked, that principal variations with passes are written and replayed, and
+ * that games end by resignation or by agreement on a standing draw offer only, and that
+ * illegal moves are rejected with the reason why.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transcripts() {
+        let line: Game = Game::from_line("f5d6c3d3c4").unwrap();
+        for transcript in ["F5D6C3D3C4", "f5 d6 c3 d3 c4", "F5-d6-C3, D3. c4"] {
+            assert_eq!(Game::from_transcript(transcript).unwrap().history(), line.history());
+        }
+        assert!(Game::from_transcript("F5PAD6").is_err());
+        assert!(Game::from_transcript("F5D6X9").is_err());
+        assert!(Game::from_transcript("F5D").is_err());
+
+        // Black has no move after this line, and White plays again.
+        let passing: &str = "f5f6f7g7c4f8h8h6";
+        let game: Game = Game::from_line(passing).unwrap();
+        assert_eq!(game.color(), Color::White);
+        assert_eq!(Game::from_transcript(&format!("{} PASS", passing)).unwrap().history(),
+            game.history());
+
+        let pv: String = format_pv(game.history());
+        assert_eq!(pv, "f5 f6 f7 g7 c4 f8 h8 h6 PA");
+        assert_eq!(Game::from_transcript(&pv).unwrap().history(), game.history());
+        let mut replayed: Game = Game::new();
+        replayed.apply_pv(&game.history()[..4]).unwrap();
+        replayed.apply_pv(&game.history()[4..]).unwrap();
+        assert_eq!(replayed.history(), game.history());
+        assert!(Game::new().apply_pv(&[1 << 37, 0]).is_err());
+    }
+
+    #[test]
+    fn illegal_moves() {
+        let rejected = |game: &mut Game, color: Color, x: u8, y: u8| -> String {
+            let history: Vec<BitBoard> = game.history().to_vec();
+            let err: OthelloError = game.try_play(color, x, y).unwrap_err();
+            assert_eq!(game.history(), history);
+            err.to_string()
+        };
+        let mut game: Game = Game::new();
+        assert_eq!(rejected(&mut game, Color::White, 4, 5),
+            "Illegal move: It is not this player's turn.");
+        assert_eq!(rejected(&mut game, Color::Black, 8, 0),
+            "Illegal move: This square is outside of the board.");
+        assert_eq!(rejected(&mut game, Color::Black, 3, 3),
+            "Illegal move: This square is already occupied.");
+        assert_eq!(rejected(&mut game, Color::Black, 0, 0),
+            "Illegal move: A move must flip at least one of the opponent's disks.");
+        assert!(matches!(game.play(0), Err(OthelloError::IllegalMove(MoveError::IllegalPass))));
+        assert_eq!(game.play(0).unwrap_err().to_string(),
+            "Illegal move: A player can only pass when he has no legal move.");
+
+        game.try_play(Color::Black, 5, 4).unwrap();
+        game.resign(Color::White);
+        assert_eq!(rejected(&mut game, Color::White, 3, 5),
+            "Illegal move: The game is already over.");
+    }
+
+    #[test]
+    fn resign_and_draws() {
+        let mut resigned: Game = Game::new();
+        resigned.resign(Color::White);
+        assert!(resigned.is_over());
+        assert!(resigned.outcome() == Some(Outcome {
+            winner: Some(Color::Black), reason: Reason::Resignation,
+        }));
+        assert!(resigned.play(1 << 37).is_err());
+
+        let mut drawn: Game = Game::new();
+        assert!(!drawn.accept_draw(Color::White));
+        drawn.offer_draw(Color::Black);
+        assert!(!drawn.accept_draw(Color::Black) && drawn.draw_offer() == Some(Color::Black));
+        assert!(drawn.accept_draw(Color::White));
+        assert!(drawn.outcome() == Some(Outcome { winner: None, reason: Reason::Agreement, }));
+        assert!(drawn.draw_offer().is_none() && !drawn.accept_draw(Color::White));
+
+        // A move declines the standing offer.
+        let mut declined: Game = Game::new();
+        declined.offer_draw(Color::White);
+        declined.play(1 << 37).unwrap();
+        assert!(!declined.accept_draw(Color::Black) && !declined.is_over());
+    }
+}
@@ -0,0 +1,285 @@
+use std::time::Duration;
+
+use crate::types::*;
+use crate::coord::*;
+use crate::othello::*;
+use crate::game::*;
+use crate::record::*;
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                       GGF FORMAT
+//
+//#################################################################################################
+
+/*
+ * The GGF format is the text format of the Generic Game Server, in which many online games were
+ * archived. Each game is enclosed in "(;" and ";)" and is a list of properties written as
+ * KEY[value]. For instance:
+ *
+ * (;GM[Othello]PC[GGS/os]DT[2003.08.22]PB[alice]PW[bob]RB[2100]RW[2050]TY[8]RE[-12.000]
+ * BO[8 -------- -------- -------- ---O*--- ---*O--- -------- -------- -------- *]
+ * B[f5//0.01]W[d6/1.20/2.3]B[c3]...;)
+ *
+ * The properties used here are PB and PW, the names of the players, DT, the date, TY, the type
+ * of the game, starting with the size of the board, BO, the starting position, RE, the final
+ * disk differential for black, suffixed with ":r" for a resignation or ":t" and ":s" for a game
+ * decided by time or by the server, and B and W, the moves, followed by an optional evaluation
+ * and time. Squares are always named with a1 in the top-left corner, and passes are "PA".
+ */
+
+/*
+ * Reads all the games of a GGF collection. Games not played on an 8x8 board from the usual
+ * starting position, which can't be recorded, are skipped.
+ */
+pub fn parse_games(text: &str) -> Result<Vec<GameRecord>, OthelloError> {
+    let mut records: Vec<GameRecord> = Vec::new();
+    let mut rest: &str = text;
+
+    while let Some(start) = rest.find("(;") {
+        let end: usize = rest[start..]
+            .find(";)")
+            .ok_or_else(|| parse_error("Unterminated GGF game."))? + start;
+        if let Some(record) = parse_game(&rest[start + 2..end])? {
+            records.push(record);
+        }
+        rest = &rest[end + 2..];
+    }
+
+    Ok(records)
+}
+
+/*
+ * Reads the properties of one game, returning None if the game is not supported.
+ */
+fn parse_game(text: &str) -> Result<Option<GameRecord>, OthelloError> {
+    let mut record: GameRecord = GameRecord {
+        black: String::new(),
+        white: String::new(),
+        date: String::new(),
+        outcome: None,
+        handicap: 0,
+        moves: Vec::new(),
+        stats: Vec::new(),
+    };
+    let mut rest: &str = text;
+
+    while let Some(open) = rest.find('[') {
+        let close: usize = rest[open..]
+            .find(']')
+            .ok_or_else(|| parse_error("Unterminated GGF property."))? + open;
+        let key: &str = rest[..open].trim();
+        let value: &str = &rest[open + 1..close];
+        rest = &rest[close + 1..];
+
+        match key {
+            "GM" if !value.eq_ignore_ascii_case("othello") => return Ok(None),
+            "TY" if !value.starts_with('8') || value[1..].starts_with(char::is_numeric) => {
+                return Ok(None);
+            },
+            "BO" => match start_handicap(value) {
+                Some(handicap) => record.handicap = handicap,
+                None => return Ok(None),
+            },
+            "PB" => record.black = value.to_string(),
+            "PW" => record.white = value.to_string(),
+            "DT" => record.date = value.to_string(),
+            "RE" => record.outcome = parse_outcome(value)?,
+            "B" | "W" => {
+                let mut fields = value.split('/').map(str::trim);
+                let square: &str = fields.next().unwrap_or("");
+                if square.eq_ignore_ascii_case("pa") || square.eq_ignore_ascii_case("pass") {
+                    record.moves.push(0);
+                } else {
+                    let coord: Coord = Coord::parse_with(square, Convention::TopLeft)
+                        .ok_or_else(|| parse_error(format!("Invalid GGF move: {}", value)))?;
+                    record.moves.push(coord.to_bitboard());
+                }
+                let score: Option<f64> = fields.next().and_then(|score| score.parse().ok());
+                let time: Option<f64> = fields.next().and_then(|time| time.parse().ok());
+                record.stats.push(MoveStats {
+                    time: time.and_then(|time| Duration::try_from_secs_f64(time).ok()),
+                    score: score.map(|score| score.round() as i32),
+                    ..MoveStats::default()
+                });
+            },
+            _ => (),
+        }
+    }
+
+    if record.stats.iter().all(|stats| *stats == MoveStats::default()) {
+        record.stats.clear();
+    }
+    Ok(Some(record))
+}
+
+/*
+ * Returns the handicap of the given board if it is the usual starting position, black to move,
+ * possibly with corners given to Black, and None otherwise.
+ */
+fn start_handicap(board: &str) -> Option<u8> {
+    let squares: Vec<char> = board.chars().filter(|c| !c.is_whitespace()).collect();
+
+    if squares.len() != 66 || squares[0] != '8' || squares[65] != '*' {
+        return None;
+    }
+
+    (0..=HANDICAP_CORNERS.len() as u8).find(|&corners| {
+        let start: Othello = Othello::with_handicap(corners);
+        let black: BitBoard = start.get_bitboard(Color::Black);
+        let white: BitBoard = start.get_bitboard(Color::White);
+        squares[1..65].iter().enumerate().all(|(i, &square)| match square {
+            '*' => black & (1u64 << i) != 0,
+            'O' => white & (1u64 << i) != 0,
+            '-' => (black | white) & (1u64 << i) == 0,
+            _ => false,
+        })
+    })
+}
+
+/*
+ * Parses a result, returning None for an unfinished game.
+ */
+fn parse_outcome(value: &str) -> Result<Option<Outcome>, OthelloError> {
+    let (score, flag) = value.split_once(':').unwrap_or((value, ""));
+    if score.trim() == "?" {
+        return Ok(None);
+    }
+
+    let score: f64 = score
+        .trim()
+        .parse()
+        .map_err(|_| parse_error(format!("Invalid GGF result: {}", value)))?;
+    let winner: Option<Color> = if score > 0.0 {
+        Some(Color::Black)
+    } else if score < 0.0 {
+        Some(Color::White)
+    } else {
+        None
+    };
+    let reason: Reason = match flag {
+        "" => Reason::Finished,
+        "r" => Reason::Resignation,
+        "t" => Reason::Timeout,
+        _ => Reason::Adjudication,
+    };
+
+    Ok(Some(Outcome { winner, reason, }))
+}
+
+/*
+ * Writes a game in the GGF format, read back by parse_games. Moves are followed by the score
+ * and the time of their stats when they are known, the score being in the units of the player,
+ * the depth and the nodes having no place in GGF. The result is the final disk differential,
+ * or the smallest one favoring the winner of a game ended otherwise, with its flag.
+ */
+pub fn write_game(record: &GameRecord) -> Result<String, OthelloError> {
+    let game: Game = record.to_game()?;
+    let score: i32 = game.board().score().get(Color::Black) as i32
+        - game.board().score().get(Color::White) as i32;
+    let result: String = match record.outcome {
+        None => "?".to_string(),
+        Some(outcome) => {
+            let score: i32 = match outcome.winner {
+                Some(Color::Black) => score.max(1),
+                Some(Color::White) => score.min(-1),
+                None => 0,
+            };
+            let flag: &str = match outcome.reason {
+                Reason::Finished => "",
+                Reason::Resignation => ":r",
+                Reason::Timeout => ":t",
+                _ => ":s",
+            };
+            format!("{:+}.000{}", score, flag)
+        },
+    };
+
+    let mut text: String = format!(
+        "(;GM[Othello]PC[Rust-Othello]DT[{}]PB[{}]PW[{}]TY[8]RE[{}]{}",
+        record.date, record.black, record.white, result,
+        write_board(Othello::with_handicap(record.handicap), Color::Black),
+    );
+    for (i, &mv) in record.moves.iter().enumerate() {
+        let square: String = if mv == 0 {
+            "PA".to_string()
+        } else {
+            Coord::from_bitboard(mv).name_with(Convention::TopLeft)
+        };
+        let stats: MoveStats = record.stats.get(i).copied().unwrap_or_default();
+        let score: String =
+            stats.score.map_or(String::new(), |score| format!("{:.2}", score as f64));
+        let time: String =
+            stats.time.map_or(String::new(), |time| format!("/{:.2}", time.as_secs_f64()));
+        let extra: String = if score.is_empty() && time.is_empty() {
+            String::new()
+        } else {
+            format!("/{}{}", score, time)
+        };
+        let color: &str = if i % 2 == 0 { "B" } else { "W" };
+        text.push_str(&format!("{}[{}{}]", color, square, extra));
+    }
+    text.push_str(";)");
+
+    Ok(text)
+}
+
+/*
+ * Writes a position as the BO property of a GGF game, the 8 rows of the board followed by the
+ * player to move.
+ */
+pub(crate) fn write_board(oth: Othello, color: Color) -> String {
+    let rows: Vec<String> = oth
+        .to_array()
+        .iter()
+        .map(|row| row.iter().map(|square| match square {
+            Square::Black => '*',
+            Square::White => 'O',
+            Square::Empty => '-',
+        }).collect())
+        .collect();
+    let to_move: char = if color == Color::Black { '*' } else { 'O' };
+    format!("BO[8 {} {}]", rows.join(" "), to_move)
+}
+
+//#################################################################################################
+//
+//                                        GGF TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that games survive a round trip through the GGF format, passes, handicaps and
+ * resignations included, that unsupported games are skipped, and that truncated collections
+ * are rejected.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        // Black has to pass after h6.
+        let mut game: Game = Game::from_line("f5f6f7g7c4f8h8h6").unwrap();
+        game.resign(Color::White);
+        let record: GameRecord = GameRecord::from_game(&game, "alice", "bob", "2003.08.22");
+        let text: String = write_game(&record).unwrap();
+        assert!(text.contains("W[h6]B[PA]") && text.contains("RE[+"));
+        let parsed: Vec<GameRecord> = parse_games(&format!("{}\n(;GM[Chess];)", text)).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert!(parsed[0].moves == record.moves && parsed[0].outcome == record.outcome);
+        assert_eq!((parsed[0].black.as_str(), parsed[0].date.as_str()), ("alice", "2003.08.22"));
+        assert_eq!(write_game(&parsed[0]).unwrap(), text);
+
+        let handicap: GameRecord =
+            GameRecord::from_game(&Game::with_handicap(2), "alice", "bob", "2003.08.22");
+        assert_eq!(parse_games(&write_game(&handicap).unwrap()).unwrap()[0].handicap, 2);
+
+        assert!(parse_games(&text[..text.len() - 2]).is_err());
+        assert!(parse_games("(;GM[Othello]PB[alice;)").is_err());
+        assert!(parse_games("(;GM[Othello]B[z9];)").is_err());
+        assert!(parse_games("(;GM[Othello]RE[+1x];)").is_err());
+        assert!(parse_games("").unwrap().is_empty());
+    }
+}
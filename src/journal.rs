@@ -0,0 +1,228 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::types::*;
+use crate::othello::*;
+use crate::book::*;
+use crate::cache::*;
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                      JOURNAL TYPE
+//
+//#################################################################################################
+
+/*
+ * A search of a position written to the journal: the depth it was completed to, the score
+ * from the point of view of the player to move, and the best move, 0 for a pass.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct JournalEntry {
+    pub depth: u8,
+    pub score: i32,
+    pub best: BitBoard,
+}
+
+/*
+ * An append-only journal of the positions searched by the AI, so that no search is ever lost:
+ * the journal of many games and analyses can later be compacted into an opening book or an
+ * analysis cache. It is a text file of one search per line, the key of the canonical position,
+ * the black and white bitboards in hexadecimal followed by b or w for the player to move, then
+ * the depth, the score, and the square index of the best move in the canonical orientation, 64
+ * for a pass. Lines are only ever appended, whole, so that a journal stays readable when the
+ * program is stopped while writing it, at worst losing its unfinished last line.
+ */
+pub struct Journal {
+    file: Mutex<File>,
+}
+
+impl Journal {
+    /*
+     * Opens the journal at the given path for appending, creating it if needed.
+     */
+    pub fn open(path: impl AsRef<Path>) -> Result<Journal, OthelloError> {
+        let mut file: File = OpenOptions::new().create(true).append(true).open(path)?;
+        if file.metadata()?.len() == 0 {
+            file.write_all(b"# Positions searched by the alpha-beta AI\n")?;
+        }
+        Ok(Journal { file: Mutex::new(file), })
+    }
+
+    /*
+     * Appends the search of the given position to the journal.
+     */
+    pub fn append(&self, oth: Othello, color: Color, entry: JournalEntry)
+        -> Result<(), OthelloError>
+    {
+        let (canonical, symmetry) = oth.canonical();
+        let best: BitBoard = symmetry.apply(entry.best);
+        let line: String = format!("{} {} {} {}\n", key(canonical, color), entry.depth,
+            entry.score, if best == 0 { 64 } else { best.trailing_zeros() });
+        let mut file = self.file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    /*
+     * Reads all the searches of the journal at the given path, in the order they were written,
+     * their positions and best moves in canonical form. An unfinished last line is skipped,
+     * while other malformed lines are errors.
+     */
+    pub fn read(path: impl AsRef<Path>)
+        -> Result<Vec<(Othello, Color, JournalEntry)>, OthelloError>
+    {
+        Journal::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /*
+     * Reads the searches of a journal written by append, see read. Empty lines and # comments
+     * are ignored.
+     */
+    pub fn parse(text: &str) -> Result<Vec<(Othello, Color, JournalEntry)>, OthelloError> {
+        let mut entries: Vec<(Othello, Color, JournalEntry)> = Vec::new();
+        let lines: Vec<&str> = text.lines().collect();
+        let finished: usize = if text.ends_with('\n') { lines.len() } else { lines.len() - 1 };
+
+        for (i, line) in lines[..finished].iter().enumerate() {
+            let line: &str = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let entry = parse_line(line)
+                .ok_or_else(|| parse_error(format!("Invalid journal entry at line {}.", i + 1)))?;
+            entries.push(entry);
+        }
+
+        Ok(entries)
+    }
+}
+
+/*
+ * Returns the key of a position in the journal, as described on Journal.
+ */
+fn key(oth: Othello, color: Color) -> String {
+    let (black, white) = (oth.get_bitboard(Color::Black), oth.get_bitboard(Color::White));
+    format!("{:016x}{:016x}{}", black, white, if color == Color::Black { 'b' } else { 'w' })
+}
+
+/*
+ * Reads a line of the journal.
+ */
+fn parse_line(line: &str) -> Option<(Othello, Color, JournalEntry)> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let [key, depth, score, best] = fields.as_slice() else {
+        return None;
+    };
+    if key.len() != 33 || !key.is_ascii() {
+        return None;
+    }
+    let black: BitBoard = BitBoard::from_str_radix(&key[..16], 16).ok()?;
+    let white: BitBoard = BitBoard::from_str_radix(&key[16..32], 16).ok()?;
+    let color: Color = match &key[32..] {
+        "b" => Color::Black,
+        "w" => Color::White,
+        _ => return None,
+    };
+    let best: BitBoard = match best.parse::<u32>().ok()? {
+        64 => 0,
+        index if index < 64 => 1 << index,
+        _ => return None,
+    };
+    let entry: JournalEntry =
+        JournalEntry { depth: depth.parse().ok()?, score: score.parse().ok()?, best, };
+    Some((Othello::create(black, white), color, entry))
+}
+
+//#################################################################################################
+//
+//                                       COMPACTION
+//
+//#################################################################################################
+
+/*
+ * Compacts the searches of a journal into an opening book. Besides its own evaluation, each
+ * position searched gives that of the position after its best move, one ply shallower, the one
+ * the AI looks up in the book to play from the position. Positions searched several times
+ * keep their deepest evaluation.
+ */
+pub fn compact_book(entries: &[(Othello, Color, JournalEntry)]) -> Book {
+    let mut book: Vec<(Othello, Color, BookEntry)> = Vec::with_capacity(2 * entries.len());
+    for &(oth, color, entry) in entries {
+        book.push((oth, color, BookEntry { score: entry.score, depth: entry.depth, }));
+        if entry.best != 0 && entry.depth > 0 {
+            let next: Othello = oth.make_move(color, entry.best);
+            // The opponent may have to pass, the position being then stored with us to move.
+            let (color, score) = if next.gen_moves(color.invert()) == 0 {
+                (color, entry.score)
+            } else {
+                (color.invert(), entry.score.saturating_neg())
+            };
+            book.push((next, color, BookEntry { score, depth: entry.depth - 1, }));
+        }
+    }
+    Book::from_entries(book)
+}
+
+/*
+ * Compacts the searches of a journal into an analysis cache of the given capacity, keeping the
+ * deepest search of each position, its principal variation being its best move alone.
+ */
+pub fn compact_cache(entries: &[(Othello, Color, JournalEntry)], capacity: usize)
+    -> AnalysisCache
+{
+    let mut cache: AnalysisCache = AnalysisCache::new(capacity);
+    for &(oth, color, entry) in entries {
+        let analysis: Analysis =
+            Analysis { score: entry.score, pv: vec![entry.best], depth: entry.depth, };
+        cache.insert(oth, color, analysis);
+    }
+    cache
+}
+
+//#################################################################################################
+//
+//                                      JOURNAL TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that the searches of the AI are journaled in canonical form, that an unfinished last
+ * line is skipped, and that the journal is compacted into a book playing the moves found.
+ */
+#[cfg(all(test, feature = "alphabeta"))]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::symmetry::*;
+    use crate::alphabeta::*;
+
+    #[test]
+    fn journal() {
+        let path = std::env::temp_dir().join(format!("othello-{}.journal", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let player: AlphaBetaPlayer =
+            AlphaBetaPlayer::new(3).with_journal(Arc::new(Journal::open(&path).unwrap()));
+
+        let oth: Othello = Othello::new().make_move(Color::Black, 1 << 37);
+        let (best, _, _) = player.best_move(oth, Color::White);
+        let symmetric: Othello = oth.transform(Symmetry::FlipVertical);
+        player.best_move(symmetric, Color::White);
+        std::fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(b"0000").unwrap();
+
+        let entries: Vec<(Othello, Color, JournalEntry)> = Journal::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0] == entries[1]);
+        assert!(entries[0].0 == oth.canonical().0);
+        assert_eq!(entries[0].2.depth, 3);
+        assert!(Journal::parse("0 3 1 64\n").is_err());
+
+        let book: Book = compact_book(&entries);
+        assert_eq!(book.moves(oth, Color::White)[0].0, best);
+        let mut cache: AnalysisCache = compact_cache(&entries, 4);
+        assert_eq!(cache.get(oth, Color::White).unwrap().pv, vec![best]);
+    }
+}
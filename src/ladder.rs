@@ -0,0 +1,197 @@
+use std::path::{Path, PathBuf};
+
+use crate::alphabeta::MAX_ELO;
+use crate::record::today;
+use crate::config::*;
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                       LADDER TYPE
+//
+//#################################################################################################
+
+/*
+ * The rating of a newcomer to the ladder, that of the weakest level of the AI.
+ */
+const INITIAL_RATING: f64 = (MAX_ELO - LEVELS_SPAN) as f64;
+
+/*
+ * How far below MAX_ELO the levels of the AI go, see AlphaBetaPlayer::with_target_elo, and the
+ * step between two levels.
+ */
+const LEVELS_SPAN: u16 = 600;
+const LEVELS_STEP: u16 = 50;
+
+/*
+ * How much a game can change the rating: a lot during the first games, while the rating is
+ * still provisional, and less afterwards.
+ */
+const PROVISIONAL_GAMES: usize = 20;
+const PROVISIONAL_K: f64 = 40.0;
+const K: f64 = 20.0;
+
+/*
+ * A game of the ladder: its date, the rating of the AI faced, the score of the user, 1 for a
+ * win, 0.5 for a draw and 0 for a loss, and his rating after it.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct LadderGame {
+    pub date: String,
+    pub opponent: u16,
+    pub score: f64,
+    pub rating: f64,
+}
+
+/*
+ * A local ladder for a human user, rating him by his games against the AI at the levels given
+ * by its target ratings. His Elo rating is updated after each game, and the next level
+ * suggested is the one closest to it, so that games stay balanced. The ladder is kept in a
+ * small text file in the config directory, one game per line.
+ */
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Ladder {
+    pub games: Vec<LadderGame>,
+}
+
+impl Ladder {
+    /*
+     * Returns the path of the ladder file, ladder.txt next to the config file.
+     */
+    pub fn default_path() -> Option<PathBuf> {
+        Config::default_path().map(|path| path.with_file_name("ladder.txt"))
+    }
+
+    /*
+     * Reads the ladder at its default path, or returns an empty one if there is none.
+     */
+    pub fn load() -> Result<Ladder, OthelloError> {
+        match Ladder::default_path() {
+            Some(path) if path.exists() => Ladder::from_file(path),
+            _ => Ok(Ladder::default()),
+        }
+    }
+
+    /*
+     * Reads the ladder file at the given path.
+     */
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Ladder, OthelloError> {
+        Ladder::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /*
+     * Writes the ladder to the given file, creating its directory if needed.
+     */
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), OthelloError> {
+        if let Some(dir) = path.as_ref().parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, self.to_text())?;
+        Ok(())
+    }
+
+    /*
+     * Reads a ladder written by to_text: one game per line, with its date, the rating of the
+     * AI, the score and the rating after it, separated by spaces. Empty lines and # comments
+     * are ignored.
+     */
+    pub fn parse(text: &str) -> Result<Ladder, OthelloError> {
+        let mut ladder: Ladder = Ladder::default();
+
+        for (i, line) in text.lines().enumerate() {
+            let line: &str = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let invalid = || parse_error(format!("Invalid ladder game at line {}.", i + 1));
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [date, opponent, score, rating] = fields.as_slice() else {
+                return Err(invalid());
+            };
+            ladder.games.push(LadderGame {
+                date: date.to_string(),
+                opponent: opponent.parse().map_err(|_| invalid())?,
+                score: score.parse().ok().filter(|score| [0.0, 0.5, 1.0].contains(score))
+                    .ok_or_else(invalid)?,
+                rating: rating.parse().map_err(|_| invalid())?,
+            });
+        }
+
+        Ok(ladder)
+    }
+
+    /*
+     * Writes the ladder in the format read by parse.
+     */
+    pub fn to_text(&self) -> String {
+        let mut text: String = String::from("# date opponent score rating\n");
+        for game in &self.games {
+            text += &format!("{} {} {} {:.1}\n", game.date, game.opponent, game.score,
+                game.rating);
+        }
+        text
+    }
+
+    /*
+     * Returns the current rating of the user.
+     */
+    pub fn rating(&self) -> f64 {
+        self.games.last().map_or(INITIAL_RATING, |game| game.rating)
+    }
+
+    /*
+     * Records a game of the user against the AI of the given rating, with the given score,
+     * and updates his rating. Returns the new rating.
+     */
+    pub fn record(&mut self, opponent: u16, score: f64) -> f64 {
+        let rating: f64 = self.rating();
+        let expected: f64 = 1.0 / (1.0 + 10f64.powf((opponent as f64 - rating) / 400.0));
+        let k: f64 = if self.games.len() < PROVISIONAL_GAMES { PROVISIONAL_K } else { K };
+        let rating: f64 = rating + k * (score - expected);
+
+        self.games.push(LadderGame { date: today(), opponent, score, rating, });
+        rating
+    }
+
+    /*
+     * Returns the level of the AI the user should face next: the one closest to his rating,
+     * within the levels the AI can play at.
+     */
+    pub fn suggestion(&self) -> u16 {
+        let level: f64 = (self.rating() / LEVELS_STEP as f64).round() * LEVELS_STEP as f64;
+        (level as u16).clamp(MAX_ELO - LEVELS_SPAN, MAX_ELO)
+    }
+}
+
+//#################################################################################################
+//
+//                                       LADDER TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that ratings move the right way, and that the ladder survives a round trip through
+ * its text format.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ratings() {
+        let mut ladder: Ladder = Ladder::default();
+        assert_eq!(ladder.suggestion(), MAX_ELO - LEVELS_SPAN);
+
+        let rating: f64 = ladder.record(1400, 1.0);
+        assert!((rating - 1420.0).abs() < 1e-9);
+        assert!(ladder.record(1800, 0.0) < rating);
+        ladder.record(2000, 1.0);
+        assert_eq!(ladder.suggestion(), 1450);
+
+        let parsed: Ladder = Ladder::parse(&ladder.to_text()).unwrap();
+        assert_eq!(parsed.games.len(), 3);
+        assert!((parsed.rating() - ladder.rating()).abs() < 0.1);
+        assert!(Ladder::parse("2026.10.14 1500 2 1500").is_err());
+    }
+}
@@ -0,0 +1,95 @@
+/*
+ * The board logic (types, coordinates, move generation, symmetries, the wire encoding, the
+ * features for machine learning, the endgame solver, the light search and the test suite)
+ * doesn't need the standard library and can be used on embedded devices by disabling the
+ * default "std" feature. Everything else, the full AI included, requires it.
+ */
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+pub mod types;
+pub mod coord;
+pub mod othello;
+pub mod symmetry;
+pub mod wire;
+pub mod features;
+pub mod phase;
+pub mod prelude;
+#[cfg(feature = "std")]
+pub mod error;
+#[cfg(feature = "std")]
+pub mod terminal;
+#[cfg(feature = "std")]
+pub mod eval;
+#[cfg(feature = "alphabeta")]
+pub mod alphabeta;
+#[cfg(feature = "mcts")]
+pub mod mcts;
+#[cfg(feature = "alphabeta")]
+pub mod ordering;
+#[cfg(feature = "std")]
+pub mod executor;
+pub mod endgame;
+pub mod light;
+#[cfg(feature = "pnsearch")]
+pub mod pnsearch;
+#[cfg(feature = "std")]
+pub mod retrograde;
+#[cfg(feature = "pnsearch")]
+pub mod proof;
+#[cfg(feature = "std")]
+pub mod reachability;
+pub mod testsuite;
+#[cfg(feature = "std")]
+pub mod game;
+#[cfg(feature = "std")]
+pub mod player;
+#[cfg(feature = "std")]
+pub mod searcher;
+#[cfg(feature = "std")]
+pub mod external;
+#[cfg(feature = "std")]
+pub mod diff;
+#[cfg(all(test, feature = "alphabeta"))]
+mod conformance;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod arena;
+#[cfg(feature = "std")]
+pub mod record;
+#[cfg(feature = "std")]
+pub mod cache;
+#[cfg(feature = "std")]
+pub mod tablebase;
+#[cfg(feature = "std")]
+pub mod mapped;
+#[cfg(feature = "std")]
+pub mod book;
+#[cfg(feature = "std")]
+pub mod journal;
+#[cfg(feature = "alphabeta")]
+pub mod config;
+#[cfg(feature = "alphabeta")]
+pub mod shell;
+#[cfg(feature = "std")]
+pub mod wthor;
+#[cfg(feature = "std")]
+pub mod ggf;
+#[cfg(feature = "std")]
+pub mod online;
+#[cfg(feature = "alphabeta")]
+pub mod report;
+#[cfg(feature = "alphabeta")]
+pub mod ladder;
+#[cfg(feature = "alphabeta")]
+pub mod trainer;
+#[cfg(feature = "alphabeta")]
+pub mod tuning;
+#[cfg(feature = "std")]
+pub mod opponent;
+#[cfg(feature = "sqlite")]
+pub mod database;
+#[cfg(feature = "dataset")]
+pub mod dataset;
+#[cfg(feature = "spectate")]
+pub mod spectate;
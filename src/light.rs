@@ -0,0 +1,157 @@
+use crate::types::*;
+use crate::othello::*;
+use crate::endgame::*;
+
+//#################################################################################################
+//
+//                                      LIGHT SEARCH
+//
+//#################################################################################################
+
+/*
+ * The deepest search light_search accepts. The search only uses the stack, a small frame per
+ * ply, so that it fits the small stacks of microcontrollers.
+ */
+pub const MAX_LIGHT_DEPTH: u8 = 8;
+
+/*
+ * The corners, good to hold, and the squares diagonally next to each of them, giving them away
+ * while they are empty, as pairs of squares.
+ */
+const CORNERS: BitBoard = 0x8100000000000081;
+const X_SQUARES: [(u8, u8); 4] = [(0, 9), (7, 14), (56, 49), (63, 54)];
+
+/*
+ * The value of a disc of final disc differential, above any evaluation, so that finished
+ * games are always preferred or avoided.
+ */
+const EXACT: i32 = 1000;
+
+/*
+ * Evaluates the position for the given player to move: the difference of mobility, of
+ * corners, and of X-squares next to an empty corner.
+ */
+fn evaluate(oth: Othello, color: Color) -> i32 {
+    let own: BitBoard = oth.get_bitboard(color);
+    let opp: BitBoard = oth.get_bitboard(color.invert());
+    let x_squares: BitBoard = X_SQUARES.iter()
+        .filter(|&&(corner, _)| (own | opp) & 1 << corner == 0)
+        .fold(0, |acc, &(_, x)| acc | 1 << x);
+    let count = |board: BitBoard| board.pop_cnt() as i32;
+
+    let mobility: i32 = count(oth.gen_moves(color)) - count(oth.gen_moves(color.invert()));
+    let corners: i32 = count(own & CORNERS) - count(opp & CORNERS);
+    let x: i32 = count(own & x_squares) - count(opp & x_squares);
+    2 * mobility + 20 * corners - 8 * x
+}
+
+/*
+ * Plain negamax with alpha-beta pruning to the given depth, searching the corners first.
+ */
+fn negamax(oth: Othello, color: Color, depth: u8, mut alpha: i32, beta: i32, passed: bool)
+    -> i32
+{
+    let moves: BitBoard = oth.gen_moves(color);
+    if moves == 0 {
+        if passed {
+            return EXACT * final_score(oth, color);
+        }
+        return -negamax(oth, color.invert(), depth, -beta, -alpha, true);
+    }
+    if depth == 0 {
+        return evaluate(oth, color);
+    }
+
+    let mut best: i32 = -EXACT * 65;
+    for mut group in [moves & CORNERS, moves & !CORNERS] {
+        while group != 0 {
+            let new_oth: Othello = oth.make_move(color, group.pop_lsb());
+            let value: i32 = -negamax(new_oth, color.invert(), depth - 1, -beta, -alpha, false);
+            best = best.max(value);
+            alpha = alpha.max(value);
+            if alpha >= beta {
+                return best;
+            }
+        }
+    }
+    best
+}
+
+/*
+ * A small search for the best move of the given player, for devices without a heap, like the
+ * microcontrollers of electronic boards: alpha-beta to the given depth, at most
+ * MAX_LIGHT_DEPTH, without transposition table and with a simple evaluation from mobility and
+ * corners. Finished games are valued by their final score, so that the search plays perfectly
+ * once it sees the end. Returns the move, 0 to pass, and its value for the player, EXACT times
+ * the final disc differential if it is known.
+ */
+pub fn light_search(oth: Othello, color: Color, depth: u8) -> (BitBoard, i32) {
+    let depth: u8 = depth.clamp(1, MAX_LIGHT_DEPTH);
+    let moves: BitBoard = oth.gen_moves(color);
+    if moves == 0 {
+        return (0, negamax(oth, color, depth, -EXACT * 65, EXACT * 65, false));
+    }
+
+    let (mut best, mut alpha): (BitBoard, i32) = (0, -EXACT * 65);
+    for mut group in [moves & CORNERS, moves & !CORNERS] {
+        while group != 0 {
+            let mv: BitBoard = group.pop_lsb();
+            let new_oth: Othello = oth.make_move(color, mv);
+            let value: i32 = -negamax(new_oth, color.invert(), depth - 1, -EXACT * 65, -alpha,
+                false);
+            if value > alpha {
+                (best, alpha) = (mv, value);
+            }
+        }
+    }
+    (best, alpha)
+}
+
+//#################################################################################################
+//
+//                                   LIGHT SEARCH TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that the light search plays perfectly when it sees the end, on positions of the test
+ * suite two moves in, and that it takes a corner when it can.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsuite::*;
+
+    #[test]
+    fn light() {
+        for position in positions().iter().take(8) {
+            // Two moves in, 8 squares are left.
+            let (mut oth, mut color) = (position.oth, position.color);
+            for _ in 0..2 {
+                let moves: BitBoard = oth.gen_moves(color);
+                oth = oth.make_move(color, moves & moves.wrapping_neg());
+                color = !color;
+            }
+            if oth.gen_moves(color) == 0 {
+                continue;
+            }
+
+            let (mv, value) = light_search(oth, color, MAX_LIGHT_DEPTH);
+            let score: i32 = solve(oth, color);
+            assert_eq!(value, EXACT * score);
+            assert_eq!(-solve(oth.make_move(color, mv), !color), score);
+        }
+
+        let oth: Othello = crate::pos!("
+            - X O - - - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - O X - - -
+            - - - X O - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - - - - - -
+        ");
+        assert_eq!(light_search(oth, Color::White, 1).0, 1);
+    }
+}
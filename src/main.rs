@@ -1,11 +1,1150 @@
-mod types;
-mod othello;
-mod terminal;
-mod alphabeta;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 
-use crate::terminal::*;
-use crate::alphabeta::*;
+use clap::{Parser, Subcommand, ValueEnum};
+
+use othello::types::*;
+use othello::othello::*;
+use othello::game::*;
+use othello::player::*;
+use othello::searcher::*;
+use othello::external::*;
+use othello::terminal::*;
+use othello::eval::*;
+use othello::alphabeta::*;
+use othello::mcts::*;
+use othello::endgame::*;
+use othello::pnsearch::*;
+use othello::proof::*;
+use othello::reachability::*;
+use othello::testsuite::*;
+use othello::arena::*;
+use othello::stats::*;
+use othello::book::*;
+use othello::journal::*;
+use othello::config::*;
+use othello::shell::*;
+use othello::ladder::*;
+use othello::trainer::*;
+use othello::record::*;
+use othello::online;
+use othello::report::*;
+use othello::tuning::*;
+#[cfg(feature = "dataset")]
+use othello::dataset::*;
+#[cfg(feature = "sqlite")]
+use othello::database::*;
+#[cfg(feature = "spectate")]
+use othello::spectate::*;
+use othello::executor::*;
+use othello::error::*;
+
+//#################################################################################################
+//
+//                                      COMMAND LINE
+//
+//#################################################################################################
+
+/*
+ * The command line of the executable. The engine options come from the config file, and can
+ * be overridden one by one with --option.
+ */
+#[derive(Parser)]
+#[command(name = "othello", version, about = "An Othello engine, with its AIs, solver and tools.")]
+struct Cli {
+    #[arg(long, global = true, help = "Reads the options from this file, not the default one")]
+    config: Option<PathBuf>,
+    #[arg(short, long = "option", global = true, value_name = "NAME=VALUE",
+        help = "Overrides an option of the config file")]
+    options: Vec<String>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/*
+ * The commands of the executable. Positions are given as the line of moves leading to them
+ * from the starting position, like "f5d6c3", passes being implied.
+ */
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Plays a game in the terminal, the default command")]
+    Play {
+        #[arg(long, value_enum, default_value = "human")]
+        black: PlayerKind,
+        #[arg(long, value_enum, default_value = "alphabeta")]
+        white: PlayerKind,
+        #[arg(long, help = "Neither saves the game after each move nor offers to resume one")]
+        no_autosave: bool,
+        #[arg(long, default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..=4),
+            help = "The number of corners given to Black before the first move")]
+        handicap: u8,
+    },
+    #[command(about = "Plays a rated game against the AI, at the level suggested by the ladder")]
+    Ladder {
+        #[arg(long, help = "Plays against the AI at this rating instead")]
+        elo: Option<u16>,
+        #[arg(long, help = "Only shows the rating and the last games")]
+        status: bool,
+    },
+    #[command(about = "Suggests the best moves of a position, with the reasons for them")]
+    Analyze {
+        line: Option<String>,
+        #[arg(long, default_value_t = 3, help = "The number of moves to suggest")]
+        hints: usize,
+        #[arg(long, value_name = "MOVES", help = "Only suggests among these moves, like d3c4")]
+        searchmoves: Option<String>,
+        #[arg(long, value_name = "MOVES", help = "Never suggests these moves, like the one played")]
+        exclude: Option<String>,
+    },
+    #[command(about = "Writes the analysis report of a game in HTML or Markdown")]
+    Report {
+        #[arg(help = "A game record, the export of an online app, or a transcript")]
+        game: String,
+        #[arg(long, default_value_t = 1, help = "The number of the game in an export")]
+        index: usize,
+        #[arg(long, help = "The report file, in Markdown if it ends with .md and HTML \
+            otherwise, printed in Markdown if missing")]
+        output: Option<PathBuf>,
+    },
+    #[command(about = "Estimates the ratings of the players of a game from their moves")]
+    Strength {
+        #[arg(help = "A game record, the export of an online app, or a transcript")]
+        game: String,
+        #[arg(long, default_value_t = 1, help = "The number of the game in an export")]
+        index: usize,
+    },
+    #[cfg(feature = "sqlite")]
+    #[command(about = "Counts the mistakes of a player over his games in a database")]
+    Blundercheck {
+        #[arg(long, help = "The SQLite game database")]
+        db: PathBuf,
+        #[arg(long, help = "The name of the player")]
+        player: String,
+        #[arg(long, default_value_t = 6, help = "The depth the moves are searched to")]
+        depth: u8,
+        #[arg(long, help = "Writes heatmaps of his moves and mistakes by square to this file, \
+            in JSON if it ends with .json and CSV otherwise")]
+        heatmaps: Option<PathBuf>,
+    },
+    #[command(about = "Counts the leaf nodes of the game tree from the starting position")]
+    Perft {
+        #[arg(default_value_t = 9)]
+        depth: usize,
+    },
+    #[command(about = "Times the alpha-beta AI on the positions of the test suite")]
+    Bench {
+        #[arg(long = "positions", default_value_t = 20, help = "The number of positions to search")]
+        count: usize,
+        #[arg(long, default_value_t = BENCH_DEPTH, help = "The depth of the searches, whatever \
+            the depth of the config")]
+        depth: u8,
+        #[arg(long, help = "Searches each position twice on a single thread, checking that both \
+            searches are the same")]
+        audit: bool,
+    },
+    #[command(about = "Cross-checks the alpha-beta AI against an external engine, showing the \
+        positions where they disagree")]
+    SecondOpinion {
+        #[arg(value_name = "COMMAND", help = "The command line of an external engine speaking \
+            NBoard, such as Edax")]
+        external: String,
+        #[arg(long, help = "A file of positions to check, one per line, the test suite by \
+            default")]
+        input: Option<PathBuf>,
+        #[arg(long, default_value_t = 0.2, help = "The difference between the probabilities \
+            of winning of both engines from which they disagree")]
+        threshold: f64,
+        #[arg(long, default_value_t = 12, help = "The depth the external engine searches to")]
+        external_depth: u8,
+    },
+    #[command(about = "Solves a position exactly, or checks the solver on the test suite")]
+    Solve {
+        line: Option<String>,
+        #[arg(long, requires = "output", conflicts_with = "line",
+            help = "A file of positions to solve, one per line")]
+        input: Option<PathBuf>,
+        #[arg(long, requires = "input", help = "The CSV file the scores are written to")]
+        output: Option<PathBuf>,
+        #[arg(long, requires = "input", help = "Skips the positions already in the output")]
+        resume: bool,
+        #[arg(long, value_name = "BACKEND", conflicts_with = "input",
+            help = "Only solves for a win, a draw or a loss with alphabeta or pn, comparing both \
+                on the test suite")]
+        wld: Option<WldBackend>,
+    },
+    #[command(about = "Proves the outcome of a line by solving every deviation along it")]
+    Prove {
+        line: String,
+        #[arg(long, default_value_t = 0,
+            help = "The number of moves of the line after which the outcome is claimed")]
+        start: usize,
+        #[arg(long, value_enum, help = "The claimed winner, or a draw")]
+        claim: Claim,
+        #[arg(long, value_name = "BACKEND", default_value = "pn",
+            help = "The WLD solver, alphabeta or pn")]
+        wld: WldBackend,
+        #[arg(long, help = "The file the proof tree is written to")]
+        output: Option<PathBuf>,
+    },
+    #[command(about = "Checks a proof tree written by prove, solving all its positions again")]
+    CheckProof {
+        file: PathBuf,
+        #[arg(long, value_name = "BACKEND", default_value = "alphabeta",
+            help = "The WLD solver, alphabeta or pn")]
+        wld: WldBackend,
+    },
+    #[command(about = "Tells whether a position, as moves or 64 squares, can arise in a game")]
+    Reachable {
+        #[arg(allow_hyphen_values = true)]
+        position: String,
+    },
+    #[command(about = "Shows the moves of a position found in an opening book")]
+    Book {
+        file: PathBuf,
+        line: Option<String>,
+    },
+    #[command(about = "Compacts a journal of the searches of the AI into an opening book")]
+    Journal {
+        file: PathBuf,
+        #[arg(long, help = "The opening book to write, only counting the positions if missing")]
+        book: Option<PathBuf>,
+    },
+    #[command(about = "Quizzes you on the lines of an opening book, with spaced repetition")]
+    Train {
+        book: PathBuf,
+        #[arg(long, value_enum, default_value = "black", help = "The side you train for")]
+        side: Side,
+        #[arg(long, default_value_t = 12, help = "The number of moves of the lines")]
+        depth: usize,
+    },
+    #[command(about = "Asks you for the exact final score of endless near-final positions")]
+    Count {
+        #[arg(long, default_value_t = 10,
+            value_parser = clap::value_parser!(u8).range(1..=MAX_COUNTING_EMPTIES as i64),
+            help = "The number of empty squares of the positions")]
+        empties: u8,
+        #[arg(long, help = "The seed of the positions, random by default")]
+        seed: Option<u64>,
+    },
+    #[command(about = "Opens an interactive shell to analyze positions")]
+    Shell,
+    #[command(about = "Plays a match between two AIs")]
+    Tournament {
+        #[arg(long, value_enum, default_value = "alphabeta")]
+        first: PlayerKind,
+        #[arg(long, value_enum, default_value = "mcts")]
+        second: PlayerKind,
+        #[arg(long, default_value_t = 10)]
+        games: u32,
+        #[arg(long, num_args = 2, value_names = ["A", "B"], help = "Plays the alpha-beta AI \
+            against itself, the first player with the weights of file A and the second with B")]
+        eval: Option<Vec<PathBuf>>,
+        #[arg(long, help = "The random plies opening each pair of games, 8 by default with \
+            --eval and none otherwise")]
+        random_plies: Option<usize>,
+        #[arg(long, default_value_t = 1, help = "The seed of the random openings")]
+        seed: u64,
+        #[arg(long, value_name = "FILE", help = "A suite of openings, one transcript per line, \
+            each played with both colors, instead of random ones")]
+        openings: Option<PathBuf>,
+        #[arg(long, value_name = "COMMAND", help = "The command line of an external engine \
+            speaking NBoard, such as Edax, playing instead of the second player")]
+        external: Option<String>,
+        #[arg(long, default_value_t = 12, help = "The depth the external engine searches to")]
+        external_depth: u8,
+        #[arg(long, value_name = "FILE", help = "Writes the time, depth, nodes and score of \
+            every move as CSV to this file")]
+        time_usage: Option<PathBuf>,
+        #[arg(long, value_name = "FILE", help = "Saves the records of the games to this file \
+            after each game")]
+        checkpoint: Option<PathBuf>,
+        #[arg(long, requires = "checkpoint", help = "Resumes the tournament from the games \
+            already in the checkpoint")]
+        resume: bool,
+        #[arg(long, value_enum, requires = "players", conflicts_with_all = ["eval", "time_usage"],
+            help = "Plays a gauntlet or a round-robin between the players instead")]
+        schedule: Option<ScheduleKind>,
+        #[arg(long, value_enum, num_args = 2.., help = "The players of the gauntlet, the \
+            candidate first, or of the round-robin, followed by the external engine if any")]
+        players: Vec<PlayerKind>,
+        #[arg(long, num_args = 2, value_names = ["ELO0", "ELO1"], conflicts_with = "schedule",
+            help = "Tests whether the first player is ELO0 or ELO1 Elo stronger, with an SPRT")]
+        sprt: Option<Vec<f64>>,
+        #[arg(long, help = "Leaves the games repeating an earlier one out of the standings")]
+        discard_duplicates: bool,
+        #[cfg(feature = "spectate")]
+        #[arg(long, help = "The address to broadcast the games to over WebSocket")]
+        spectate: Option<String>,
+    },
+    #[command(about = "Generates a suite of balanced openings of random plies")]
+    Openings {
+        output: PathBuf,
+        #[arg(long, default_value_t = 100, help = "The number of openings")]
+        count: usize,
+        #[arg(long, default_value_t = 8, help = "The number of random plies of each opening")]
+        plies: usize,
+        #[arg(long, default_value_t = 4, help = "The depth of the search checking the balance")]
+        depth: u8,
+        #[arg(long, default_value_t = 20, help = "The largest evaluation of a balanced opening")]
+        margin: i32,
+        #[arg(long, default_value_t = 1)]
+        seed: u64,
+    },
+    #[command(about = "Measures the strength of the AI at each target rating it is calibrated \
+        for, against itself at full strength")]
+    Calibrate {
+        #[arg(long, default_value_t = 200, help = "The number of games at each rating")]
+        games: u32,
+        #[arg(long, default_value_t = 3, help = "The depth the AIs search to")]
+        depth: u8,
+        #[arg(long, default_value_t = 1, help = "The seed of the random openings")]
+        seed: u64,
+    },
+    #[command(about = "Tunes the constants of the search by SPSA, playing the alpha-beta AI \
+        against itself")]
+    Tune {
+        #[arg(long, default_value_t = 10, help = "The number of iterations to run")]
+        iterations: u32,
+        #[arg(long, default_value_t = 20, help = "The number of games of each iteration")]
+        games: u32,
+        #[arg(long, default_value_t = 4, help = "The depth the AIs search to")]
+        depth: u8,
+        #[arg(long, help = "The file the tuning is resumed from and saved to, tuning.txt next \
+            to the config by default")]
+        state: Option<PathBuf>,
+    },
+    #[cfg(feature = "dataset")]
+    #[command(about = "Writes the positions of self-play games of the AI as a NumPy .npz file")]
+    Dataset {
+        output: PathBuf,
+        #[arg(long, default_value_t = 100)]
+        games: usize,
+        #[arg(long, default_value_t = 8, help = "The random plies opening alpha-beta games")]
+        random_plies: usize,
+        #[arg(long, default_value_t = 1)]
+        seed: u64,
+        #[arg(long, help = "Plays the games with the MCTS AI, doing this many playouts a move")]
+        mcts: Option<u32>,
+        #[arg(long, default_value_t = 1.0, help = "The temperature of the moves of the MCTS AI")]
+        temperature: f64,
+        #[arg(long, default_value_t = 20, help = "The number of plies played with temperature")]
+        temperature_plies: usize,
+    },
+}
+
+/*
+ * The kinds of players a game can be played by.
+ */
+#[derive(ValueEnum, Clone, Copy)]
+enum PlayerKind {
+    Human, Alphabeta, Mcts, Random,
+}
+
+/*
+ * The schedules of tournaments between more than two players.
+ */
+#[derive(ValueEnum, Clone, Copy)]
+enum ScheduleKind {
+    Gauntlet, RoundRobin,
+}
+
+/*
+ * The outcomes a line can be claimed to lead to, by the winner or a draw.
+ */
+#[derive(ValueEnum, Clone, Copy)]
+enum Claim {
+    Black, Draw, White,
+}
+
+/*
+ * The sides of the board.
+ */
+#[derive(ValueEnum, Clone, Copy)]
+enum Side {
+    Black, White,
+}
 
 fn main() {
-    terminal_play(&AlphaBetaPlayer::new(9), &AlphaBetaPlayer::new(10));
+    let cli: Cli = Cli::parse();
+
+    if let Err(err) = run(cli) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
+
+/*
+ * Reads the options and runs the command.
+ */
+fn run(cli: Cli) -> Result<(), OthelloError> {
+    let mut config: Config = match &cli.config {
+        Some(path) => Config::from_file(path)?,
+        None => Config::load()?,
+    };
+    for option in &cli.options {
+        let (name, value) = option.split_once('=').ok_or_else(|| {
+            OthelloError::ParseError(format!("Expected NAME=VALUE, got {}", option))
+        })?;
+        config.set_option(name.trim(), value.trim())?;
+    }
+    for warning in config.warnings() {
+        eprintln!("Warning: {}.", warning);
+    }
+
+    let command: Command = cli.command.unwrap_or(Command::Play {
+        black: PlayerKind::Human,
+        white: PlayerKind::Alphabeta,
+        no_autosave: false,
+        handicap: 0,
+    });
+
+    match command {
+        Command::Play { black, white, no_autosave, handicap } => {
+            let mut black: Box<dyn Player> = create_player(black, &config)?;
+            let mut white: Box<dyn Player> = create_player(white, &config)?;
+            let game: Game = Game::with_handicap(handicap);
+            match autosave_path().filter(|_| !no_autosave) {
+                Some(path) => terminal_play_saved(game, &mut *black, &mut *white, &path)?,
+                None => terminal_play(game, &mut *black, &mut *white)?,
+            };
+            Ok(())
+        },
+        Command::Ladder { elo, status } => ladder_command(&config, elo, status),
+        Command::Analyze { line, hints, searchmoves, exclude } => {
+            analyze(&config, line.as_deref(), hints, searchmoves.as_deref(), exclude.as_deref())
+        },
+        Command::Report { game, index, output } => {
+            report_command(&config, &game, index, output.as_deref())
+        },
+        Command::Strength { game, index } => strength_command(&game, index),
+        #[cfg(feature = "sqlite")]
+        Command::Blundercheck { db, player, depth, heatmaps } => {
+            config.depth = depth;
+            blundercheck_command(&config, &db, &player, heatmaps.as_deref())
+        },
+        Command::Perft { depth } => {
+            perft_command(depth);
+            Ok(())
+        },
+        Command::Bench { count, depth, audit } => {
+            config.depth = depth;
+            if audit { audit_command(&config, count) } else { bench(&config, count) }
+        },
+        Command::SecondOpinion { external, input, threshold, external_depth } => {
+            second_opinion_command(&config, &external, input.as_deref(), threshold, external_depth)
+        },
+        Command::Solve { line, input, output, resume, wld } => match (input, output, wld) {
+            (Some(input), Some(output), _) => solve_file_command(&config, &input, &output, resume),
+            (_, _, Some(backend)) => solve_wld_command(backend, line.as_deref()),
+            _ => solve_command(&config, line.as_deref()),
+        },
+        Command::Prove { line, start, claim, wld, output } => {
+            prove_command(&config, &line, start, claim, wld, output.as_deref())
+        },
+        Command::CheckProof { file, wld } => {
+            let start: Instant = Instant::now();
+            let records: usize = check_proof(&ThreadPool::new(config.threads), file, wld)?;
+            println!("The proof holds, {} records checked in {:.3}s.", records,
+                start.elapsed().as_secs_f64());
+            Ok(())
+        },
+        Command::Reachable { position } => {
+            let (oth, _) = parse_position(&position)?;
+            println!("The position is {}.", is_reachable(&oth));
+            Ok(())
+        },
+        Command::Book { file, line } => book_command(file, line.as_deref()),
+        Command::Journal { file, book } => journal_command(&file, book.as_deref()),
+        Command::Train { book, side, depth } => train_command(&book, side, depth),
+        Command::Count { empties, seed } => {
+            let seed: u64 = seed.unwrap_or_else(|| {
+                SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)
+                    .map_or(0, |time| time.as_nanos() as u64)
+            });
+            let (answered, right) = terminal_count(Exercises::new(seed, empties));
+            println!("{} positions counted, {} right.", answered, right);
+            Ok(())
+        },
+        Command::Shell => Shell::new(config).run(),
+        Command::Tournament {
+            first, second, games, eval, random_plies, seed, openings, external, external_depth,
+            time_usage,
+            checkpoint, resume, schedule, players, sprt, discard_duplicates,
+            #[cfg(feature = "spectate")] spectate,
+        } => {
+            let (mut first, mut second): (Box<dyn Player>, Box<dyn Player>) = match &eval {
+                Some(files) => {
+                    let with_weights = |path: &PathBuf| -> Result<AlphaBetaPlayer, OthelloError> {
+                        Ok(config.player()?.with_weights(EvalWeights::load(path)?))
+                    };
+                    (Box::new(with_weights(&files[0])?), Box::new(with_weights(&files[1])?))
+                },
+                None => (create_player(first, &config)?, create_player(second, &config)?),
+            };
+            if let Some(line) = &external {
+                let engine: ExternalEngine = ExternalEngine::from_command_line(line)?;
+                second = Box::new(engine.with_depth(external_depth));
+            }
+            let plies: usize = random_plies.unwrap_or(if eval.is_some() { 8 } else { 0 });
+            let arena: Arena = Arena::new(games)
+                .with_random_openings(plies, seed)
+                .with_duplicates_discarded(discard_duplicates);
+            let arena: Arena = match checkpoint {
+                Some(path) => arena.with_checkpoint(path, resume),
+                None => arena,
+            };
+            let arena: Arena = match openings {
+                Some(path) => arena.with_openings(parse_openings(&std::fs::read_to_string(path)?)?),
+                None => arena,
+            };
+            #[cfg(feature = "spectate")]
+            let arena: Arena = match spectate {
+                Some(addr) => {
+                    let spectators: Spectators = Spectators::bind(addr.as_str())?;
+                    println!("Spectators can watch at http://{}.", spectators.local_addr());
+                    arena.with_spectators(std::sync::Arc::new(spectators))
+                },
+                None => arena,
+            };
+            if let Some(schedule) = schedule {
+                let schedule: Schedule = match schedule {
+                    ScheduleKind::Gauntlet => Schedule::Gauntlet,
+                    ScheduleKind::RoundRobin => Schedule::RoundRobin,
+                };
+                let mut players: Vec<Box<dyn Player>> = players.into_iter()
+                    .map(|kind| create_player(kind, &config))
+                    .collect::<Result<_, _>>()?;
+                if external.is_some() {
+                    players.push(second);
+                }
+                let mut players: Vec<&mut dyn Player> =
+                    players.iter_mut().map(|player| &mut **player as &mut dyn Player).collect();
+                let table: CrossTable = arena.run_schedule(schedule, &mut players)?;
+                print!("{}", table.to_text());
+                let duplicates: u32 = table.results.iter().flatten().flatten()
+                    .map(|standings| standings.duplicates)
+                    .sum::<u32>() / 2;
+                print_duplicates(duplicates, games * schedule.pairings(players.len()).len() as u32,
+                    discard_duplicates);
+                return Ok(());
+            }
+            let (standings, records) = arena.run_recorded(&mut *first, &mut *second)?;
+            if let Some(path) = &time_usage {
+                std::fs::write(path, write_time_usage(&records))?;
+            }
+            let names: [String; 2] = match &eval {
+                Some(files) => [0, 1].map(|i| files[i].display().to_string()),
+                None => [first.name(), second.name()],
+            };
+            println!("{}: {} wins, {} draws, {} losses against {}.",
+                names[0], standings.wins, standings.draws, standings.losses, names[1]);
+            print_duplicates(standings.duplicates, records.len() as u32, discard_duplicates);
+            println!("Strength difference: {}.", standings.elo());
+            if let Some(bounds) = sprt {
+                let sprt: Sprt = Sprt::new(bounds[0], bounds[1]);
+                let (lower, upper): (f64, f64) = sprt.bounds();
+                let verdict: &str = match sprt.result(standings.wins, standings.draws,
+                    standings.losses)
+                {
+                    SprtResult::H0 => "H0 accepted",
+                    SprtResult::H1 => "H1 accepted",
+                    SprtResult::Continue => "more games needed",
+                };
+                println!("SPRT [{}, {}]: LLR {:.2} ({:.2}, {:.2}), {}.", sprt.elo0, sprt.elo1,
+                    sprt.llr(standings.wins, standings.draws, standings.losses), lower, upper,
+                    verdict);
+            }
+            let score: f64 = standings.score();
+            if eval.is_some() && score == 0.5 {
+                println!("Neither weights won.");
+            } else if eval.is_some() {
+                let (winner, loser) = if score > 0.5 { (0, 1) } else { (1, 0) };
+                println!("The weights {} won, scoring {:.1}% against {}.", names[winner],
+                    100.0 * score.max(1.0 - score), names[loser]);
+            }
+            let [first_name, second_name] = names;
+            for (name, forfeits) in [(first_name, standings.forfeits),
+                (second_name, standings.opponent_forfeits)]
+            {
+                if forfeits.total() > 0 {
+                    println!("{} forfeited {} games: {} illegal moves, {} on time.",
+                        name, forfeits.total(), forfeits.illegal_moves, forfeits.timeouts);
+                }
+            }
+            Ok(())
+        },
+        Command::Openings { output, count, plies, depth, margin, seed } => {
+            config.depth = depth;
+            let openings: Vec<Game> =
+                balanced_openings(&config.player()?, count, plies, margin, seed);
+            std::fs::write(&output, write_openings(&openings))?;
+            println!("{} balanced openings written to {}.", openings.len(), output.display());
+            Ok(())
+        },
+        Command::Calibrate { games, depth, seed } => calibrate_command(games, depth, seed),
+        Command::Tune { iterations, games, depth, state } => {
+            config.depth = depth;
+            tune_command(&config, iterations, games, state)
+        },
+        #[cfg(feature = "dataset")]
+        Command::Dataset {
+            output, games, random_plies, seed, mcts, temperature, temperature_plies,
+        } => {
+            let dataset: Dataset = match mcts {
+                Some(playouts) => {
+                    let player: MctsPlayer = MctsPlayer::new(playouts)
+                        .with_threads(config.threads)
+                        .with_root_noise(0.3, 0.25);
+                    Dataset::mcts_self_play(&player, games, temperature, temperature_plies, seed)?
+                },
+                None => Dataset::self_play(&mut config.player()?, games, random_plies, seed)?,
+            };
+            dataset.write_npz(&output)?;
+            println!("{} positions written to {}.", dataset.len(), output.display());
+            Ok(())
+        },
+    }
+}
+
+//#################################################################################################
+//
+//                                        COMMANDS
+//
+//#################################################################################################
+
+/*
+ * Shows the rating of the user on the ladder and, unless only the status is asked for, plays
+ * a game against the AI at the suggested level, or the given one, and updates the rating. The
+ * user plays Black and White in turn.
+ */
+fn ladder_command(config: &Config, elo: Option<u16>, status: bool) -> Result<(), OthelloError> {
+    let path: PathBuf = Ladder::default_path().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::NotFound, "No config directory for the ladder.")
+    })?;
+    let mut ladder: Ladder = Ladder::load()?;
+
+    println!("Rating {:.0} after {} games, suggested level {}.", ladder.rating(),
+        ladder.games.len(), ladder.suggestion());
+    if status {
+        for game in ladder.games.iter().rev().take(10) {
+            let result: &str = match game.score {
+                score if score > 0.5 => "won",
+                score if score < 0.5 => "lost",
+                _ => "drew",
+            };
+            println!("{}  {} against {}, rating {:.0}", game.date, result, game.opponent,
+                game.rating);
+        }
+        return Ok(());
+    }
+
+    let level: u16 = elo.unwrap_or_else(|| ladder.suggestion());
+    let color: Color = if ladder.games.len().is_multiple_of(2) {
+        Color::Black
+    } else {
+        Color::White
+    };
+    let mut human: TerminalPlayer = TerminalPlayer;
+    let mut ai: AlphaBetaPlayer = config.player()?.with_target_elo(level);
+    let game: Game = match color {
+        Color::Black => terminal_play(Game::new(), &mut human, &mut ai)?,
+        Color::White => terminal_play(Game::new(), &mut ai, &mut human)?,
+    };
+
+    let score: f64 = match game.outcome().and_then(|outcome| outcome.winner) {
+        Some(winner) if winner == color => 1.0,
+        Some(_) => 0.0,
+        None => 0.5,
+    };
+    let previous: f64 = ladder.rating();
+    let rating: f64 = ladder.record(level, score);
+    ladder.save(&path)?;
+    println!("Your rating is now {:.0} ({:+.0}), suggested level {}.", rating, rating - previous,
+        ladder.suggestion());
+    Ok(())
+}
+
+/*
+ * Reads a game from a record file, or from the given game of the JSON or CSV export of an online
+ * app, or as a transcript.
+ */
+fn read_game(game: &str, index: usize) -> Result<GameRecord, OthelloError> {
+    Ok(if Path::new(game).is_file() {
+        let text: String = std::fs::read_to_string(game)?;
+        match GameRecord::from_text(&text) {
+            Ok(record) => record,
+            Err(_) => online::parse_games(&text)?.into_iter().nth(index.saturating_sub(1))
+                .ok_or_else(|| {
+                    OthelloError::ParseError(format!("No game {} in {}.", index, game))
+                })?,
+        }
+    } else {
+        GameRecord::from_game(&Game::from_transcript(game)?, "Black", "White", "")
+    })
+}
+
+/*
+ * Reviews every move of the game with the AI, and writes its analysis report to the given
+ * file or prints it.
+ */
+fn report_command(config: &Config, game: &str, index: usize, output: Option<&Path>)
+    -> Result<(), OthelloError>
+{
+    let record: GameRecord = read_game(game, index)?;
+    let start: Instant = Instant::now();
+    let reviews: Vec<MoveReview> = review_game(&config.player()?, &record.to_game()?);
+    eprintln!("{} moves reviewed in {:.3}s.", reviews.len(), start.elapsed().as_secs_f64());
+    match output {
+        Some(path) => std::fs::write(path,
+            write_report(&record, &reviews, ReportFormat::from_path(path))?)?,
+        None => print!("{}", write_report(&record, &reviews, ReportFormat::Markdown)?),
+    }
+    Ok(())
+}
+
+/*
+ * Reviews the moves of the player in all his games of the database, and prints the statistics
+ * of his mistakes, writing their heatmaps to the given file if any.
+ */
+#[cfg(feature = "sqlite")]
+fn blundercheck_command(config: &Config, db: &Path, name: &str, heatmaps: Option<&Path>)
+    -> Result<(), OthelloError>
+{
+    let db: Database = Database::open(db)?;
+    let player: AlphaBetaPlayer = config.player()?;
+    let mut check: BlunderCheck = BlunderCheck::default();
+    let mut maps: Heatmaps = Heatmaps::default();
+    let start: Instant = Instant::now();
+
+    for game in db.query(&GameQuery::new().with_player(name)) {
+        let (_, record) = game?;
+        let game: Game = record.to_game()?;
+        let names: [(Color, &String); 2] =
+            [(Color::Black, &record.black), (Color::White, &record.white)];
+        for (color, player_name) in names {
+            if player_name == name {
+                let reviews: Vec<MoveReview> = review_color(&player, &game, color);
+                check.add_game(&reviews);
+                maps.add_game(&reviews);
+            }
+        }
+    }
+    eprintln!("{} games reviewed in {:.3}s.", check.games, start.elapsed().as_secs_f64());
+    print!("{}", check.to_table());
+    if let Some(path) = heatmaps {
+        let json: bool = path.extension().is_some_and(|extension| extension == "json");
+        std::fs::write(path, if json { maps.to_json() } else { maps.to_csv() })?;
+    }
+    Ok(())
+}
+
+/*
+ * Runs the given number of iterations of the tuning resumed from the file, saving it after
+ * each, and prints the constants it reached.
+ */
+fn tune_command(config: &Config, iterations: u32, games: u32, state: Option<PathBuf>)
+    -> Result<(), OthelloError>
+{
+    let path: PathBuf = state.or_else(Tuner::default_path).ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::NotFound, "No config directory for the tuning.")
+    })?;
+    let mut tuner: Tuner = if path.exists() { Tuner::from_file(&path)? } else { Tuner::default() };
+
+    for _ in 0..iterations {
+        let start: Instant = Instant::now();
+        let score: f64 = tuner.step(config, games)?;
+        tuner.save(&path)?;
+        println!("Iteration {}: {:.1}% in {:.1}s, {:?}", tuner.iteration, 100.0 * score,
+            start.elapsed().as_secs_f64(), tuner.params());
+    }
+    println!("Tuning saved to {}, {:?}", path.display(), tuner.params());
+    Ok(())
+}
+
+/*
+ * Prints the estimated rating of each player of the game, and how closely his moves matched
+ * the AI's at each depth.
+ */
+fn strength_command(game: &str, index: usize) -> Result<(), OthelloError> {
+    let record: GameRecord = read_game(game, index)?;
+    let strengths: [Strength; 2] = estimate_strength(&record.to_game()?);
+    for (color, name) in [(Color::Black, &record.black), (Color::White, &record.white)] {
+        let strength: &Strength = &strengths[color as usize];
+        let matches: Vec<String> = STRENGTH_DEPTHS.iter().enumerate()
+            .map(|(n, depth)| format!("{:.0}% at depth {}", 100.0 * strength.match_rate(n), depth))
+            .collect();
+        println!("{} ({}): about {:.0} Elo, over {} moves matching the best {}, losing {:.1}% \
+            a move.", name, color, strength.elo(), strength.moves, matches.join(", "),
+            100.0 * strength.average_loss(STRENGTH_DEPTHS.len() - 1));
+    }
+    Ok(())
+}
+
+/*
+ * Adds the lines of the book for the given side to the trainer, and quizzes the user on the
+ * positions due today.
+ */
+fn train_command(book: &Path, side: Side, depth: usize) -> Result<(), OthelloError> {
+    let path: PathBuf = Trainer::default_path().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::NotFound, "No config directory for the trainer.")
+    })?;
+    let book: Book = Book::open(book)?;
+    let mut trainer: Trainer = Trainer::load()?;
+    let color: Color = match side { Side::Black => Color::Black, Side::White => Color::White };
+
+    let added: usize = trainer.add_lines(&book, color, depth);
+    trainer.save(&path)?;
+    println!("{} positions to train on, {} new, {} due today.", trainer.cards.len(), added,
+        trainer.due_today().len());
+    let (reviewed, right) = terminal_train(&mut trainer, &book, &path)?;
+    println!("{} positions reviewed, {} right.", reviewed, right);
+    Ok(())
+}
+
+/*
+ * Prints the best moves of the position, among the given ones if any, but the excluded ones.
+ */
+fn analyze(
+    config: &Config, line: Option<&str>, hints: usize, searchmoves: Option<&str>,
+    exclude: Option<&str>,
+) -> Result<(), OthelloError> {
+    let game: Game = Game::from_transcript(line.unwrap_or(""))?;
+    print_oth(&game.board(), game.moves(), game.last_move());
+    if game.is_over() {
+        println!("The game is over.");
+        return Ok(());
+    }
+
+    let mut player: AlphaBetaPlayer = config.player()?;
+    if let Some(moves) = searchmoves {
+        player = player.with_search_moves(parse_moves(moves)?);
+    }
+    if let Some(moves) = exclude {
+        player = player.with_excluded_moves(parse_moves(moves)?);
+    }
+    for (i, hint) in player.hint(game.board(), game.color(), hints).iter().enumerate() {
+        println!("{}. {} {:>6}  {}", i + 1, hint.mv.square_name(), value_name(hint.value),
+            hint.reason);
+    }
+
+    Ok(())
+}
+
+/*
+ * Prints the perft counts of every depth up to the given one, and the speed of the move
+ * generator.
+ */
+fn perft_command(depth: usize) {
+    for depth in 1..=depth {
+        let start: Instant = Instant::now();
+        let count: u64 = perft(Othello::new(), Color::Black, depth);
+        let time: Duration = start.elapsed();
+        println!("perft {:>2}: {:>14} leaves in {:>8.3}s, {:>6.1} Mleaves/s", depth, count,
+            time.as_secs_f64(), count as f64 / time.as_secs_f64().max(1e-9) / 1e6);
+    }
+}
+
+/*
+ * The depth of the bench, fixed so that its node count is a signature of the search.
+ */
+const BENCH_DEPTH: u8 = 11;
+
+/*
+ * Searches the first positions of the test suite with the alpha-beta AI, and prints the
+ * number of nodes visited and the speed of the search. The searches being deterministic, the
+ * total number of nodes is a signature of the search: it only changes with the search itself,
+ * its options or the positions, which catches unintended changes. An AI of its own searches
+ * the first position before, so that the timings don't count warming up.
+ */
+fn bench(config: &Config, count: usize) -> Result<(), OthelloError> {
+    let player: AlphaBetaPlayer = config.player()?;
+    let (mut nodes, mut time): (u64, Duration) = (0, Duration::ZERO);
+    if let Some(position) = positions().first() {
+        config.player()?.best_move(position.oth, position.color);
+    }
+
+    for (i, position) in positions().iter().take(count).enumerate() {
+        let (mv, _, stats) = player.best_move(position.oth, position.color);
+        let found: &str = if mv & position.best_moves != 0 { "best" } else { "    " };
+        println!("#{:<3} {} {}  {:>10} nodes in {:>7.3}s", i + 1, mv.square_name(), found,
+            stats.nodes, stats.time.as_secs_f64());
+        nodes += stats.nodes;
+        time += stats.time;
+    }
+
+    println!("Total: {} nodes in {:.3}s, {:.0} nodes/s", nodes, time.as_secs_f64(),
+        nodes as f64 / time.as_secs_f64().max(1e-9));
+    println!("Signature: {}", nodes);
+    Ok(())
+}
+
+/*
+ * Checks that the searches of the first positions of the bench are deterministic, exiting with
+ * an error at the first difference between two searches of the same position.
+ */
+fn audit_command(config: &Config, count: usize) -> Result<(), OthelloError> {
+    let suite: Vec<(Othello, Color)> = positions().iter().take(count)
+        .map(|position| (position.oth, position.color))
+        .collect();
+    match config.player()?.audit_determinism(&suite) {
+        Ok(nodes) => println!("The searches of {} positions are deterministic, {} nodes.",
+            suite.len(), nodes),
+        Err(difference) => {
+            eprintln!("The searches differ, {}.", difference);
+            std::process::exit(1);
+        },
+    }
+    Ok(())
+}
+
+/*
+ * Plays the AI at each rating deficit of the temperature table against itself at full
+ * strength, both searching to the given depth from random openings, and prints the deficit
+ * measured against the one targeted.
+ */
+fn calibrate_command(games: u32, depth: u8, seed: u64) -> Result<(), OthelloError> {
+    let arena: Arena = Arena::new(games).with_random_openings(8, seed);
+    for &(deficit, temperature) in TEMPERATURES.iter().filter(|&&(deficit, _)| deficit > 0.0) {
+        let mut weak: AlphaBetaPlayer =
+            AlphaBetaPlayer::new(depth).with_target_elo(MAX_ELO - deficit as u16);
+        let standings: Standings = arena.run(&mut weak, &mut AlphaBetaPlayer::new(depth))?;
+        println!("Temperature {}, targeting {:+}: {}.", temperature, -deficit, standings.elo());
+    }
+    Ok(())
+}
+
+/*
+ * Prints how many of the games of a tournament repeated an earlier one, if any.
+ */
+fn print_duplicates(duplicates: u32, games: u32, discarded: bool) {
+    if duplicates > 0 {
+        println!("{} of {} games ({:.1}%) repeated an earlier one{}.", duplicates, games,
+            100.0 * duplicates as f64 / games as f64, if discarded { ", discarded" } else { "" });
+    }
+}
+
+/*
+ * Searches the positions of the input file, or of the test suite, with the alpha-beta AI to
+ * the depth of the config and with the external engine, and prints those where their
+ * probabilities of winning differ by more than the threshold.
+ */
+fn second_opinion_command(
+    config: &Config, external: &str, input: Option<&Path>, threshold: f64, external_depth: u8,
+) -> Result<(), OthelloError> {
+    let suite: Vec<(Othello, Color)> = match input {
+        Some(input) => std::fs::read_to_string(input)?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(parse_position)
+            .collect::<Result<_, _>>()?,
+        None => positions().iter().map(|position| (position.oth, position.color)).collect(),
+    };
+    let mut ours: AlphaBetaPlayer = config.player()?;
+    let mut theirs: ExternalEngine =
+        ExternalEngine::from_command_line(external)?.with_depth(external_depth);
+    let disagreements: Vec<Disagreement> =
+        second_opinion(&mut ours, &mut theirs, &suite, &SearchLimits::default(), threshold);
+
+    let name: String = Searcher::name(&theirs);
+    for disagreement in &disagreements {
+        let (ours, theirs): (&SearchResult, &SearchResult) =
+            (&disagreement.ours, &disagreement.theirs);
+        println!("#{:<3} {} at {:.0}%, {} at {:.0}% for {}", disagreement.index + 1,
+            format_pv(&[ours.best]), 100.0 * ours.win_probability, format_pv(&[theirs.best]),
+            100.0 * theirs.win_probability, name);
+    }
+    println!("{} of {} positions disagree by more than {:.0}%.", disagreements.len(), suite.len(),
+        100.0 * threshold);
+    Ok(())
+}
+
+/*
+ * Solves the position and prints its score and its perfect moves, or checks the solver on
+ * every position of the test suite if none is given, solving them on as many threads as the
+ * config asks for.
+ */
+fn solve_command(config: &Config, line: Option<&str>) -> Result<(), OthelloError> {
+    let line: &str = match line {
+        Some(line) => line,
+        None => {
+            let start: Instant = Instant::now();
+            let mut failed: usize = 0;
+            let suite: Vec<(Othello, Color)> = positions().iter()
+                .map(|position| (position.oth, position.color))
+                .collect();
+            let scores: Vec<i32> = solve_batch(&ThreadPool::new(config.threads), &suite);
+            for (i, (position, &score)) in positions().iter().zip(&scores).enumerate() {
+                if score == position.score as i32 {
+                    println!("#{:<3} {:+}", i + 1, score);
+                } else {
+                    println!("#{:<3} {:+}, expected {:+}", i + 1, score, position.score);
+                    failed += 1;
+                }
+            }
+            println!("{} positions solved in {:.3}s, {} wrong.", positions().len(),
+                start.elapsed().as_secs_f64(), failed);
+            return Ok(());
+        },
+    };
+
+    let game: Game = Game::from_transcript(line)?;
+    let (oth, color) = (game.board(), game.color());
+    let start: Instant = Instant::now();
+    let score: i32 = solve(oth, color);
+
+    let mut best: Vec<String> = Vec::new();
+    let mut moves: BitBoard = game.moves();
+    while moves != 0 {
+        let mv: BitBoard = moves.pop_lsb();
+        if -solve(oth.make_move(color, mv), color.invert()) == score {
+            best.push(mv.square_name());
+        }
+    }
+
+    println!("Score {} for the player to move, perfect moves: {} ({:.3}s)",
+        Value::Exact(score as i8),
+        if best.is_empty() { "pass".to_string() } else { best.join(" ") },
+        start.elapsed().as_secs_f64());
+    Ok(())
+}
+
+/*
+ * Solves the position for a win, a draw or a loss with the given backend, or compares the
+ * alpha-beta and proof-number backends on every position of the test suite if none is given.
+ */
+fn solve_wld_command(backend: WldBackend, line: Option<&str>) -> Result<(), OthelloError> {
+    let outcome = |wld: i32| ["loss", "draw", "win"][(wld + 1) as usize];
+    let timed = |backend: WldBackend, oth: Othello, color: Color| {
+        let start: Instant = Instant::now();
+        (backend.solve(oth, color), start.elapsed())
+    };
+
+    let line: &str = match line {
+        Some(line) => line,
+        None => {
+            let backends: [WldBackend; 2] = [WldBackend::AlphaBeta, WldBackend::ProofNumber];
+            let (mut totals, mut failed): ([Duration; 2], usize) = ([Duration::ZERO; 2], 0);
+            for (i, position) in positions().iter().enumerate() {
+                let expected: i32 = (position.score as i32).signum();
+                print!("#{:<3} {:<4}", i + 1, outcome(expected));
+                for (backend, total) in backends.iter().zip(&mut totals) {
+                    let (wld, time) = timed(*backend, position.oth, position.color);
+                    print!("  {} {:>7.3}s", backend.name(), time.as_secs_f64());
+                    if wld != expected {
+                        print!(" (found {})", outcome(wld));
+                        failed += 1;
+                    }
+                    *total += time;
+                }
+                println!();
+            }
+            println!("{} positions solved in {:.3}s with alphabeta and {:.3}s with pn, {} wrong.",
+                positions().len(), totals[0].as_secs_f64(), totals[1].as_secs_f64(), failed);
+            return Ok(());
+        },
+    };
+
+    let game: Game = Game::from_transcript(line)?;
+    let (wld, time) = timed(backend, game.board(), game.color());
+    println!("A {} for the player to move ({}, {:.3}s)", outcome(wld), backend.name(),
+        time.as_secs_f64());
+    Ok(())
+}
+
+/*
+ * Solves the positions of the input file into the output file, see solve_file, and prints how
+ * many were solved.
+ */
+fn solve_file_command(
+    config: &Config, input: &Path, output: &Path, resume: bool,
+) -> Result<(), OthelloError> {
+    let start: Instant = Instant::now();
+    let executor: ThreadPool = ThreadPool::new(config.threads);
+    let summary: SolveSummary = solve_file(&executor, input, output, 4 * config.threads, resume)?;
+    println!("{} positions solved in {:.3}s, {} already solved.", summary.solved,
+        start.elapsed().as_secs_f64(), summary.skipped);
+    Ok(())
+}
+
+/*
+ * Proves that the position after the first moves of the line has the claimed outcome, see
+ * verify_line, prints whether it holds and the deviations refuting it, and writes the proof
+ * tree to the output file if any.
+ */
+fn prove_command(
+    config: &Config, line: &str, start: usize, claim: Claim, backend: WldBackend,
+    output: Option<&Path>,
+) -> Result<(), OthelloError> {
+    let claim: i8 = match claim { Claim::Black => 1, Claim::Draw => 0, Claim::White => -1 };
+    let outcome = |outcome: i8| ["a White win", "a draw", "a Black win"][(outcome + 1) as usize];
+    let begin: Instant = Instant::now();
+    let tree: ProofTree = verify_line(&ThreadPool::new(config.threads), line, start, claim,
+        backend)?;
+
+    println!("{} positions solved in {:.3}s, the line ends in {}.", tree.deviations.len() + 1,
+        begin.elapsed().as_secs_f64(), outcome(tree.end));
+    for refutation in tree.refutations() {
+        println!("Refuted by {} instead of {} at move {}: {}.", refutation.mv.square_name(),
+            tree.line[refutation.ply].square_name(), refutation.ply + 1,
+            outcome(refutation.outcome));
+    }
+    println!("The claim of {} {}.", outcome(claim), if tree.holds() { "holds" } else { "fails" });
+
+    if let Some(path) = output {
+        tree.save(path)?;
+    }
+    Ok(())
+}
+
+/*
+ * Prints the moves of the position found in the book.
+ */
+fn book_command(file: PathBuf, line: Option<&str>) -> Result<(), OthelloError> {
+    let book: Book = Book::open(file)?;
+    let game: Game = Game::from_transcript(line.unwrap_or(""))?;
+    let moves: Vec<(BitBoard, BookEntry)> = book.moves(game.board(), game.color());
+
+    println!("{} positions in the book, {} moves found.", book.len(), moves.len());
+    for (mv, entry) in moves {
+        println!("{} {:>6}  depth {}", mv.square_name(), value_name(entry.score), entry.depth);
+    }
+
+    Ok(())
+}
+
+/*
+ * Reads a journal of searches, and compacts it into an opening book written to the given file,
+ * if any.
+ */
+fn journal_command(file: &Path, output: Option<&Path>) -> Result<(), OthelloError> {
+    let entries: Vec<(Othello, Color, JournalEntry)> = Journal::read(file)?;
+    let book: Book = compact_book(&entries);
+    println!("{} searches in the journal, {} positions in the book.", entries.len(), book.len());
+    if let Some(path) = output {
+        book.save(path)?;
+    }
+    Ok(())
+}
+
+//#################################################################################################
+//
+//                                        HELPERS
+//
+//#################################################################################################
+
+/*
+ * Creates a player of the given kind, its options coming from the config.
+ */
+fn create_player(kind: PlayerKind, config: &Config) -> Result<Box<dyn Player>, OthelloError> {
+    Ok(match kind {
+        PlayerKind::Human => Box::new(TerminalPlayer),
+        PlayerKind::Alphabeta => Box::new(config.player()?),
+        PlayerKind::Mcts => Box::new(MctsPlayer::new(20000).with_threads(config.threads)),
+        PlayerKind::Random => {
+            let seed: u64 = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |time| time.as_nanos() as u64);
+            Box::new(RandomPlayer::new(seed))
+        },
+    })
 }
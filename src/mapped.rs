@@ -0,0 +1,164 @@
+use std::ffi::OsString;
+use std::fs::File;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                      MAPPED FILES
+//
+//#################################################################################################
+
+/*
+ * The data files of the AI, evaluation weights and opening books, are read-only and mapped
+ * into memory rather than read, so that opening them costs next to nothing and several
+ * processes using the same file share its pages. Each file starts with a 32 bytes header:
+ *   - a magic number of 8 bytes, telling what kind of file it is,
+ *   - the version of its format, as a little-endian u32, then 4 reserved zero bytes,
+ *   - the size of the payload following the header, as a little-endian u64,
+ *   - the FNV-1a checksum of the payload, as a little-endian u64.
+ * The header is validated when the file is opened, the checksum included.
+ */
+const HEADER_SIZE: usize = 32;
+
+/*
+ * A read-only file mapped into memory, whose header has been validated.
+ */
+pub struct MappedFile {
+    map: Mmap,
+}
+
+impl MappedFile {
+    /*
+     * Maps the file at the given path, checking that it has the given magic number and version,
+     * and that its payload is complete and intact.
+     */
+    pub fn open(path: impl AsRef<Path>, magic: &[u8; 8], version: u32)
+        -> Result<MappedFile, OthelloError>
+    {
+        let file: File = File::open(path)?;
+        // The file must not be modified while it is mapped, which data files never are.
+        let map: Mmap = unsafe { Mmap::map(&file)? };
+        payload(&map, magic, version)?;
+        Ok(MappedFile { map, })
+    }
+
+    /*
+     * Returns the payload of the file, after its header.
+     */
+    pub fn payload(&self) -> &[u8] {
+        &self.map[HEADER_SIZE..]
+    }
+}
+
+/*
+ * Returns the payload of the bytes of a data file, checking that they have the given magic number
+ * and version, and that the payload is complete and intact. Used for the files mapped into
+ * memory, and for those embedded in the executable.
+ */
+pub fn payload<'a>(bytes: &'a [u8], magic: &[u8; 8], version: u32)
+    -> Result<&'a [u8], OthelloError>
+{
+    if bytes.len() < HEADER_SIZE || &bytes[..8] != magic {
+        return Err(parse_error("Unknown file type."));
+    }
+
+    let found: u32 = u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]);
+    if found != version {
+        let msg: String = format!("Unsupported version {}, expected {}.", found, version);
+        return Err(parse_error(msg));
+    }
+
+    let mut word: [u8; 8] = [0; 8];
+    word.copy_from_slice(&bytes[16..24]);
+    let size: u64 = u64::from_le_bytes(word);
+    word.copy_from_slice(&bytes[24..32]);
+    let sum: u64 = u64::from_le_bytes(word);
+
+    if bytes.len() as u64 - HEADER_SIZE as u64 != size {
+        return Err(parse_error("Truncated file."));
+    }
+    if checksum(&bytes[HEADER_SIZE..]) != sum {
+        return Err(parse_error("Corrupted file, the checksum doesn't match."));
+    }
+
+    Ok(&bytes[HEADER_SIZE..])
+}
+
+/*
+ * Writes a file with the given magic number, version and payload, in the format above. The
+ * file is written next to its path then renamed, so that programs having the previous file
+ * mapped keep reading it whole.
+ */
+pub fn write_file(path: impl AsRef<Path>, magic: &[u8; 8], version: u32, payload: &[u8])
+    -> Result<(), OthelloError>
+{
+    let mut bytes: Vec<u8> = Vec::with_capacity(HEADER_SIZE + payload.len());
+    bytes.extend_from_slice(magic);
+    bytes.extend_from_slice(&version.to_le_bytes());
+    bytes.extend_from_slice(&[0; 4]);
+    bytes.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&checksum(payload).to_le_bytes());
+    bytes.extend_from_slice(payload);
+
+    let mut temporary: OsString = path.as_ref().as_os_str().to_os_string();
+    temporary.push(".tmp");
+    std::fs::write(&temporary, bytes)?;
+    Ok(std::fs::rename(&temporary, path)?)
+}
+
+/*
+ * The 64 bits FNV-1a hash of the given bytes.
+ */
+pub(crate) fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xCBF29CE484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001B3)
+    })
+}
+
+//#################################################################################################
+//
+//                                    MAPPED FILE TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that a data file is mapped back with its payload, and that truncated, foreign,
+ * outdated and corrupted files are rejected with an error.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mapped_files() {
+        const MAGIC: &[u8; 8] = b"OTHTEST1";
+        let path: std::path::PathBuf =
+            std::env::temp_dir().join(format!("othello-{}.mapped", std::process::id()));
+        let payload_bytes: Vec<u8> = (0..100).collect();
+        write_file(&path, MAGIC, 3, &payload_bytes).unwrap();
+        assert_eq!(MappedFile::open(&path, MAGIC, 3).unwrap().payload(), &payload_bytes[..]);
+
+        let bytes: Vec<u8> = std::fs::read(&path).unwrap();
+        let error = |bytes: &[u8], magic: &[u8; 8], version: u32| -> String {
+            std::fs::write(&path, bytes).unwrap();
+            let opened: Result<MappedFile, OthelloError> = MappedFile::open(&path, magic, version);
+            let err: OthelloError = opened.err().expect("The file should be rejected.");
+            assert_eq!(payload(bytes, magic, version).unwrap_err().to_string(), err.to_string());
+            err.to_string()
+        };
+        assert!(error(&bytes[..bytes.len() - 1], MAGIC, 3).contains("Truncated"));
+        assert!(error(&bytes[..HEADER_SIZE - 1], MAGIC, 3).contains("Unknown file type"));
+        assert!(error(&[], MAGIC, 3).contains("Unknown file type"));
+        assert!(error(&bytes, b"OTHELLEV", 3).contains("Unknown file type"));
+        assert!(error(&bytes, MAGIC, 4).contains("Unsupported version 3"));
+        let mut corrupted: Vec<u8> = bytes.clone();
+        corrupted[HEADER_SIZE + 50] ^= 1;
+        assert!(error(&corrupted, MAGIC, 3).contains("checksum"));
+        std::fs::remove_file(&path).unwrap();
+        assert!(MappedFile::open(&path, MAGIC, 3).is_err());
+    }
+}
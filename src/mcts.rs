@@ -0,0 +1,651 @@
+use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::time::Instant;
+
+use crate::types::*;
+use crate::othello::*;
+use crate::game::*;
+use crate::player::*;
+use crate::searcher::{SearchLimits, SearchResult};
+use crate::eval::{EvalWeights, win_probability};
+use crate::executor::*;
+
+//#################################################################################################
+//
+//                                        NODE TYPE
+//
+//#################################################################################################
+
+/*
+ * The score of a won game, in the units of the scores of the nodes, fine enough for the
+ * probabilities given by leaf evaluation.
+ */
+const POINT: u64 = 1000;
+
+/*
+ * A node of the search tree. Its statistics are atomic so that several threads can search the
+ * same tree at once: the visits and the score, in thousandths of points, of the player who made
+ * the move leading to the node. Children are created once, by the first thread needing them, a
+ * pass being a child with an empty move. With leaf evaluation, they are evaluated all at once
+ * then, from black's point of view.
+ */
+struct Node {
+    oth: Othello,
+    color: Color,
+    mv: BitBoard,
+    visits: AtomicU32,
+    score: AtomicU64,
+    value: Option<i32>,
+    children: OnceLock<Vec<Node>>,
+}
+
+impl Node {
+    /*
+     * Creates a new unvisited node, color being the player to move.
+     */
+    fn new(oth: Othello, color: Color, mv: BitBoard) -> Node {
+        Node {
+            oth,
+            color,
+            mv,
+            visits: AtomicU32::new(0),
+            score: AtomicU64::new(0),
+            value: None,
+            children: OnceLock::new(),
+        }
+    }
+
+    /*
+     * Returns the children of the node, creating them if needed, and evaluating them with the
+     * given weights if any. A finished game has none.
+     */
+    fn children(&self, weights: Option<&EvalWeights>) -> &[Node] {
+        self.children.get_or_init(|| {
+            let mut moves: BitBoard = self.oth.gen_moves(self.color);
+            let mut children: Vec<Node> = Vec::with_capacity(moves.pop_cnt() as usize);
+
+            if moves == 0 && self.oth.gen_moves(self.color.invert()) != 0 {
+                children.push(Node::new(self.oth, self.color.invert(), 0));
+            }
+
+            while moves != 0 {
+                let mv: BitBoard = moves.pop_lsb();
+                let oth: Othello = self.oth.make_move(self.color, mv);
+                children.push(Node::new(oth, self.color.invert(), mv));
+            }
+
+            if let Some(weights) = weights {
+                let boards: Vec<Othello> = children.iter().map(|child| child.oth).collect();
+                for (child, value) in children.iter_mut().zip(weights.evaluate_batch(&boards)) {
+                    child.value = Some(value);
+                }
+            }
+
+            children
+        })
+    }
+
+    /*
+     * Selects the child with the highest UCT value, unvisited children first. The noise given
+     * for the children, if any, adds an exploration bonus like that of PUCT, fading as they get
+     * visited.
+     */
+    fn select(
+        &self, exploration: f64, weights: Option<&EvalWeights>, noise: Option<&[f64]>,
+    ) -> &Node {
+        let children: &[Node] = self.children(weights);
+        let parent_visits: f64 = self.visits.load(Ordering::Relaxed).max(1) as f64;
+        let log_visits: f64 = parent_visits.ln();
+        let mut best: &Node = &children[0];
+        let mut best_value: f64 = f64::NEG_INFINITY;
+
+        for (i, child) in children.iter().enumerate() {
+            let visits: u32 = child.visits.load(Ordering::Relaxed);
+            let value: f64 = if visits == 0 {
+                f64::INFINITY
+            } else {
+                let mean: f64 = child.score.load(Ordering::Relaxed) as f64 / mean_scale(visits);
+                let bonus: f64 = noise.map_or(0.0, |noise| {
+                    noise[i] * parent_visits.sqrt() / (1.0 + visits as f64)
+                });
+                mean + exploration * (log_visits / visits as f64).sqrt() + bonus
+            };
+            if value > best_value {
+                best = child;
+                best_value = value;
+            }
+        }
+
+        best
+    }
+
+    /*
+     * Looks for the node of the given position among the descendants of this node, at most
+     * depth plies below it, and returns it, the rest of the tree being dropped.
+     */
+    fn into_subtree(self, oth: Othello, color: Color, depth: u8) -> Option<Node> {
+        if self.oth == oth && self.color == color {
+            return Some(self);
+        }
+        if depth == 0 {
+            return None;
+        }
+
+        self.children
+            .into_inner()?
+            .into_iter()
+            .find_map(|child| child.into_subtree(oth, color, depth - 1))
+    }
+}
+
+//#################################################################################################
+//
+//                                      MCTS TYPE
+//
+//#################################################################################################
+
+/*
+ * The type describing an AI using Monte-Carlo tree search with random playouts. The given number
+ * of playouts is shared among several threads searching the same tree. A thread going down the
+ * tree adds a visit to every node on its way before its playout is over, a virtual loss that
+ * steers the other threads toward other parts of the tree in the meantime.
+ *
+ * With tree reuse enabled, the tree is kept after each search, and the subtree of the position
+ * reached after the opponent's reply is used as a starting point for the next one. The visits
+ * it already has count toward the playouts of the new search.
+ */
+pub struct MctsPlayer {
+    playouts: u32,
+    threads: usize,
+    exploration: f64,
+    reuse: bool,
+    tree: Mutex<Option<Node>>,
+    executor: Arc<dyn Executor>,
+    evaluation: Option<EvalWeights>,
+    noise: Option<(f64, f64)>,
+    noise_rng: AtomicU64,
+    stop: Arc<AtomicBool>,
+}
+
+impl MctsPlayer {
+    /*
+     * Creates a new single-threaded MctsPlayer AI, doing the given number of playouts per move.
+     */
+    pub fn new(playouts: u32) -> MctsPlayer {
+        MctsPlayer {
+            playouts,
+            threads: 1,
+            exploration: std::f64::consts::SQRT_2,
+            reuse: false,
+            tree: Mutex::new(None),
+            executor: Arc::new(ThreadPerJob),
+            evaluation: None,
+            noise: None,
+            noise_rng: AtomicU64::new(0x2545F4914F6CDD1D),
+            stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /*
+     * Sets the number of threads searching the tree.
+     */
+    pub fn with_threads(mut self, threads: usize) -> MctsPlayer {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /*
+     * Runs the threads searching the tree with the given executor instead of spawning them.
+     */
+    pub fn with_executor(mut self, executor: Arc<dyn Executor>) -> MctsPlayer {
+        self.executor = executor;
+        self
+    }
+
+    /*
+     * Sets the exploration constant of the UCT formula, the square root of 2 by default. A
+     * negative or NaN constant, which would steer the search away from unexplored moves, is
+     * ignored.
+     */
+    pub fn with_exploration(mut self, exploration: f64) -> MctsPlayer {
+        self.exploration = Some(exploration).filter(|&exploration| exploration >= 0.0)
+            .unwrap_or(self.exploration);
+        self
+    }
+
+    /*
+     * Evaluates the leaves of the tree with the given weights instead of playing random games
+     * from them, the children of a node being evaluated in one batch when it is expanded. A
+     * leaf then scores its probability of winning, see alphabeta::win_probability.
+     */
+    pub fn with_evaluation(mut self, weights: EvalWeights) -> MctsPlayer {
+        self.evaluation = Some(weights);
+        self
+    }
+
+    /*
+     * Adds Dirichlet noise of the given concentration to the root of each search, weighted by
+     * epsilon, as in AlphaZero self-play, so that the policy targets given by root_visits
+     * explore moves the search would neglect. Without priors to mix it into, the noise makes
+     * an exploration bonus for the children of the root, see Node::select. A concentration
+     * that isn't positive or a weight outside of [0, 1], NaNs included, disables the noise.
+     */
+    pub fn with_root_noise(mut self, alpha: f64, epsilon: f64) -> MctsPlayer {
+        self.noise = Some((alpha, epsilon))
+            .filter(|_| alpha > 0.0 && (0.0..=1.0).contains(&epsilon));
+        self
+    }
+
+    /*
+     * Returns the flag stopping the searches of the AI as a Searcher, see Searcher::stop.
+     */
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    /*
+     * Keeps the search tree from one move to the next.
+     */
+    pub fn with_tree_reuse(mut self, reuse: bool) -> MctsPlayer {
+        self.reuse = reuse;
+        self
+    }
+
+    /*
+     * Searches the position and returns the most visited move, along with the proportion of
+     * half points it scored for the player to move. If the player has to pass, the move is an
+     * empty BitBoard, and if the game is over, the proportion is its actual result.
+     */
+    pub fn best_move(&self, oth: Othello, color: Color) -> (BitBoard, f64) {
+        let root: Node = self.search(oth, color);
+        let children: &[Node] = root.children(self.evaluation.as_ref());
+        let best: &Node = match children.iter().max_by_key(|c| c.visits.load(Ordering::Relaxed)) {
+            Some(best) => best,
+            None => return match oth.score().winner() {
+                Some(winner) if winner == color => (0, 1.0),
+                Some(_) => (0, 0.0),
+                None => (0, 0.5),
+            },
+        };
+        let visits: u32 = best.visits.load(Ordering::Relaxed).max(1);
+        let result = (best.mv, best.score.load(Ordering::Relaxed) as f64 / mean_scale(visits));
+        self.keep(root);
+
+        result
+    }
+
+    /*
+     * Searches the position and returns the number of visits of each move, passes being empty
+     * BitBoards, the policy targets of AlphaZero-like training once normalized. There is none
+     * if the game is over.
+     */
+    pub fn root_visits(&self, oth: Othello, color: Color) -> Vec<(BitBoard, u32)> {
+        let root: Node = self.search(oth, color);
+        let visits: Vec<(BitBoard, u32)> = root.children(self.evaluation.as_ref()).iter()
+            .map(|child| (child.mv, child.visits.load(Ordering::Relaxed)))
+            .collect();
+        self.keep(root);
+
+        visits
+    }
+
+    /*
+     * Runs the playouts of a search of the position, starting from the tree kept from the
+     * previous one when possible, and returns its root.
+     */
+    fn search(&self, oth: Othello, color: Color) -> Node {
+        self.search_within(oth, color, self.playouts, None)
+    }
+
+    /*
+     * Runs the given number of playouts like search, stopping early at the deadline, if any,
+     * or when the stop flag is set.
+     */
+    fn search_within(&self, oth: Othello, color: Color, playouts: u32, deadline: Option<Instant>)
+        -> Node
+    {
+        let root: Node = self.tree.lock().unwrap()
+            .take()
+            .and_then(|tree| tree.into_subtree(oth, color, 2))
+            .unwrap_or_else(|| Node::new(oth, color, 0));
+        let count: AtomicU32 = AtomicU32::new(root.visits.load(Ordering::Relaxed));
+        let noise: Option<Vec<f64>> = self.noise.map(|(alpha, epsilon)| {
+            let mut rng: u64 = self.noise_rng.fetch_add(0x9E3779B97F4A7C15, Ordering::Relaxed);
+            let children: usize = root.children(self.evaluation.as_ref()).len();
+            dirichlet(alpha, children, &mut rng).into_iter().map(|x| epsilon * x).collect()
+        });
+
+        let jobs: Vec<Job> = (0..self.threads).map(|thread| {
+            let (root, count, noise) = (&root, &count, noise.as_deref());
+            Box::new(move || {
+                let mut rng: u64 = seed(thread);
+                while count.fetch_add(1, Ordering::Relaxed) < playouts {
+                    if self.stop.load(Ordering::Relaxed)
+                        || deadline.is_some_and(|deadline| Instant::now() >= deadline)
+                    {
+                        break;
+                    }
+                    self.iterate(root, noise, &mut rng);
+                }
+            }) as Job
+        }).collect();
+        self.executor.execute(jobs);
+
+        root
+    }
+
+    /*
+     * Keeps the tree of the last search for the next one, if tree reuse is enabled.
+     */
+    fn keep(&self, root: Node) {
+        if self.reuse {
+            *self.tree.lock().unwrap() = Some(root);
+        }
+    }
+
+    /*
+     * Does one iteration of the search: goes down the tree, adding virtual losses on the way,
+     * expands the reached node if it was already visited, does a random playout from it, or
+     * evaluates it, and turns the virtual losses into the actual result.
+     */
+    fn iterate(&self, root: &Node, mut noise: Option<&[f64]>, rng: &mut u64) {
+        let weights: Option<&EvalWeights> = self.evaluation.as_ref();
+        let mut path: Vec<&Node> = Vec::with_capacity(64);
+        let mut node: &Node = root;
+
+        node.visits.fetch_add(1, Ordering::Relaxed);
+        path.push(node);
+
+        while node.visits.load(Ordering::Relaxed) > 1 && !node.children(weights).is_empty() {
+            node = node.select(self.exploration, weights, noise.take());
+            node.visits.fetch_add(1, Ordering::Relaxed);
+            path.push(node);
+        }
+
+        // The score of black, the playout or the evaluation of finished games being exact.
+        let (oth, color) = (node.oth, node.color);
+        let over: bool = oth.gen_moves(color) == 0 && oth.gen_moves(color.invert()) == 0;
+        let black: u64 = match weights {
+            Some(weights) if !over => {
+                let value: i32 = node.value.unwrap_or_else(|| weights.evaluate(oth));
+                (win_probability(value, oth.empties()) * POINT as f64).round() as u64
+            },
+            _ => match playout(oth, color, rng) {
+                Some(Color::Black) => POINT,
+                Some(Color::White) => 0,
+                None => POINT / 2,
+            },
+        };
+
+        for node in path {
+            let points: u64 = match node.color {
+                Color::Black => POINT - black,
+                Color::White => black,
+            };
+            node.score.fetch_add(points, Ordering::Relaxed);
+        }
+    }
+}
+
+/*
+ * Returns what the score of a node must be divided by to give the mean score per visit.
+ */
+fn mean_scale(visits: u32) -> f64 {
+    POINT as f64 * visits as f64
+}
+
+/*
+ * Returns a distinct, non-zero, seed for the random number generator of each thread, so that
+ * single-threaded searches are reproducible.
+ */
+fn seed(thread: usize) -> u64 {
+    0x9E3779B97F4A7C15 ^ (thread as u64 + 1).wrapping_mul(0xBF58476D1CE4E5B9)
+}
+
+/*
+ * Returns a uniform random number in (0, 1].
+ */
+fn uniform(rng: &mut u64) -> f64 {
+    ((next_random(rng) >> 11) + 1) as f64 / (1u64 << 53) as f64
+}
+
+/*
+ * Draws a sample of the gamma distribution of the given shape and of scale 1, with the method
+ * of Marsaglia and Tsang, boosted for shapes below 1.
+ */
+fn gamma(shape: f64, rng: &mut u64) -> f64 {
+    if shape < 1.0 {
+        return gamma(shape + 1.0, rng) * uniform(rng).powf(1.0 / shape);
+    }
+
+    let d: f64 = shape - 1.0 / 3.0;
+    let c: f64 = 1.0 / (9.0 * d).sqrt();
+    loop {
+        // A standard normal sample, by the Box-Muller transform.
+        let normal: f64 = (-2.0 * uniform(rng).ln()).sqrt()
+            * (2.0 * std::f64::consts::PI * uniform(rng)).cos();
+        let v: f64 = (1.0 + c * normal).powi(3);
+        if v > 0.0 && uniform(rng).ln() < 0.5 * normal * normal + d - d * v + d * v.ln() {
+            return d * v;
+        }
+    }
+}
+
+/*
+ * Draws a sample of the symmetric Dirichlet distribution of the given concentration and
+ * dimension: n non-negative numbers summing up to 1.
+ */
+pub(crate) fn dirichlet(alpha: f64, n: usize, rng: &mut u64) -> Vec<f64> {
+    let samples: Vec<f64> = (0..n).map(|_| gamma(alpha, rng)).collect();
+    let sum: f64 = samples.iter().sum::<f64>().max(f64::MIN_POSITIVE);
+    samples.into_iter().map(|x| x / sum).collect()
+}
+
+/*
+ * Plays random moves until the end of the game and returns the winner.
+ */
+fn playout(mut oth: Othello, mut color: Color, rng: &mut u64) -> Option<Color> {
+    let mut passed: bool = false;
+
+    loop {
+        let mut moves: BitBoard = oth.gen_moves(color);
+
+        if moves == 0 {
+            if passed {
+                break;
+            }
+            passed = true;
+        } else {
+            passed = false;
+            for _ in 0..next_random(rng) % moves.pop_cnt() as u64 {
+                moves &= moves - 1;
+            }
+            oth = oth.make_move(color, moves & moves.wrapping_neg());
+        }
+
+        color = color.invert();
+    }
+
+    oth.score().winner()
+}
+
+impl crate::searcher::Searcher for MctsPlayer {
+    /*
+     * Searches the position with as many playouts as the nodes of the limits, or those of the
+     * AI, within their time, and returns the most visited move. The principal variation follows
+     * the most visited child of each node, and the depth is its length. The stop flag is
+     * cleared once the search is over, so that a stop sent while it starts isn't lost.
+     */
+    fn best_move(&mut self, (oth, color): (Othello, Color), limits: &SearchLimits)
+        -> SearchResult
+    {
+        let start: Instant = Instant::now();
+        let playouts: u32 =
+            limits.nodes.map_or(self.playouts, |nodes| nodes.min(u32::MAX as u64) as u32);
+        let deadline: Option<Instant> = limits.time.map(|time| start + time);
+        let root: Node = self.search_within(oth, color, playouts, deadline);
+        self.stop.store(false, Ordering::Relaxed);
+
+        let weights: Option<&EvalWeights> = self.evaluation.as_ref();
+        let mut pv: Vec<BitBoard> = Vec::new();
+        let mut node: &Node = &root;
+        let mut win_probability: f64 = match oth.score().winner() {
+            Some(winner) if winner == color => 1.0,
+            Some(_) => 0.0,
+            None => 0.5,
+        };
+        while let Some(child) = node.children(weights).iter()
+            .max_by_key(|child| child.visits.load(Ordering::Relaxed))
+            .filter(|child| child.visits.load(Ordering::Relaxed) > 0 || pv.is_empty())
+        {
+            if pv.is_empty() {
+                let visits: u32 = child.visits.load(Ordering::Relaxed).max(1);
+                win_probability = child.score.load(Ordering::Relaxed) as f64 / mean_scale(visits);
+            }
+            pv.push(child.mv);
+            node = child;
+        }
+        let nodes: u64 = root.visits.load(Ordering::Relaxed) as u64;
+        let best: BitBoard = pv.first().copied().unwrap_or(0);
+        self.keep(root);
+
+        SearchResult {
+            best,
+            value: None,
+            win_probability,
+            depth: pv.len() as u8,
+            pv,
+            nodes,
+            time: start.elapsed(),
+        }
+    }
+
+    fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    fn name(&self) -> String {
+        Player::name(self)
+    }
+}
+
+impl Player for MctsPlayer {
+    /*
+     * Searches the position and plays the most visited move.
+     */
+    fn choose_move(&mut self, game: &Game, _clock: &Clock) -> Action {
+        Action::Play(self.best_move(game.board(), game.color()).0)
+    }
+
+    fn name(&self) -> String {
+        format!("MCTS {}", self.playouts)
+    }
+}
+
+//#################################################################################################
+//
+//                                        MCTS TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that a search shared by several threads does exactly its playouts, each of their
+ * visits, counted as a virtual loss on the way down, getting its score, and that the tree is
+ * reused after the reply of the opponent, its visits carried over, and rebuilt for another
+ * position, and that the noise at the root changes its visits, invalid settings being ignored.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /*
+     * Checks that the visits of the node that went on to its children did, and that the score
+     * of the node is what they gave it back, plus the results of the visits stopping at it.
+     */
+    fn check_scores(node: &Node) {
+        let children: &[Node] = match node.children.get() {
+            Some(children) if !children.is_empty() => children,
+            _ => return,
+        };
+        let visits: u64 = node.visits.load(Ordering::Relaxed) as u64;
+        let passed: u64 = children.iter().map(|child| child.visits.load(Ordering::Relaxed) as u64)
+            .sum();
+        let given: u64 = children.iter()
+            .map(|child| POINT * child.visits.load(Ordering::Relaxed) as u64
+                - child.score.load(Ordering::Relaxed))
+            .sum();
+        let score: u64 = node.score.load(Ordering::Relaxed);
+        assert!(passed <= visits && visits <= passed + 1);
+        assert!(given <= score && score <= given + POINT * (visits - passed));
+        children.iter().for_each(check_scores);
+    }
+
+    #[test]
+    fn threads() {
+        let (oth, color): (Othello, Color) = (Othello::new(), Color::Black);
+        let player: MctsPlayer = MctsPlayer::new(2000).with_threads(4);
+        let root: Node = player.search(oth, color);
+        assert_eq!(root.visits.load(Ordering::Relaxed), 2000);
+        check_scores(&root);
+
+        let (mv, score): (BitBoard, f64) = player.best_move(oth, color);
+        assert!(mv & oth.gen_moves(color) != 0 && (0.0..=1.0).contains(&score));
+    }
+
+    #[test]
+    fn tree_reuse() {
+        let game: Game = Game::from_line("f5").unwrap();
+        let player: MctsPlayer = MctsPlayer::new(2000).with_tree_reuse(true);
+        let (mv, _): (BitBoard, f64) = player.best_move(game.board(), game.color());
+
+        // The reply the search expects most, whose subtree is the most visited.
+        let (reply, color, visits): (Othello, Color, u32) = {
+            let tree = player.tree.lock().unwrap();
+            let child: &Node = tree.as_ref().unwrap().children(None).iter()
+                .find(|child| child.mv == mv)
+                .unwrap();
+            let reply: &Node = child.children(None).iter()
+                .max_by_key(|reply| reply.visits.load(Ordering::Relaxed))
+                .unwrap();
+            (reply.oth, reply.color, reply.visits.load(Ordering::Relaxed))
+        };
+        assert!(visits > 1);
+
+        // A search of a single playout keeps the visits of the subtree, a new tree having one.
+        let root: Node = player.search_within(reply, color, 1, None);
+        assert_eq!(root.visits.load(Ordering::Relaxed), visits);
+        check_scores(&root);
+        player.keep(root);
+
+        let other: Game = Game::from_line("d3c3").unwrap();
+        let root: Node = player.search_within(other.board(), other.color(), 10, None);
+        assert_eq!(root.visits.load(Ordering::Relaxed), 10);
+    }
+
+    #[test]
+    fn root_noise() {
+        let game: Game = Game::from_line("f5d6c3").unwrap();
+        let visits = |player: MctsPlayer| -> Vec<(BitBoard, u32)> {
+            player.root_visits(game.board(), game.color())
+        };
+        let plain: Vec<(BitBoard, u32)> = visits(MctsPlayer::new(500));
+        assert_eq!(plain, visits(MctsPlayer::new(500)));
+        let noisy: Vec<(BitBoard, u32)> = visits(MctsPlayer::new(500).with_root_noise(0.3, 1.0));
+        assert!(noisy != plain);
+        assert_eq!(noisy.iter().map(|&(_, visits)| visits).sum::<u32>(),
+            plain.iter().map(|&(_, visits)| visits).sum::<u32>());
+        assert_eq!(visits(MctsPlayer::new(500).with_root_noise(0.3, 0.0)), plain);
+
+        let invalid: [(f64, f64); 6] =
+            [(0.0, 0.25), (-0.3, 0.25), (0.3, 1.5), (0.3, -0.1), (f64::NAN, 0.25), (0.3, f64::NAN)];
+        for (alpha, epsilon) in invalid {
+            assert!(MctsPlayer::new(1).with_root_noise(alpha, epsilon).noise.is_none());
+        }
+        assert!(MctsPlayer::new(1).with_root_noise(0.3, 0.25).noise == Some((0.3, 0.25)));
+        for exploration in [-1.0, f64::NAN] {
+            let player: MctsPlayer = MctsPlayer::new(1).with_exploration(exploration);
+            assert_eq!(player.exploration, std::f64::consts::SQRT_2);
+        }
+        assert_eq!(MctsPlayer::new(1).with_exploration(0.0).exploration, 0.0);
+    }
+}
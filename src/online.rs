@@ -0,0 +1,482 @@
+use crate::types::*;
+use crate::game::*;
+use crate::record::*;
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                     ONLINE EXPORTS
+//
+//#################################################################################################
+
+/*
+ * The apps to play Othello online, eOthello and Othello Quest among them, export the games of
+ * a player as lists of games: either in JSON, as an array of objects or an object holding one
+ * under "games", or in CSV, one game per row after a header row naming the columns. For
+ * instance:
+ *
+ * [{"black": "alice", "white": "bob", "date": "2024-03-05T18:12:00Z",
+ *   "moves": "F5D6C3D3C4...", "result": "36-28"}]
+ *
+ * The names of the fields differ from an app to another, so each one is looked up under the
+ * names they use, whatever their case and their underscores: the players under "black",
+ * "black_player", "player_black" or "black_name", and the same for white, the date under
+ * "date", "played_at" or "datetime", the moves, a transcript read by parse_transcript, under
+ * "moves", "transcript", "record" or "move_list", and the result, as final disk counts like
+ * "36-28" or the disk differential for black, under "result" or "score". Other fields are
+ * ignored.
+ */
+const BLACK: [&str; 4] = ["black", "blackplayer", "playerblack", "blackname"];
+const WHITE: [&str; 4] = ["white", "whiteplayer", "playerwhite", "whitename"];
+const DATE: [&str; 3] = ["date", "playedat", "datetime"];
+const MOVES: [&str; 4] = ["moves", "transcript", "record", "movelist"];
+const RESULT: [&str; 2] = ["result", "score"];
+
+/*
+ * Reads all the games of an export, in JSON or in CSV, told apart by their first character.
+ * Games without moves are skipped, and an error is returned for a game with an illegal move.
+ */
+pub fn parse_games(text: &str) -> Result<Vec<GameRecord>, OthelloError> {
+    let text: &str = text.trim_start_matches('\u{feff}').trim();
+    let games: Vec<Vec<(String, String)>> = if text.starts_with(['[', '{']) {
+        parse_json_games(text)?
+    } else {
+        parse_csv_games(text)?
+    };
+
+    let mut records: Vec<GameRecord> = Vec::new();
+    for (i, fields) in games.iter().enumerate() {
+        let field = |names: &[&str]| {
+            fields.iter()
+                .find(|(key, _)| names.contains(&normalize(key).as_str()))
+                .map_or("", |(_, value)| value.trim())
+        };
+        if field(&MOVES).is_empty() {
+            continue;
+        }
+        let record: GameRecord = parse_game(field(&BLACK), field(&WHITE), field(&DATE),
+            field(&MOVES), field(&RESULT))
+            .map_err(|err| parse_error(format!("Game {}: {}", i + 1, err)))?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/*
+ * Returns the name of a field in lowercase, without its underscores, dashes and spaces.
+ */
+fn normalize(key: &str) -> String {
+    key.chars()
+        .filter(|&c| !matches!(c, '_' | '-' | ' '))
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/*
+ * Makes the record of a game from its fields. A game that didn't end on the board keeps the
+ * winner of its result, as if the loser resigned.
+ */
+fn parse_game(black: &str, white: &str, date: &str, moves: &str, result: &str)
+    -> Result<GameRecord, OthelloError>
+{
+    let game: Game = Game::from_transcript(moves)?;
+    let mut record: GameRecord = GameRecord::from_game(&game, black, white, &parse_date(date));
+    if record.outcome.is_none() {
+        record.outcome = parse_result(result)?.map(|winner| Outcome {
+            winner,
+            reason: Reason::Resignation,
+        });
+    }
+    Ok(record)
+}
+
+/*
+ * Returns the date of a timestamp like "2024-03-05T18:12:00Z" or "2024/03/05" in the format of
+ * the records, or the timestamp as it is if it doesn't start with a date.
+ */
+fn parse_date(date: &str) -> String {
+    let bytes: &[u8] = date.as_bytes();
+    let is_date: bool = bytes.len() >= 10
+        && bytes[..10].iter().enumerate().all(|(i, &byte)| match i {
+            4 | 7 => matches!(byte, b'-' | b'/' | b'.'),
+            _ => byte.is_ascii_digit(),
+        });
+    if is_date {
+        format!("{}.{}.{}", &date[..4], &date[5..7], &date[8..10])
+    } else {
+        date.to_string()
+    }
+}
+
+/*
+ * Reads the winner of a result, final disk counts like "36-28" or a disk differential for
+ * black like "+8". Returns None if the result is missing, and Some(None) for a draw.
+ */
+fn parse_result(result: &str) -> Result<Option<Option<Color>>, OthelloError> {
+    if result.is_empty() {
+        return Ok(None);
+    }
+    let invalid = || parse_error(format!("Invalid result: {}", result));
+    let count = |discs: &str| discs.trim().parse::<i32>().map_err(|_| invalid());
+
+    let differential: i32 = match result.split_once(['-', ':']) {
+        Some((black, white)) if !black.trim().is_empty() => count(black)? - count(white)?,
+        _ => count(result.trim_start_matches('+'))?,
+    };
+    Ok(Some(match differential {
+        d if d > 0 => Some(Color::Black),
+        d if d < 0 => Some(Color::White),
+        _ => None,
+    }))
+}
+
+//#################################################################################################
+//
+//                                          CSV
+//
+//#################################################################################################
+
+/*
+ * Reads the rows of a CSV export as the fields of games, named by the header row. Fields can
+ * be quoted, quotes being doubled inside them, and hold commas and line breaks then.
+ */
+fn parse_csv_games(text: &str) -> Result<Vec<Vec<(String, String)>>, OthelloError> {
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut row: Vec<String> = Vec::new();
+    let mut field: String = String::new();
+    let mut chars: std::iter::Peekable<std::str::Chars> = text.chars().peekable();
+    let mut quoted: bool = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            },
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            },
+            '\r' if !quoted => (),
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(parse_error("Unterminated quoted CSV field."));
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    let mut rows = rows.into_iter().filter(|row| row.iter().any(|field| !field.is_empty()));
+    let header: Vec<String> = rows.next().unwrap_or_default();
+    Ok(rows.map(|row| header.iter().cloned().zip(row).collect()).collect())
+}
+
+//#################################################################################################
+//
+//                                          JSON
+//
+//#################################################################################################
+
+/*
+ * A JSON value, as far as exports and records need it: numbers are kept as written.
+ */
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /*
+     * Returns the value as the text of a field, or None for arrays and objects.
+     */
+    pub(crate) fn text(&self) -> Option<String> {
+        match self {
+            Json::Null => Some(String::new()),
+            Json::Bool(value) => Some(value.to_string()),
+            Json::Number(value) | Json::String(value) => Some(value.clone()),
+            Json::Array(_) | Json::Object(_) => None,
+        }
+    }
+}
+
+/*
+ * Reads the games of a JSON export as their fields, those that are arrays or objects being
+ * left out.
+ */
+fn parse_json_games(text: &str) -> Result<Vec<Vec<(String, String)>>, OthelloError> {
+    let games: Vec<Json> = match parse_json(text)? {
+        Json::Array(games) => games,
+        Json::Object(fields) => match fields.into_iter().find(|(key, _)| key == "games") {
+            Some((_, Json::Array(games))) => games,
+            _ => return Err(parse_error("No array of games in the JSON export.")),
+        },
+        _ => return Err(parse_error("No array of games in the JSON export.")),
+    };
+
+    Ok(games.into_iter().map(|game| match game {
+        Json::Object(fields) => fields.into_iter()
+            .filter_map(|(key, value)| Some((key, value.text()?)))
+            .collect(),
+        _ => Vec::new(),
+    }).collect())
+}
+
+/*
+ * Parses a whole JSON document.
+ */
+pub(crate) fn parse_json(text: &str) -> Result<Json, OthelloError> {
+    let mut parser: JsonParser = JsonParser { bytes: text.as_bytes(), pos: 0, depth: 0, };
+    let value: Json = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos != parser.bytes.len() {
+        return Err(parser.error());
+    }
+    Ok(value)
+}
+
+/*
+ * How deep arrays and objects may be nested, so that hostile input can't overflow the stack
+ * of the parser.
+ */
+const MAX_NESTING: usize = 128;
+
+/*
+ * A small recursive descent parser of JSON, keeping track of how deep in arrays and objects
+ * it is.
+ */
+struct JsonParser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl JsonParser<'_> {
+    /*
+     * Returns the error of an unexpected character at the current position.
+     */
+    fn error(&self) -> OthelloError {
+        parse_error(format!("Invalid JSON at byte {}.", self.pos))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.bytes.get(self.pos).is_some_and(u8::is_ascii_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /*
+     * Consumes the given text, after whitespace, or returns an error.
+     */
+    fn expect(&mut self, text: &[u8]) -> Result<(), OthelloError> {
+        self.skip_whitespace();
+        if !self.bytes[self.pos..].starts_with(text) {
+            return Err(self.error());
+        }
+        self.pos += text.len();
+        Ok(())
+    }
+
+    /*
+     * Reads the value at the current position, failing if it is nested more than MAX_NESTING
+     * deep.
+     */
+    fn value(&mut self) -> Result<Json, OthelloError> {
+        if self.depth == MAX_NESTING {
+            return Err(self.error());
+        }
+        self.depth += 1;
+        let value: Result<Json, OthelloError> = self.nested_value();
+        self.depth -= 1;
+        value
+    }
+
+    fn nested_value(&mut self) -> Result<Json, OthelloError> {
+        self.skip_whitespace();
+        match self.bytes.get(self.pos) {
+            Some(b'{') => {
+                self.pos += 1;
+                let mut fields: Vec<(String, Json)> = Vec::new();
+                self.skip_whitespace();
+                if self.bytes.get(self.pos) == Some(&b'}') {
+                    self.pos += 1;
+                    return Ok(Json::Object(fields));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key: String = self.string()?;
+                    self.expect(b":")?;
+                    fields.push((key, self.value()?));
+                    self.skip_whitespace();
+                    match self.bytes.get(self.pos) {
+                        Some(b',') => self.pos += 1,
+                        Some(b'}') => {
+                            self.pos += 1;
+                            return Ok(Json::Object(fields));
+                        },
+                        _ => return Err(self.error()),
+                    }
+                }
+            },
+            Some(b'[') => {
+                self.pos += 1;
+                let mut values: Vec<Json> = Vec::new();
+                self.skip_whitespace();
+                if self.bytes.get(self.pos) == Some(&b']') {
+                    self.pos += 1;
+                    return Ok(Json::Array(values));
+                }
+                loop {
+                    values.push(self.value()?);
+                    self.skip_whitespace();
+                    match self.bytes.get(self.pos) {
+                        Some(b',') => self.pos += 1,
+                        Some(b']') => {
+                            self.pos += 1;
+                            return Ok(Json::Array(values));
+                        },
+                        _ => return Err(self.error()),
+                    }
+                }
+            },
+            Some(b'"') => Ok(Json::String(self.string()?)),
+            Some(b't') => self.expect(b"true").map(|_| Json::Bool(true)),
+            Some(b'f') => self.expect(b"false").map(|_| Json::Bool(false)),
+            Some(b'n') => self.expect(b"null").map(|_| Json::Null),
+            Some(byte) if *byte == b'-' || byte.is_ascii_digit() => {
+                let start: usize = self.pos;
+                while self.bytes.get(self.pos)
+                    .is_some_and(|&byte| byte.is_ascii_digit() || b"+-.eE".contains(&byte))
+                {
+                    self.pos += 1;
+                }
+                let number: &str = std::str::from_utf8(&self.bytes[start..self.pos])
+                    .map_err(|_| self.error())?;
+                Ok(Json::Number(number.to_string()))
+            },
+            _ => Err(self.error()),
+        }
+    }
+
+    /*
+     * Reads the string at the current position, with its escapes.
+     */
+    fn string(&mut self) -> Result<String, OthelloError> {
+        if self.bytes.get(self.pos) != Some(&b'"') {
+            return Err(self.error());
+        }
+        self.pos += 1;
+        let mut bytes: Vec<u8> = Vec::new();
+
+        loop {
+            let byte: u8 = *self.bytes.get(self.pos).ok_or_else(|| self.error())?;
+            self.pos += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let escape: u8 = *self.bytes.get(self.pos).ok_or_else(|| self.error())?;
+                    self.pos += 1;
+                    let c: char = match escape {
+                        b'n' => '\n',
+                        b't' => '\t',
+                        b'r' => '\r',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'u' => {
+                            let mut code: u32 = self.hex()?;
+                            // Characters beyond the first plane come as a pair of surrogates.
+                            if (0xD800..0xDC00).contains(&code)
+                                && self.bytes[self.pos..].starts_with(b"\\u")
+                            {
+                                let pos: usize = self.pos;
+                                self.pos += 2;
+                                match self.hex()? {
+                                    low @ 0xDC00..0xE000 => {
+                                        code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                                    },
+                                    _ => self.pos = pos,
+                                }
+                            }
+                            char::from_u32(code).unwrap_or('\u{fffd}')
+                        },
+                        other => other as char,
+                    };
+                    bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                },
+                byte => bytes.push(byte),
+            }
+        }
+
+        String::from_utf8(bytes).map_err(|_| self.error())
+    }
+
+    /*
+     * Reads the 4 hexadecimal digits of a \u escape.
+     */
+    fn hex(&mut self) -> Result<u32, OthelloError> {
+        let hex: &[u8] = self.bytes.get(self.pos..self.pos + 4).ok_or_else(|| self.error())?;
+        let code: u32 = std::str::from_utf8(hex).ok()
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .ok_or_else(|| self.error())?;
+        self.pos += 4;
+        Ok(code)
+    }
+}
+
+//#################################################################################################
+//
+//                                   ONLINE EXPORTS TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that the same games are read from a JSON and a CSV export, with their players, dates
+ * and results, that escaped surrogate pairs in names are decoded, and that deeply nested JSON
+ * is rejected rather than overflowing the stack.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exports() {
+        let json: &str = r#"{"games": [
+            {"Black_Player": "alice", "whitePlayer": "bob", "played_at": "2024-03-05T18:12:00Z",
+             "moves": "F5D6C3D3C4", "result": "36-28", "tags": ["blitz"], "rated": true},
+            {"black": "carol", "white": "alice", "moves": ""},
+            {"black": "bob", "white": "carol", "transcript": "f5 f6 e6 f4", "score": -4}
+        ]}"#;
+        let csv: &str = "black,white,date,moves,result\r\n\
+            alice,bob,2024/03/05,F5D6C3D3C4,36-28\r\n\
+            \"bob\",\"carol\",,\"f5,f6,e6,f4\",-4\r\n";
+
+        for text in [json, csv] {
+            let records: Vec<GameRecord> = parse_games(text).unwrap();
+            assert_eq!(records.len(), 2);
+            assert_eq!((records[0].black.as_str(), records[0].white.as_str()), ("alice", "bob"));
+            assert_eq!(records[0].date, "2024.03.05");
+            assert_eq!(records[0].moves, Game::from_line("f5d6c3d3c4").unwrap().history());
+            assert!(records[0].outcome.unwrap().winner == Some(Color::Black));
+            assert!(records[1].outcome.unwrap().winner == Some(Color::White));
+        }
+
+        assert!(parse_games("[{\"moves\": \"F5F5\"}]").is_err());
+        assert!(parse_games("[{\"moves\": \"F5\"").is_err());
+        assert!(parse_games("moves\n\"F5").is_err());
+
+        let names: &str = r#"[{"black": "\uD83D\uDE00", "white": "\u00e9\uD83Dx", "moves": "f5"}]"#;
+        let records: Vec<GameRecord> = parse_games(names).unwrap();
+        assert_eq!(records[0].black, "\u{1F600}");
+        assert_eq!(records[0].white, "é\u{fffd}x");
+        assert!(parse_json(&"[".repeat(100_000)).is_err());
+        assert!(parse_json(&format!("{}{}", "[".repeat(100), "]".repeat(100))).is_ok());
+    }
+}
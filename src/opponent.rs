@@ -0,0 +1,243 @@
+use std::time::Duration;
+
+use crate::types::*;
+use crate::othello::*;
+use crate::game::*;
+use crate::player::*;
+use crate::eval::{CORNERS, is_proven};
+
+//#################################################################################################
+//
+//                                    OPPONENT MODEL
+//
+//#################################################################################################
+
+/*
+ * How far below the best, in the units of the evaluation, a move may be for the opponent model
+ * to prefer it, and how many replies of the opponent a situation needs before it is trusted.
+ */
+pub const TIE_MARGIN: i32 = 10;
+const MIN_SAMPLES: u32 = 3;
+
+/*
+ * The number of legal moves at or below which the opponent is considered short of moves.
+ */
+const LOW_MOBILITY: u8 = 4;
+
+/*
+ * The position left by the AI after its move, waiting for the reply of the opponent: the number
+ * of plies played then, the board, the evaluation of the AI and the time the opponent had.
+ */
+#[derive(Clone, Copy)]
+struct Pending {
+    plies: usize,
+    board: Othello,
+    value: i32,
+    remaining: Option<Duration>,
+}
+
+/*
+ * A model of the opponent of an AI over a match, built from his replies: how often he takes a
+ * corner when he can, how much time he spends on his moves, and how much the evaluation of the
+ * AI rose after his replies in each kind of situation, whether he could take a corner and
+ * whether he was short of moves. The situations where it rose the most are those troubling
+ * him, and the AI prefers moves leading to them among moves about as good as its best one, in
+ * the book or after searching.
+ */
+#[derive(Clone, Default)]
+pub struct OpponentModel {
+    replies: u32,
+    corner_chances: u32,
+    corners_taken: u32,
+    time_spent: Duration,
+    timed_replies: u32,
+    situations: [(u32, i64); 4],
+    pending: Option<Pending>,
+    observed: Option<usize>,
+}
+
+impl OpponentModel {
+    /*
+     * Creates a new model, knowing nothing about the opponent.
+     */
+    pub fn new() -> OpponentModel {
+        OpponentModel::default()
+    }
+
+    /*
+     * Returns the number of replies of the opponent seen so far.
+     */
+    pub fn replies(&self) -> u32 {
+        self.replies
+    }
+
+    /*
+     * Returns the proportion of the chances to take a corner the opponent took, or None if he
+     * never had one.
+     */
+    pub fn corner_greed(&self) -> Option<f64> {
+        Some(self.corners_taken as f64 / self.corner_chances as f64)
+            .filter(|_| self.corner_chances > 0)
+    }
+
+    /*
+     * Returns the average time the opponent spent on a move, or None if his time isn't
+     * counted.
+     */
+    pub fn average_time(&self) -> Option<Duration> {
+        Some(self.time_spent / self.timed_replies.max(1)).filter(|_| self.timed_replies > 0)
+    }
+
+    /*
+     * Returns how much the evaluation of the AI rose on average after the replies of the
+     * opponent in the situation he is left in after the given move, or 0 if too few replies
+     * were seen in it.
+     */
+    pub fn trouble(&self, oth: Othello, color: Color, mv: BitBoard) -> f64 {
+        let (count, total) = self.situations[situation(oth.make_move(color, mv), !color)];
+        if count < MIN_SAMPLES {
+            0.0
+        } else {
+            total as f64 / count as f64
+        }
+    }
+
+    /*
+     * Chooses among the given moves, the preferred one first, the one leading to the
+     * situation troubling the opponent the most, the first one if none does.
+     */
+    pub fn choose(&self, oth: Othello, color: Color, moves: &[BitBoard]) -> Option<BitBoard> {
+        let mut best: Option<(BitBoard, f64)> = None;
+        for &mv in moves {
+            let trouble: f64 = self.trouble(oth, color, mv);
+            if best.is_none_or(|(_, best)| trouble > best) {
+                best = Some((mv, trouble));
+            }
+        }
+        best.map(|(mv, _)| mv)
+    }
+
+    /*
+     * Learns from the reply of the opponent to the last move of the AI, if the game went on
+     * from it, before the AI of the given color chooses its next move.
+     */
+    pub fn before_move(&mut self, game: &Game, clock: &Clock, color: Color) {
+        self.observed = None;
+        let pending: Pending = match self.pending.take() {
+            Some(pending) if game.history().len() > pending.plies => pending,
+            _ => return,
+        };
+
+        let reply: BitBoard = game.history()[pending.plies];
+        if reply == 0 {
+            return;
+        }
+
+        self.replies += 1;
+        if pending.board.gen_moves(!color) & CORNERS != 0 {
+            self.corner_chances += 1;
+            self.corners_taken += (reply & CORNERS != 0) as u32;
+        }
+        if let (Some(before), Some(after)) = (pending.remaining, clock.remaining(!color)) {
+            self.time_spent += (before + clock.increment()).saturating_sub(after);
+            self.timed_replies += 1;
+        }
+        self.observed = Some(situation(pending.board, !color));
+        self.pending = Some(pending);
+    }
+
+    /*
+     * Records the move chosen by the AI of the given color, with its evaluation from the AI's
+     * point of view, which tells how good the last reply of the opponent was.
+     */
+    pub fn after_move(&mut self, game: &Game, clock: &Clock, color: Color, mv: BitBoard,
+        value: i32)
+    {
+        if let (Some(observed), Some(pending)) = (self.observed.take(), self.pending) {
+            let gain: i64 = value.clamp(-i32::MAX, i32::MAX) as i64 - pending.value as i64;
+            // Won and lost games would swamp the rest.
+            if !is_proven(value) && !is_proven(pending.value) {
+                self.situations[observed].0 += 1;
+                self.situations[observed].1 += gain;
+            }
+        }
+
+        self.pending = Some(Pending {
+            plies: game.history().len() + 1,
+            board: game.board().make_move(color, mv),
+            value: value.clamp(-i32::MAX, i32::MAX),
+            remaining: clock.remaining(!color),
+        });
+    }
+}
+
+/*
+ * Returns the situation of the given player to move: 2 if he can take a corner, plus 1 if he is
+ * short of moves.
+ */
+fn situation(oth: Othello, color: Color) -> usize {
+    let moves: BitBoard = oth.gen_moves(color);
+    2 * (moves & CORNERS != 0) as usize + (moves.pop_cnt() <= LOW_MOBILITY) as usize
+}
+
+//#################################################################################################
+//
+//                                     OPPONENT TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that the model prefers the moves leading to the situations where the opponent did
+ * worst, here those offering him a corner, and that it counts the time he spends.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opponent_model() {
+        let mut model: OpponentModel = OpponentModel::new();
+        let oth: Othello = crate::pos!("
+            - X O - - - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - O X - - -
+            - - - X O - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - - - - - -
+        ");
+        let offering: BitBoard = oth.gen_moves(Color::White) & CORNERS;
+        assert_ne!(offering, 0);
+        assert_eq!(situation(oth, Color::White), 2);
+
+        // The opponent was offered a corner three times, and the AI gained a lot each time.
+        for offered in &mut model.situations[2..] {
+            *offered = (3, 150);
+        }
+        let moves: Vec<BitBoard> = {
+            let mut moves: BitBoard = oth.gen_moves(Color::Black);
+            let mut list: Vec<BitBoard> = Vec::new();
+            while moves != 0 {
+                list.push(moves.pop_lsb());
+            }
+            list
+        };
+        let chosen: BitBoard = model.choose(oth, Color::Black, &moves).unwrap();
+        assert_eq!(situation(oth.make_move(Color::Black, chosen), Color::White) / 2, 1);
+        assert_eq!(model.choose(oth, Color::Black, &[]), None);
+
+        let mut game: Game = Game::new();
+        let clock: Clock = Clock::new(Duration::from_secs(60), Duration::ZERO);
+        model.after_move(&game, &clock, Color::Black, game.moves() & game.moves().wrapping_neg(),
+            0);
+        game.play(game.moves() & game.moves().wrapping_neg()).unwrap();
+        game.play(game.moves() & game.moves().wrapping_neg()).unwrap();
+        let mut later: Clock = clock;
+        later.charge(Color::White, Duration::from_secs(2));
+        model.before_move(&game, &later, Color::Black);
+        assert_eq!(model.replies(), 1);
+        assert_eq!(model.average_time(), Some(Duration::from_secs(2)));
+        assert_eq!(model.corner_greed(), None);
+    }
+}
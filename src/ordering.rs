@@ -0,0 +1,181 @@
+use crate::types::*;
+use crate::othello::*;
+use crate::eval::{CORNERS, A_SQUARES, B_SQUARES, C_SQUARES, X_SQUARES};
+
+//#################################################################################################
+//
+//                                      MOVE ORDERING
+//
+//#################################################################################################
+
+/*
+ * The remaining depth from which the mobility left to the opponent by each move is worth
+ * computing. Closer to the leaves, moves are only ordered by their squares.
+ */
+const MOBILITY_DEPTH: u8 = 3;
+
+/*
+ * The ways the search can order the moves it tries, the best ones first giving more cutoffs:
+ *   - Natural tries them from a1 to h8, as the search originally did.
+ *   - Heuristic tries the move given first, the one of the principal variation of the previous
+ *     iteration for example, then corners, edges and the other squares, C-squares and
+ *     X-squares last. Far enough from the leaves, moves leaving the opponent with few moves
+ *     and flipping few disks come first too.
+ * Both are kept so that they can be compared in the bench.
+ */
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum MoveOrdering {
+    Natural,
+    #[default]
+    Heuristic,
+}
+
+impl MoveOrdering {
+    /*
+     * All the move orderings.
+     */
+    pub const ALL: [MoveOrdering; 2] = [MoveOrdering::Natural, MoveOrdering::Heuristic];
+
+    /*
+     * Returns the name of the ordering, in lowercase.
+     */
+    pub fn name(self) -> &'static str {
+        match self {
+            MoveOrdering::Natural => "natural",
+            MoveOrdering::Heuristic => "heuristic",
+        }
+    }
+
+    /*
+     * Returns the ordering with the given name, case insensitive.
+     */
+    pub fn from_name(name: &str) -> Option<MoveOrdering> {
+        MoveOrdering::ALL.iter().copied().find(|o| o.name().eq_ignore_ascii_case(name))
+    }
+
+    /*
+     * Orders the given moves of the player, the given first move, if any, being tried first,
+     * for a search of the given remaining depth.
+     */
+    pub fn order(
+        self, oth: Othello, color: Color, mut moves: BitBoard, first: BitBoard, depth: u8,
+    ) -> MoveList {
+        let mut list: MoveList = MoveList { moves: [0; 64], scores: [0; 64], len: 0, next: 0, };
+
+        while moves != 0 {
+            let mv: BitBoard = moves.pop_lsb();
+            let score: i32 = match self {
+                MoveOrdering::Natural => 0,
+                MoveOrdering::Heuristic if mv == first => i32::MAX,
+                MoveOrdering::Heuristic => score(oth, color, mv, depth),
+            };
+
+            // Insertion sort, stable so that equal moves stay in their natural order.
+            let mut i: usize = list.len;
+            while i > 0 && list.scores[i - 1] < score {
+                list.moves[i] = list.moves[i - 1];
+                list.scores[i] = list.scores[i - 1];
+                i -= 1;
+            }
+            list.moves[i] = mv;
+            list.scores[i] = score;
+            list.len += 1;
+        }
+
+        list
+    }
+}
+
+/*
+ * Scores a move for the heuristic ordering, the higher the sooner it is tried.
+ */
+fn score(oth: Othello, color: Color, mv: BitBoard, depth: u8) -> i32 {
+    let mut score: i32 = if mv & CORNERS != 0 {
+        400
+    } else if mv & X_SQUARES != 0 {
+        -400
+    } else if mv & C_SQUARES != 0 {
+        -100
+    } else if mv & (A_SQUARES | B_SQUARES) != 0 {
+        100
+    } else {
+        0
+    };
+
+    if depth >= MOBILITY_DEPTH {
+        let new_oth: Othello = oth.make_move(color, mv);
+        let flips: i32 = (new_oth.get_bitboard(color).pop_cnt()
+            - oth.get_bitboard(color).pop_cnt()) as i32 - 1;
+        score -= 16 * new_oth.gen_moves(color.invert()).pop_cnt() as i32 + flips;
+    }
+
+    score
+}
+
+/*
+ * The moves of a position, in the order they should be tried.
+ */
+pub struct MoveList {
+    moves: [BitBoard; 64],
+    scores: [i32; 64],
+    len: usize,
+    next: usize,
+}
+
+impl Iterator for MoveList {
+    type Item = BitBoard;
+
+    fn next(&mut self) -> Option<BitBoard> {
+        if self.next == self.len {
+            return None;
+        }
+        self.next += 1;
+        Some(self.moves[self.next - 1])
+    }
+}
+
+//#################################################################################################
+//
+//                                   MOVE ORDERING TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that the natural ordering goes from a1 to h8, and that the heuristic one tries the
+ * given move first, then corners.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn order() {
+        let oth: Othello = crate::pos!("
+            - - - - - - - -
+            - O - - - - - -
+            - - X - - - - -
+            - - - O X - - -
+            - - - X O - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - - - - - -
+        ");
+        let moves: BitBoard = oth.gen_moves(Color::Black);
+        assert_ne!(moves & CORNERS, 0);
+
+        let order = |ordering: MoveOrdering, first: BitBoard| -> Vec<BitBoard> {
+            ordering.order(oth, Color::Black, moves, first, 5).collect()
+        };
+
+        let natural: Vec<BitBoard> = order(MoveOrdering::Natural, 0);
+        assert_eq!(natural.iter().fold(0, |acc, mv| acc | mv), moves);
+        assert!(natural.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let heuristic: Vec<BitBoard> = order(MoveOrdering::Heuristic, 0);
+        assert_eq!(heuristic.len(), natural.len());
+        assert_eq!(heuristic[0], 1);
+
+        let first: BitBoard = *natural.last().unwrap();
+        assert_eq!(order(MoveOrdering::Heuristic, first)[0], first);
+    }
+}
@@ -43,6 +43,29 @@ macro_rules! south_east {
     ($x: ident) => { ($x & 0xFEFEFEFEFEFEFEFE).wrapping_shr(9) }
 }
 
+/*
+ * Builds an Othello board from an ASCII art picture at compile time, see Othello::from_ascii.
+ * A malformed picture is a compilation error.
+ *
+ * let oth: Othello = pos!("
+ *     - - - - - - - -
+ *     - - - - - - - -
+ *     - - - - - - - -
+ *     - - - O X - - -
+ *     - - - X O - - -
+ *     - - - - - - - -
+ *     - - - - - - - -
+ *     - - - - - - - -
+ * ");
+ */
+#[macro_export]
+macro_rules! pos {
+    ($art: expr) => {{
+        const OTH: $crate::othello::Othello = $crate::othello::Othello::from_ascii($art);
+        OTH
+    }}
+}
+
 //#################################################################################################
 //
 //                                    OTHELLO TYPE
@@ -50,32 +73,109 @@ macro_rules! south_east {
 //#################################################################################################
 
 /*
- * An Othello board only needs two BitBoards. First BitBoard is White's and second is Black's.
+ * The corners given to Black in handicap games, in the order they are given.
+ */
+pub const HANDICAP_CORNERS: [BitBoard; 4] = [1 << 0, 1 << 63, 1 << 7, 1 << 56];
+
+/*
+ * An Othello board only needs two BitBoards. First BitBoard is Black's and second is White's.
  */
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Othello(BitBoard, BitBoard);
 
 impl Othello {
     /*
      * Creates a new Othello board in the starting position.
      */
-    pub fn new() -> Othello {
+    pub const fn new() -> Othello {
         Othello(0x0000000810000000, 0x0000001008000000)
     }
 
+    /*
+     * Creates a new Othello board in the starting position, Black being given the traditional
+     * handicap of the given number of corners, up to 4: a1, then h8, h1 and a8.
+     */
+    pub const fn with_handicap(corners: u8) -> Othello {
+        let mut black: BitBoard = Othello::new().0;
+        let mut i: usize = 0;
+        while i < corners as usize && i < HANDICAP_CORNERS.len() {
+            black |= HANDICAP_CORNERS[i];
+            i += 1;
+        }
+        Othello(black, Othello::new().1)
+    }
+
     /*
      * Creates a new Othello with the given BitBoards.
      */
     #[inline(always)]
-    fn create(black: BitBoard, white: BitBoard) -> Othello {
+    pub const fn create(black: BitBoard, white: BitBoard) -> Othello {
         Othello(black, white)
     }
 
+    /*
+     * Creates a new Othello from an ASCII art picture of the board, as printed in the terminal:
+     * 'X' for black disks, 'O' for white disks and '-' or '.' for empty squares, row by row from
+     * a1 to h8. Whitespaces are ignored. Being a const function, it can be evaluated at compile
+     * time, see the pos! macro. Panics if the picture doesn't have exactly 64 squares.
+     */
+    pub const fn from_ascii(art: &str) -> Othello {
+        let bytes: &[u8] = art.as_bytes();
+        let mut black: BitBoard = 0;
+        let mut white: BitBoard = 0;
+        let mut square: u32 = 0;
+        let mut i: usize = 0;
+
+        while i < bytes.len() {
+            match bytes[i] {
+                b' ' | b'\t' | b'\n' | b'\r' => (),
+                c => {
+                    if square >= 64 {
+                        panic!("Too many squares in the picture of the board.");
+                    }
+                    match c {
+                        b'X' => black |= 1u64 << square,
+                        b'O' => white |= 1u64 << square,
+                        b'-' | b'.' => (),
+                        _ => panic!("Invalid character in the picture of the board."),
+                    }
+                    square += 1;
+                },
+            }
+            i += 1;
+        }
+
+        if square != 64 {
+            panic!("Not enough squares in the picture of the board.");
+        }
+
+        Othello(black, white)
+    }
+
+    /*
+     * Creates a new Othello from a matrix of squares, indexed by row then column, the first
+     * row being the one of a1, as given by a vision system or written in a test.
+     */
+    pub fn from_array(squares: &[[Square; 8]; 8]) -> Othello {
+        let mut oth: Othello = Othello(0, 0);
+        for (y, row) in squares.iter().enumerate() {
+            for (x, &square) in row.iter().enumerate() {
+                let bit: BitBoard = 1 << (x + 8 * y);
+                match square {
+                    Square::Black => oth.0 |= bit,
+                    Square::White => oth.1 |= bit,
+                    Square::Empty => (),
+                }
+            }
+        }
+        oth
+    }
+
     /*
      * Returns the BitBoard associated with the color given in argument.
      */
     #[inline(always)]
-    pub fn get_bitboard(&self, color: Color) -> BitBoard {
+    pub const fn get_bitboard(&self, color: Color) -> BitBoard {
         match color {
             Color::Black => self.0,
             Color::White => self.1,
@@ -92,7 +192,7 @@ impl Othello {
      * Generates all legal moves for the given color and returns the result as a
      * BitBoard.
      */
-    pub fn gen_moves(&self, playing: Color) -> BitBoard {
+    pub const fn gen_moves(&self, playing: Color) -> BitBoard {
         let own: BitBoard = self.get_bitboard(playing);
         let opp: BitBoard = self.get_bitboard(playing.invert());
 
@@ -134,7 +234,7 @@ impl Othello {
     /*
      * Makes the given move on the board and returns the new board.
      */
-    pub fn make_move(&self, playing: Color, mv: BitBoard) -> Othello {
+    pub const fn make_move(&self, playing: Color, mv: BitBoard) -> Othello {
         let mut own: BitBoard = self.get_bitboard(playing);
         let mut opp: BitBoard = self.get_bitboard(playing.invert());
 
@@ -168,13 +268,49 @@ impl Othello {
         change_in_direction!(south_east);
         change_in_direction!(east);
 
-        if playing == Color::Black {
-            Self::create(own, opp)
+        match playing {
+            Color::Black => Self::create(own, opp),
+            Color::White => Self::create(opp, own),
+        }
+    }
+
+    /*
+     * Makes the move at (x, y) on the board if it is legal and returns the new board, or the
+     * reason why the move is illegal otherwise.
+     */
+    pub fn try_make_move(&self, playing: Color, x: u8, y: u8) -> Result<Othello, MoveError> {
+        if x >= 8 || y >= 8 {
+            return Err(MoveError::OutOfBounds);
+        }
+
+        let mv: BitBoard = 1u64 << (x + 8 * y);
+
+        if (self.0 | self.1) & mv != 0 {
+            Err(MoveError::Occupied)
+        } else if self.gen_moves(playing) & mv == 0 {
+            Err(MoveError::NoFlips)
         } else {
-            Self::create(opp, own)
+            Ok(self.make_move(playing, mv))
         }
     }
 
+    /*
+     * Finds the move turning one board into the other, with the player who made it, for
+     * front-ends only seeing the boards, like a camera over a physical board or a GUI sending
+     * positions. Returns None if no single legal move does it, passes included since they
+     * leave the board as it is.
+     */
+    pub fn infer_move(before: &Othello, after: &Othello) -> Option<(Color, BitBoard)> {
+        let added: BitBoard = (after.0 | after.1) & !(before.0 | before.1);
+        if added.pop_cnt() != 1 || (before.0 | before.1) & !(after.0 | after.1) != 0 {
+            return None;
+        }
+
+        Color::iter().find(|&color| {
+            before.gen_moves(color) & added != 0 && before.make_move(color, added) == *after
+        }).map(|color| (color, added))
+    }
+
     /*
      * Returns the state of the square at (x, y), where x and y are in 0..8.
      */
@@ -204,6 +340,62 @@ impl Othello {
 
         Score::new(black_score, white_score)
     }
+
+    /*
+     * Returns the number of empty squares left on the board.
+     */
+    #[inline(always)]
+    pub const fn empties(&self) -> u8 {
+        64 - (self.0 | self.1).count_ones() as u8
+    }
+
+    /*
+     * Returns the board as a matrix of squares, indexed by row then column like from_array.
+     */
+    pub fn to_array(&self) -> [[Square; 8]; 8] {
+        let mut squares: [[Square; 8]; 8] = [[Square::Empty; 8]; 8];
+        for (y, row) in squares.iter_mut().enumerate() {
+            for (x, square) in row.iter_mut().enumerate() {
+                *square = self.get_square(x as u8, y as u8);
+            }
+        }
+        squares
+    }
+}
+
+impl Default for Othello {
+    fn default() -> Othello {
+        Othello::new()
+    }
+}
+
+//#################################################################################################
+//
+//                                          PERFT
+//
+//#################################################################################################
+
+/*
+ * Counts the number of leaf nodes of the game tree at the given depth, passes counting as
+ * moves and finished games as leaves, to check and benchmark the move generator.
+ */
+pub fn perft(oth: Othello, color: Color, depth: usize) -> u64 {
+    if depth == 0 { return 1; }
+
+    let mut res: u64 = 0;
+    let mut moves: BitBoard = oth.gen_moves(color);
+
+    if moves == 0 {
+        moves = oth.gen_moves(color.invert());
+        if moves == 0 { return 1; }
+        return perft(oth, color.invert(), depth-1);
+    }
+
+    while moves != 0 {
+        res += perft(oth.make_move(color, moves.pop_lsb()), color.invert(), depth-1)
+    }
+
+    res
 }
 
 //#################################################################################################
@@ -223,25 +415,68 @@ mod tests {
     const DEPTH: usize = 10;
 
     /*
-     * The perft function in itself, that counts the number of leaf nodes at depth 9.
+     * Checks that the starting position can be built from its picture.
      */
-    fn perft(oth: Othello, color: Color, depth: usize) -> u64 {
-        if depth == 0 { return 1; }
-
-        let mut res: u64 = 0;
-        let mut moves: BitBoard = oth.gen_moves(color);
+    #[test]
+    fn ascii_art() {
+        let oth: Othello = crate::pos!("
+            - - - - - - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - O X - - -
+            - - - X O - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - - - - - -
+        ");
+
+        assert_eq!(oth.get_bitboard(Color::Black), Othello::new().get_bitboard(Color::Black));
+        assert_eq!(oth.get_bitboard(Color::White), Othello::new().get_bitboard(Color::White));
+    }
 
-        if moves == 0 {
-            moves = oth.gen_moves(color.invert());
-            if moves == 0 { return 1; }
-            return perft(oth, color.invert(), depth-1);
-        }
+    /*
+     * Checks that a move is found back from the boards before and after it, but not from
+     * boards no single move links.
+     */
+    #[test]
+    fn infer_move() {
+        let before: Othello = Othello::new();
+        let after: Othello = before.make_move(Color::Black, 1 << 37);
+        assert_eq!(Othello::infer_move(&before, &after), Some((Color::Black, 1 << 37)));
+        let reply: Othello = after.make_move(Color::White, 1 << 45);
+        assert_eq!(Othello::infer_move(&after, &reply), Some((Color::White, 1 << 45)));
+
+        assert_eq!(Othello::infer_move(&before, &before), None);
+        assert_eq!(Othello::infer_move(&before, &reply), None);
+        assert_eq!(Othello::infer_move(&after, &before), None);
+        assert_eq!(Othello::infer_move(&before, &Othello::create(after.0, after.1 | 1)), None);
+    }
 
-        while moves != 0 {
-            res += perft(oth.make_move(color, moves.pop_lsb()), color.invert(), depth-1)
-        }
+    /*
+     * Checks that illegal moves are rejected with the reason why, and legal ones made.
+     */
+    #[test]
+    fn try_make_move() {
+        let oth: Othello = Othello::new();
+        assert_eq!(oth.try_make_move(Color::Black, 0, 8).err(), Some(MoveError::OutOfBounds));
+        assert_eq!(oth.try_make_move(Color::Black, 4, 4).err(), Some(MoveError::Occupied));
+        assert_eq!(oth.try_make_move(Color::Black, 2, 2).err(), Some(MoveError::NoFlips));
+        assert_eq!(MoveError::NoFlips.to_string(),
+            "A move must flip at least one of the opponent's disks.");
+        assert!(oth.try_make_move(Color::Black, 5, 4) == Ok(oth.make_move(Color::Black, 1 << 37)));
+    }
 
-        res
+    /*
+     * Checks that boards round-trip through matrices of squares, rows going from a1.
+     */
+    #[test]
+    fn array() {
+        let oth: Othello = Othello::with_handicap(1).make_move(Color::Black, 1 << 37);
+        let squares: [[Square; 8]; 8] = oth.to_array();
+        assert_eq!(squares[0][0], Square::Black);
+        assert_eq!(squares[4][5], Square::Black);
+        assert_eq!(squares[3][3], Square::White);
+        assert!(Othello::from_array(&squares) == oth);
     }
 
     /*
@@ -0,0 +1,138 @@
+use crate::othello::*;
+
+//#################################################################################################
+//
+//                                         PHASES
+//
+//#################################################################################################
+
+/*
+ * The phases of a game, from the number of empty squares: the opening, the midgame, the
+ * pre-endgame, where the exact search starts to see the end of the game, and the endgame,
+ * solved perfectly.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Phase {
+    Opening,
+    Midgame,
+    PreEndgame,
+    Endgame,
+}
+
+impl Phase {
+    /*
+     * Every phase, in the order of the game.
+     */
+    pub const ALL: [Phase; 4] =
+        [Phase::Opening, Phase::Midgame, Phase::PreEndgame, Phase::Endgame];
+
+    /*
+     * Returns the phase of the position, with the default thresholds.
+     */
+    pub fn of(oth: &Othello) -> Phase {
+        PhaseThresholds::default().classify(oth.empties())
+    }
+
+    /*
+     * Returns the phase of the position, with the given thresholds.
+     */
+    pub fn with_thresholds(oth: &Othello, thresholds: &PhaseThresholds) -> Phase {
+        thresholds.classify(oth.empties())
+    }
+
+    /*
+     * Returns the index of the phase in ALL.
+     */
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Opening => "opening",
+            Phase::Midgame => "midgame",
+            Phase::PreEndgame => "pre-endgame",
+            Phase::Endgame => "endgame",
+        }
+    }
+}
+
+/*
+ * The numbers of empty squares at and below which the midgame, the pre-endgame and the endgame
+ * start. By default, the opening lasts for the first 20 moves, the midgame down to 24 empty
+ * squares, and the endgame starts at 14, about where the exact search solves the game.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PhaseThresholds {
+    pub midgame: u8,
+    pub pre_endgame: u8,
+    pub endgame: u8,
+}
+
+impl Default for PhaseThresholds {
+    fn default() -> PhaseThresholds {
+        PhaseThresholds { midgame: 40, pre_endgame: 24, endgame: 14, }
+    }
+}
+
+impl PhaseThresholds {
+    /*
+     * Creates thresholds from the numbers of the first moves of the midgame, the pre-endgame
+     * and the endgame, counting from 1 and assuming nobody passes, the n-th move being played
+     * with 61 - n empty squares.
+     */
+    pub fn from_move_numbers(midgame: u8, pre_endgame: u8, endgame: u8) -> PhaseThresholds {
+        let empties = |number: u8| 61u8.saturating_sub(number.max(1));
+        PhaseThresholds {
+            midgame: empties(midgame),
+            pre_endgame: empties(pre_endgame),
+            endgame: empties(endgame),
+        }
+    }
+
+    /*
+     * Returns the phase of a position with the given number of empty squares.
+     */
+    pub fn classify(&self, empties: u8) -> Phase {
+        if empties <= self.endgame {
+            Phase::Endgame
+        } else if empties <= self.pre_endgame {
+            Phase::PreEndgame
+        } else if empties <= self.midgame {
+            Phase::Midgame
+        } else {
+            Phase::Opening
+        }
+    }
+}
+
+//#################################################################################################
+//
+//                                       PHASES TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that positions are classified by their number of empty squares, and that thresholds
+ * given by move number match those given by empty squares.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phases() {
+        assert_eq!(Phase::of(&Othello::new()), Phase::Opening);
+        let thresholds: PhaseThresholds = PhaseThresholds::default();
+        assert_eq!(thresholds.classify(41), Phase::Opening);
+        assert_eq!(thresholds.classify(40), Phase::Midgame);
+        assert_eq!(thresholds.classify(24), Phase::PreEndgame);
+        assert_eq!(thresholds.classify(14), Phase::Endgame);
+        assert_eq!(thresholds.classify(0), Phase::Endgame);
+        assert_eq!(PhaseThresholds::from_move_numbers(21, 37, 47), thresholds);
+        assert!(Phase::ALL.iter().enumerate().all(|(i, phase)| phase.index() == i));
+
+        let late: PhaseThresholds = PhaseThresholds { midgame: 60, pre_endgame: 60, endgame: 58, };
+        assert_eq!(Phase::with_thresholds(&Othello::new(), &late), Phase::PreEndgame);
+    }
+}
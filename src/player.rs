@@ -0,0 +1,538 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use crate::types::*;
+use crate::othello::*;
+use crate::game::*;
+use crate::wire::*;
+use crate::diff::*;
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                       CLOCK TYPE
+//
+//#################################################################################################
+
+/*
+ * The clock of a game, holding the time left to each player, or None if his time is unlimited,
+ * and the increment added to it after each of his moves.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Clock {
+    remaining: [Option<Duration>; 2],
+    increment: Duration,
+}
+
+impl Clock {
+    /*
+     * Creates a clock giving unlimited time to both players.
+     */
+    pub fn unlimited() -> Clock {
+        Clock {
+            remaining: [None, None],
+            increment: Duration::ZERO,
+        }
+    }
+
+    /*
+     * Creates a clock giving the same time to both players, plus the given increment per move.
+     */
+    pub fn new(time: Duration, increment: Duration) -> Clock {
+        Clock {
+            remaining: [Some(time), Some(time)],
+            increment,
+        }
+    }
+
+    /*
+     * Returns the time left to the given player, or None if it is unlimited.
+     */
+    pub fn remaining(&self, color: Color) -> Option<Duration> {
+        self.remaining[color as usize]
+    }
+
+    /*
+     * Returns the increment added after each move.
+     */
+    pub fn increment(&self) -> Duration {
+        self.increment
+    }
+
+    /*
+     * Returns true if the given player has run out of time.
+     */
+    pub fn is_flagged(&self, color: Color) -> bool {
+        self.remaining(color) == Some(Duration::ZERO)
+    }
+
+    /*
+     * Takes the time spent on a move from the given player's time, then adds the increment to
+     * it, unless he has run out of time.
+     */
+    pub fn charge(&mut self, color: Color, elapsed: Duration) {
+        if let Some(remaining) = &mut self.remaining[color as usize] {
+            *remaining = remaining.saturating_sub(elapsed);
+            if !remaining.is_zero() {
+                *remaining += self.increment;
+            }
+        }
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Clock {
+        Clock::unlimited()
+    }
+}
+
+//#################################################################################################
+//
+//                                      PLAYER TRAIT
+//
+//#################################################################################################
+
+/*
+ * The Action type, what a player decides to do when it is his turn: playing a move, playing a
+ * move while offering a draw to the opponent, or resigning the game. The move is an empty
+ * BitBoard when the player has to pass.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Action {
+    Play(BitBoard), OfferDraw(BitBoard), Resign,
+}
+
+/*
+ * A trait representing a player by it's means of choosing a move, given the game so far and the
+ * clock. Humans, AIs and remote programs all implement it, so that games can be played between
+ * any two of them.
+ */
+pub trait Player {
+    fn choose_move(&mut self, game: &Game, clock: &Clock) -> Action;
+
+    /*
+     * Answers a draw offer made by the opponent of the given player. Offers are declined by
+     * default.
+     */
+    fn accept_draw(&mut self, _game: &Game, _color: Color) -> bool {
+        false
+    }
+
+    /*
+     * Returns the player's evaluation of the position after his last move, from his own point
+     * of view, if he has one. Used by arbiters to adjudicate games.
+     */
+    fn evaluation(&self) -> Option<i32> {
+        None
+    }
+
+    /*
+     * Returns the depth and the number of nodes of the search of his last move, if he searched
+     * it, written in game records along with the time it took and his evaluation.
+     */
+    fn search_stats(&self) -> Option<(u8, u64)> {
+        None
+    }
+
+    /*
+     * Returns the name of the player, written in game records.
+     */
+    fn name(&self) -> String {
+        "Anonymous".to_string()
+    }
+}
+
+impl<P: Player + ?Sized> Player for &mut P {
+    fn choose_move(&mut self, game: &Game, clock: &Clock) -> Action {
+        (**self).choose_move(game, clock)
+    }
+
+    fn accept_draw(&mut self, game: &Game, color: Color) -> bool {
+        (**self).accept_draw(game, color)
+    }
+
+    fn evaluation(&self) -> Option<i32> {
+        (**self).evaluation()
+    }
+
+    fn search_stats(&self) -> Option<(u8, u64)> {
+        (**self).search_stats()
+    }
+
+    fn name(&self) -> String {
+        (**self).name()
+    }
+}
+
+//#################################################################################################
+//
+//                                   SIMPLE PLAYER TYPES
+//
+//#################################################################################################
+
+/*
+ * A xorshift pseudo-random number generator, good enough for random
+ * players and playouts.
+ */
+#[inline(always)]
+pub(crate) fn next_random(rng: &mut u64) -> u64 {
+    *rng ^= *rng << 13;
+    *rng ^= *rng >> 7;
+    *rng ^= *rng << 17;
+    *rng
+}
+
+/*
+ * A player choosing his moves uniformly at random, a baseline for the AIs. The same seed always
+ * gives the same moves.
+ */
+pub struct RandomPlayer {
+    rng: u64,
+}
+
+impl RandomPlayer {
+    /*
+     * Creates a new RandomPlayer with the given seed.
+     */
+    pub fn new(seed: u64) -> RandomPlayer {
+        RandomPlayer {
+            rng: (seed ^ 0x9E3779B97F4A7C15).max(1),
+        }
+    }
+}
+
+impl Player for RandomPlayer {
+    /*
+     * Plays one of the legal moves at random.
+     */
+    fn choose_move(&mut self, game: &Game, _clock: &Clock) -> Action {
+        let mut moves: BitBoard = game.moves();
+        if moves != 0 {
+            for _ in 0..next_random(&mut self.rng) % moves.pop_cnt() as u64 {
+                moves.pop_lsb();
+            }
+        }
+        Action::Play(moves & moves.wrapping_neg())
+    }
+
+    fn name(&self) -> String {
+        "Random".to_string()
+    }
+}
+
+/*
+ * A player following a script: it plays the given moves, in order, then resigns once it has
+ * none left. Useful to replay games or to test opponents from a known position.
+ */
+pub struct ScriptedPlayer {
+    moves: std::vec::IntoIter<BitBoard>,
+}
+
+impl ScriptedPlayer {
+    /*
+     * Creates a new ScriptedPlayer playing the given moves.
+     */
+    pub fn new(moves: Vec<BitBoard>) -> ScriptedPlayer {
+        ScriptedPlayer {
+            moves: moves.into_iter(),
+        }
+    }
+}
+
+impl Player for ScriptedPlayer {
+    /*
+     * Plays the next move of the script, or resigns if it is over.
+     */
+    fn choose_move(&mut self, _game: &Game, _clock: &Clock) -> Action {
+        match self.moves.next() {
+            Some(mv) => Action::Play(mv),
+            None => Action::Resign,
+        }
+    }
+}
+
+//#################################################################################################
+//
+//                                   NETWORK PLAYER TYPE
+//
+//#################################################################################################
+
+/*
+ * A player on the other end of a TCP connection, talking a line-based text protocol:
+ *   - "play <position> <time>" asks for a move, the position being the hexadecimal plain wire
+ *     encoding of the board and the player to move, and the time the number of milliseconds he
+ *     has left, or "-" if it is unlimited. The answer is a square name, "pass", either followed
+ *     by " draw" to offer a draw, or "resign".
+ *   - "draw <position>" asks whether the player accepts a draw offered by his opponent, the
+ *     position being the board just reached and the color of the player asked. The answer is
+ *     "y" or "n".
+ * A remote player closing the connection or answering something else resigns, or declines the
+ * draw.
+ */
+pub struct NetworkPlayer {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl NetworkPlayer {
+    /*
+     * Connects to a remote player listening at the given address.
+     */
+    pub fn connect(addr: &str) -> Result<NetworkPlayer, OthelloError> {
+        NetworkPlayer::from_stream(TcpStream::connect(addr)?)
+    }
+
+    /*
+     * Creates a NetworkPlayer talking through an already open connection.
+     */
+    pub fn from_stream(stream: TcpStream) -> Result<NetworkPlayer, OthelloError> {
+        Ok(NetworkPlayer {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+        })
+    }
+
+    /*
+     * Sends a request and returns the trimmed answer, or None if the connection is broken.
+     */
+    fn request(&mut self, request: &str) -> Option<String> {
+        writeln!(self.writer, "{}", request).ok()?;
+        let mut answer: String = String::new();
+        match self.reader.read_line(&mut answer) {
+            Ok(n) if n > 0 => Some(answer.trim().to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl Player for NetworkPlayer {
+    /*
+     * Asks the remote player for his move.
+     */
+    fn choose_move(&mut self, game: &Game, clock: &Clock) -> Action {
+        let time: String = match clock.remaining(game.color()) {
+            Some(time) => time.as_millis().to_string(),
+            None => "-".to_string(),
+        };
+        let request: String = format!("play {} {}", to_hex(game.board(), game.color()), time);
+
+        match self.request(&request) {
+            Some(answer) => parse_action(&answer).unwrap_or(Action::Resign),
+            None => Action::Resign,
+        }
+    }
+
+    /*
+     * Asks the remote player whether he accepts the draw.
+     */
+    fn accept_draw(&mut self, game: &Game, color: Color) -> bool {
+        let request: String = format!("draw {}", to_hex(game.board(), color));
+        self.request(&request).as_deref() == Some("y")
+    }
+
+    fn name(&self) -> String {
+        match self.writer.peer_addr() {
+            Ok(addr) => addr.to_string(),
+            Err(_) => "Remote".to_string(),
+        }
+    }
+}
+
+/*
+ * Answers the requests of a NetworkPlayer on the other end of the connection with the moves of
+ * the given player, until the connection is closed. Returns an error if a request is malformed.
+ * Since only positions are sent, the player sees each of them as a new game. Still, a position
+ * following the last move played, with one more disk or as many, must be reached from it by a
+ * move or a pass of the opponent: otherwise the two ends got out of sync, and the error shows
+ * both boards and where they differ.
+ */
+pub fn serve(stream: TcpStream, player: &mut dyn Player) -> Result<(), OthelloError> {
+    let mut writer: TcpStream = stream.try_clone()?;
+    let mut line: String = String::new();
+    let mut reader: BufReader<TcpStream> = BufReader::new(stream);
+    let mut played: Option<(Othello, Color)> = None;
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let answer: String = match words.as_slice() {
+            ["play", position, time] => {
+                let (oth, color) = from_hex(position)?;
+                if let Some(played) = played {
+                    check_sync(played, (oth, color))?;
+                }
+                let clock: Clock = match *time {
+                    "-" => Clock::unlimited(),
+                    time => {
+                        let ms: u64 = time.parse().map_err(|_| protocol_error(&line))?;
+                        Clock::new(Duration::from_millis(ms), Duration::ZERO)
+                    },
+                };
+                let action: Action = player.choose_move(&Game::from_position(oth, color), &clock);
+                played = match action {
+                    Action::Play(mv) | Action::OfferDraw(mv) => {
+                        Some((oth.make_move(color, mv), color.invert()))
+                    },
+                    Action::Resign => None,
+                };
+                match action {
+                    Action::Play(mv) => move_name(mv),
+                    Action::OfferDraw(mv) => format!("{} draw", move_name(mv)),
+                    Action::Resign => "resign".to_string(),
+                }
+            },
+            ["draw", position] => {
+                let (oth, color) = from_hex(position)?;
+                let accepted: bool = player.accept_draw(&Game::from_position(oth, color), color);
+                if accepted { "y" } else { "n" }.to_string()
+            },
+            _ => return Err(protocol_error(&line)),
+        };
+
+        writeln!(writer, "{}", answer)?;
+    }
+}
+
+/*
+ * Checks that the position received follows the one reached by the last move played, if it
+ * has one more disk or as many, the others being positions of new games. Returns a protocol
+ * error showing the received board next to the closest one that could follow otherwise.
+ */
+fn check_sync(played: (Othello, Color), received: (Othello, Color)) -> Result<(), OthelloError> {
+    let ((oth, color), (received_oth, received_color)) = (played, received);
+    let disks = |oth: Othello| 64 - oth.empties();
+    if !(disks(oth)..=disks(oth) + 1).contains(&disks(received_oth)) {
+        return Ok(());
+    }
+
+    let mut moves: BitBoard = oth.gen_moves(color);
+    let mut next: Vec<Othello> = Vec::new();
+    if moves == 0 {
+        next.push(oth);
+    }
+    while moves != 0 {
+        next.push(oth.make_move(color, moves.pop_lsb()));
+    }
+    if received_color == color.invert() && next.contains(&received_oth) {
+        return Ok(());
+    }
+
+    let closest: Othello = next.into_iter()
+        .min_by_key(|&next| differing_squares(next, received_oth).count_ones())
+        .unwrap();
+    Err(OthelloError::ProtocolError(format!("The position received, {} to move, doesn't follow \
+        the last move played.\n{}", received_color, board_diff(closest, received_oth,
+        ("Expected", "Received")))))
+}
+
+/*
+ * Parses the answer of a remote player to a move request.
+ */
+fn parse_action(answer: &str) -> Option<Action> {
+    if answer == "resign" {
+        return Some(Action::Resign);
+    }
+
+    let (square, draw) = match answer.strip_suffix(" draw") {
+        Some(square) => (square, true),
+        None => (answer, false),
+    };
+    let mv: BitBoard = if square == "pass" { 0 } else { parse_square(square)? };
+
+    Some(if draw { Action::OfferDraw(mv) } else { Action::Play(mv) })
+}
+
+/*
+ * Returns the name of a move, "pass" for an empty BitBoard.
+ */
+fn move_name(mv: BitBoard) -> String {
+    if mv == 0 { "pass".to_string() } else { mv.square_name() }
+}
+
+/*
+ * Writes a position in hexadecimal.
+ */
+pub(crate) fn to_hex(oth: Othello, color: Color) -> String {
+    encode(oth, color).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/*
+ * Reads a position written by to_hex.
+ */
+fn from_hex(hex: &str) -> Result<(Othello, Color), OthelloError> {
+    let bytes: Option<Vec<u8>> = (0..hex.len())
+        .step_by(2)
+        .map(|i| hex.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+        .collect();
+    bytes
+        .as_deref()
+        .and_then(decode)
+        .ok_or_else(|| OthelloError::ProtocolError(format!("Invalid position: {}", hex)))
+}
+
+/*
+ * Builds the error returned for a malformed request.
+ */
+fn protocol_error(line: &str) -> OthelloError {
+    OthelloError::ProtocolError(format!("Invalid request: {}", line.trim()))
+}
+
+//#################################################################################################
+//
+//                                       PLAYER TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that a game can be played against a remote player, that positions out of sync with
+ * the last move played are caught, and that the random player is reproducible.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn network() {
+        let listener: TcpListener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr: String = listener.local_addr().unwrap().to_string();
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            serve(stream, &mut RandomPlayer::new(1)).unwrap();
+        });
+
+        let mut local: RandomPlayer = RandomPlayer::new(2);
+        let mut remote: NetworkPlayer = NetworkPlayer::connect(&addr).unwrap();
+        let mut game: Game = Game::new();
+        let mut clock: Clock = Clock::unlimited();
+        while !game.is_over() {
+            game.step(&mut local, &mut remote, &mut clock).unwrap();
+        }
+        assert!(game.outcome().unwrap().reason == Reason::Finished);
+        assert!(!remote.accept_draw(&game, Color::Black));
+        drop(remote);
+        server.join().unwrap();
+
+        let f5: Othello = Othello::new().make_move(Color::Black, 1 << 37);
+        let played: (Othello, Color) = (f5, Color::White);
+        assert!(check_sync(played, (f5.make_move(Color::White, 1 << 45), Color::Black)).is_ok());
+        assert!(check_sync(played, (Othello::new(), Color::Black)).is_ok());
+        let d3: Othello = Othello::new().make_move(Color::Black, 1 << 19);
+        let desync: (Othello, Color) = (d3, Color::White);
+        match check_sync(played, desync) {
+            Err(OthelloError::ProtocolError(msg)) => {
+                assert!(msg.ends_with("squares: d3, d4, f5, f6.\n"))
+            },
+            _ => panic!("Expected a protocol error"),
+        }
+
+        let (mut a, mut b) = (RandomPlayer::new(3), RandomPlayer::new(3));
+        assert!(a.choose_move(&game, &clock) == b.choose_move(&game, &clock));
+        assert!(a.choose_move(&Game::new(), &clock) == b.choose_move(&Game::new(), &clock));
+    }
+}
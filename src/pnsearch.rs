@@ -0,0 +1,291 @@
+use std::str::FromStr;
+
+use crate::types::*;
+use crate::othello::*;
+use crate::endgame::*;
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                     PROOF-NUMBER SEARCH
+//
+//#################################################################################################
+
+/*
+ * The number of empty squares at or below which a new node is solved right away by the
+ * alpha-beta solver instead of being left for the proof-number search to expand.
+ */
+const LEAF_EMPTIES: u8 = 8;
+
+/*
+ * The proof or disproof number of a node that can't be proven or disproven.
+ */
+const INFINITE: u32 = u32::MAX;
+
+/*
+ * A node of the proof tree: its position and player to move, its parent, its children
+ * which are stored next to each other, their count being 0 while it isn't expanded, and its
+ * proof and disproof numbers.
+ */
+#[derive(Clone, Copy)]
+struct Node {
+    oth: Othello,
+    color: Color,
+    parent: u32,
+    children: u32,
+    count: u8,
+    proof: u32,
+    disproof: u32,
+}
+
+/*
+ * A proof-number search, proving or disproving that the attacker gets at least the threshold
+ * as final disc differential. The tree is grown best first, always expanding the most proving
+ * node, the one whose proof or disproof would change the numbers of the root the most, which
+ * finds forced wins with far fewer nodes than alpha-beta when the moves of the defender are
+ * few. The proof and disproof numbers of new nodes are initialized from their mobility.
+ */
+struct ProofSearch {
+    attacker: Color,
+    threshold: i32,
+    nodes: Vec<Node>,
+}
+
+impl ProofSearch {
+    /*
+     * Creates the search, its tree made of the root only.
+     */
+    fn new(oth: Othello, color: Color, attacker: Color, threshold: i32) -> ProofSearch {
+        let mut search: ProofSearch = ProofSearch { attacker, threshold, nodes: Vec::new(), };
+        let root: Node = search.node(oth, color, 0);
+        search.nodes.push(root);
+        search
+    }
+
+    /*
+     * Creates a new node with the given parent, solving it if the game is over or if few
+     * squares are left to fill.
+     */
+    fn node(&self, oth: Othello, color: Color, parent: u32) -> Node {
+        let mut node: Node = Node { oth, color, parent, children: 0, count: 0, proof: 1,
+            disproof: 1, };
+        let moves: u32 = oth.gen_moves(color).pop_cnt() as u32;
+        let opp_moves: u32 = oth.gen_moves(color.invert()).pop_cnt() as u32;
+
+        let proven: Option<bool> = if moves == 0 && opp_moves == 0 {
+            Some(final_score(oth, self.attacker) >= self.threshold)
+        } else if oth.empties() <= LEAF_EMPTIES {
+            Some(self.holds(oth, color))
+        } else {
+            None
+        };
+
+        match proven {
+            Some(true) => (node.proof, node.disproof) = (0, INFINITE),
+            Some(false) => (node.proof, node.disproof) = (INFINITE, 0),
+            None if color == self.attacker => node.disproof = moves.max(1),
+            None => node.proof = moves.max(1),
+        }
+        node
+    }
+
+    /*
+     * Solves the position with a null window search, telling whether the attacker reaches
+     * the threshold.
+     */
+    fn holds(&self, oth: Othello, color: Color) -> bool {
+        let t: i32 = self.threshold;
+        if color == self.attacker {
+            negamax(oth, color, t - 1, t, false) >= t
+        } else {
+            negamax(oth, color, -t, -t + 1, false) <= -t
+        }
+    }
+
+    /*
+     * Grows the tree until the root is proven or disproven, and returns which.
+     */
+    fn run(&mut self) -> bool {
+        while self.nodes[0].proof != 0 && self.nodes[0].disproof != 0 {
+            let leaf: usize = self.most_proving();
+            self.expand(leaf);
+            self.update(leaf);
+        }
+        self.nodes[0].proof == 0
+    }
+
+    /*
+     * Returns the most proving node, going down from the root through the child with the same
+     * proof number at the nodes of the attacker, and with the same disproof number at those
+     * of the defender.
+     */
+    fn most_proving(&self) -> usize {
+        let mut index: usize = 0;
+        loop {
+            let node: Node = self.nodes[index];
+            if node.count == 0 {
+                return index;
+            }
+            let children = node.children as usize..node.children as usize + node.count as usize;
+            index = if node.color == self.attacker {
+                children.clone().find(|&child| self.nodes[child].proof == node.proof)
+            } else {
+                children.clone().find(|&child| self.nodes[child].disproof == node.disproof)
+            }.unwrap_or(children.start);
+        }
+    }
+
+    /*
+     * Adds the children of a node to the tree, a single one for the position after the pass
+     * if the player to move has no legal move.
+     */
+    fn expand(&mut self, index: usize) {
+        let Node { oth, color, .. } = self.nodes[index];
+        let first: u32 = self.nodes.len() as u32;
+        let mut moves: BitBoard = oth.gen_moves(color);
+
+        if moves == 0 {
+            let child: Node = self.node(oth, color.invert(), index as u32);
+            self.nodes.push(child);
+        }
+        while moves != 0 {
+            let child: Node = self.node(oth.make_move(color, moves.pop_lsb()), color.invert(),
+                index as u32);
+            self.nodes.push(child);
+        }
+
+        self.nodes[index].children = first;
+        self.nodes[index].count = (self.nodes.len() as u32 - first) as u8;
+    }
+
+    /*
+     * Updates the proof and disproof numbers of the given node and of its ancestors, stopping
+     * as soon as they don't change. At the nodes of the attacker, the proof number is the
+     * smallest of the children and the disproof number their sum, and the other way around at
+     * those of the defender.
+     */
+    fn update(&mut self, mut index: usize) {
+        loop {
+            let node: Node = self.nodes[index];
+            let children: &[Node] = &self.nodes[node.children as usize..][..node.count as usize];
+            let min = |f: fn(&Node) -> u32| children.iter().map(f).min().unwrap_or(INFINITE);
+            let sum = |f: fn(&Node) -> u32| {
+                children.iter().map(f).fold(0, |acc: u32, n| acc.saturating_add(n))
+            };
+            let (proof, disproof): (u32, u32) = if node.color == self.attacker {
+                (min(|child| child.proof), sum(|child| child.disproof))
+            } else {
+                (sum(|child| child.proof), min(|child| child.disproof))
+            };
+
+            if (proof, disproof) == (node.proof, node.disproof) && index != 0 {
+                return;
+            }
+            self.nodes[index].proof = proof;
+            self.nodes[index].disproof = disproof;
+            if index == 0 {
+                return;
+            }
+            index = node.parent as usize;
+        }
+    }
+}
+
+/*
+ * Solves the position for a win, a draw or a loss of the given color with proof-number
+ * search, like solve_wld, returning 1, 0 or -1 accordingly. A first search tells whether he
+ * wins, and a second one whether he draws if he doesn't. The tree is kept in memory, a few
+ * dozen bytes per node.
+ */
+pub fn solve_wld_pn(oth: Othello, color: Color) -> i32 {
+    if ProofSearch::new(oth, color, color, 1).run() {
+        1
+    } else if ProofSearch::new(oth, color, color, 0).run() {
+        0
+    } else {
+        -1
+    }
+}
+
+//#################################################################################################
+//
+//                                       WLD BACKENDS
+//
+//#################################################################################################
+
+/*
+ * The solvers a position can be solved for a win, a draw or a loss with: the alpha-beta
+ * solver with a null window, or proof-number search, often faster on forced wins.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WldBackend {
+    AlphaBeta,
+    ProofNumber,
+}
+
+impl WldBackend {
+    /*
+     * Solves the position with the backend, see solve_wld.
+     */
+    pub fn solve(self, oth: Othello, color: Color) -> i32 {
+        match self {
+            WldBackend::AlphaBeta => solve_wld(oth, color),
+            WldBackend::ProofNumber => solve_wld_pn(oth, color),
+        }
+    }
+
+    /*
+     * Returns the short name of the backend, as parsed by from_str.
+     */
+    pub fn name(self) -> &'static str {
+        match self {
+            WldBackend::AlphaBeta => "alphabeta",
+            WldBackend::ProofNumber => "pn",
+        }
+    }
+}
+
+impl FromStr for WldBackend {
+    type Err = OthelloError;
+
+    fn from_str(s: &str) -> Result<WldBackend, OthelloError> {
+        match s {
+            "alphabeta" | "ab" => Ok(WldBackend::AlphaBeta),
+            "pn" | "proof-number" => Ok(WldBackend::ProofNumber),
+            _ => Err(parse_error(format!("Unknown WLD backend {}, expected alphabeta or pn.", s))),
+        }
+    }
+}
+
+//#################################################################################################
+//
+//                                    PROOF-NUMBER TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that proof-number search agrees with the alpha-beta solver on the first positions of
+ * the test suite and on the positions after their moves, among which are wins, draws and
+ * losses.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsuite::*;
+
+    #[test]
+    fn agrees_with_alphabeta() {
+        for position in positions().iter().take(4) {
+            let (oth, color) = (position.oth, position.color);
+            assert_eq!(solve_wld_pn(oth, color), (position.score as i32).signum());
+
+            let mut moves: BitBoard = oth.gen_moves(color);
+            while moves != 0 {
+                let next: Othello = oth.make_move(color, moves.pop_lsb());
+                assert_eq!(solve_wld_pn(next, !color), solve_wld(next, !color));
+            }
+        }
+        assert_eq!("pn".parse::<WldBackend>().unwrap(), WldBackend::ProofNumber);
+        assert!("mcts".parse::<WldBackend>().is_err());
+    }
+}
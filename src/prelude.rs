@@ -0,0 +1,25 @@
+/*
+ * The types most users of the crate need, for a one-line import: `use othello::prelude::*;`.
+ * Moves are single-bit BitBoards, passes being 0, searched by the Engine, the alpha-beta AI, as
+ * deep as its depth and as long as its clock allows. Only the board logic is there without the
+ * standard library.
+ */
+
+pub use crate::types::{BitBoard, BitBoardTrait, Color, Square, Score};
+pub use crate::coord::Coord;
+pub use crate::othello::Othello;
+pub use crate::phase::Phase;
+#[cfg(feature = "std")]
+pub use crate::error::OthelloError;
+#[cfg(feature = "std")]
+pub use crate::game::{Game, Outcome, Reason};
+#[cfg(feature = "std")]
+pub use crate::player::{Action, Clock, Player};
+#[cfg(feature = "alphabeta")]
+pub use crate::alphabeta::{AlphaBetaPlayer, Hint, TimeManager};
+#[cfg(feature = "std")]
+pub use crate::eval::Value;
+#[cfg(feature = "std")]
+pub use crate::record::GameRecord;
+#[cfg(feature = "alphabeta")]
+pub use crate::config::{Config, Engine, EngineBuilder};
@@ -0,0 +1,398 @@
+use std::path::Path;
+
+use crate::types::*;
+use crate::othello::*;
+use crate::game::*;
+use crate::pnsearch::*;
+use crate::executor::*;
+use crate::mapped::*;
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                       PROOF TREE
+//
+//#################################################################################################
+
+/*
+ * A deviation from a line, checked by the solver: the number of moves of the line played
+ * before it, the move played instead, and the outcome for Black of the position it leads to
+ * with perfect play, 1 for a win, 0 for a draw and -1 for a loss, like solve_wld.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Deviation {
+    pub ply: usize,
+    pub mv: BitBoard,
+    pub outcome: i8,
+}
+
+/*
+ * The proof that the position after the first moves of a line, start of them, has the
+ * claimed outcome for Black with perfect play, the rest of the line being the perfect play:
+ * the outcome at its end, and every deviation the player whom the claim doesn't suit could
+ * try along the rest of the line, each of which must do no better for him. If Black is
+ * claimed to win, only the deviations of White matter, if he is claimed to lose, only his own,
+ * and both for a draw.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct ProofTree {
+    pub line: Vec<BitBoard>,
+    pub start: usize,
+    pub claim: i8,
+    pub end: i8,
+    pub deviations: Vec<Deviation>,
+}
+
+impl ProofTree {
+    /*
+     * Returns true if the proof holds: the line ends with the claimed outcome, and no
+     * deviation does better than the claim for the player who made it.
+     */
+    pub fn holds(&self) -> bool {
+        self.end == self.claim && self.refutations().is_empty()
+    }
+
+    /*
+     * Returns the deviations refuting the claim.
+     */
+    pub fn refutations(&self) -> Vec<Deviation> {
+        let mut game: Game = Game::new();
+        let mut refutations: Vec<Deviation> = Vec::new();
+        let mut deviations: std::iter::Peekable<std::slice::Iter<Deviation>> =
+            self.deviations.iter().peekable();
+
+        for (ply, &mv) in self.line.iter().enumerate() {
+            let sign: i8 = if game.color() == Color::Black { 1 } else { -1 };
+            while let Some(deviation) = deviations.next_if(|deviation| deviation.ply == ply) {
+                if deviation.outcome * sign > self.claim * sign {
+                    refutations.push(*deviation);
+                }
+            }
+            if game.play(mv).is_err() {
+                break;
+            }
+        }
+        refutations
+    }
+
+    /*
+     * Writes the proof as text, one deviation per line: the number of moves of the line
+     * before it, the move and its outcome for Black, after a header with the line, the number
+     * of its moves the claim is made after, the claim and the outcome at its end.
+     */
+    pub fn to_text(&self) -> String {
+        let line: String = self.line.iter().map(|mv| mv.square_name()).collect();
+        let mut text: String = format!("line {}\nstart {}\nclaim {}\nend {}\n", line,
+            self.start, self.claim, self.end);
+        for deviation in &self.deviations {
+            text += &format!("{} {} {}\n", deviation.ply, deviation.mv.square_name(),
+                deviation.outcome);
+        }
+        text
+    }
+
+    /*
+     * Writes the proof to the given file, in the format described with MAGIC.
+     */
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), OthelloError> {
+        write_file(path, MAGIC, VERSION, &self.to_bytes())
+    }
+
+    /*
+     * Reads a proof written by save. The file is only checked to be well formed, not the
+     * proof, see check_proof.
+     */
+    pub fn open(path: impl AsRef<Path>) -> Result<ProofTree, OthelloError> {
+        let file: MappedFile = MappedFile::open(path, MAGIC, VERSION)?;
+        ProofTree::from_bytes(file.payload())
+    }
+
+    /*
+     * Encodes the proof as the payload of a proof file.
+     */
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        bytes.extend_from_slice(&(self.start as u16).to_le_bytes());
+        bytes.extend_from_slice(&[self.claim as u8, 0]);
+
+        let mut game: Game = Game::new();
+        let mut record = |game: &Game, square: u8, bound: i8| {
+            bytes.extend_from_slice(&position_hash(game.board(), game.color()).to_le_bytes());
+            bytes.extend_from_slice(&[square, bound as u8]);
+        };
+        let mut deviations: std::iter::Peekable<std::slice::Iter<Deviation>> =
+            self.deviations.iter().peekable();
+        for (ply, &mv) in self.line.iter().enumerate() {
+            while let Some(deviation) = deviations.next_if(|deviation| deviation.ply == ply) {
+                record(&game, deviation.mv.trailing_zeros() as u8, deviation.outcome);
+            }
+            record(&game, LINE_MOVE | mv.trailing_zeros() as u8, self.claim);
+            if game.play(mv).is_err() {
+                break;
+            }
+        }
+        record(&game, END, self.end);
+        bytes
+    }
+
+    /*
+     * Decodes the payload of a proof file. Only the moves of the line are checked to be
+     * legal, the hashes being checked by check_proof.
+     */
+    pub fn from_bytes(bytes: &[u8]) -> Result<ProofTree, OthelloError> {
+        let invalid = || parse_error("Invalid proof file.");
+        if bytes.len() < 4 || !(bytes.len() - 4).is_multiple_of(RECORD_SIZE) {
+            return Err(invalid());
+        }
+        let start: usize = u16::from_le_bytes([bytes[0], bytes[1]]) as usize;
+        let mut tree: ProofTree = ProofTree {
+            line: Vec::new(), start, claim: bytes[2] as i8, end: 0, deviations: Vec::new(),
+        };
+
+        for (i, record) in bytes[4..].chunks(RECORD_SIZE).enumerate() {
+            let (square, bound): (u8, i8) = (record[8], record[9] as i8);
+            let last: bool = i == (bytes.len() - 4) / RECORD_SIZE - 1;
+            if !(-1..=1).contains(&bound) || (square == END) != last {
+                return Err(invalid());
+            }
+            match square {
+                END => tree.end = bound,
+                _ if square & LINE_MOVE != 0 && square & !LINE_MOVE < 64 => {
+                    tree.line.push(1 << (square & !LINE_MOVE));
+                },
+                _ if square < 64 => tree.deviations.push(Deviation {
+                    ply: tree.line.len(), mv: 1 << square, outcome: bound,
+                }),
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(tree)
+    }
+}
+
+/*
+ * The magic number and version of proof files. Their payload starts with the number of moves
+ * of the line after which the outcome is claimed, as a little-endian u16, the claim, as an i8,
+ * and a reserved zero byte. Then come records of RECORD_SIZE bytes, one for each move played
+ * from a position of the line, the moves of the line and their deviations, in the order of
+ * the line, the deviations first at each position, and one for the end of the line:
+ *   - the hash of the position the move is played from, see position_hash, as a u64,
+ *   - the square of the move, with LINE_MOVE set for the moves of the line, or END,
+ *   - the outcome for Black after the move, the claim for the moves of the line, and the
+ *     outcome of the position itself for the end.
+ */
+const MAGIC: &[u8; 8] = b"OTHPROOF";
+const VERSION: u32 = 1;
+const RECORD_SIZE: usize = 10;
+const LINE_MOVE: u8 = 0x80;
+const END: u8 = 64;
+
+/*
+ * Returns the hash of the position with the player to move stored in proof files, the FNV-1a
+ * hash of its BitBoards, Black's first, as little-endian u64, and of the color, 0 for Black.
+ */
+pub fn position_hash(oth: Othello, color: Color) -> u64 {
+    let mut bytes: [u8; 17] = [0; 17];
+    bytes[..8].copy_from_slice(&oth.get_bitboard(Color::Black).to_le_bytes());
+    bytes[8..16].copy_from_slice(&oth.get_bitboard(Color::White).to_le_bytes());
+    bytes[16] = color as u8;
+    checksum(&bytes)
+}
+
+//#################################################################################################
+//
+//                                     LINE VERIFICATION
+//
+//#################################################################################################
+
+/*
+ * Verifies that the position after the first start moves of the line has the claimed outcome
+ * for Black with perfect play, 1 for a win, 0 for a draw and -1 for a loss, the rest of the
+ * line being claimed perfect. The position at its end is solved, with every deviation along
+ * the rest of the line which could do better for the player making it, by the given solver.
+ * The positions are solved in parallel with the executor, and must have few enough empty
+ * squares to be solvable, the start of the line being the part already proven or assumed.
+ * Returns the proof tree, which tells whether the claim holds and which deviations refute it
+ * otherwise.
+ */
+pub fn verify_line(
+    executor: &dyn Executor, line: &str, start: usize, claim: i8, backend: WldBackend,
+) -> Result<ProofTree, OthelloError> {
+    let mut game: Game = Game::new();
+    let mut positions: Vec<(usize, BitBoard, Othello, Color)> = Vec::new();
+    let moves: Vec<BitBoard> = parse_line(line)?;
+
+    for (ply, &mv) in moves.iter().enumerate() {
+        let (oth, color) = (game.board(), game.color());
+        let sign: i8 = if color == Color::Black { 1 } else { -1 };
+        // The mover can't do better than a claimed win for him.
+        if ply >= start && claim * sign < 1 {
+            let mut others: BitBoard = game.moves() & !mv;
+            while others != 0 {
+                let other: BitBoard = others.pop_lsb();
+                positions.push((ply, other, oth.make_move(color, other), !color));
+            }
+        }
+        game.play(mv)?;
+    }
+    positions.push((moves.len(), 0, game.board(), game.color()));
+
+    let mut outcomes: Vec<i8> = map(executor, positions.clone(), |(_, _, oth, color)| {
+        let outcome: i32 = backend.solve(oth, color);
+        (if color == Color::Black { outcome } else { -outcome }) as i8
+    });
+    let end: i8 = outcomes.pop().unwrap_or(0);
+
+    Ok(ProofTree {
+        line: moves,
+        start,
+        claim,
+        end,
+        deviations: positions.iter().zip(outcomes)
+            .map(|(&(ply, mv, _, _), outcome)| Deviation { ply, mv, outcome, })
+            .collect(),
+    })
+}
+
+//#################################################################################################
+//
+//                                      PROOF CHECK
+//
+//#################################################################################################
+
+/*
+ * Checks a proof file independently from the search that wrote it: replays its line from the
+ * starting position, checking the hash of every position, that every move is legal, that
+ * every deviation the claim depends on is there and that none refutes it, then solves again
+ * the end of the line and every deviation with the given solver, in parallel with the
+ * executor. Returns the number of records checked, or an error telling the first one found
+ * wrong.
+ */
+pub fn check_proof(
+    executor: &dyn Executor, path: impl AsRef<Path>, backend: WldBackend,
+) -> Result<usize, OthelloError> {
+    let file: MappedFile = MappedFile::open(path, MAGIC, VERSION)?;
+    let tree: ProofTree = ProofTree::from_bytes(file.payload())?;
+    let wrong = |i: usize, what: &str| parse_error(format!("Record {}: {}.", i + 1, what));
+
+    let mut game: Game = Game::new();
+    let (mut ply, mut deviated): (usize, BitBoard) = (0, 0);
+    let mut positions: Vec<(usize, Othello, Color, i8)> = Vec::new();
+    let records: std::slice::Chunks<u8> = file.payload()[4..].chunks(RECORD_SIZE);
+    let count: usize = records.len();
+
+    for (i, record) in records.enumerate() {
+        let (oth, color) = (game.board(), game.color());
+        let mut hash: [u8; 8] = [0; 8];
+        hash.copy_from_slice(&record[..8]);
+        if u64::from_le_bytes(hash) != position_hash(oth, color) {
+            return Err(wrong(i, "the position doesn't match its hash"));
+        }
+
+        let (square, bound): (u8, i8) = (record[8], record[9] as i8);
+        let sign: i8 = if color == Color::Black { 1 } else { -1 };
+        let needed: bool = ply >= tree.start && tree.claim * sign < 1;
+        if square == END {
+            if bound != tree.claim {
+                return Err(wrong(i, "the line doesn't end with the claimed outcome"));
+            }
+            positions.push((i, oth, color, bound));
+        } else if square & LINE_MOVE != 0 {
+            let mv: BitBoard = 1 << (square & !LINE_MOVE);
+            if needed && deviated != game.moves() & !mv {
+                return Err(wrong(i, "deviations are missing before this move"));
+            }
+            game.play(mv).map_err(|_| wrong(i, "the move of the line is illegal"))?;
+            (ply, deviated) = (ply + 1, 0);
+        } else {
+            let mv: BitBoard = 1 << square;
+            if mv & game.moves() == 0 || deviated & mv != 0 {
+                return Err(wrong(i, "the deviation is illegal or repeated"));
+            } else if bound * sign > tree.claim * sign {
+                return Err(wrong(i, "the deviation refutes the claim"));
+            }
+            deviated |= mv;
+            positions.push((i, oth.make_move(color, mv), !color, bound));
+        }
+    }
+
+    let outcomes: Vec<i8> = map(executor, positions.clone(), |(_, oth, color, _)| {
+        let outcome: i32 = backend.solve(oth, color);
+        (if color == Color::Black { outcome } else { -outcome }) as i8
+    });
+    for (&(i, _, _, bound), outcome) in positions.iter().zip(outcomes) {
+        if outcome != bound {
+            return Err(wrong(i, "the solver finds another outcome"));
+        }
+    }
+    Ok(count)
+}
+
+//#################################################################################################
+//
+//                                       PROOF TEST
+//
+//#################################################################################################
+
+/*
+ * Checks the proof of a line played until 12 squares are left, for its true outcome and for
+ * the other ones.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endgame::*;
+
+    #[test]
+    fn verify() {
+        let mut game: Game = Game::new();
+        let mut line: String = String::new();
+        while game.board().empties() > 14 {
+            let mv: BitBoard = game.moves() & game.moves().wrapping_neg();
+            line += &mv.square_name();
+            game.play(mv).unwrap();
+        }
+        let (start, color): (usize, Color) = (parse_line(&line).unwrap().len(), game.color());
+        let outcome: i32 = solve_wld(game.board(), color);
+        let outcome: i8 = (if color == Color::Black { outcome } else { -outcome }) as i8;
+
+        // The rest of the line is played perfectly.
+        while !game.is_over() {
+            let (oth, color) = (game.board(), game.color());
+            let mut moves: BitBoard = game.moves();
+            let mut best: (i32, BitBoard) = (-65, 0);
+            while moves != 0 {
+                let mv: BitBoard = moves.pop_lsb();
+                best = best.max((-solve(oth.make_move(color, mv), !color), mv));
+            }
+            line += &best.1.square_name();
+            game.play(best.1).unwrap();
+        }
+
+        for claim in -1..=1 {
+            let tree: ProofTree = verify_line(&Inline, &line, start, claim,
+                WldBackend::ProofNumber).unwrap();
+            assert_eq!(tree.end, outcome);
+            assert_eq!(tree.holds(), claim == outcome);
+            assert!(tree.deviations.iter().all(|deviation| deviation.ply >= start));
+        }
+        assert!(verify_line(&Inline, "f5f5", 0, 0, WldBackend::AlphaBeta).is_err());
+
+        let path: std::path::PathBuf = std::env::temp_dir().join("othello-test-proof.bin");
+        let mut tree: ProofTree = verify_line(&Inline, &line, start, outcome,
+            WldBackend::AlphaBeta).unwrap();
+        tree.save(&path).unwrap();
+        assert_eq!(ProofTree::open(&path).unwrap(), tree);
+        let records: usize = tree.line.len() + tree.deviations.len() + 1;
+        assert_eq!(check_proof(&Inline, &path, WldBackend::ProofNumber).unwrap(), records);
+
+        // A wrong outcome, or a missing deviation, is caught.
+        tree.deviations[0].outcome = (tree.deviations[0].outcome + 2) % 3 - 1;
+        tree.save(&path).unwrap();
+        assert!(check_proof(&Inline, &path, WldBackend::AlphaBeta).is_err());
+        tree.deviations.remove(0);
+        tree.save(&path).unwrap();
+        assert!(check_proof(&Inline, &path, WldBackend::AlphaBeta).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
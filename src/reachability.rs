@@ -0,0 +1,206 @@
+use std::collections::HashSet;
+
+use crate::types::*;
+use crate::othello::*;
+use crate::features::*;
+
+//#################################################################################################
+//
+//                                      REACHABILITY
+//
+//#################################################################################################
+
+/*
+ * The most positions the exhaustive search of is_reachable visits before giving up.
+ */
+const MAX_NODES: usize = 1 << 18;
+
+/*
+ * The four squares of the center, filled from the start and never emptied.
+ */
+const CENTER: BitBoard = 0x0000001818000000;
+
+/*
+ * Whether a position can arise in a game from the starting position: it can, and is reached
+ * by the given line of moves, passes being implied, or it can't, for the given reason, or the
+ * search gave up before finding out, no obvious reason ruling it out.
+ */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Reachability {
+    Reachable(Vec<BitBoard>),
+    Unreachable(&'static str),
+    Unknown,
+}
+
+impl std::fmt::Display for Reachability {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Reachability::Reachable(line) => {
+                let line: String = line.iter().map(|mv| mv.square_name()).collect();
+                write!(f, "reachable, by {}", if line.is_empty() { "no move" } else { &line })
+            },
+            Reachability::Unreachable(reason) => write!(f, "unreachable: {}", reason),
+            Reachability::Unknown => write!(f, "unknown, nothing obviously rules it out"),
+        }
+    }
+}
+
+/*
+ * Returns the squares next to the given ones, them included.
+ */
+fn dilate(board: BitBoard) -> BitBoard {
+    let row: BitBoard = board | (board & 0x7F7F7F7F7F7F7F7F) << 1
+        | (board & 0xFEFEFEFEFEFEFEFE) >> 1;
+    row | row << 8 | row >> 8
+}
+
+/*
+ * The exhaustive search for a line of moves leading to the target, playing only on its filled
+ * squares and never past a position whose stable disks have the wrong color.
+ */
+struct Search {
+    target: Othello,
+    filled: BitBoard,
+    visited: HashSet<(Othello, Color)>,
+    line: Vec<BitBoard>,
+}
+
+impl Search {
+    /*
+     * Returns true if the target is reached from the position, the line holding the moves.
+     */
+    fn run(&mut self, oth: Othello, color: Color) -> bool {
+        if oth == self.target {
+            return true;
+        }
+        if self.visited.len() >= MAX_NODES || !self.visited.insert((oth, color)) {
+            return false;
+        }
+        let wrong = |color: Color| stable(oth, color) & !self.target.get_bitboard(color) != 0;
+        if wrong(Color::Black) || wrong(Color::White) {
+            return false;
+        }
+
+        let moves: BitBoard = oth.gen_moves(color);
+        if moves == 0 {
+            return oth.gen_moves(!color) != 0 && self.run(oth, !color);
+        }
+        let mut moves: BitBoard = moves & self.filled;
+        while moves != 0 {
+            let mv: BitBoard = moves.pop_lsb();
+            self.line.push(mv);
+            if self.run(oth.make_move(color, mv), !color) {
+                return true;
+            }
+            self.line.pop();
+        }
+        false
+    }
+}
+
+/*
+ * Tells whether the position can arise in a game from the starting position, without
+ * handicap, to catch the impossible positions among those made by users. The obvious
+ * impossibilities are looked for first: squares of both colors, an empty square in the
+ * center, or disks not connected to the center, each move being made next to a disk. Then a
+ * line of moves leading to the position is searched for exhaustively, which proves or
+ * disproves it when few disks are on the board, but usually gives up once there are more
+ * than about twenty.
+ */
+pub fn is_reachable(oth: &Othello) -> Reachability {
+    let (black, white): (BitBoard, BitBoard) =
+        (oth.get_bitboard(Color::Black), oth.get_bitboard(Color::White));
+    let filled: BitBoard = black | white;
+    if black & white != 0 {
+        return Reachability::Unreachable("some squares hold disks of both colors");
+    }
+    if filled & CENTER != CENTER {
+        return Reachability::Unreachable("a square of the center is empty");
+    }
+
+    let mut connected: BitBoard = CENTER;
+    loop {
+        let grown: BitBoard = dilate(connected) & filled;
+        if grown == connected {
+            break;
+        }
+        connected = grown;
+    }
+    if connected != filled {
+        return Reachability::Unreachable("some disks aren't connected to the center");
+    }
+
+    let mut search: Search = Search { target: *oth, filled, visited: HashSet::new(),
+        line: Vec::new(), };
+    if search.run(Othello::new(), Color::Black) {
+        Reachability::Reachable(search.line)
+    } else if search.visited.len() >= MAX_NODES {
+        Reachability::Unknown
+    } else {
+        Reachability::Unreachable("no line of moves leads to it")
+    }
+}
+
+//#################################################################################################
+//
+//                                   REACHABILITY TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that positions of a game are found reachable by a line leading to them, and that
+ * impossible positions are found unreachable for the right reason.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::*;
+
+    #[test]
+    fn reachability() {
+        assert_eq!(is_reachable(&Othello::new()), Reachability::Reachable(Vec::new()));
+        let game: Game = Game::from_line("f5d6c3d3c4f4f6").unwrap();
+        match is_reachable(&game.board()) {
+            Reachability::Reachable(line) => {
+                let line: String = line.iter().map(|mv| mv.square_name()).collect();
+                assert!(Game::from_line(&line).unwrap().board() == game.board());
+            },
+            other => panic!("Expected a line, got {}", other),
+        }
+
+        let unreachable = |art: &str| match is_reachable(&Othello::from_ascii(art)) {
+            Reachability::Unreachable(reason) => reason,
+            other => panic!("Expected an unreachable position, got {}", other),
+        };
+        assert_eq!(unreachable("
+            X - - - - - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - O X - - -
+            - - - X O - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - - - - - -
+        "), "some disks aren't connected to the center");
+        assert_eq!(unreachable("
+            - - - - - - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - - X - - -
+            - - - X O - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - - - - - -
+        "), "a square of the center is empty");
+        assert_eq!(unreachable("
+            - - - - - - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - X X - - -
+            - - - X X - - -
+            - - - - - - - -
+            - - - - - - - -
+            - - - - - - - -
+        "), "no line of moves leads to it");
+    }
+}
@@ -0,0 +1,535 @@
+use std::time::Duration;
+
+use crate::types::*;
+use crate::game::*;
+use crate::online::{Json, parse_json};
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                    GAME RECORD TYPE
+//
+//#################################################################################################
+
+/*
+ * What is known of how a move was chosen: the time the player took, the depth and the nodes
+ * of his search, and his evaluation after it, from his own point of view, None being unknown.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct MoveStats {
+    pub time: Option<Duration>,
+    pub depth: Option<u8>,
+    pub nodes: Option<u64>,
+    pub score: Option<i32>,
+}
+
+/*
+ * A record of a game, with the names of the players, the date, the outcome (None for an
+ * unfinished game), the number of corners given to Black as a handicap and every ply played,
+ * passes being empty BitBoards. The stats of the moves, one per ply, are empty unless they were
+ * recorded, by a Match for instance.
+ */
+#[derive(Clone)]
+pub struct GameRecord {
+    pub black: String,
+    pub white: String,
+    pub date: String,
+    pub outcome: Option<Outcome>,
+    pub handicap: u8,
+    pub moves: Vec<BitBoard>,
+    pub stats: Vec<MoveStats>,
+}
+
+impl GameRecord {
+    /*
+     * Creates a record of the given game.
+     */
+    pub fn from_game(game: &Game, black: &str, white: &str, date: &str) -> GameRecord {
+        GameRecord {
+            black: black.to_string(),
+            white: white.to_string(),
+            date: date.to_string(),
+            outcome: game.outcome(),
+            handicap: game.handicap(),
+            moves: game.history().to_vec(),
+            stats: Vec::new(),
+        }
+    }
+
+    /*
+     * Gives the stats of the moves of the record, one per ply.
+     */
+    pub fn with_stats(mut self, stats: Vec<MoveStats>) -> GameRecord {
+        self.stats = stats;
+        self
+    }
+
+    /*
+     * Replays the recorded moves from the starting position, with its handicap if any, and
+     * returns the resulting game,
+     * or an error if one of them is illegal. Omitted passes are tolerated. If the recorded
+     * game ended early, by resignation for example, the outcome is applied to the game.
+     */
+    pub fn to_game(&self) -> Result<Game, OthelloError> {
+        let mut game: Game = Game::with_handicap(self.handicap);
+        let mut plies: usize = 0;
+
+        for (i, &mv) in self.moves.iter().enumerate() {
+            if mv == 0 {
+                if game.history().get(plies) != Some(&0) {
+                    return Err(parse_error(format!("Illegal pass at ply {}.", i + 1)));
+                }
+                plies += 1;
+            } else {
+                if game.is_over() || mv.pop_cnt() != 1 || mv & game.moves() == 0 {
+                    let name: String = mv.square_name();
+                    return Err(parse_error(format!("Illegal move {} at ply {}.", name, i + 1)));
+                }
+                plies = game.history().len() + 1;
+                game.play(mv)?;
+            }
+        }
+
+        if let Some(outcome) = self.outcome {
+            match outcome.reason {
+                Reason::Finished => (),
+                Reason::Resignation => match outcome.winner {
+                    Some(winner) => game.resign(winner.invert()),
+                    None => return Err(parse_error("A resigned game must have a winner.")),
+                },
+                Reason::Agreement => {
+                    game.offer_draw(game.color().invert());
+                    game.accept_draw(game.color());
+                },
+                Reason::Adjudication => game.adjudicate(outcome.winner),
+                reason => match outcome.winner {
+                    Some(winner) => game.forfeit(winner.invert(), reason),
+                    None => return Err(parse_error("A forfeited game must have a winner.")),
+                },
+            }
+        }
+
+        Ok(game)
+    }
+
+//#################################################################################################
+//
+//                                      TEXT FORMAT
+//
+//#################################################################################################
+
+    /*
+     * Writes the record in a PGN-like text format: a few headers followed by the numbered
+     * moves, one black ply and one white ply per move number, and the result. Results are
+     * "1-0" when black wins, "0-1" when white wins, "1/2-1/2" for a draw and "*" for an
+     * unfinished game. For instance:
+     *
+     * [Black "Alice"]
+     * [White "Bob"]
+     * [Date "2020.05.17"]
+     * [Result "0-1"]
+     * [Termination "resignation"]
+     *
+     * 1. f5 d6 2. c3 d3 3. c4 f4 0-1
+     */
+    pub fn to_text(&self) -> String {
+        let mut text: String = String::new();
+        let result: &str = result_token(self.outcome);
+
+        text.push_str(&format!("[Black \"{}\"]\n", self.black));
+        text.push_str(&format!("[White \"{}\"]\n", self.white));
+        text.push_str(&format!("[Date \"{}\"]\n", self.date));
+        text.push_str(&format!("[Result \"{}\"]\n", result));
+        if let Some(termination) = self.outcome.and_then(|outcome| termination(outcome.reason)) {
+            text.push_str(&format!("[Termination \"{}\"]\n", termination));
+        }
+        if self.handicap > 0 {
+            text.push_str(&format!("[Handicap \"{}\"]\n", self.handicap));
+        }
+        text.push('\n');
+
+        // Lines are wrapped at 80 characters, like in PGN.
+        let mut line: String = String::new();
+        let mut tokens: Vec<String> = Vec::new();
+        for (i, &mv) in self.moves.iter().enumerate() {
+            if i % 2 == 0 {
+                tokens.push(format!("{}.", i / 2 + 1));
+            }
+            tokens.push(if mv == 0 { "pass".to_string() } else { mv.square_name() });
+        }
+        tokens.push(result.to_string());
+
+        for token in tokens {
+            if !line.is_empty() && line.len() + token.len() >= 80 {
+                text.push_str(&line);
+                text.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        text.push_str(&line);
+        text.push('\n');
+
+        text
+    }
+
+    /*
+     * Parses a record written in the text format described above. Unknown headers are ignored,
+     * and so are move numbers, which are not checked.
+     */
+    pub fn from_text(text: &str) -> Result<GameRecord, OthelloError> {
+        let mut record: GameRecord = GameRecord {
+            black: String::new(),
+            white: String::new(),
+            date: String::new(),
+            outcome: None,
+            handicap: 0,
+            moves: Vec::new(),
+            stats: Vec::new(),
+        };
+        let mut winner: Option<Option<Color>> = None;
+        let mut reason: Reason = Reason::Finished;
+        let mut lines = text.lines().map(str::trim).peekable();
+
+        while let Some(line) = lines.next_if(|line| line.is_empty() || line.starts_with('[')) {
+            if line.is_empty() {
+                continue;
+            }
+
+            let (tag, value) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+                .and_then(|line| line.split_once(' '))
+                .and_then(|(tag, value)| Some((tag, value.strip_prefix('"')?.strip_suffix('"')?)))
+                .ok_or_else(|| parse_error(format!("Malformed header: {}", line)))?;
+
+            match tag {
+                "Black" => record.black = value.to_string(),
+                "White" => record.white = value.to_string(),
+                "Date" => record.date = value.to_string(),
+                "Result" => winner = parse_result(value)?,
+                "Termination" => reason = parse_termination(value)?,
+                "Handicap" => {
+                    record.handicap = value.parse().ok().filter(|&n| n <= 4)
+                        .ok_or_else(|| parse_error(format!("Invalid handicap: {}", value)))?;
+                },
+                _ => (),
+            }
+        }
+
+        for token in lines.flat_map(str::split_whitespace) {
+            if token.ends_with('.') && token[..token.len() - 1].parse::<u32>().is_ok() {
+                continue;
+            } else if token == "pass" {
+                record.moves.push(0);
+            } else if let Some(mv) = parse_square(token) {
+                record.moves.push(mv);
+            } else if parse_result(token).is_ok() {
+                break;
+            } else {
+                return Err(parse_error(format!("Unexpected token: {}", token)));
+            }
+        }
+
+        record.outcome = winner.map(|winner| Outcome { winner, reason, });
+
+        Ok(record)
+    }
+}
+
+//#################################################################################################
+//
+//                                      JSON FORMAT
+//
+//#################################################################################################
+
+impl GameRecord {
+    /*
+     * Writes the record as JSON, with the stats of each move, null when unknown, times being
+     * in seconds. For instance:
+     *
+     * {"black":"Alice","white":"Bob","date":"2020.05.17","result":"0-1",
+     * "termination":"resignation","handicap":0,"moves":[{"move":"f5","time":1.25,"depth":12,
+     * "nodes":48213,"score":-3},{"move":"d6","time":null,"depth":null,"nodes":null,
+     * "score":null}]}
+     */
+    pub fn to_json(&self) -> String {
+        let null = |value: Option<String>| value.unwrap_or_else(|| "null".to_string());
+        let moves: Vec<String> = self.moves.iter().enumerate().map(|(i, &mv)| {
+            let stats: MoveStats = self.stats.get(i).copied().unwrap_or_default();
+            format!("{{\"move\":{},\"time\":{},\"depth\":{},\"nodes\":{},\"score\":{}}}",
+                json_string(&if mv == 0 { "pass".to_string() } else { mv.square_name() }),
+                null(stats.time.map(|time| format!("{:.3}", time.as_secs_f64()))),
+                null(stats.depth.map(|depth| depth.to_string())),
+                null(stats.nodes.map(|nodes| nodes.to_string())),
+                null(stats.score.map(|score| score.to_string())))
+        }).collect();
+        let termination: Option<&str> =
+            self.outcome.and_then(|outcome| termination(outcome.reason));
+
+        format!("{{\"black\":{},\"white\":{},\"date\":{},\"result\":\"{}\",\"termination\":{},\
+            \"handicap\":{},\"moves\":[{}]}}",
+            json_string(&self.black), json_string(&self.white), json_string(&self.date),
+            result_token(self.outcome), null(termination.map(json_string)), self.handicap,
+            moves.join(","))
+    }
+}
+
+impl GameRecord {
+    /*
+     * Reads a record written by to_json. Missing stats are unknown, and so are unknown fields
+     * ignored.
+     */
+    pub fn from_json(text: &str) -> Result<GameRecord, OthelloError> {
+        let fields: Vec<(String, Json)> = match parse_json(text)? {
+            Json::Object(fields) => fields,
+            _ => return Err(parse_error("A JSON record must be an object.")),
+        };
+        let mut record: GameRecord = GameRecord {
+            black: String::new(),
+            white: String::new(),
+            date: String::new(),
+            outcome: None,
+            handicap: 0,
+            moves: Vec::new(),
+            stats: Vec::new(),
+        };
+        let (mut winner, mut reason): (Option<Option<Color>>, Reason) = (None, Reason::Finished);
+
+        for (key, value) in fields {
+            let text: String = value.text().unwrap_or_default();
+            match (key.as_str(), value) {
+                ("black", _) => record.black = text,
+                ("white", _) => record.white = text,
+                ("date", _) => record.date = text,
+                ("result", _) => winner = parse_result(&text)?,
+                ("termination", Json::String(name)) => reason = parse_termination(&name)?,
+                ("handicap", _) => {
+                    record.handicap = text.parse().ok().filter(|&n| n <= 4)
+                        .ok_or_else(|| parse_error(format!("Invalid handicap: {}", text)))?;
+                },
+                ("moves", Json::Array(moves)) => for mv in moves {
+                    let (mv, stats): (BitBoard, MoveStats) = parse_json_move(mv)?;
+                    record.moves.push(mv);
+                    record.stats.push(stats);
+                },
+                _ => (),
+            }
+        }
+
+        record.outcome = winner.map(|winner| Outcome { winner, reason, });
+        Ok(record)
+    }
+}
+
+/*
+ * Reads a move of a JSON record and its stats.
+ */
+fn parse_json_move(mv: Json) -> Result<(BitBoard, MoveStats), OthelloError> {
+    let Json::Object(fields) = mv else {
+        return Err(parse_error("A JSON move must be an object."));
+    };
+    let mut stats: MoveStats = MoveStats::default();
+    let mut square: Option<BitBoard> = None;
+
+    for (key, value) in fields {
+        let text: Option<String> = value.text().filter(|text| !text.is_empty());
+        let invalid = || parse_error(format!("Invalid {} of a JSON move.", key));
+        match (key.as_str(), text) {
+            ("move", Some(name)) if name == "pass" => square = Some(0),
+            ("move", Some(name)) => square = Some(parse_square(&name).ok_or_else(invalid)?),
+            ("time", Some(time)) => {
+                let seconds: f64 = time.parse().map_err(|_| invalid())?;
+                stats.time = Some(Duration::try_from_secs_f64(seconds).map_err(|_| invalid())?);
+            },
+            ("depth", Some(depth)) => stats.depth = Some(depth.parse().map_err(|_| invalid())?),
+            ("nodes", Some(nodes)) => stats.nodes = Some(nodes.parse().map_err(|_| invalid())?),
+            ("score", Some(score)) => stats.score = Some(score.parse().map_err(|_| invalid())?),
+            _ => (),
+        }
+    }
+
+    Ok((square.ok_or_else(|| parse_error("A JSON move must have a square."))?, stats))
+}
+
+/*
+ * Returns the text as a JSON string, quoted and escaped.
+ */
+pub(crate) fn json_string(text: &str) -> String {
+    let mut json: String = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/*
+ * Returns the result token of the given outcome, "*" for an unfinished game.
+ */
+pub(crate) fn result_token(outcome: Option<Outcome>) -> &'static str {
+    match outcome {
+        Some(Outcome { winner: Some(Color::Black), .. }) => "1-0",
+        Some(Outcome { winner: Some(Color::White), .. }) => "0-1",
+        Some(Outcome { winner: None, .. }) => "1/2-1/2",
+        None => "*",
+    }
+}
+
+/*
+ * Returns the name of the termination of a game ending for the given reason, or None if it
+ * came to its natural end.
+ */
+pub(crate) fn termination(reason: Reason) -> Option<&'static str> {
+    match reason {
+        Reason::Finished => None,
+        Reason::Resignation => Some("resignation"),
+        Reason::Agreement => Some("agreement"),
+        Reason::Adjudication => Some("adjudication"),
+        Reason::IllegalMove => Some("illegal move"),
+        Reason::Timeout => Some("time forfeit"),
+    }
+}
+
+/*
+ * Parses the name of a termination.
+ */
+pub(crate) fn parse_termination(name: &str) -> Result<Reason, OthelloError> {
+    match name {
+        "resignation" => Ok(Reason::Resignation),
+        "agreement" => Ok(Reason::Agreement),
+        "adjudication" => Ok(Reason::Adjudication),
+        "illegal move" => Ok(Reason::IllegalMove),
+        "time forfeit" => Ok(Reason::Timeout),
+        _ => Err(parse_error(format!("Unknown termination: {}", name))),
+    }
+}
+
+/*
+ * Parses a result token, returning None for an unfinished game, and the winner otherwise.
+ */
+pub(crate) fn parse_result(token: &str) -> Result<Option<Option<Color>>, OthelloError> {
+    match token {
+        "1-0" => Ok(Some(Some(Color::Black))),
+        "0-1" => Ok(Some(Some(Color::White))),
+        "1/2-1/2" => Ok(Some(None)),
+        "*" => Ok(None),
+        _ => Err(parse_error(format!("Unknown result: {}", token))),
+    }
+}
+
+/*
+ * Returns today's date, in UTC, in the "YYYY.MM.DD" format of the records.
+ */
+pub(crate) fn today() -> String {
+    date(day_number())
+}
+
+/*
+ * Returns the number of days from 1970-01-01 to today, in UTC.
+ */
+pub(crate) fn day_number() -> i64 {
+    let secs: u64 = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    (secs / 86400) as i64
+}
+
+/*
+ * Returns the date of the given day, counted from 1970-01-01, in the format of the records.
+ */
+pub(crate) fn date(day_number: i64) -> String {
+    // Converts days since 1970-01-01 to a civil date, with years starting in March so that
+    // leap days come last, as described by Howard Hinnant.
+    let days: i64 = day_number + 719468;
+    let era: i64 = days.div_euclid(146097);
+    let day_of_era: i64 = days.rem_euclid(146097);
+    let year_of_era: i64 =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year: i64 = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp: i64 = (5 * day_of_year + 2) / 153;
+    let day: i64 = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month: i64 = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year: i64 = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+//#################################################################################################
+//
+//                                      FORMAT TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that records survive a round trip through the text and GGF formats, and that matches
+ * record the stats of the moves, kept through JSON and GGF.
+ */
+#[cfg(all(test, feature = "alphabeta"))]
+mod tests {
+    use super::*;
+    use crate::alphabeta::*;
+    use crate::arena::*;
+    use crate::player::*;
+    use crate::ggf::*;
+
+    #[test]
+    fn round_trip() {
+        let (mut black, mut white) = (AlphaBetaPlayer::new(1), AlphaBetaPlayer::new(2));
+        let mut game: Game = Arena::new(1).play_game(&mut black, &mut white).unwrap();
+        let record: GameRecord = GameRecord::from_game(&game, "Alice", "Bob", "2020.05.17");
+        let parsed: GameRecord = GameRecord::from_text(&record.to_text()).unwrap();
+
+        assert_eq!(parsed.black, "Alice");
+        assert_eq!(parsed.date, "2020.05.17");
+        assert_eq!(parsed.to_text(), record.to_text());
+        assert!(parsed.to_game().unwrap().history() == game.history());
+
+        game = Game::new();
+        game.play(0x0000000000080000).unwrap();
+        game.resign(Color::White);
+        let text: String = GameRecord::from_game(&game, "Alice", "Bob", "2020.05.17").to_text();
+        assert!(text.ends_with("[Termination \"resignation\"]\n\n1. d3 1-0\n"));
+        let parsed: Game = GameRecord::from_text(&text).unwrap().to_game().unwrap();
+        assert!(parsed.outcome() == game.outcome());
+
+        game = Arena::new(1).with_handicap(2).play_game(&mut black, &mut white).unwrap();
+        let text: String = GameRecord::from_game(&game, "Alice", "Bob", "2020.05.17").to_text();
+        assert!(text.contains("[Handicap \"2\"]\n"));
+        let parsed: Game = GameRecord::from_text(&text).unwrap().to_game().unwrap();
+        assert_eq!(parsed.handicap(), 2);
+        assert!(parsed.board() == game.board());
+        assert_eq!(Game::with_handicap(2).board().score().get(Color::Black), 4);
+    }
+
+    #[test]
+    fn move_stats() {
+        let record: GameRecord = Match::play(AlphaBetaPlayer::new(2), RandomPlayer::new(5),
+            Rules::new(), TimeControl::Unlimited).unwrap();
+        assert_eq!(record.stats.len(), record.moves.len());
+        let first: MoveStats = record.stats[0];
+        assert!(first.time.is_some() && first.nodes.unwrap() > 0 && first.score.is_some());
+        assert!(record.stats[1].nodes.is_none() && record.stats[1].time.is_some());
+        assert!(record.to_json().contains(&format!("\"depth\":{},", first.depth.unwrap())));
+        assert!(record.to_json().ends_with("}]}"));
+        let read: GameRecord = GameRecord::from_json(&record.to_json()).unwrap();
+        assert!(read.moves == record.moves && read.outcome == record.outcome);
+        assert_eq!(read.stats[0].nodes, first.nodes);
+
+        let parsed: GameRecord = parse_games(&write_game(&record).unwrap()).unwrap().remove(0);
+        assert!(parsed.moves == record.moves && parsed.outcome == record.outcome);
+        assert_eq!(parsed.stats[0].score, first.score);
+        assert!(parsed.stats.iter().zip(&record.stats).all(|(a, b)| match (a.time, b.time) {
+            (Some(a), Some(b)) => a.abs_diff(b) < Duration::from_millis(10),
+            (a, b) => a == b,
+        }));
+    }
+}
@@ -0,0 +1,700 @@
+use std::path::Path;
+
+use crate::types::*;
+use crate::coord::*;
+use crate::othello::*;
+use crate::phase::*;
+use crate::game::*;
+use crate::record::*;
+use crate::alphabeta::*;
+use crate::eval::*;
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                       GAME REVIEW
+//
+//#################################################################################################
+
+/*
+ * How much a move can lower the winning chances of the player making it, as given by
+ * win_probability, before it is a mistake, and before it is a blunder.
+ */
+pub const MISTAKE: f64 = 0.1;
+pub const BLUNDER: f64 = 0.25;
+
+/*
+ * The review of a move of a game: its ply, counting passes, the player who made it, the move
+ * and the best one according to the AI, their evaluations from his point of view, and the
+ * number of empty squares before it.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct MoveReview {
+    pub ply: usize,
+    pub color: Color,
+    pub mv: BitBoard,
+    pub best: BitBoard,
+    pub value: i32,
+    pub best_value: i32,
+    pub empties: u8,
+}
+
+impl MoveReview {
+    /*
+     * Returns how much the move lowered the winning chances of the player, 0 for the best
+     * move.
+     */
+    pub fn loss(&self) -> f64 {
+        let chances = |value: i32| win_probability(value, self.empties);
+        (chances(self.best_value) - chances(self.value)).max(0.0)
+    }
+
+    /*
+     * Returns the winning chances of Black after the move, as far as the AI can tell.
+     */
+    pub fn black_chances(&self) -> f64 {
+        let chances: f64 = win_probability(self.value, self.empties);
+        if self.color == Color::Black { chances } else { 1.0 - chances }
+    }
+}
+
+/*
+ * Reviews every move of a game with the AI, searching all the moves of each position to
+ * compare the one played with the best. Passes are left out.
+ */
+pub fn review_game(player: &AlphaBetaPlayer, game: &Game) -> Vec<MoveReview> {
+    review_moves(player, game, None)
+}
+
+/*
+ * Reviews the moves of the given player only, like review_game.
+ */
+pub fn review_color(player: &AlphaBetaPlayer, game: &Game, color: Color) -> Vec<MoveReview> {
+    review_moves(player, game, Some(color))
+}
+
+/*
+ * Reviews the moves of the given player, or of both.
+ */
+fn review_moves(player: &AlphaBetaPlayer, game: &Game, only: Option<Color>) -> Vec<MoveReview> {
+    let mut replay: Game = Game::with_handicap(game.handicap());
+    let mut reviews: Vec<MoveReview> = Vec::new();
+
+    for (ply, &mv) in game.history().iter().enumerate() {
+        if mv == 0 {
+            continue;
+        }
+        let (oth, color): (Othello, Color) = (replay.board(), replay.color());
+        if only.is_some_and(|only| only != color) {
+            if replay.play(mv).is_err() {
+                break;
+            }
+            continue;
+        }
+        let hints: Vec<Hint> = player.hint(oth, color, 64);
+        let best: &Hint = &hints[0];
+        let value: i32 = hints.iter().find(|hint| hint.mv == mv).map_or(best.value, |hint| {
+            hint.value
+        });
+        reviews.push(MoveReview {
+            ply,
+            color,
+            mv,
+            best: best.mv,
+            value,
+            best_value: best.value,
+            empties: oth.empties(),
+        });
+        if replay.play(mv).is_err() {
+            break;
+        }
+    }
+    reviews
+}
+
+//#################################################################################################
+//
+//                                     ANALYSIS REPORT
+//
+//#################################################################################################
+
+/*
+ * The formats reports can be written in.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReportFormat {
+    Html,
+    Markdown,
+}
+
+impl ReportFormat {
+    /*
+     * Returns the format of a report file from its extension, Markdown for .md and HTML
+     * otherwise.
+     */
+    pub fn from_path(path: impl AsRef<Path>) -> ReportFormat {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some("md") | Some("markdown") => ReportFormat::Markdown,
+            _ => ReportFormat::Html,
+        }
+    }
+}
+
+/*
+ * The most boards shown in a report, the positions before the worst mistakes.
+ */
+const MAX_DIAGRAMS: usize = 4;
+
+/*
+ * Writes the analysis report of a game from the reviews of its moves: the players and the
+ * result, the graph of the winning chances of Black along the game, the list of mistakes
+ * with the move the AI prefers, and the boards before the worst of them and at the end. The
+ * graph is drawn in SVG in HTML, and as a line of blocks in Markdown.
+ */
+pub fn write_report(record: &GameRecord, reviews: &[MoveReview], format: ReportFormat)
+    -> Result<String, OthelloError>
+{
+    let game: Game = record.to_game()?;
+    let html: bool = format == ReportFormat::Html;
+    let mut text: String = String::new();
+    let title: String = format!("{} - {}, {}", record.black, record.white,
+        result_token(record.outcome));
+
+    // Header and summary.
+    if html {
+        text += &format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+            <title>{}</title>\n</head>\n<body>\n<h1>{}</h1>\n", escape(&title), escape(&title));
+    } else {
+        text += &format!("# {}\n\n", title);
+    }
+    let mut summary: String = if record.date.is_empty() {
+        String::new()
+    } else {
+        format!("Played on {}. ", record.date)
+    };
+    for color in Color::iter() {
+        let own = || reviews.iter().filter(|review| review.color == color);
+        let loss: f64 = own().map(MoveReview::loss).sum::<f64>() / own().count().max(1) as f64;
+        summary += &format!("{}: {} mistakes, {} blunders, {:.1}% lost per move. ", color,
+            own().filter(|review| review.loss() >= MISTAKE && review.loss() < BLUNDER).count(),
+            own().filter(|review| review.loss() >= BLUNDER).count(), 100.0 * loss);
+    }
+    text += &paragraph(summary.trim_end(), html);
+
+    // Evaluation graph.
+    let chances: Vec<f64> = reviews.iter().map(MoveReview::black_chances).collect();
+    text += &heading("Winning chances of Black", html);
+    text += &if html { svg_graph(&chances) } else { block_graph(&chances) };
+
+    // Mistakes.
+    let mut mistakes: Vec<&MoveReview> = reviews.iter()
+        .filter(|review| review.loss() >= MISTAKE)
+        .collect();
+    text += &heading("Mistakes", html);
+    if mistakes.is_empty() {
+        text += &paragraph("None.", html);
+    } else {
+        let rows: Vec<[String; 5]> = mistakes.iter().map(|review| [
+            format!("{}", review.ply + 1),
+            review.color.to_string(),
+            review.mv.square_name(),
+            review.best.square_name(),
+            format!("{:.0}%{}", 100.0 * review.loss(),
+                if review.loss() >= BLUNDER { ", blunder" } else { "" }),
+        ]).collect();
+        text += &table(&["Ply", "Player", "Played", "Best", "Chances lost"], &rows, html);
+    }
+
+    // Boards before the worst mistakes, in the order of the game, and at the end.
+    mistakes.sort_by(|a, b| b.loss().total_cmp(&a.loss()));
+    mistakes.truncate(MAX_DIAGRAMS);
+    mistakes.sort_by_key(|review| review.ply);
+    for review in mistakes {
+        let mut before: Game = Game::with_handicap(record.handicap);
+        for &mv in &game.history()[..review.ply] {
+            if mv != 0 {
+                before.play(mv)?;
+            }
+        }
+        text += &heading(&format!("Before ply {}, {} played {} instead of {}", review.ply + 1,
+            review.color, review.mv.square_name(), review.best.square_name()), html);
+        text += &diagram(&before.board(), review.mv | review.best, html);
+    }
+    text += &heading("Final position", html);
+    text += &diagram(&game.board(), 0, html);
+
+    if html {
+        text += "</body>\n</html>\n";
+    }
+    Ok(text)
+}
+
+/*
+ * Escapes the characters of a text written in HTML.
+ */
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn heading(title: &str, html: bool) -> String {
+    if html {
+        format!("<h2>{}</h2>\n", escape(title))
+    } else {
+        format!("## {}\n\n", title)
+    }
+}
+
+fn paragraph(text: &str, html: bool) -> String {
+    if html {
+        format!("<p>{}</p>\n", escape(text))
+    } else {
+        format!("{}\n\n", text)
+    }
+}
+
+/*
+ * Writes a table with the given header.
+ */
+fn table(header: &[&str], rows: &[[String; 5]], html: bool) -> String {
+    if html {
+        let cells = |cells: &[String], tag: &str| -> String {
+            cells.iter().map(|cell| format!("<{}>{}</{}>", tag, escape(cell), tag)).collect()
+        };
+        let header: Vec<String> = header.iter().map(|name| name.to_string()).collect();
+        let mut text: String = format!("<table>\n<tr>{}</tr>\n", cells(&header, "th"));
+        for row in rows {
+            text += &format!("<tr>{}</tr>\n", cells(row, "td"));
+        }
+        text + "</table>\n"
+    } else {
+        let mut text: String = format!("| {} |\n|{}\n", header.join(" | "), "---|".repeat(5));
+        for row in rows {
+            text += &format!("| {} |\n", row.join(" | "));
+        }
+        text + "\n"
+    }
+}
+
+/*
+ * Draws a board as text, the given squares being marked with '*' if they are empty.
+ */
+fn diagram(oth: &Othello, marked: BitBoard, html: bool) -> String {
+    let mut board: String = String::from("  a b c d e f g h\n");
+    for y in 0..8 {
+        board += &Coord { x: 0, y, }.row(convention()).to_string();
+        for x in 0..8 {
+            board.push(' ');
+            board.push(match oth.get_square(x, y) {
+                Square::Empty if marked.contains(x, y) => '*',
+                square => square.to_char(),
+            });
+        }
+        board.push('\n');
+    }
+    if html {
+        format!("<pre>\n{}</pre>\n", board)
+    } else {
+        format!("```\n{}```\n\n", board)
+    }
+}
+
+/*
+ * Draws the winning chances of Black after each move as an SVG line, over a dashed line at
+ * even chances.
+ */
+fn svg_graph(chances: &[f64]) -> String {
+    const WIDTH: usize = 600;
+    const HEIGHT: usize = 200;
+    let step: f64 = WIDTH as f64 / chances.len().max(2).saturating_sub(1) as f64;
+    let points: Vec<String> = chances.iter().enumerate()
+        .map(|(i, chance)| format!("{:.1},{:.1}", i as f64 * step, (1.0 - chance) * HEIGHT as f64))
+        .collect();
+
+    format!("<svg width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n\
+        <rect width=\"{w}\" height=\"{h}\" fill=\"#eee\"/>\n\
+        <line x1=\"0\" y1=\"{m}\" x2=\"{w}\" y2=\"{m}\" stroke=\"#999\" stroke-dasharray=\"4\"/>\n\
+        <polyline points=\"{p}\" fill=\"none\" stroke=\"#000\" stroke-width=\"2\"/>\n\
+        </svg>\n", w = WIDTH, h = HEIGHT, m = HEIGHT / 2, p = points.join(" "))
+}
+
+/*
+ * Draws the winning chances of Black after each move as a line of blocks, higher when they
+ * are better.
+ */
+fn block_graph(chances: &[f64]) -> String {
+    const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let line: String = chances.iter()
+        .map(|chance| BLOCKS[((chance * BLOCKS.len() as f64) as usize).min(BLOCKS.len() - 1)])
+        .collect();
+    format!("```\n{}\n```\n\n", line)
+}
+
+//#################################################################################################
+//
+//                                      BLUNDER CHECK
+//
+//#################################################################################################
+
+/*
+ * The kinds of moves mistakes are counted by, from the square played.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MoveKind {
+    Corner,
+    XSquare,
+    CSquare,
+    Edge,
+    Inner,
+}
+
+impl MoveKind {
+    /*
+     * Every kind of move, in the order of the tables.
+     */
+    pub const ALL: [MoveKind; 5] =
+        [MoveKind::Corner, MoveKind::XSquare, MoveKind::CSquare, MoveKind::Edge, MoveKind::Inner];
+
+    /*
+     * Returns the kind of a move: a corner, an X-square diagonally next to one, a C-square
+     * next to one along an edge, another square of an edge, or an inner square.
+     */
+    pub fn of(mv: BitBoard) -> MoveKind {
+        if mv & 0x8100000000000081 != 0 {
+            MoveKind::Corner
+        } else if mv & 0x0042000000004200 != 0 {
+            MoveKind::XSquare
+        } else if mv & 0x4281000000008142 != 0 {
+            MoveKind::CSquare
+        } else if mv & 0xFF818181818181FF != 0 {
+            MoveKind::Edge
+        } else {
+            MoveKind::Inner
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MoveKind::Corner => "corner",
+            MoveKind::XSquare => "X-square",
+            MoveKind::CSquare => "C-square",
+            MoveKind::Edge => "edge",
+            MoveKind::Inner => "inner",
+        }
+    }
+}
+
+/*
+ * The mistakes among some moves: how many moves there are, how many are mistakes or
+ * blunders, and the winning chances they lose in total.
+ */
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct MistakeStats {
+    pub moves: usize,
+    pub mistakes: usize,
+    pub blunders: usize,
+    pub loss: f64,
+}
+
+impl MistakeStats {
+    /*
+     * Counts a reviewed move.
+     */
+    pub fn add(&mut self, review: &MoveReview) {
+        let loss: f64 = review.loss();
+        self.moves += 1;
+        self.mistakes += (MISTAKE..BLUNDER).contains(&loss) as usize;
+        self.blunders += (loss >= BLUNDER) as usize;
+        self.loss += loss;
+    }
+}
+
+/*
+ * The statistics of the mistakes of a player over his games, by phase of the game, see Phase,
+ * with the given thresholds, and by kind of move.
+ */
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct BlunderCheck {
+    pub games: usize,
+    pub phases: PhaseThresholds,
+    pub by_phase: [MistakeStats; 4],
+    pub by_kind: [MistakeStats; 5],
+}
+
+impl BlunderCheck {
+    /*
+     * Counts the reviewed moves of a game of the player.
+     */
+    pub fn add_game(&mut self, reviews: &[MoveReview]) {
+        self.games += 1;
+        for review in reviews {
+            let phase: Phase = self.phases.classify(review.empties);
+            self.by_phase[phase.index()].add(review);
+            let kind: usize = MoveKind::ALL.iter()
+                .position(|&kind| kind == MoveKind::of(review.mv))
+                .unwrap_or(0);
+            self.by_kind[kind].add(review);
+        }
+    }
+
+    /*
+     * Writes the statistics as a table, one row per phase and then per kind of move, with
+     * the number of moves, of mistakes and of blunders, and the average winning chances lost
+     * per move.
+     */
+    pub fn to_table(&self) -> String {
+        let mut text: String = format!("{} games\n{:<11} {:>6} {:>9} {:>9} {:>10}\n",
+            self.games, "", "moves", "mistakes", "blunders", "lost/move");
+        let rows = Phase::ALL.iter().map(|phase| phase.name())
+            .zip(self.by_phase.iter())
+            .chain(MoveKind::ALL.iter().map(|kind| kind.name()).zip(self.by_kind.iter()));
+        for (name, stats) in rows {
+            text += &format!("{:<11} {:>6} {:>9} {:>9} {:>9.1}%\n", name, stats.moves,
+                stats.mistakes, stats.blunders, 100.0 * stats.loss / stats.moves.max(1) as f64);
+        }
+        text
+    }
+}
+
+//#################################################################################################
+//
+//                                        HEATMAPS
+//
+//#################################################################################################
+
+/*
+ * Per-square heatmaps of the moves of a player over his games: how often he plays on each
+ * square, how often his move there is a mistake or a blunder, and the winning chances his
+ * moves there lose in total. Squares are indexed [y][x], row 0 holding a1 to h1.
+ */
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Heatmaps {
+    pub moves: [[usize; 8]; 8],
+    pub mistakes: [[usize; 8]; 8],
+    pub loss: [[f64; 8]; 8],
+}
+
+impl Heatmaps {
+    /*
+     * Counts the reviewed moves of a game of the player.
+     */
+    pub fn add_game(&mut self, reviews: &[MoveReview]) {
+        for review in reviews {
+            let index: usize = review.mv.trailing_zeros() as usize;
+            let (x, y): (usize, usize) = (index % 8, index / 8);
+            self.moves[y][x] += 1;
+            self.mistakes[y][x] += (review.loss() >= MISTAKE) as usize;
+            self.loss[y][x] += review.loss();
+        }
+    }
+
+    /*
+     * Returns the average winning chances lost by the moves on each square, 0 where the player
+     * never moved.
+     */
+    pub fn average_loss(&self) -> [[f64; 8]; 8] {
+        let mut average: [[f64; 8]; 8] = [[0.0; 8]; 8];
+        for (y, row) in average.iter_mut().enumerate() {
+            for (x, square) in row.iter_mut().enumerate() {
+                *square = self.loss[y][x] / self.moves[y][x].max(1) as f64;
+            }
+        }
+        average
+    }
+
+    /*
+     * Writes the heatmaps as CSV, a row per rank of each map, named by the first two columns.
+     */
+    pub fn to_csv(&self) -> String {
+        let mut text: String = String::from("map,rank,a,b,c,d,e,f,g,h\n");
+        let average: [[f64; 8]; 8] = self.average_loss();
+        for y in 0..8 {
+            let row: Vec<String> = self.moves[y].iter().map(|n| n.to_string()).collect();
+            text += &format!("moves,{},{}\n", y + 1, row.join(","));
+        }
+        for y in 0..8 {
+            let row: Vec<String> = self.mistakes[y].iter().map(|n| n.to_string()).collect();
+            text += &format!("mistakes,{},{}\n", y + 1, row.join(","));
+        }
+        for (y, row) in average.iter().enumerate() {
+            let row: Vec<String> = row.iter().map(|loss| format!("{:.4}", loss)).collect();
+            text += &format!("loss,{},{}\n", y + 1, row.join(","));
+        }
+        text
+    }
+
+    /*
+     * Writes the heatmaps as a JSON object of three 8x8 matrices, the average loss of the
+     * moves on each square standing for the loss.
+     */
+    pub fn to_json(&self) -> String {
+        let matrix = |rows: Vec<Vec<String>>| -> String {
+            let rows: Vec<String> = rows.iter().map(|row| format!("[{}]", row.join(","))).collect();
+            format!("[{}]", rows.join(","))
+        };
+        let counts = |map: &[[usize; 8]; 8]| -> String {
+            matrix(map.iter().map(|row| row.iter().map(|n| n.to_string()).collect()).collect())
+        };
+        let loss: String = matrix(self.average_loss().iter()
+            .map(|row| row.iter().map(|loss| format!("{:.4}", loss)).collect())
+            .collect());
+        format!("{{\"moves\":{},\"mistakes\":{},\"loss\":{}}}\n", counts(&self.moves),
+            counts(&self.mistakes), loss)
+    }
+}
+
+//#################################################################################################
+//
+//                                   STRENGTH ESTIMATION
+//
+//#################################################################################################
+
+/*
+ * The depths the moves of a game are compared to when estimating the strength of its players.
+ */
+pub const STRENGTH_DEPTHS: [u8; 3] = [1, 3, 5];
+
+/*
+ * The index in STRENGTH_DEPTHS of the depth the ratings are estimated from, the depth of the
+ * AIs the ratings of the ladder are measured with.
+ */
+const RATING_DEPTH: usize = 1;
+
+/*
+ * The average winning chances lost per move at a few ratings, interpolated in between. They are
+ * rough estimates for the AI playing at each rating with with_target_elo, reviewed 3 plies
+ * deep. Ratings under 1400 aren't told apart.
+ */
+const RATING_LOSSES: [(f64, f64); 7] = [
+    (0.0, 2000.0), (0.009, 1900.0), (0.02, 1800.0), (0.028, 1700.0), (0.044, 1600.0),
+    (0.07, 1500.0), (0.079, 1400.0),
+];
+
+/*
+ * How closely a player of a game followed the AI: how many moves he made, how many matched
+ * the best move at each of the STRENGTH_DEPTHS, and the winning chances they lost in total
+ * at each.
+ */
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Strength {
+    pub moves: usize,
+    pub matches: [usize; STRENGTH_DEPTHS.len()],
+    pub loss: [f64; STRENGTH_DEPTHS.len()],
+}
+
+impl Strength {
+    /*
+     * Returns the share of the moves matching the best move at the nth of the STRENGTH_DEPTHS.
+     */
+    pub fn match_rate(&self, n: usize) -> f64 {
+        self.matches[n] as f64 / self.moves.max(1) as f64
+    }
+
+    /*
+     * Returns the average winning chances lost per move at the nth of the STRENGTH_DEPTHS.
+     */
+    pub fn average_loss(&self, n: usize) -> f64 {
+        self.loss[n] / self.moves.max(1) as f64
+    }
+
+    /*
+     * Returns the approximate rating of the player, on the scale of the ladder, from the
+     * winning chances he lost per move. It is rough over a single game, and can't tell the
+     * players stronger than the AI at MAX_ELO apart.
+     */
+    pub fn elo(&self) -> f64 {
+        interpolate(&RATING_LOSSES, self.average_loss(RATING_DEPTH))
+    }
+}
+
+/*
+ * Estimates the strength of both players of a game, indexed by color, by comparing their moves
+ * to the best ones at each of the STRENGTH_DEPTHS, like the rating estimates of chess tools.
+ */
+pub fn estimate_strength(game: &Game) -> [Strength; 2] {
+    let mut strengths: [Strength; 2] = [Strength::default(), Strength::default()];
+    for (n, &depth) in STRENGTH_DEPTHS.iter().enumerate() {
+        for review in review_game(&AlphaBetaPlayer::new(depth), game) {
+            let strength: &mut Strength = &mut strengths[review.color as usize];
+            strength.moves += (n == 0) as usize;
+            strength.matches[n] += (review.mv == review.best) as usize;
+            strength.loss[n] += review.loss();
+        }
+    }
+    strengths
+}
+
+//#################################################################################################
+//
+//                                        REPORT TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that the moves of a game are reviewed, that reports hold their sections in both
+ * formats, that the mistakes of a player are counted by phase and kind of move, and by
+ * square, and that the strength of the players is estimated, the AI playing the best moves
+ * being rated at the top and a random player lower.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::*;
+
+    #[test]
+    fn report() {
+        let game: Game = Game::from_line("f5d6c3d3c4f4f6f3e6e7d7c6").unwrap();
+        let reviews: Vec<MoveReview> = review_game(&AlphaBetaPlayer::new(3), &game);
+        assert_eq!(reviews.len(), game.history().len());
+        assert!(reviews.iter().all(|review| review.loss() >= 0.0 && review.empties > 0));
+        assert!(reviews.iter().any(|review| review.mv == review.best));
+
+        let record: GameRecord = GameRecord::from_game(&game, "alice", "bob", "2024.03.05");
+        let html: String = write_report(&record, &reviews, ReportFormat::Html).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>") && html.contains("<polyline"));
+        let markdown: String = write_report(&record, &reviews, ReportFormat::Markdown).unwrap();
+        assert!(markdown.starts_with("# alice - bob, *"));
+        assert!(markdown.contains("## Mistakes") && markdown.contains("## Final position"));
+        assert_eq!(ReportFormat::from_path("game.md"), ReportFormat::Markdown);
+
+        let black: Vec<MoveReview> = review_color(&AlphaBetaPlayer::new(3), &game, Color::Black);
+        assert!(black.iter().all(|review| review.color == Color::Black));
+        let mut check: BlunderCheck = BlunderCheck::default();
+        check.add_game(&black);
+        assert_eq!(check.by_phase[0].moves, black.len());
+        assert_eq!(check.by_kind.iter().map(|stats| stats.moves).sum::<usize>(), black.len());
+        assert_eq!(MoveKind::of(1 << 9), MoveKind::XSquare);
+        assert_eq!(MoveKind::of(1 << 57), MoveKind::CSquare);
+        assert!(check.to_table().contains("opening"));
+
+        let strengths: [Strength; 2] = estimate_strength(&game);
+        assert_eq!(strengths[0].moves + strengths[1].moves, game.history().len());
+        assert!(strengths.iter().all(|strength| (1400.0..=2000.0).contains(&strength.elo())));
+        assert_eq!(strengths[0].matches[RATING_DEPTH], reviews.iter()
+            .filter(|review| review.color == Color::Black && review.mv == review.best).count());
+
+        let mut heatmaps: Heatmaps = Heatmaps::default();
+        heatmaps.add_game(&black);
+        assert_eq!(heatmaps.moves.iter().flatten().sum::<usize>(), black.len());
+        assert_eq!(heatmaps.moves[4][5], 1);
+        assert_eq!(heatmaps.to_csv().lines().count(), 25);
+        assert!(heatmaps.to_json().starts_with("{\"moves\":[[0,0,0,0,0,0,0,0],"));
+    }
+
+    #[test]
+    fn strength() {
+        let play = |player: &mut dyn Player| {
+            let mut game: Game = Game::new();
+            while game.history().len() < 20 {
+                let Action::Play(mv) = player.choose_move(&game, &Clock::unlimited()) else {
+                    panic!("The player should move.");
+                };
+                game.play(mv).unwrap();
+            }
+            game
+        };
+        let perfect: [Strength; 2] = estimate_strength(&play(&mut AlphaBetaPlayer::new(3)));
+        assert!(perfect.iter().all(|strength| strength.elo() == 2000.0));
+        let random: [Strength; 2] = estimate_strength(&play(&mut RandomPlayer::new(1)));
+        assert!(random.iter().all(|strength| strength.elo() < 2000.0));
+    }
+}
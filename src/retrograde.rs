@@ -0,0 +1,431 @@
+use std::collections::HashMap;
+
+use crate::types::*;
+use crate::othello::*;
+use crate::symmetry::*;
+
+//#################################################################################################
+//
+//                                      SMALL BOARDS
+//
+//#################################################################################################
+
+/*
+ * A square board smaller than the usual one, of an even size up to 8, played in the corner of
+ * the 8x8 board at a1. The squares outside of it stay empty, and as flips never go through an
+ * empty square the move generator and the move making of Othello work on it unchanged, as long
+ * as the moves are kept within the board. The smaller boards are solved: the 4x4 and the 6x6
+ * ones are both won by White, the 6x6 one by 20 disks to 16.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SmallBoard {
+    size: u8,
+}
+
+impl SmallBoard {
+    pub const FOUR: SmallBoard = SmallBoard { size: 4, };
+    pub const SIX: SmallBoard = SmallBoard { size: 6, };
+
+    /*
+     * Returns the board of the given size, or None if it isn't an even number between 4 and 8.
+     */
+    pub fn new(size: u8) -> Option<SmallBoard> {
+        Some(SmallBoard { size, }).filter(|_| (4..=8).contains(&size) && size.is_multiple_of(2))
+    }
+
+    /*
+     * Returns the number of squares along a side.
+     */
+    pub fn size(self) -> u8 {
+        self.size
+    }
+
+    /*
+     * Returns the squares of the board.
+     */
+    pub fn mask(self) -> BitBoard {
+        let row: BitBoard = (1 << self.size) - 1;
+        (0..self.size as u32).fold(0, |mask, y| mask | row << (8 * y))
+    }
+
+    /*
+     * Returns the starting position of the board, with the four disks at its center placed
+     * like on the usual board.
+     */
+    pub fn start(self) -> Othello {
+        let c: u8 = self.size / 2;
+        let bit = |x: u8, y: u8| 1 << (x + 8 * y);
+        Othello::create(bit(c, c - 1) | bit(c - 1, c), bit(c - 1, c - 1) | bit(c, c))
+    }
+
+    /*
+     * Returns the legal moves of the given player, within the board.
+     */
+    pub fn gen_moves(self, oth: Othello, color: Color) -> BitBoard {
+        oth.gen_moves(color) & self.mask()
+    }
+
+    /*
+     * Returns the number of empty squares left on the board.
+     */
+    pub fn empties(self, oth: Othello) -> u8 {
+        self.size * self.size - (64 - oth.empties())
+    }
+
+    /*
+     * Returns the final disc differential of a finished game from the point of view of the
+     * given color, empty squares going to the winner.
+     */
+    pub fn final_score(self, oth: Othello, color: Color) -> i8 {
+        let own: i8 = oth.get_bitboard(color).pop_cnt() as i8;
+        let opp: i8 = oth.get_bitboard(color.invert()).pop_cnt() as i8;
+        let empties: i8 = self.empties(oth) as i8;
+        match own.cmp(&opp) {
+            std::cmp::Ordering::Greater => own - opp + empties,
+            std::cmp::Ordering::Less => own - opp - empties,
+            std::cmp::Ordering::Equal => 0,
+        }
+    }
+
+    /*
+     * Solves the position by searching, with alpha-beta pruning and a table of the bounds
+     * found, and returns the final disc differential with perfect play from the point of view
+     * of the given color. Independent from the retrograde analysis, it checks it, and solves
+     * the 6x6 board whose positions are too many to be enumerated. The value is narrowed down
+     * by null window searches, like MTD(f), each reusing the bounds found by the previous ones.
+     */
+    pub fn solve(self, oth: Othello, color: Color) -> i8 {
+        let max: i8 = (self.size * self.size) as i8;
+        let mut table: Vec<Option<Entry>> = vec![None; 1 << self.table_bits()];
+        let (mut lower, mut upper, mut guess): (i8, i8, i8) = (-max, max, 0);
+
+        while lower < upper {
+            let beta: i8 = if guess == lower { guess + 1 } else { guess };
+            guess = self.negamax(oth, color, beta - 1, beta, &mut table);
+            if guess < beta {
+                upper = guess;
+            } else {
+                lower = guess;
+            }
+        }
+        lower
+    }
+
+    /*
+     * Returns the corners of the board.
+     */
+    pub fn corners(self) -> BitBoard {
+        let far: u8 = self.size - 1;
+        1 | 1 << far | 1 << (8 * far) | 1 << (9 * far)
+    }
+
+    /*
+     * Returns the squares diagonally next to the corners, which give them away.
+     */
+    fn x_squares(self) -> BitBoard {
+        let far: u8 = self.size - 2;
+        1 << 9 | 1 << (far + 8) | 1 << (8 * far + 1) | 1 << (9 * far)
+    }
+
+    /*
+     * Applies the symmetry to the squares of the board, as a symmetry of the board itself
+     * rather than of the 8x8 one.
+     */
+    pub fn transform(self, bb: BitBoard, symmetry: Symmetry) -> BitBoard {
+        let bits: u8 = symmetry as u8;
+        let shift: u8 = 8 - self.size;
+        symmetry.apply(bb) >> (shift * (bits >> 1 & 1) + 8 * shift * (bits & 1))
+    }
+
+    /*
+     * Returns the canonical form of the position on the board, the same for all its symmetric
+     * ones, along with the symmetry turning the position into it.
+     */
+    pub fn canonical(self, oth: Othello) -> (Othello, Symmetry) {
+        let key = |oth: Othello| (oth.get_bitboard(Color::Black), oth.get_bitboard(Color::White));
+        let mut best: (Othello, Symmetry) = (oth, Symmetry::Identity);
+
+        for &symmetry in &Symmetry::ALL[1..] {
+            let transformed: Othello = Othello::create(
+                self.transform(oth.get_bitboard(Color::Black), symmetry),
+                self.transform(oth.get_bitboard(Color::White), symmetry),
+            );
+            if key(transformed) < key(best.0) {
+                best = (transformed, symmetry);
+            }
+        }
+
+        best
+    }
+
+    /*
+     * Returns the number of slots of the table of the solver as a power of two, as many as
+     * needed by the small boards and at most 2^MAX_TABLE_BITS.
+     */
+    fn table_bits(self) -> u8 {
+        (3 * self.size + 3).min(MAX_TABLE_BITS)
+    }
+
+    /*
+     * Negamax with alpha-beta pruning. The best move found before is searched first, then
+     * those leaving the opponent the fewest replies and no corner, except close to the end
+     * where ordering costs more than it saves. The bounds found for each position and its best
+     * move are kept in the table, under its canonical form far from the end, at the slot given
+     * by its hash, replacing the position there unless it is closer to the start.
+     */
+    fn negamax(self, oth: Othello, color: Color, mut alpha: i8, mut beta: i8,
+        table: &mut [Option<Entry>]) -> i8
+    {
+        let max: i8 = (self.size * self.size) as i8;
+        let (key, symmetry): (Othello, Symmetry) = if self.empties(oth) > SYMMETRY_EMPTIES {
+            self.canonical(oth)
+        } else {
+            (oth, Symmetry::Identity)
+        };
+        let slot: usize = slot(key, color, table.len());
+        let (lower, upper, hint): (i8, i8, BitBoard) = match table[slot] {
+            Some(entry) if entry.oth == key && entry.color == color => {
+                (entry.bounds.0, entry.bounds.1, self.transform(entry.best, symmetry.inverse()))
+            },
+            _ => (-max, max, 0),
+        };
+        if lower >= beta || lower == upper {
+            return lower;
+        }
+        if upper <= alpha {
+            return upper;
+        }
+        let (original_alpha, original_beta): (i8, i8) = (alpha.max(lower), beta.min(upper));
+        (alpha, beta) = (original_alpha, original_beta);
+
+        let mut moves: BitBoard = self.gen_moves(oth, color);
+        if moves == 0 {
+            if self.gen_moves(oth, color.invert()) == 0 {
+                return self.final_score(oth, color);
+            }
+            return -self.negamax(oth, color.invert(), -beta, -alpha, table);
+        }
+
+        let mut children: [(u8, BitBoard, Othello); 64] = [(0, 0, oth); 64];
+        let mut count: usize = 0;
+        while moves != 0 {
+            let mv: BitBoard = moves.pop_lsb();
+            let child: Othello = oth.make_move(color, mv);
+            let order: u8 = if mv == hint {
+                0
+            } else if self.empties(oth) > ORDERING_EMPTIES {
+                let replies: BitBoard = self.gen_moves(child, color.invert());
+                2 + 2 * replies.pop_cnt() + 4 * (replies & self.corners()).pop_cnt()
+                    + 3 * (mv & self.x_squares() != 0) as u8 - (mv & self.corners() != 0) as u8
+            } else {
+                1
+            };
+            children[count] = (order, mv, child);
+            count += 1;
+        }
+        children[..count].sort_unstable_by_key(|&(order, _, _)| order);
+
+        let (mut best, mut best_move): (i8, BitBoard) = (-max, 0);
+        for &(_, mv, child) in &children[..count] {
+            let value: i8 = -self.negamax(child, color.invert(), -beta, -alpha, table);
+            if value > best {
+                (best, best_move) = (value, mv);
+                alpha = alpha.max(best);
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+
+        let bounds: (i8, i8) = if best <= original_alpha {
+            (lower, best)
+        } else if best >= original_beta {
+            (best, upper)
+        } else {
+            (best, best)
+        };
+        let replace: bool = match table[slot] {
+            Some(entry) => entry.oth.empties() <= key.empties(),
+            None => true,
+        };
+        if self.empties(oth) > TABLE_EMPTIES && replace {
+            let best: BitBoard = self.transform(best_move, symmetry);
+            table[slot] = Some(Entry { oth: key, color, bounds, best, });
+        }
+        best
+    }
+}
+
+/*
+ * The numbers of empty squares down to which the small board solver orders the moves, looks
+ * for symmetric positions in its table and stores the positions in it, the most slots the
+ * table has, as a power of two, and what a slot holds: a position with the player to move, the
+ * lower and upper bounds found for its value and its best move.
+ */
+const ORDERING_EMPTIES: u8 = 4;
+const SYMMETRY_EMPTIES: u8 = 12;
+const TABLE_EMPTIES: u8 = 2;
+const MAX_TABLE_BITS: u8 = 21;
+
+#[derive(Clone, Copy)]
+struct Entry {
+    oth: Othello,
+    color: Color,
+    bounds: (i8, i8),
+    best: BitBoard,
+}
+
+/*
+ * Returns the slot of the position in the table of the small board solver.
+ */
+fn slot(oth: Othello, color: Color, slots: usize) -> usize {
+    let hash: u64 = (oth.get_bitboard(Color::Black).wrapping_mul(0x9E3779B97F4A7C15)
+        ^ oth.get_bitboard(Color::White).wrapping_mul(0xC2B2AE3D27D4EB4F))
+        .rotate_left(color as u32 * 17);
+    (hash >> (64 - slots.trailing_zeros())) as usize
+}
+
+//#################################################################################################
+//
+//                                   RETROGRADE ANALYSIS
+//
+//#################################################################################################
+
+/*
+ * The game-theoretic value of every position reachable on a small board, computed by
+ * retrograde analysis: all the positions reachable from the start are first enumerated, then
+ * valued from the full boards back to the start, each from the values of the positions it
+ * leads to, already known as each move adds a disc. A position without legal moves is valued
+ * after the one the pass leads to, having the same disks. The values are final disc
+ * differentials from the point of view of the player to move. Only the 4x4 board is small
+ * enough, the 6x6 one having billions of reachable positions.
+ */
+pub struct Retrograde {
+    board: SmallBoard,
+    values: HashMap<(Othello, Color), i8>,
+}
+
+impl Retrograde {
+    /*
+     * Computes the values of all the positions reachable on the board, Black moving first.
+     */
+    pub fn compute(board: SmallBoard) -> Retrograde {
+        let mut positions: Vec<(Othello, Color)> = vec![(board.start(), Color::Black)];
+        let mut values: HashMap<(Othello, Color), i8> = HashMap::new();
+        values.insert(positions[0], 0);
+
+        let mut i: usize = 0;
+        while i < positions.len() {
+            let (oth, color) = positions[i];
+            let mut moves: BitBoard = board.gen_moves(oth, color);
+            let mut next: Vec<(Othello, Color)> = Vec::new();
+            if moves == 0 && board.gen_moves(oth, color.invert()) != 0 {
+                next.push((oth, color.invert()));
+            }
+            while moves != 0 {
+                next.push((oth.make_move(color, moves.pop_lsb()), color.invert()));
+            }
+            for position in next {
+                if values.insert(position, 0).is_none() {
+                    positions.push(position);
+                }
+            }
+            i += 1;
+        }
+
+        // The fullest boards first, and among the same disks those with legal moves first.
+        positions.sort_by_key(|&(oth, color)| {
+            (oth.empties(), board.gen_moves(oth, color) == 0)
+        });
+        for (oth, color) in positions {
+            let mut moves: BitBoard = board.gen_moves(oth, color);
+            let value: i8 = if moves != 0 {
+                let mut best: i8 = i8::MIN;
+                while moves != 0 {
+                    let child: Othello = oth.make_move(color, moves.pop_lsb());
+                    best = best.max(-values[&(child, color.invert())]);
+                }
+                best
+            } else if board.gen_moves(oth, color.invert()) != 0 {
+                -values[&(oth, color.invert())]
+            } else {
+                board.final_score(oth, color)
+            };
+            values.insert((oth, color), value);
+        }
+
+        Retrograde { board, values, }
+    }
+
+    /*
+     * Returns the board analyzed.
+     */
+    pub fn board(&self) -> SmallBoard {
+        self.board
+    }
+
+    /*
+     * Returns the number of positions reachable on the board, with the player to move.
+     */
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /*
+     * Returns true if no position was analyzed, which never happens.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /*
+     * Returns the value of the position for the given player to move, or None if it can't be
+     * reached from the start.
+     */
+    pub fn value(&self, oth: Othello, color: Color) -> Option<i8> {
+        self.values.get(&(oth, color)).copied()
+    }
+
+    /*
+     * Returns the value of the starting position, for Black.
+     */
+    pub fn start_value(&self) -> i8 {
+        self.values[&(self.board.start(), Color::Black)]
+    }
+}
+
+//#################################################################################################
+//
+//                                    RETROGRADE TEST
+//
+//#################################################################################################
+
+/*
+ * Checks the values of the 4x4 board against the search, and the known result of the 6x6
+ * board, a White win by 20 disks to 16, which takes a while to solve and is ignored unless
+ * asked for.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn four_by_four() {
+        assert_eq!(SmallBoard::new(5), None);
+        assert_eq!(SmallBoard::FOUR.mask(), 0x0F0F0F0F);
+        assert!(SmallBoard::new(8).unwrap().start() == Othello::new());
+
+        let retrograde: Retrograde = Retrograde::compute(SmallBoard::FOUR);
+        assert_eq!(retrograde.start_value(), -10);
+        assert_eq!(SmallBoard::FOUR.solve(SmallBoard::FOUR.start(), Color::Black), -10);
+        for (&(oth, color), &value) in retrograde.values.iter().step_by(97) {
+            assert_eq!(SmallBoard::FOUR.solve(oth, color), value);
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn six_by_six() {
+        assert_eq!(SmallBoard::SIX.solve(SmallBoard::SIX.start(), Color::Black), -4);
+    }
+}
@@ -0,0 +1,253 @@
+use std::time::Duration;
+
+use crate::types::*;
+use crate::othello::*;
+use crate::game::*;
+use crate::player::*;
+use crate::eval::*;
+
+//#################################################################################################
+//
+//                                      SEARCHER TRAIT
+//
+//#################################################################################################
+
+/*
+ * The limits of a search: the depth in plies, the number of nodes, playouts for Monte-Carlo
+ * tree search, and the time it may take. None is no limit, and a searcher ignores the limits
+ * that mean nothing to it, falling back on its own settings when none applies.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct SearchLimits {
+    pub depth: Option<u8>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+}
+
+/*
+ * The result of a search: the best move, an empty BitBoard for a pass, its value from the
+ * point of view of the player to move if the searcher evaluates positions that way, and his
+ * probability of winning, which every searcher gives, the principal variation starting with
+ * the move, when known, and the depth, nodes and time of the search.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct SearchResult {
+    pub best: BitBoard,
+    pub value: Option<Value>,
+    pub win_probability: f64,
+    pub pv: Vec<BitBoard>,
+    pub depth: u8,
+    pub nodes: u64,
+    pub time: Duration,
+}
+
+/*
+ * An engine searching positions, so that applications and the tournament runner can use the
+ * alpha-beta AI, the Monte-Carlo tree search or third-party engines interchangeably, see
+ * SearcherPlayer. A search stops at its limits, or soon after stop is called, from another
+ * thread holding the stop flag of the searcher, still returning the best move found so far.
+ */
+pub trait Searcher {
+    fn best_move(&mut self, pos: (Othello, Color), limits: &SearchLimits) -> SearchResult;
+
+    fn stop(&self);
+
+    fn name(&self) -> String {
+        "Searcher".to_string()
+    }
+}
+
+//#################################################################################################
+//
+//                                     SEARCHER PLAYER
+//
+//#################################################################################################
+
+/*
+ * A player searching his moves with a searcher, within the given limits and, when his time
+ * is limited, an equal share of it for each of the moves he has left to play, plus most of the
+ * increment. The result of his last search is kept for his evaluation and stats.
+ */
+pub struct SearcherPlayer<S: Searcher> {
+    pub searcher: S,
+    pub limits: SearchLimits,
+    last: Option<SearchResult>,
+}
+
+impl<S: Searcher> SearcherPlayer<S> {
+    pub fn new(searcher: S, limits: SearchLimits) -> SearcherPlayer<S> {
+        SearcherPlayer { searcher, limits, last: None, }
+    }
+}
+
+impl<S: Searcher> Player for SearcherPlayer<S> {
+    fn choose_move(&mut self, game: &Game, clock: &Clock) -> Action {
+        let (oth, color): (Othello, Color) = (game.board(), game.color());
+        let share: Option<Duration> = clock.remaining(game.color()).map(|remaining| {
+            let moves: u32 = oth.empties() as u32 / 2 + 1;
+            (remaining / moves + clock.increment() * 3 / 4).min(remaining / 2)
+        });
+        let time: Option<Duration> = match (self.limits.time, share) {
+            (Some(time), Some(share)) => Some(time.min(share)),
+            (time, share) => time.or(share),
+        };
+        let limits: SearchLimits = SearchLimits { time, ..self.limits };
+        let result: SearchResult = self.searcher.best_move((oth, color), &limits);
+        let best: BitBoard = result.best;
+        self.last = Some(result);
+        Action::Play(best)
+    }
+
+    /*
+     * Returns the value of the last search, if the searcher gives one, as the search values it.
+     */
+    fn evaluation(&self) -> Option<i32> {
+        self.last.as_ref()?.value.map(Value::to_search)
+    }
+
+    fn search_stats(&self) -> Option<(u8, u64)> {
+        self.last.as_ref().map(|result| (result.depth, result.nodes))
+    }
+
+    fn name(&self) -> String {
+        self.searcher.name()
+    }
+}
+
+//#################################################################################################
+//
+//                                     SECOND OPINION
+//
+//#################################################################################################
+
+/*
+ * A position on which two searchers disagree, given by its index in the positions checked,
+ * with the results of both searches.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Disagreement {
+    pub index: usize,
+    pub ours: SearchResult,
+    pub theirs: SearchResult,
+}
+
+/*
+ * Searches each position with both searchers within the same limits, and returns those on
+ * which their probabilities of winning, the only value every searcher gives on the same scale,
+ * differ by more than the threshold. Cross-checking the AI against a trusted engine this way,
+ * such as an external one, points at the positions its evaluation or its search gets wrong.
+ */
+pub fn second_opinion(
+    ours: &mut dyn Searcher, theirs: &mut dyn Searcher, positions: &[(Othello, Color)],
+    limits: &SearchLimits, threshold: f64,
+) -> Vec<Disagreement> {
+    let mut disagreements: Vec<Disagreement> = Vec::new();
+
+    for (index, &pos) in positions.iter().enumerate() {
+        let (mine, other): (SearchResult, SearchResult) =
+            (ours.best_move(pos, limits), theirs.best_move(pos, limits));
+        if (mine.win_probability - other.win_probability).abs() > threshold {
+            disagreements.push(Disagreement { index, ours: mine, theirs: other, });
+        }
+    }
+
+    disagreements
+}
+
+//#################################################################################################
+//
+//                                      SEARCHER TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that the alpha-beta AI and the Monte-Carlo tree search answer within their limits as
+ * searchers, with the nodes of all their iterations, that a search without limits stops when
+ * told to, that searchers play games, and that a second opinion flags the positions where two
+ * searchers disagree too much.
+ */
+#[cfg(all(test, feature = "alphabeta", feature = "mcts"))]
+mod tests {
+    use super::*;
+    use std::sync::atomic::Ordering;
+    use crate::alphabeta::*;
+    use crate::mcts::*;
+    use crate::arena::*;
+    use crate::record::*;
+
+    #[test]
+    fn searchers() {
+        let (oth, color): (Othello, Color) = (Othello::new(), Color::Black);
+        let limits: SearchLimits = SearchLimits { depth: Some(3), ..SearchLimits::default() };
+        let mut alphabeta: AlphaBetaPlayer = AlphaBetaPlayer::new(9);
+        let result: SearchResult = Searcher::best_move(&mut alphabeta, (oth, color), &limits);
+        assert!(result.best & oth.gen_moves(color) != 0 && result.depth == 3);
+        assert!(result.value.is_some() && result.pv[0] == result.best);
+        let mut iterated: u64 = 0;
+        alphabeta.analyze(oth, color, alphabeta.stop_flag(), |iteration| {
+            if iteration.depth <= 3 {
+                iterated += iteration.stats.nodes;
+            }
+            if iteration.depth >= 3 {
+                alphabeta.stop_flag().store(true, Ordering::Relaxed);
+            }
+        });
+        alphabeta.stop_flag().store(false, Ordering::Relaxed);
+        assert_eq!(result.nodes, iterated);
+
+        let limits: SearchLimits = SearchLimits { nodes: Some(200), ..SearchLimits::default() };
+        let mut mcts: MctsPlayer = MctsPlayer::new(100_000);
+        let result: SearchResult = Searcher::best_move(&mut mcts, (oth, color), &limits);
+        assert!(result.best & oth.gen_moves(color) != 0 && result.nodes == 200);
+        assert!(result.value.is_none() && (0.0..=1.0).contains(&result.win_probability));
+
+        let stop = alphabeta.stop_flag();
+        let stopper = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            stop.store(true, Ordering::Relaxed);
+        });
+        let unlimited: SearchLimits = SearchLimits { depth: Some(60), ..SearchLimits::default() };
+        let result: SearchResult = Searcher::best_move(&mut alphabeta, (oth, color), &unlimited);
+        stopper.join().unwrap();
+        assert!(result.best & oth.gen_moves(color) != 0 && result.depth < 60);
+
+        let limits: SearchLimits = SearchLimits { depth: Some(1), ..SearchLimits::default() };
+        let player: SearcherPlayer<AlphaBetaPlayer> =
+            SearcherPlayer::new(AlphaBetaPlayer::new(4), limits);
+        let record: GameRecord =
+            Match::play(player, RandomPlayer::new(3), Rules::new(), TimeControl::Unlimited)
+                .unwrap();
+        assert_eq!(record.black, "AlphaBeta 4");
+    }
+
+    /*
+     * A searcher seeing the same chances in every position, but those of the opening.
+     */
+    struct Optimist(f64);
+
+    impl Searcher for Optimist {
+        fn best_move(&mut self, (oth, _): (Othello, Color), _: &SearchLimits) -> SearchResult {
+            let win_probability: f64 = if oth.empties() == 60 { 0.5 } else { self.0 };
+            SearchResult {
+                best: 0, value: None, win_probability, pv: Vec::new(), depth: 0, nodes: 0,
+                time: Duration::ZERO,
+            }
+        }
+
+        fn stop(&self) {}
+    }
+
+    #[test]
+    fn second_opinions() {
+        let start: Othello = Othello::new();
+        let positions: [(Othello, Color); 2] =
+            [(start, Color::Black), (start.make_move(Color::Black, 1 << 19), Color::White)];
+        let (mut ours, mut theirs): (Optimist, Optimist) = (Optimist(0.9), Optimist(0.6));
+        let disagreements: Vec<Disagreement> =
+            second_opinion(&mut ours, &mut theirs, &positions, &SearchLimits::default(), 0.2);
+        assert!(disagreements.len() == 1 && disagreements[0].index == 1);
+        assert_eq!(disagreements[0].theirs.win_probability, 0.6);
+        assert!(second_opinion(&mut ours, &mut theirs, &positions, &SearchLimits::default(), 0.5)
+            .is_empty());
+    }
+}
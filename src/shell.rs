@@ -0,0 +1,444 @@
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+
+use crate::types::*;
+use crate::coord::*;
+use crate::othello::*;
+use crate::game::*;
+use crate::record::*;
+use crate::alphabeta::*;
+use crate::eval::*;
+use crate::book::*;
+use crate::journal::*;
+use crate::config::*;
+use crate::diff::*;
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                       SHELL TYPE
+//
+//#################################################################################################
+
+/*
+ * The help of the shell, listing its commands.
+ */
+const HELP: &str = "\
+Commands:
+  board                 shows the position
+  new [corners]         goes back to the starting position, with the given number
+                        of corners given to Black as a handicap
+  load <line|file>      plays a line of moves like f5d6c3 or a transcript like
+                        F5D6C3 or f5-d6-pass, or loads a game record
+  play <moves>          plays moves, which can also be typed alone
+  sync <board>          plays the move leading to the board, given as 64 squares
+                        and the player to move, like from an external GUI
+  undo [n]              takes back the last n moves, 1 by default
+  hint [n]              suggests the n best moves, 3 by default
+  go [searchmoves <moves>] [excludemoves <moves>]
+                        starts the infinite analysis of the position, of the given
+                        moves only or of all but the excluded ones, if any
+  stop                  stops the infinite analysis
+  set <option> <value>  sets an option of the engine, like depth, personality or
+                        weights (or evalfile), restarting the analysis if any, or
+                        autobook, the book the analyses reaching autobook_depth
+                        are added to
+  save <file>           saves the game as a record
+  quit                  leaves the shell";
+
+/*
+ * An interactive shell to analyze positions with the engine: moves can be made and taken
+ * back, hints asked for, and the position analyzed deeper and deeper in the background until
+ * told to stop, the principal variation being printed after each iteration.
+ */
+pub struct Shell {
+    config: Config,
+    game: Game,
+    analysis: Option<(Arc<AtomicBool>, JoinHandle<()>)>,
+    searched: (BitBoard, BitBoard),
+}
+
+impl Shell {
+    /*
+     * Creates a new shell in the starting position, with the given engine options.
+     */
+    pub fn new(config: Config) -> Shell {
+        Shell {
+            config,
+            game: Game::new(),
+            analysis: None,
+            searched: (!0, 0),
+        }
+    }
+
+    /*
+     * Returns the game being analyzed.
+     */
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /*
+     * Reads commands from the terminal and executes them until told to quit or the input is
+     * closed. Errors are printed and don't stop the shell.
+     */
+    pub fn run(&mut self) -> Result<(), OthelloError> {
+        let stdin = std::io::stdin();
+        let mut stdout = std::io::stdout();
+        writeln!(stdout, "Type help for the list of commands.")?;
+
+        loop {
+            write!(stdout, "> ")?;
+            stdout.flush()?;
+
+            let mut line: String = String::new();
+            if stdin.lock().read_line(&mut line)? == 0 {
+                break;
+            }
+            match self.execute(&line, &mut stdout) {
+                Ok(true) => (),
+                Ok(false) => break,
+                Err(err) => writeln!(stdout, "{}", err)?,
+            }
+        }
+
+        self.stop();
+        Ok(())
+    }
+
+    /*
+     * Executes one command, writing its output. Returns false if the shell should quit.
+     */
+    pub fn execute(&mut self, line: &str, out: &mut dyn Write) -> Result<bool, OthelloError> {
+        let mut words = line.split_whitespace();
+        let command: &str = match words.next() {
+            Some(command) => command,
+            None => return Ok(true),
+        };
+        let rest: Vec<&str> = words.collect();
+
+        match command {
+            "help" => writeln!(out, "{}", HELP)?,
+            "board" => write!(out, "{}", board_text(&self.game))?,
+            "new" => match parse_count(rest.first(), 0)? {
+                corners @ 0..=4 => self.set_game(Game::with_handicap(corners as u8), out)?,
+                _ => return Err(parse_error("The handicap is at most 4 corners.")),
+            },
+            "load" => {
+                let arg: String = rest.join(" ");
+                let game: Game = if Path::new(&arg).is_file() {
+                    GameRecord::from_text(&std::fs::read_to_string(&arg)?)?.to_game()?
+                } else {
+                    Game::from_transcript(&arg)?
+                };
+                self.set_game(game, out)?;
+            },
+            "play" => self.play(&rest.concat(), out)?,
+            "sync" => {
+                let (after, _) = parse_position(&rest.concat())?;
+                let mut game: Game = self.game.clone();
+                match Othello::infer_move(&game.board(), &after) {
+                    Some((color, mv)) if color == game.color() => game.play(mv)?,
+                    _ => return Err(OthelloError::ProtocolError(format!("No move of the player \
+                        to move leads there.\n{}", board_diff(game.board(), after,
+                        ("Engine", "GUI"))))),
+                }
+                self.set_game(game, out)?;
+            },
+            "undo" => {
+                let n: usize = parse_count(rest.first(), 1)?;
+                let moves: Vec<BitBoard> = self.game.history().iter().copied()
+                    .filter(|&mv| mv != 0)
+                    .collect();
+                let mut game: Game = Game::with_handicap(self.game.handicap());
+                for &mv in &moves[..moves.len().saturating_sub(n)] {
+                    game.play(mv)?;
+                }
+                self.set_game(game, out)?;
+            },
+            "hint" => {
+                let n: usize = parse_count(rest.first(), 3)?;
+                let player: AlphaBetaPlayer = self.config.player()?;
+                for (i, hint) in player.hint(self.game.board(), self.game.color(), n)
+                    .iter()
+                    .enumerate()
+                {
+                    writeln!(out, "{}. {} {:>6}  {}", i + 1, hint.mv.square_name(),
+                        value_name(hint.value), hint.reason)?;
+                }
+            },
+            "go" => {
+                let (moves, excluded) = parse_go(&rest)?;
+                self.go(moves, excluded)?;
+            },
+            "stop" => self.stop(),
+            "set" => match rest.as_slice() {
+                [name, value @ ..] if !value.is_empty() => self.set(name, &value.join(" "), out)?,
+                _ => return Err(parse_error("Usage: set <option> <value>")),
+            },
+            "save" => {
+                let record: GameRecord = GameRecord::from_game(&self.game, "?", "?", &today());
+                std::fs::write(rest.join(" "), record.to_text())?;
+            },
+            "quit" | "exit" => return Ok(false),
+            _ if parse_square(command).is_some() => self.play(line, out)?,
+            _ => return Err(parse_error(format!("Unknown command: {}, type help.", command))),
+        }
+
+        Ok(true)
+    }
+
+    /*
+     * Plays a line of moves from the current position, or none of them if one is illegal.
+     */
+    fn play(&mut self, line: &str, out: &mut dyn Write) -> Result<(), OthelloError> {
+        let mut game: Game = self.game.clone();
+        game.play_line(line)?;
+        self.set_game(game, out)
+    }
+
+    /*
+     * Replaces the game, stopping the analysis of the previous position, and shows the new
+     * position.
+     */
+    fn set_game(&mut self, game: Game, out: &mut dyn Write) -> Result<(), OthelloError> {
+        self.stop();
+        self.game = game;
+        write!(out, "{}", board_text(&self.game))?;
+        Ok(())
+    }
+
+    /*
+     * Sets an option of the engine, the running analysis, if any, starting over with it. The
+     * weights and the book are loaded right away, so that a file swapped while tuning weights
+     * is checked, the option being left as it was if the file is malformed, and that the new
+     * analysis keeps nothing from the search with the previous file.
+     */
+    fn set(&mut self, name: &str, value: &str, out: &mut dyn Write) -> Result<(), OthelloError> {
+        let mut config: Config = self.config.clone();
+        config.set_option(name, value)?;
+        config.player()?;
+
+        let analyzing: bool = self.analysis.is_some();
+        self.stop();
+        self.config = config;
+        let file: bool =
+            ["book", "weights", "evalfile"].contains(&name.to_ascii_lowercase().as_str());
+        for warning in self.config.warnings().iter().filter(|_| file) {
+            writeln!(out, "Warning: {}.", warning)?;
+        }
+        if analyzing {
+            self.go(self.searched.0, self.searched.1)?;
+        }
+        Ok(())
+    }
+
+    /*
+     * Starts analyzing the given moves of the position but the excluded ones in the
+     * background, one more ply at a time, unless it is already being analyzed.
+     */
+    fn go(&mut self, moves: BitBoard, excluded: BitBoard) -> Result<(), OthelloError> {
+        if self.analysis.is_some() || self.game.is_over() {
+            return Ok(());
+        }
+        self.searched = (moves, excluded);
+
+        let player: AlphaBetaPlayer = self.config.player()?
+            .with_search_moves(moves)
+            .with_excluded_moves(excluded);
+        let (oth, color) = (self.game.board(), self.game.color());
+        let stop: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+        let flag: Arc<AtomicBool> = stop.clone();
+        let autobook: Option<(PathBuf, u8)> =
+            self.config.autobook.clone().map(|path| (path, self.config.autobook_depth));
+        let handle: JoinHandle<()> = std::thread::spawn(move || {
+            player.analyze(oth, color, flag, |iteration| {
+                print_iteration(iteration);
+                match &autobook {
+                    Some((path, depth)) if iteration.depth >= *depth => {
+                        if let Err(err) = add_to_book(path, oth, color, iteration) {
+                            println!("The position couldn't be added to the book: {}", err);
+                        }
+                    },
+                    _ => (),
+                }
+            });
+        });
+        self.analysis = Some((stop, handle));
+        Ok(())
+    }
+
+    /*
+     * Stops the analysis, if any, and waits for the search to be aborted.
+     */
+    fn stop(&mut self) {
+        if let Some((stop, handle)) = self.analysis.take() {
+            stop.store(true, Ordering::Relaxed);
+            let _ = handle.join();
+        }
+    }
+}
+
+/*
+ * Prints an iteration of the analysis on one line.
+ */
+fn print_iteration(iteration: &Iteration) {
+    println!("depth {:>2} {:>6}  {}  ({} nodes, {:.3}s)", iteration.depth,
+        value_name(iteration.value), format_pv(&iteration.pv), iteration.stats.nodes,
+        iteration.stats.time.as_secs_f64());
+}
+
+/*
+ * Adds the position analyzed to the book in the given file, creating it if needed, with the
+ * position after the best move so that the book plays it, see compact_book. Each deeper
+ * iteration replaces the evaluation of the position by the previous one.
+ */
+fn add_to_book(path: &Path, oth: Othello, color: Color, iteration: &Iteration)
+    -> Result<(), OthelloError>
+{
+    let entry: JournalEntry =
+        JournalEntry { depth: iteration.depth, score: iteration.value, best: iteration.mv, };
+    let added: Book = compact_book(&[(oth, color, entry)]);
+    let book: Book = if path.exists() { Book::open(path)? } else { Book::from_entries(Vec::new()) };
+    book.with_entries(added.entries()).save(path)
+}
+
+/*
+ * Parses the arguments of the go command, returning the moves to search, all by default, and
+ * those to exclude.
+ */
+fn parse_go(words: &[&str]) -> Result<(BitBoard, BitBoard), OthelloError> {
+    let (mut moves, mut excluded): (BitBoard, BitBoard) = (!0, 0);
+    let mut words = words.iter().peekable();
+
+    while let Some(&word) = words.next() {
+        let mut list: String = String::new();
+        while let Some(square) = words.next_if(|word| parse_moves(word).is_ok()) {
+            list += square;
+        }
+        match word {
+            "searchmoves" if !list.is_empty() => moves = parse_moves(&list)?,
+            "excludemoves" if !list.is_empty() => excluded = parse_moves(&list)?,
+            _ => return Err(parse_error("Usage: go [searchmoves <moves>] [excludemoves <moves>]")),
+        }
+    }
+
+    Ok((moves, excluded))
+}
+
+/*
+ * Parses the optional count of a command.
+ */
+fn parse_count(word: Option<&&str>, default: usize) -> Result<usize, OthelloError> {
+    match word {
+        Some(word) => word.parse().map_err(|_| parse_error(format!("Invalid count: {}", word))),
+        None => Ok(default),
+    }
+}
+
+/*
+ * Draws the board as plain text, legal moves being marked with a star, followed by the player
+ * to move or the outcome of the game.
+ */
+fn board_text(game: &Game) -> String {
+    let (oth, moves) = (game.board(), game.moves());
+    let mut text: String = String::from("  a b c d e f g h\n");
+
+    for y in 0..8 {
+        text += &format!("{}", Coord { x: 0, y, }.row(convention()));
+        for x in 0..8 {
+            text.push(' ');
+            text.push(match oth.get_square(x, y) {
+                Square::Empty if moves.contains(x, y) => '*',
+                square => square.to_char(),
+            });
+        }
+        text.push('\n');
+    }
+
+    text += &match game.outcome() {
+        Some(Outcome { winner: Some(winner), .. }) => format!("{} won.\n", winner),
+        Some(Outcome { winner: None, .. }) => "Draw.\n".to_string(),
+        None => format!("{} to move.\n", game.color()),
+    };
+    text
+}
+
+//#################################################################################################
+//
+//                                       SHELL TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that moves can be made, taken back, analyzed and saved from the shell, that weights
+ * can be swapped during an analysis, and that deep enough analyses are added to the autobook.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands() {
+        let mut shell: Shell = Shell::new(Config { depth: 2, ..Config::default() });
+        let mut out: Vec<u8> = Vec::new();
+
+        assert!(shell.execute("load f5d6", &mut out).unwrap());
+        assert!(shell.execute("c3 d3", &mut out).unwrap());
+        assert_eq!(shell.game().history().len(), 4);
+        assert!(shell.execute("play a1", &mut out).is_err());
+        assert!(shell.execute("undo 3", &mut out).unwrap());
+        assert_eq!(shell.game().history(), &[1 << 37]);
+
+        let after: Othello = shell.game().board().make_move(Color::White, 1 << 45);
+        let board: String = (0..64).map(|i| after.get_square(i % 8, i / 8).to_char()).collect();
+        assert!(shell.execute(&format!("sync {} X", board), &mut out).unwrap());
+        assert_eq!(shell.game().history(), &[1 << 37, 1 << 45]);
+        assert!(shell.execute(&format!("sync {} X", board), &mut out).is_err());
+        assert!(shell.execute("undo", &mut out).unwrap());
+
+        assert!(shell.execute("go searchmoves f4 c3", &mut out).unwrap());
+        let weights = std::env::temp_dir().join(format!("othello-shell-{}.weights",
+            std::process::id()));
+        Personality::Greedy.weights().save(&weights).unwrap();
+        assert!(shell.execute(&format!("set evalfile {}", weights.display()), &mut out).unwrap());
+        assert!(shell.analysis.is_some() && shell.searched == (1 << 29 | 1 << 18, 0));
+        std::fs::write(&weights, b"not weights").unwrap();
+        assert!(shell.execute(&format!("set weights {}", weights.display()), &mut out).is_err());
+        assert!(shell.config.weights.is_some() && shell.analysis.is_some());
+        std::fs::remove_file(&weights).unwrap();
+        assert!(shell.execute("stop", &mut out).unwrap());
+        assert!(shell.execute("go searchmoves", &mut out).is_err());
+        assert_eq!(parse_go(&["excludemoves", "c3", "searchmoves", "c3e6"]).unwrap(),
+            (1 << 18 | 1 << 44, 1 << 18));
+
+        let book = std::env::temp_dir().join(format!("othello-shell-{}.book", std::process::id()));
+        assert!(shell.execute(&format!("set autobook {}", book.display()), &mut out).unwrap());
+        assert!(shell.execute("set autobook_depth 3", &mut out).unwrap());
+        assert!(shell.execute("go", &mut out).unwrap());
+        let booked = || Book::open(&book).ok()?.probe(shell.game().board(), Color::White);
+        while booked().is_none_or(|entry| entry.depth < 4) {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert!(shell.execute("stop", &mut out).unwrap());
+        assert!(!Book::open(&book).unwrap().moves(shell.game().board(), Color::White).is_empty());
+        std::fs::remove_file(&book).unwrap();
+
+        out.clear();
+        assert!(shell.execute("hint 2", &mut out).unwrap());
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 2);
+
+        let path = std::env::temp_dir().join(format!("othello-shell-{}", std::process::id()));
+        let mut out: Vec<u8> = Vec::new();
+        shell.execute(&format!("save {}", path.display()), &mut out).unwrap();
+        shell.execute("new", &mut out).unwrap();
+        shell.execute(&format!("load {}", path.display()), &mut out).unwrap();
+        assert_eq!(shell.game().history(), &[1 << 37]);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(!shell.execute("quit", &mut out).unwrap());
+    }
+}
@@ -0,0 +1,379 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use crate::types::*;
+use crate::game::*;
+use crate::player::*;
+use crate::record::{json_string, termination};
+use crate::eval::value_name;
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                     SPECTATORS TYPE
+//
+//#################################################################################################
+
+/*
+ * The GUID appended to the key of a client to accept its WebSocket connection, per RFC 6455.
+ */
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/*
+ * The page served to browsers asking for it without upgrading to WebSocket, showing the board
+ * of the game broadcast, its score, clock and evaluation, and its outcome once over.
+ */
+const VIEWER: &str = r#"<!DOCTYPE html>
+<html><head><meta charset="utf-8"><title>Othello</title></head>
+<body style="font-family: monospace">
+<h3 id="players"></h3><pre id="board" style="font-size: 2em"></pre><p id="info"></p>
+<script>
+const socket = new WebSocket("ws://" + location.host + "/");
+socket.onmessage = (event) => {
+    const state = JSON.parse(event.data);
+    document.getElementById("players").textContent = state.black + " (X) - " + state.white
+        + " (O)";
+    let board = "  a b c d e f g h\n";
+    for (let y = 0; y < 8; y++) {
+        board += (y + 1) + " " + state.board.slice(8 * y, 8 * y + 8).split("").join(" ") + "\n";
+    }
+    document.getElementById("board").textContent = board;
+    const clock = state.clock.map((ms) => ms === null ? "-" : (ms / 1000).toFixed(1) + "s");
+    let info = "Ply " + state.ply + ", " + state.score.join(" - ") + ", clock " + clock.join(" / ")
+        + (state.evaluation === null ? "" : ", evaluation " + state.evaluation);
+    if (state.outcome !== null) {
+        info += ", " + (state.outcome.winner || "nobody") + " won (" + state.outcome.reason + ")";
+    }
+    document.getElementById("info").textContent = info;
+};
+</script></body></html>
+"#;
+
+/*
+ * How long reading the handshake of a spectator or sending him a message may take before he is
+ * dropped, so that a stalled browser can't hold the match up.
+ */
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/*
+ * The spectators of matches, browsers connected through WebSocket to the address the server
+ * listens on, where they are also served a page showing the game. Connections are accepted in
+ * a background thread, which stops at the next one once the server is dropped, and each
+ * message is broadcast to every spectator connected, as a text frame. Spectators never send
+ * anything but their handshake, and those who can't be written to anymore are dropped. Matches
+ * and the tournament runner send the state of their game after each move, see state_json.
+ */
+pub struct Spectators {
+    addr: SocketAddr,
+    clients: Arc<Mutex<Vec<TcpStream>>>,
+}
+
+impl Spectators {
+    /*
+     * Listens for spectators on the given address, "127.0.0.1:8080" for instance.
+     */
+    pub fn bind(addr: impl ToSocketAddrs) -> Result<Spectators, OthelloError> {
+        let listener: TcpListener = TcpListener::bind(addr)?;
+        let addr: SocketAddr = listener.local_addr()?;
+        let clients: Arc<Mutex<Vec<TcpStream>>> = Arc::new(Mutex::new(Vec::new()));
+        let weak: Weak<Mutex<Vec<TcpStream>>> = Arc::downgrade(&clients);
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let clients: Arc<Mutex<Vec<TcpStream>>> = match weak.upgrade() {
+                    Some(clients) => clients,
+                    None => return,
+                };
+                // A failed handshake only loses that spectator.
+                if let Ok(stream) = stream {
+                    let _ = accept(stream, &clients);
+                }
+            }
+        });
+
+        Ok(Spectators { addr, clients, })
+    }
+
+    /*
+     * Returns the address the server listens on, useful when binding to port 0.
+     */
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /*
+     * Returns the number of spectators connected.
+     */
+    pub fn count(&self) -> usize {
+        self.clients.lock().unwrap().len()
+    }
+
+    /*
+     * Sends the given text to every spectator, dropping those who can't receive it.
+     */
+    pub fn broadcast(&self, text: &str) {
+        let frame: Vec<u8> = text_frame(text);
+        self.clients.lock().unwrap().retain_mut(|client| client.write_all(&frame).is_ok());
+    }
+}
+
+/*
+ * Reads the handshake of a new spectator and accepts his connection, adding him to the
+ * clients. The lock is held while answering, so that he gets no frame before the answer. A
+ * plain request gets the viewer page instead.
+ */
+fn accept(stream: TcpStream, clients: &Mutex<Vec<TcpStream>>) -> Result<(), OthelloError> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut reader: BufReader<TcpStream> = BufReader::new(stream.try_clone()?);
+    let mut key: Option<String> = None;
+
+    loop {
+        let mut line: String = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            let message: &str = "Connection closed during the WebSocket handshake.";
+            return Err(OthelloError::ProtocolError(message.to_string()));
+        }
+        let line: &str = line.trim();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("Sec-WebSocket-Key") {
+                key = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    let mut stream: TcpStream = stream;
+    let key: String = match key {
+        Some(key) => key,
+        None => {
+            write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\
+                Connection: close\r\n\r\n{}", VIEWER.len(), VIEWER)?;
+            return Ok(());
+        },
+    };
+    let mut clients = clients.lock().unwrap();
+    write!(stream, "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\
+        Connection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n", accept_key(&key))?;
+    clients.push(stream);
+    Ok(())
+}
+
+/*
+ * Returns the answer to the key of a WebSocket handshake: the base64 encoding of the SHA-1
+ * hash of the key followed by the GUID.
+ */
+fn accept_key(key: &str) -> String {
+    base64(&sha1(format!("{}{}", key, WEBSOCKET_GUID).as_bytes()))
+}
+
+/*
+ * Returns the unmasked WebSocket frame carrying the given text in one piece.
+ */
+fn text_frame(text: &str) -> Vec<u8> {
+    let len: usize = text.len();
+    let mut frame: Vec<u8> = vec![0x81];
+    if len < 126 {
+        frame.push(len as u8);
+    } else if len <= u16::MAX as usize {
+        frame.push(126);
+        frame.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        frame.push(127);
+        frame.extend_from_slice(&(len as u64).to_be_bytes());
+    }
+    frame.extend_from_slice(text.as_bytes());
+    frame
+}
+
+/*
+ * Returns the SHA-1 hash of the data, only used for the handshake.
+ */
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+    let mut message: Vec<u8> = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut w: [u32; 80] = [0; 80];
+        for i in 0..80 {
+            w[i] = if i < 16 {
+                u32::from_be_bytes([block[4 * i], block[4 * i + 1], block[4 * i + 2],
+                    block[4 * i + 3]])
+            } else {
+                (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1)
+            };
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k): (u32, u32) = match i / 20 {
+                0 => ((b & c) | (!b & d), 0x5A827999),
+                1 => (b ^ c ^ d, 0x6ED9EBA1),
+                2 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp: u32 = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k)
+                .wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (h, x) in h.iter_mut().zip([a, b, c, d, e]) {
+            *h = h.wrapping_add(x);
+        }
+    }
+
+    let mut hash: [u8; 20] = [0; 20];
+    for (chunk, h) in hash.chunks_mut(4).zip(h) {
+        chunk.copy_from_slice(&h.to_be_bytes());
+    }
+    hash
+}
+
+/*
+ * Returns the standard base64 encoding of the data, padded.
+ */
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text: String = String::new();
+    for chunk in data.chunks(3) {
+        let bytes: [u8; 3] = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n: u32 = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+//#################################################################################################
+//
+//                                       GAME STATE
+//
+//#################################################################################################
+
+/*
+ * Returns the state of a game as a JSON object, sent to spectators after each move:
+ *   - "black" and "white", the names of the players.
+ *   - "board", the 64 squares from a1 to h8, row by row, 'X' for black, 'O' for white and '-'
+ *     for empty ones, and "toMove", "black" or "white".
+ *   - "ply", the number of plies played, and "lastMove", the last one, like "f5", or null.
+ *   - "score", the disks of each player, as [black, white].
+ *   - "clock", the milliseconds left to each player, null when unlimited.
+ *   - "evaluation", that of the last move from black's point of view, or null, and "value",
+ *     the same written for users, like "+12" or "win in 7", see Value.
+ *   - "outcome", null while the game goes on, and otherwise the "winner", "black", "white" or
+ *     null for a draw, and the "reason", "finished" or the termination of the game records.
+ */
+pub fn state_json(
+    game: &Game, clock: &Clock, black: &str, white: &str, evaluation: Option<i32>,
+) -> String {
+    let board: String = (0..64).map(|square| {
+        let oth = game.board();
+        if oth.get_bitboard(Color::Black) >> square & 1 != 0 {
+            'X'
+        } else if oth.get_bitboard(Color::White) >> square & 1 != 0 {
+            'O'
+        } else {
+            '-'
+        }
+    }).collect();
+    let color = |color: Color| match color {
+        Color::Black => "\"black\"",
+        Color::White => "\"white\"",
+    };
+    let null = |value: Option<String>| value.unwrap_or_else(|| "null".to_string());
+    let remaining = |color: Color| null(clock.remaining(color).map(|t| t.as_millis().to_string()));
+    let last_move: BitBoard = game.last_move();
+    let outcome: Option<String> = game.outcome().map(|outcome| format!(
+        "{{\"winner\":{},\"reason\":{}}}",
+        outcome.winner.map_or("null", color),
+        json_string(termination(outcome.reason).unwrap_or("finished")),
+    ));
+
+    format!("{{\"black\":{},\"white\":{},\"board\":\"{}\",\"toMove\":{},\"ply\":{},\
+        \"lastMove\":{},\"score\":[{},{}],\"clock\":[{},{}],\"evaluation\":{},\"value\":{},\
+        \"outcome\":{}}}",
+        json_string(black), json_string(white), board, color(game.color()),
+        game.history().len(),
+        null(Some(last_move).filter(|&mv| mv != 0).map(|mv| json_string(&mv.square_name()))),
+        game.board().score().get(Color::Black), game.board().score().get(Color::White),
+        remaining(Color::Black), remaining(Color::White),
+        null(evaluation.map(|value| value.to_string())),
+        null(evaluation.map(|value| json_string(&value_name(value)))), null(outcome))
+}
+
+//#################################################################################################
+//
+//                                     SPECTATE TEST
+//
+//#################################################################################################
+
+/*
+ * Checks the handshake against the example of RFC 6455, that browsers get the viewer page, and
+ * that a connected spectator receives the broadcast states.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn spectate() {
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+        assert_eq!(base64(b"ab"), "YWI=");
+
+        let spectators: Spectators = Spectators::bind("127.0.0.1:0").unwrap();
+        let mut browser: TcpStream = TcpStream::connect(spectators.local_addr()).unwrap();
+        write!(browser, "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut page: String = String::new();
+        browser.read_to_string(&mut page).unwrap();
+        assert!(page.starts_with("HTTP/1.1 200 OK") && page.ends_with(VIEWER));
+
+        let mut client: TcpStream = TcpStream::connect(spectators.local_addr()).unwrap();
+        write!(client, "GET / HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\n\
+            Connection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+            Sec-WebSocket-Version: 13\r\n\r\n").unwrap();
+        let mut reader: BufReader<TcpStream> = BufReader::new(client.try_clone().unwrap());
+        let mut answer: String = String::new();
+        while !answer.ends_with("\r\n\r\n") {
+            reader.read_line(&mut answer).unwrap();
+        }
+        assert!(answer.starts_with("HTTP/1.1 101"));
+        assert!(answer.contains("s3pPLMBiTxaQ9kYGzzhZRbK+xOo="));
+        assert_eq!(spectators.count(), 1);
+
+        let mut game: Game = Game::new();
+        game.play_line("f5").unwrap();
+        let state: String = state_json(&game, &Clock::unlimited(), "Alice", "B\"ob", Some(3));
+        assert!(state.contains("\"white\":\"B\\\"ob\""));
+        assert!(state.contains("\"lastMove\":\"f5\",\"score\":[4,1],\"clock\":[null,null]"));
+        assert!(state.ends_with("\"evaluation\":3,\"value\":\"+3\",\"outcome\":null}"));
+
+        spectators.broadcast(&state);
+        let mut header: [u8; 2] = [0; 2];
+        reader.read_exact(&mut header).unwrap();
+        assert_eq!(header, [0x81, 126]);
+        let mut len: [u8; 2] = [0; 2];
+        reader.read_exact(&mut len).unwrap();
+        let mut payload: Vec<u8> = vec![0; u16::from_be_bytes(len) as usize];
+        reader.read_exact(&mut payload).unwrap();
+        assert_eq!(payload, state.as_bytes());
+    }
+}
@@ -0,0 +1,216 @@
+//#################################################################################################
+//
+//                                          ELO
+//
+//#################################################################################################
+
+/*
+ * The quantile of the normal distribution bounding 95% confidence intervals on both sides.
+ */
+const Z_95: f64 = 1.959964;
+
+/*
+ * Returns the Elo difference that makes a player expect the given score, between 0 and 1, on
+ * average against his opponent. A perfect score is infinitely better, and a null one
+ * infinitely worse.
+ */
+pub fn elo_difference(score: f64) -> f64 {
+    if score <= 0.0 {
+        f64::NEG_INFINITY
+    } else if score >= 1.0 {
+        f64::INFINITY
+    } else {
+        -400.0 * (1.0 / score - 1.0).log10()
+    }
+}
+
+/*
+ * Returns the score a player expects against an opponent rated the given Elo difference
+ * lower, the inverse of elo_difference.
+ */
+pub fn expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/*
+ * The Elo difference between two players measured from the wins, draws and losses of the
+ * first one, with the half width of its 95% confidence interval, and the likelihood of
+ * superiority: the probability that the first player is really the stronger one, given his
+ * wins and losses, draws telling nothing about it.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct EloEstimate {
+    pub elo: f64,
+    pub error: f64,
+    pub los: f64,
+}
+
+impl EloEstimate {
+    /*
+     * Estimates the Elo difference from the games, its error from the variance of their scores.
+     * Without games, the difference is 0, with an infinite error, and the likelihood 0.5, and
+     * the error of a perfect or null score is infinite too.
+     */
+    pub fn new(wins: u32, draws: u32, losses: u32) -> EloEstimate {
+        let games: f64 = (wins + draws + losses) as f64;
+        let decisive: f64 = (wins + losses) as f64;
+        let los: f64 = if decisive == 0.0 {
+            0.5
+        } else {
+            0.5 * (1.0 + erf((wins as f64 - losses as f64) / (2.0 * decisive).sqrt()))
+        };
+        if games == 0.0 {
+            return EloEstimate { elo: 0.0, error: f64::INFINITY, los, };
+        }
+
+        let score: f64 = (wins as f64 + draws as f64 / 2.0) / games;
+        let variance: f64 = (wins as f64 * (1.0 - score).powi(2)
+            + draws as f64 * (0.5 - score).powi(2)
+            + losses as f64 * score.powi(2)) / games;
+        let margin: f64 = Z_95 * (variance / games).sqrt();
+        let error: f64 = if score == 0.0 || score == 1.0 {
+            f64::INFINITY
+        } else {
+            (elo_difference(score + margin) - elo_difference(score - margin)) / 2.0
+        };
+
+        EloEstimate { elo: elo_difference(score), error, los, }
+    }
+}
+
+/*
+ * Writes the estimate as "+35.2 ± 12.4 Elo, LOS 99.8%".
+ */
+impl std::fmt::Display for EloEstimate {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:+.1} ± {:.1} Elo, LOS {:.1}%", self.elo, self.error, 100.0 * self.los)
+    }
+}
+
+/*
+ * Returns the error function of x, within 1.5e-7, by the approximation 7.1.26 of Abramowitz and
+ * Stegun.
+ */
+fn erf(x: f64) -> f64 {
+    let t: f64 = 1.0 / (1.0 + 0.3275911 * x.abs());
+    let poly: f64 = t * (0.254829592
+        + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    (1.0 - poly * (-x * x).exp()).copysign(x)
+}
+
+//#################################################################################################
+//
+//                                          SPRT
+//
+//#################################################################################################
+
+/*
+ * A sequential probability ratio test, telling whether a player is elo0 or elo1 Elo stronger
+ * than his opponent, with the given probabilities of false positives, alpha, and false
+ * negatives, beta. The log-likelihood ratio of the games, approximated from the mean and the
+ * variance of their scores, is compared to the bounds of the test after each game, and the
+ * test stops as soon as it crosses one.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+/*
+ * The outcome of an SPRT so far: elo0 or elo1 accepted, or more games needed.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SprtResult {
+    H0, H1, Continue,
+}
+
+impl Sprt {
+    /*
+     * Creates a test between the two Elo differences, with 5% of false positives and negatives.
+     */
+    pub fn new(elo0: f64, elo1: f64) -> Sprt {
+        Sprt { elo0, elo1, alpha: 0.05, beta: 0.05, }
+    }
+
+    /*
+     * Returns the lower and upper bounds of the log-likelihood ratio, below which elo0 is
+     * accepted and above which elo1 is.
+     */
+    pub fn bounds(&self) -> (f64, f64) {
+        ((self.beta / (1.0 - self.alpha)).ln(), ((1.0 - self.beta) / self.alpha).ln())
+    }
+
+    /*
+     * Returns the log-likelihood ratio of elo1 against elo0 given the games, 0 before any
+     * game or while all of them have the same score.
+     */
+    pub fn llr(&self, wins: u32, draws: u32, losses: u32) -> f64 {
+        let games: f64 = (wins + draws + losses) as f64;
+        if games == 0.0 {
+            return 0.0;
+        }
+        let score: f64 = (wins as f64 + draws as f64 / 2.0) / games;
+        let variance: f64 = (wins as f64 * (1.0 - score).powi(2)
+            + draws as f64 * (0.5 - score).powi(2)
+            + losses as f64 * score.powi(2)) / games;
+        if variance == 0.0 {
+            return 0.0;
+        }
+        let (score0, score1): (f64, f64) = (expected_score(self.elo0), expected_score(self.elo1));
+        games * (score1 - score0) * (2.0 * score - score0 - score1) / (2.0 * variance)
+    }
+
+    /*
+     * Returns the outcome of the test given the games.
+     */
+    pub fn result(&self, wins: u32, draws: u32, losses: u32) -> SprtResult {
+        let (lower, upper): (f64, f64) = self.bounds();
+        let llr: f64 = self.llr(wins, draws, losses);
+        if llr <= lower {
+            SprtResult::H0
+        } else if llr >= upper {
+            SprtResult::H1
+        } else {
+            SprtResult::Continue
+        }
+    }
+}
+
+//#################################################################################################
+//
+//                                       STATS TEST
+//
+//#################################################################################################
+
+/*
+ * Checks Elo differences against known values, that their error shrinks with more games and
+ * that the likelihood of superiority and the SPRT side with the player winning more.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elo() {
+        assert!(elo_difference(0.5).abs() < 1e-9);
+        assert!((elo_difference(0.75) - 190.85).abs() < 0.01);
+        assert!((expected_score(elo_difference(0.3)) - 0.3).abs() < 1e-9);
+        assert!((erf(1.0) - 0.842700793).abs() < 1e-6 && (erf(-0.5) + 0.520499878).abs() < 1e-6);
+
+        let few: EloEstimate = EloEstimate::new(6, 2, 2);
+        let many: EloEstimate = EloEstimate::new(60, 20, 20);
+        assert!((few.elo - many.elo).abs() < 1e-9 && few.elo > 0.0);
+        assert!(many.error < few.error && many.los > few.los && few.los > 0.5);
+        assert!((EloEstimate::new(5, 10, 5).los - 0.5).abs() < 1e-6);
+        assert_eq!(EloEstimate::new(0, 0, 0).to_string(), "+0.0 ± inf Elo, LOS 50.0%");
+        assert_eq!(EloEstimate::new(0, 0, 3).error, f64::INFINITY);
+
+        let sprt: Sprt = Sprt::new(0.0, 10.0);
+        assert_eq!(sprt.result(10, 10, 10), SprtResult::Continue);
+        assert_eq!(sprt.result(600, 200, 400), SprtResult::H1);
+        assert_eq!(sprt.result(400, 200, 600), SprtResult::H0);
+    }
+}
@@ -0,0 +1,143 @@
+use crate::types::*;
+use crate::othello::*;
+
+//#################################################################################################
+//
+//                                     SYMMETRY TYPE
+//
+//#################################################################################################
+
+/*
+ * The 8 symmetries of the board. Each one is a combination of a transposition along the a1-h8
+ * diagonal, a mirroring of the columns and a flipping of the rows, applied in that order, the
+ * bits of its discriminant telling which ones are used.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Symmetry {
+    Identity = 0,
+    FlipVertical = 1,
+    FlipHorizontal = 2,
+    Rotate180 = 3,
+    Transpose = 4,
+    RotateLeft = 5,
+    RotateRight = 6,
+    AntiTranspose = 7,
+}
+
+impl Symmetry {
+    /*
+     * All the symmetries, the identity first.
+     */
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity, Symmetry::FlipVertical, Symmetry::FlipHorizontal,
+        Symmetry::Rotate180, Symmetry::Transpose, Symmetry::RotateLeft, Symmetry::RotateRight,
+        Symmetry::AntiTranspose,
+    ];
+
+    /*
+     * Applies the symmetry to a BitBoard.
+     */
+    pub const fn apply(self, mut bb: BitBoard) -> BitBoard {
+        let bits: u8 = self as u8;
+
+        if bits & 4 != 0 {
+            let mut t: BitBoard;
+            t = 0x0F0F0F0F00000000 & (bb ^ (bb << 28));
+            bb ^= t ^ (t >> 28);
+            t = 0x3333000033330000 & (bb ^ (bb << 14));
+            bb ^= t ^ (t >> 14);
+            t = 0x5500550055005500 & (bb ^ (bb << 7));
+            bb ^= t ^ (t >> 7);
+        }
+        if bits & 2 != 0 {
+            bb = ((bb >> 1) & 0x5555555555555555) | ((bb & 0x5555555555555555) << 1);
+            bb = ((bb >> 2) & 0x3333333333333333) | ((bb & 0x3333333333333333) << 2);
+            bb = ((bb >> 4) & 0x0F0F0F0F0F0F0F0F) | ((bb & 0x0F0F0F0F0F0F0F0F) << 4);
+        }
+        if bits & 1 != 0 {
+            bb = bb.swap_bytes();
+        }
+
+        bb
+    }
+
+    /*
+     * Returns the symmetry undoing this one. Every symmetry is its own inverse, except for the
+     * rotations by a quarter turn.
+     */
+    pub const fn inverse(self) -> Symmetry {
+        match self {
+            Symmetry::RotateLeft => Symmetry::RotateRight,
+            Symmetry::RotateRight => Symmetry::RotateLeft,
+            symmetry => symmetry,
+        }
+    }
+}
+
+impl Othello {
+    /*
+     * Applies the given symmetry to the board.
+     */
+    pub const fn transform(&self, symmetry: Symmetry) -> Othello {
+        Othello::create(
+            symmetry.apply(self.get_bitboard(Color::Black)),
+            symmetry.apply(self.get_bitboard(Color::White)),
+        )
+    }
+
+    /*
+     * Returns the canonical form of the board, the same for all its symmetric boards, along with
+     * the symmetry turning the board into it.
+     */
+    pub fn canonical(&self) -> (Othello, Symmetry) {
+        let key = |oth: Othello| (oth.get_bitboard(Color::Black), oth.get_bitboard(Color::White));
+        let mut best: (Othello, Symmetry) = (*self, Symmetry::Identity);
+
+        for &symmetry in &Symmetry::ALL[1..] {
+            let oth: Othello = self.transform(symmetry);
+            if key(oth) < key(best.0) {
+                best = (oth, symmetry);
+            }
+        }
+
+        best
+    }
+}
+
+//#################################################################################################
+//
+//                                     SYMMETRY TEST
+//
+//#################################################################################################
+
+/*
+ * Checks the symmetries square by square against their definition, and the canonical form.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symmetries() {
+        for &symmetry in &Symmetry::ALL {
+            let bits: u8 = symmetry as u8;
+            for index in 0..64 {
+                let (mut x, mut y): (u8, u8) = (index % 8, index / 8);
+                if bits & 4 != 0 { core::mem::swap(&mut x, &mut y); }
+                if bits & 2 != 0 { x = 7 - x; }
+                if bits & 1 != 0 { y = 7 - y; }
+
+                let bb: BitBoard = 1u64 << index;
+                assert_eq!(symmetry.apply(bb), 1u64 << (x + 8 * y));
+                assert_eq!(symmetry.inverse().apply(symmetry.apply(bb)), bb);
+            }
+        }
+
+        let oth: Othello = Othello::new().make_move(Color::Black, 1u64 << 37);
+        for &symmetry in &Symmetry::ALL {
+            let (canonical, to_canonical) = oth.transform(symmetry).canonical();
+            assert!(canonical == oth.canonical().0);
+            assert!(oth.transform(symmetry).transform(to_canonical) == canonical);
+        }
+    }
+}
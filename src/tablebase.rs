@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::types::*;
+use crate::othello::*;
+use crate::wire::*;
+use crate::endgame::*;
+use crate::game::*;
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                     TABLEBASE TYPE
+//
+//#################################################################################################
+
+/*
+ * The number of empty squares a line must leave for a tablebase to be generated from it, beyond
+ * which there are far too many reachable positions.
+ */
+pub const MAX_EMPTIES: u8 = 12;
+
+/*
+ * The number of empty squares at and below which positions aren't stored, the solver being
+ * faster than a lookup there.
+ */
+const SOLVER_EMPTIES: u8 = 6;
+
+/*
+ * The header of tablebase files: a magic number, then the number of entries as a little-endian
+ * u32. Each entry then takes 14 bytes: the packed wire encoding of the canonical position and
+ * the player to move, and the final disc differential with perfect play for that player, as
+ * an i8. Entries are sorted by encoding, so that they can be found by binary search.
+ */
+const MAGIC: &[u8; 8] = b"OTHELLTB";
+const HEADER_SIZE: usize = 12;
+const ENTRY_SIZE: usize = PACKED_SIZE + 1;
+
+type Entry = [u8; ENTRY_SIZE];
+
+/*
+ * An endgame tablebase, holding the exact scores of every position reachable from the end of
+ * a given line, down to a few empty squares. The AI can consult it before searching.
+ */
+pub struct Tablebase {
+    entries: Vec<Entry>,
+}
+
+impl Tablebase {
+    /*
+     * Plays the given line from the starting position, which must leave at most MAX_EMPTIES
+     * empty squares, and solves every position reachable from there. Each position is solved
+     * once, from the scores of the positions following it, whatever the number of move orders
+     * leading to it. Returns an error if the line is illegal or too short.
+     */
+    pub fn generate(line: &[BitBoard]) -> Result<Tablebase, OthelloError> {
+        let mut game: Game = Game::new();
+        // Passes are made automatically by the game.
+        for &mv in line.iter().filter(|&&mv| mv != 0) {
+            game.play(mv)?;
+        }
+
+        if game.board().empties() > MAX_EMPTIES {
+            let msg: String = format!("The line leaves more than {} empty squares.", MAX_EMPTIES);
+            return Err(parse_error(msg));
+        }
+
+        let mut scores: HashMap<[u8; PACKED_SIZE], i8> = HashMap::new();
+        let score: i32 = solve_all(game.board(), game.color(), &mut scores);
+        scores.insert(key(game.board(), game.color()), score as i8);
+
+        let mut entries: Vec<Entry> = scores.into_iter().map(|(key, score)| {
+            let mut entry: Entry = [0; ENTRY_SIZE];
+            entry[..PACKED_SIZE].copy_from_slice(&key);
+            entry[PACKED_SIZE] = score as u8;
+            entry
+        }).collect();
+        entries.sort_unstable();
+
+        Ok(Tablebase { entries, })
+    }
+
+    /*
+     * Returns the number of positions in the tablebase.
+     */
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /*
+     * Returns true if the tablebase holds no position.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /*
+     * Returns the final disc differential of the position with perfect play, from the point of
+     * view of the player to move, if it is in the tablebase.
+     */
+    pub fn probe(&self, oth: Othello, color: Color) -> Option<i32> {
+        let key: [u8; PACKED_SIZE] = key(oth, color);
+        let index: usize = self.entries
+            .binary_search_by(|entry| entry[..PACKED_SIZE].cmp(&key))
+            .ok()?;
+        Some(self.entries[index][PACKED_SIZE] as i8 as i32)
+    }
+
+    /*
+     * Returns a perfect move in the position, an empty BitBoard if the player has to pass,
+     * with the final disc differential it leads to, if the position is in the tablebase.
+     */
+    pub fn best_move(&self, oth: Othello, color: Color) -> Option<(BitBoard, i32)> {
+        let score: i32 = self.probe(oth, color)?;
+        let mut moves: BitBoard = oth.gen_moves(color);
+        if moves == 0 {
+            return Some((0, score));
+        }
+
+        while moves != 0 {
+            let mv: BitBoard = moves.pop_lsb();
+            let new_oth: Othello = oth.make_move(color, mv);
+            let value: i32 = match self.probe(new_oth, color.invert()) {
+                Some(value) => -value,
+                None if new_oth.empties() <= SOLVER_EMPTIES => -solve(new_oth, color.invert()),
+                None => return None,
+            };
+            if value == score {
+                return Some((mv, score));
+            }
+        }
+
+        None
+    }
+
+//#################################################################################################
+//
+//                                       FILE FORMAT
+//
+//#################################################################################################
+
+    /*
+     * Writes the tablebase in the file format described above.
+     */
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::with_capacity(HEADER_SIZE + self.entries.len() * ENTRY_SIZE);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        for entry in &self.entries {
+            bytes.extend_from_slice(entry);
+        }
+        bytes
+    }
+
+    /*
+     * Reads a tablebase written by to_bytes.
+     */
+    pub fn from_bytes(bytes: &[u8]) -> Result<Tablebase, OthelloError> {
+        if bytes.len() < HEADER_SIZE || &bytes[..8] != MAGIC {
+            return Err(parse_error("Not a tablebase file."));
+        }
+
+        let count: usize = u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize;
+        if bytes.len() != HEADER_SIZE + count * ENTRY_SIZE {
+            return Err(parse_error("Truncated tablebase file."));
+        }
+
+        let entries: Vec<Entry> = bytes[HEADER_SIZE..].chunks_exact(ENTRY_SIZE).map(|chunk| {
+            let mut entry: Entry = [0; ENTRY_SIZE];
+            entry.copy_from_slice(chunk);
+            entry
+        }).collect();
+
+        if entries.windows(2).any(|pair| pair[0][..PACKED_SIZE] >= pair[1][..PACKED_SIZE]) {
+            return Err(parse_error("Unsorted tablebase file."));
+        }
+
+        Ok(Tablebase { entries, })
+    }
+
+    /*
+     * Writes the tablebase to a file.
+     */
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), OthelloError> {
+        Ok(std::fs::write(path, self.to_bytes())?)
+    }
+
+    /*
+     * Reads a tablebase from a file.
+     */
+    pub fn load(path: impl AsRef<Path>) -> Result<Tablebase, OthelloError> {
+        Tablebase::from_bytes(&std::fs::read(path)?)
+    }
+}
+
+/*
+ * Returns the key of a position in the tablebase.
+ */
+fn key(oth: Othello, color: Color) -> [u8; PACKED_SIZE] {
+    encode_packed(oth.canonical().0, color)
+}
+
+/*
+ * Solves the position and every position reachable from it, storing the scores of those with
+ * more than SOLVER_EMPTIES empty squares, and returns the score of the position.
+ */
+fn solve_all(oth: Othello, color: Color, scores: &mut HashMap<[u8; PACKED_SIZE], i8>) -> i32 {
+    if oth.empties() <= SOLVER_EMPTIES {
+        return solve(oth, color);
+    }
+
+    let key: [u8; PACKED_SIZE] = key(oth, color);
+    if let Some(&score) = scores.get(&key) {
+        return score as i32;
+    }
+
+    let mut moves: BitBoard = oth.gen_moves(color);
+    let score: i32 = if moves == 0 {
+        if oth.gen_moves(color.invert()) == 0 {
+            solve(oth, color)
+        } else {
+            -solve_all(oth, color.invert(), scores)
+        }
+    } else {
+        let mut best: i32 = -64;
+        while moves != 0 {
+            let new_oth: Othello = oth.make_move(color, moves.pop_lsb());
+            best = best.max(-solve_all(new_oth, color.invert(), scores));
+        }
+        best
+    };
+
+    scores.insert(key, score as i8);
+    score
+}
+
+//#################################################################################################
+//
+//                                     TABLEBASE TEST
+//
+//#################################################################################################
+
+/*
+ * Checks the tablebase against the solver, and that it survives a round trip through the file
+ * format.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symmetry::*;
+
+    #[test]
+    fn generate() {
+        let mut game: Game = Game::new();
+        while game.board().empties() > 10 {
+            let moves: BitBoard = game.moves();
+            game.play(moves & moves.wrapping_neg()).unwrap();
+        }
+        assert!(Tablebase::generate(&game.history()[..10]).is_err());
+
+        let tablebase: Tablebase = Tablebase::generate(game.history()).unwrap();
+        let (oth, color) = (game.board(), game.color());
+        assert_eq!(tablebase.probe(oth, color), Some(solve(oth, color)));
+        let flipped: Othello = oth.transform(Symmetry::Rotate180);
+        assert_eq!(tablebase.probe(flipped, color), Some(solve(oth, color)));
+
+        while game.board().empties() > SOLVER_EMPTIES {
+            let (oth, color) = (game.board(), game.color());
+            let (mv, score) = tablebase.best_move(oth, color).unwrap();
+            assert_eq!(score, solve(oth, color));
+            game.play(mv).unwrap();
+        }
+
+        let read: Tablebase = Tablebase::from_bytes(&tablebase.to_bytes()).unwrap();
+        assert_eq!(read.len(), tablebase.len());
+        assert_eq!(read.probe(oth, color), tablebase.probe(oth, color));
+        assert!(Tablebase::from_bytes(&tablebase.to_bytes()[..20]).is_err());
+    }
+}
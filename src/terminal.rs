@@ -1,5 +1,20 @@
+use std::path::{Path, PathBuf};
+#[cfg(feature = "alphabeta")]
+use std::time::Instant;
+
 use crate::types::*;
 use crate::othello::*;
+use crate::game::*;
+use crate::player::*;
+use crate::coord::*;
+use crate::record::*;
+#[cfg(feature = "alphabeta")]
+use crate::book::*;
+#[cfg(feature = "alphabeta")]
+use crate::trainer::*;
+#[cfg(feature = "alphabeta")]
+use crate::config::*;
+use crate::error::*;
 
 //#################################################################################################
 //
@@ -42,13 +57,14 @@ macro_rules! clear {
 //#################################################################################################
 
 /*
- * Prints the othello board to the terminal, with pretty colors.
+ * Prints the othello board to the terminal, with pretty colors. Rows are numbered according to
+ * the default coordinates convention.
  */
 pub fn print_oth(oth: &Othello, moves: BitBoard, mv: BitBoard) {
     clear!();
     green!("  a b c d e f g h\n");
     for y in 0..8 {
-        green!(format!("{} ", y + 1));
+        green!(format!("{} ", Coord { x: 0, y, }.row(convention())));
         for x in 0..8 {
             match oth.get_square(x, y) {
                 Square::White => {
@@ -87,16 +103,26 @@ pub fn print_oth(oth: &Othello, moves: BitBoard, mv: BitBoard) {
 
 pub struct TerminalPlayer;
 
+/*
+ * Reads a line from the terminal into the given string. Returns false if the terminal can't be
+ * read from anymore.
+ */
+fn read_line(input: &mut String) -> bool {
+    input.clear();
+    matches!(std::io::stdin().read_line(input), Ok(n) if n > 0)
+}
+
 impl Player for TerminalPlayer {
     /*
-     * Gets a user input of the form "[a-h][1-8]\n" and verifies it's validity. Converts The
-     * input to a BitBoard representing the desired move and checks that it is present in
-     * the moves BitBoard.
+     * Gets a user input of the form "[a-h][1-8]( draw)?\n" or "resign\n" and verifies it's
+     * validity. Parses the input into a BitBoard representing the desired move and checks that
+     * it is present in the moves BitBoard. Resigns if the terminal is closed.
      */
-    fn chose_move(&self, _oth: Othello, moves: BitBoard, color: Color) -> BitBoard {
+    fn choose_move(&mut self, game: &Game, _clock: &Clock) -> Action {
+        let (oth, moves, color) = (game.board(), game.moves(), game.color());
         let mut input: String = String::new();
-        let mut bytes: &[u8];
-        let mut mv: BitBoard;
+        let mv: BitBoard;
+        let mut draw: bool;
 
         if color == Color::Black {
             blue!("X player");
@@ -106,25 +132,59 @@ impl Player for TerminalPlayer {
 
         print!(", where do you want to ");
         yellow!("play ");
-        println!("? (regex format: \"[a-h][1-8]\\n\")");
+        println!("? (regex format: \"[a-h][1-8]( draw)?\\n\" or \"resign\\n\")");
 
         loop {
-            input.clear();
-            match std::io::stdin().read_line(&mut input) {
-                Ok(_) => (), // The professional way to handle exceptions.
-                Err(_) => panic!("Couldn't read from the terminal."),
+            if !read_line(&mut input) {
+                return Action::Resign;
+            }
+
+            let mut text: &str = input.trim();
+            if text == "resign" { return Action::Resign; }
+            draw = text.ends_with(" draw");
+            if draw { text = text.trim_end_matches(" draw"); }
+
+            match parse_square(text) {
+                Some(square) if square & moves != 0 => {
+                    mv = square;
+                    break;
+                },
+                Some(square) => {
+                    let coord: Coord = Coord::from_bitboard(square);
+                    if let Err(err) = oth.try_make_move(color, coord.x, coord.y) {
+                        red!(format!("{}\n", err));
+                    }
+                },
+                None => { red!("Invalid input.\n"); },
             }
+        }
+
+        if draw {
+            Action::OfferDraw(mv)
+        } else {
+            Action::Play(mv)
+        }
+    }
 
-            bytes = input.as_bytes();
-            if bytes.len() != 3 { continue; }
-            if bytes[0] < 97 || bytes[1] < 49 { continue; }
-            mv = 8 * (bytes[1] as BitBoard - 49) + bytes[0] as BitBoard - 97;
-            if mv > 63 { continue; }
-            mv = 1u64 << mv;
-            if mv & moves != 0 { break; }
+    /*
+     * Asks the user whether he accepts the draw offered by his opponent. Declines it if the
+     * terminal is closed.
+     */
+    fn accept_draw(&mut self, _game: &Game, color: Color) -> bool {
+        if color == Color::Black {
+            blue!("X player");
+        } else {
+            cyan!("O player");
         }
 
-        mv
+        print!(", your opponent offers a ");
+        yellow!("draw");
+        println!(", do you accept ? (y/n)");
+
+        ask()
+    }
+    fn name(&self) -> String {
+        "Human".to_string()
     }
 }
 
@@ -135,48 +195,246 @@ impl Player for TerminalPlayer {
 //#################################################################################################
 
 /*
- * Play a game in the terminal, one player taking turn after the other, both being asked
- * what they want to play each time. Gives the score at the end of the game.
+ * Play the given game in the terminal, a new one or one with a handicap for instance, one
+ * player taking turn after the other, both being asked what they want to play each time. Gives
+ * the score at the end of the game and returns the finished game. Returns an error if a player
+ * chooses an illegal move.
+ */
+pub fn terminal_play(
+    game: Game, black: &mut dyn Player, white: &mut dyn Player,
+) -> Result<Game, OthelloError> {
+    play_game(game, black, white, None)
+}
+
+/*
+ * Plays a game in the terminal like terminal_play, saving it to the given file after each
+ * move, so that it isn't lost if the program crashes or the terminal is closed. If the file
+ * holds a game, from a previous run that didn't finish, the user is offered to resume it
+ * instead of the given one.
+ * The file is removed once the game is over.
+ */
+pub fn terminal_play_saved(
+    game: Game, black: &mut dyn Player, white: &mut dyn Player, path: &Path,
+) -> Result<Game, OthelloError> {
+    // A save that can't be read is ignored, and overwritten by the new game.
+    let saved: Option<Game> = std::fs::read_to_string(path).ok()
+        .and_then(|text| GameRecord::from_text(&text).ok()?.to_game().ok())
+        .filter(|game| !game.is_over());
+    let game: Game = match saved {
+        Some(saved) => {
+            print_oth(&saved.board(), saved.moves(), saved.last_move());
+            print!("An unfinished game of {} moves was saved, do you want to ",
+                saved.history().len());
+            yellow!("resume ");
+            println!("it ? (y/n)");
+            if ask() { saved } else { game }
+        },
+        None => game,
+    };
+
+    let game: Game = play_game(game, black, white, Some(path))?;
+    std::fs::remove_file(path)?;
+    Ok(game)
+}
+
+/*
+ * Returns the path of the file the games played in the terminal are saved to, autosave.txt
+ * next to the config file.
  */
-pub fn terminal_play(black: &dyn Player, white: &dyn Player) {
-    let mut oth: Othello = Othello::new();
-    let mut moves: BitBoard;
-    let mut mv: BitBoard = 0;
-    let mut color: Color = Color::Black;
+#[cfg(feature = "alphabeta")]
+pub fn autosave_path() -> Option<PathBuf> {
+    Config::default_path().map(|path| path.with_file_name("autosave.txt"))
+}
 
+/*
+ * Asks the user a yes or no question, the answer being no if the terminal is closed.
+ */
+fn ask() -> bool {
+    let mut input: String = String::new();
     loop {
-        moves = oth.gen_moves(color);
-        if moves == 0 {
-            color = color.invert();
-            moves = oth.gen_moves(color);
-            if moves == 0 { break; }
+        if !read_line(&mut input) {
+            return false;
         }
+        match input.trim() {
+            "y" => return true,
+            "n" => return false,
+            _ => (),
+        }
+    }
+}
 
-        print_oth(&oth, moves, mv);
+/*
+ * Saves the game to the given file, by writing it next to it first and then replacing it, so
+ * that a crash while writing leaves the previous save intact.
+ */
+fn autosave(game: &Game, black: &dyn Player, white: &dyn Player, path: &Path)
+    -> Result<(), OthelloError>
+{
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let record: GameRecord = GameRecord::from_game(game, &black.name(), &white.name(), &today());
+    let temp: PathBuf = path.with_extension("tmp");
+    std::fs::write(&temp, record.to_text())?;
+    std::fs::rename(&temp, path)?;
+    Ok(())
+}
 
-        if color == Color::Black {
-            mv = black.chose_move(oth, moves, Color::Black);
-        } else {
-            mv = white.chose_move(oth, moves, Color::White);
-        }
+/*
+ * Plays the game in the terminal until it is over, saving it after each move if a file is
+ * given, shows its result and returns it.
+ */
+fn play_game(
+    mut game: Game, black: &mut dyn Player, white: &mut dyn Player, path: Option<&Path>,
+) -> Result<Game, OthelloError> {
+    let mut clock: Clock = Clock::unlimited();
 
-        oth = oth.make_move(color, mv);
-        color = color.invert();
+    while !game.is_over() {
+        print_oth(&game.board(), game.moves(), game.last_move());
+        game.step(black, white, &mut clock)?;
+        if let Some(path) = path {
+            autosave(&game, black, white, path)?;
+        }
     }
 
-    print_oth(&oth, moves, mv);
-    let score: Score = oth.score();
+    print_oth(&game.board(), 0, game.last_move());
+    let outcome: Outcome = game.outcome().unwrap();
+    let score: Score = game.board().score();
     print!("Game over! Final score is [");
     blue!(format!("X: {}", score.get(Color::Black)));
     print!(" - ");
     cyan!(format!("O: {}", score.get(Color::White)));
     println!("]");
-    if score.get(Color::Black) > score.get(Color::White) {
-        blue!("X player won ! Congatulations !\n");
-    } else if score.get(Color::Black) < score.get(Color::White) {
-        cyan!("O player won ! Congatulations !\n");
-    } else {
-        println!("It's a draw !");
+    match outcome.reason {
+        Reason::Finished => (),
+        Reason::Resignation => println!("The game ended by resignation."),
+        Reason::Agreement => println!("The players agreed on a draw."),
+        Reason::Adjudication => println!("The game was adjudicated."),
+        Reason::IllegalMove => println!("The game was forfeited by an illegal move."),
+        Reason::Timeout => println!("The game was lost on time."),
+    }
+    match outcome.winner {
+        Some(Color::Black) => { blue!("X player won ! Congatulations !\n"); },
+        Some(Color::White) => { cyan!("O player won ! Congatulations !\n"); },
+        None => println!("It's a draw !"),
     }
     println!();
+
+    Ok(game)
+}
+
+//#################################################################################################
+//
+//                                    TERMINAL TRAINING
+//
+//#################################################################################################
+
+/*
+ * Quizzes the user in the terminal on the cards of the trainer due today, showing him each
+ * position and asking for the best book move, and saves the trainer to the given file after
+ * each answer so that no review is lost. Stops once no card is due anymore, or when he types
+ * "quit" or the terminal is closed. Returns the number of cards reviewed and of right answers.
+ */
+#[cfg(feature = "alphabeta")]
+pub fn terminal_train(trainer: &mut Trainer, book: &Book, path: &Path)
+    -> Result<(usize, usize), OthelloError>
+{
+    let mut input: String = String::new();
+    let (mut reviewed, mut right): (usize, usize) = (0, 0);
+
+    for index in trainer.due_today() {
+        let line: String = trainer.cards[index].line.clone();
+        let game: Game = Game::from_line(&line)?;
+        // The card is skipped if the book doesn't have the position anymore.
+        let answers: BitBoard = book_moves(book, &line)?;
+        if answers == 0 {
+            continue;
+        }
+
+        print_oth(&game.board(), game.moves(), game.last_move());
+        print!("{} to play after {}, what is the book ", game.color(), line);
+        yellow!("move ");
+        println!("? (\"quit\" to stop)");
+
+        let start: Instant = Instant::now();
+        let mv: BitBoard = loop {
+            if !read_line(&mut input) || input.trim() == "quit" {
+                return Ok((reviewed, right));
+            }
+            match parse_square(input.trim()) {
+                Some(square) if square & game.moves() != 0 => break square,
+                _ => { red!("Not a legal move.\n"); },
+            }
+        };
+
+        let correct: bool = mv & answers != 0;
+        trainer.review(index, grade(correct, start.elapsed().as_secs_f64()));
+        trainer.save(path)?;
+        reviewed += 1;
+        if correct {
+            right += 1;
+            green!("Right");
+        } else {
+            let mut names: Vec<String> = Vec::new();
+            let mut answers: BitBoard = answers;
+            while answers != 0 {
+                names.push(answers.pop_lsb().square_name());
+            }
+            red!(format!("The book plays {}", names.join(" or ")));
+        }
+        println!(", next review on {}. Press enter to go on.", trainer.cards[index].due);
+        if !read_line(&mut input) {
+            break;
+        }
+    }
+
+    Ok((reviewed, right))
+}
+
+/*
+ * Asks the user in the terminal for the final disc differential with perfect play of the
+ * exercises, one after the other, telling him the right one and the moves reaching it after
+ * each answer. Stops when he types "quit" or the terminal is closed. Returns the number of
+ * exercises answered and of right answers.
+ */
+#[cfg(feature = "alphabeta")]
+pub fn terminal_count(exercises: impl Iterator<Item = Exercise>) -> (usize, usize) {
+    let mut input: String = String::new();
+    let (mut answered, mut right): (usize, usize) = (0, 0);
+
+    for exercise in exercises {
+        print_oth(&exercise.oth, exercise.oth.gen_moves(exercise.color), 0);
+        print!("{} to play, what is his final disc ", exercise.color);
+        yellow!("differential ");
+        println!("with perfect play, like +4 or -2 ? (\"quit\" to stop)");
+
+        let answer: i32 = loop {
+            if !read_line(&mut input) || input.trim() == "quit" {
+                return (answered, right);
+            }
+            match input.trim().parse() {
+                Ok(answer) => break answer,
+                Err(_) => { red!("Not a disc differential.\n"); },
+            }
+        };
+
+        answered += 1;
+        if answer == exercise.score {
+            right += 1;
+            green!("Right");
+        } else {
+            red!(format!("It is {:+}", exercise.score));
+        }
+        let mut names: Vec<String> = Vec::new();
+        let mut best: BitBoard = exercise.best;
+        while best != 0 {
+            names.push(best.pop_lsb().square_name());
+        }
+        println!(", by playing {}. Press enter to go on.", names.join(" or "));
+        if !read_line(&mut input) {
+            break;
+        }
+    }
+
+    (answered, right)
 }
@@ -0,0 +1,586 @@
+use crate::types::*;
+use crate::othello::*;
+
+//#################################################################################################
+//
+//                                   TEST POSITION TYPE
+//
+//#################################################################################################
+
+/*
+ * A test position, with the player to move, every move leading to the best final score with
+ * perfect play and that score, a final disc differential from the point of view of the player
+ * to move, empty squares going to the winner.
+ */
+#[derive(Clone, Copy)]
+pub struct TestPosition {
+    pub oth: Othello,
+    pub color: Color,
+    pub best_moves: BitBoard,
+    pub score: i8,
+}
+
+impl TestPosition {
+    /*
+     * Creates a new TestPosition from the picture of the board, see Othello::from_ascii.
+     */
+    const fn new(art: &str, color: Color, best_moves: BitBoard, score: i8) -> TestPosition {
+        TestPosition { oth: Othello::from_ascii(art), color, best_moves, score, }
+    }
+}
+
+/*
+ * Returns the test suite, sorted by increasing number of empty squares, to be used by
+ * benchmarks and strength tests.
+ */
+pub fn positions() -> &'static [TestPosition] {
+    &POSITIONS
+}
+
+//#################################################################################################
+//
+//                                     TEST POSITIONS
+//
+//#################################################################################################
+
+/*
+ * The positions come from games between weak AIs picking randomly among their three best moves,
+ * stopped when 10 to 20 empty squares were left. They were then solved exactly, so the best
+ * moves and scores are proven. The boards are given row by row, from a1 to h8.
+ */
+static POSITIONS: [TestPosition; 100] = [
+    // #1: 10 empties, black plays a8 for +20.
+    TestPosition::new(
+        "--OXXOO- XXXXXO-- XXXXOOOO XOXXOOOO XOOXXOXO XXXOXXXO X-XXXXXO -OXX-O--",
+        Color::Black, 0x0100000000000000, 20,
+    ),
+    // #2: 10 empties, black plays g7 or b8 for +26.
+    TestPosition::new(
+        "--OXXXX- -OOOXXX- OOOXOXXX OXOOXOXX XXXXOOXX OXOXOOOX --OOXO-X --OXXXXX",
+        Color::Black, 0x0240000000000000, 26,
+    ),
+    // #3: 10 empties, black plays c2 for -28.
+    TestPosition::new(
+        "-XXXOOOO ---OOX-O OOOOXOXO OOOXOXOO OOOOXOOO OOOOOXOX -OXOOOX- O-OOOO--",
+        Color::Black, 0x0000000000000400, -28,
+    ),
+    // #4: 10 empties, black plays a1 or a2 for +40.
+    TestPosition::new(
+        "-OXXXXXX --OXOO-X XOXOXOOX XOXOXXOX XOXOXXXX XOOOXOXX -OOXXX-- -OOOXO--",
+        Color::Black, 0x0000000000000101, 40,
+    ),
+    // #5: 10 empties, black plays g2 for -14.
+    TestPosition::new(
+        "XOOOOOOO X-OOXO-O XOXOOXXO XOOOOOOO XXXXOOOO XXXXXXOO X-O-OO-O ----OOO-",
+        Color::Black, 0x0000000000004000, -14,
+    ),
+    // #6: 10 empties, black plays g2 or f3 for -36.
+    TestPosition::new(
+        "O-X-O--- XXXX---- OXOXX-OO OOOOXOOO OXOXOOXO OXXOOOXO OXXOOXXX OOOOOOOO",
+        Color::Black, 0x0000000000204000, -36,
+    ),
+    // #7: 10 empties, black plays g2 for -10.
+    TestPosition::new(
+        "-X-OOOO- X-XXXO-- XXXXOX-- XXXOOX-X XXOXOXXX XOXXXOXX XXXXXXXX -XXXXXXX",
+        Color::Black, 0x0000000000004000, -10,
+    ),
+    // #8: 10 empties, black plays a1 for +6.
+    TestPosition::new(
+        "--XXXXX- -OXXXX-O OOOXXOOO OXXOXOOO OXOOOXXO OOXOXXXO OXXXXX-O --OXXO--",
+        Color::Black, 0x0000000000000001, 6,
+    ),
+    // #9: 10 empties, black plays a2 or h7 for -18.
+    TestPosition::new(
+        "X-XXXXOO -XXXXOOO OXXOOOXO XXXXOXOO XXXOXOOO OXOOOOOO --OOXX-- --O-XXX-",
+        Color::Black, 0x0080000000000100, -18,
+    ),
+    // #10: 10 empties, black plays g8 for +42.
+    TestPosition::new(
+        "-XXXXXXX -OOXXX-O OOOXXXOX -OXOXOXX OOOOOOXX X-OOXXXX --OOXXOX --OXXO-X",
+        Color::Black, 0x4000000000000000, 42,
+    ),
+    // #11: 11 empties, white plays h8 for +2.
+    TestPosition::new(
+        "OOOOOO-- OOXXXO-- OXOOXOOO OXXOOOOO OOXXXOOX OOOOOXOO --OOOOX- --X-OOO-",
+        Color::White, 0x8000000000000000, 2,
+    ),
+    // #12: 11 empties, white plays b2 for -12.
+    TestPosition::new(
+        "OOOOOOO- X-XXOX-- XXOXXO-- XXXXXXO- XXXOOOX- XXXOXXXX XXXXXX-- XXXXXXX-",
+        Color::White, 0x0000000000000200, -12,
+    ),
+    // #13: 11 empties, white plays g4 or a8 for 0.
+    TestPosition::new(
+        "-OOOOOO- --OOXO-X OOOOOOXX XXXXXX-X XXXXXXOX OXXOXXXX -XXXOO-X -OOOOO--",
+        Color::White, 0x0100000040000000, 0,
+    ),
+    // #14: 11 empties, white plays g1 for +16.
+    TestPosition::new(
+        "--OXXX-- ---XXX-O O-XXOXXO OXXXOOOO OXOOXOOO OXOOOXXO OXXXXXX- OXOOOOO-",
+        Color::White, 0x0000000000000040, 16,
+    ),
+    // #15: 11 empties, white plays a8 for +32.
+    TestPosition::new(
+        "-OOOOO-- O-OOOOX- OOOOXXXX OXOXXXXX -XXXOOXX XXXXOXOX --XOOO-O -XOOOOO-",
+        Color::White, 0x0100000000000000, 32,
+    ),
+    // #16: 11 empties, white plays f8 for -34.
+    TestPosition::new(
+        "XXXXXX-- X-OOOOOO OXXOXOOO OOXOOOXO OOXOOXXO OOOOXXXO O-OOOX-O ---OX---",
+        Color::White, 0x2000000000000000, -34,
+    ),
+    // #17: 11 empties, white plays b2 for -48.
+    TestPosition::new(
+        "X--OOOO- X-OOXOOX XOXOOXOX -XOOXXXX OOOXXXOX OOOXXXXX O-OOOO-X --X-OOO-",
+        Color::White, 0x0000000000000200, -48,
+    ),
+    // #18: 11 empties, white plays h5 or f7 for -40.
+    TestPosition::new(
+        "-OOOOO-- -OOOXO-- OOOOOOO- -OOOOO-X OOOOOOX- OOOOXXOX OOOXX-OX XXXXXXXX",
+        Color::White, 0x0020008000000000, -40,
+    ),
+    // #19: 11 empties, white plays g7 for -36.
+    TestPosition::new(
+        "XOOOOO-- XOOOOO-O XXXXXOOO XOXXXXXO XOOXXOXO XOOOOOOO --OXXX-- --OXXO--",
+        Color::White, 0x0040000000000000, -36,
+    ),
+    // #20: 11 empties, white plays a7 for -16.
+    TestPosition::new(
+        "--XXXX-- XOOOOO-- XXOXXXOO XXXOOXOO XXXXOXXO XXXXXXXO --XXOOX- -OOOOO-X",
+        Color::White, 0x0001000000000000, -16,
+    ),
+    // #21: 12 empties, black plays h2 for -2.
+    TestPosition::new(
+        "-XXXXXXO X-XXOXO- XOOOXOXO XOOXOOXO -XOOOOXO X--OOXOO --OOOO-O --OOOOO-",
+        Color::Black, 0x0000000000008000, -2,
+    ),
+    // #22: 12 empties, black plays b3 for +16.
+    TestPosition::new(
+        "-OOOOO-- --OXXOO- X-OXXOXX XXOXOXXX XOXOXXXX XXOXOXXX X-XXXO-X --OOOOO-",
+        Color::Black, 0x0000000000020000, 16,
+    ),
+    // #23: 12 empties, black plays g7 for +10.
+    TestPosition::new(
+        "-OOOXXXO --OOXXXO X-OXXXOO -XOXXOXO --OOOXOO -XOOXOXO --OOXX-O -OOOOOOO",
+        Color::Black, 0x0040000000000000, 10,
+    ),
+    // #24: 12 empties, black plays g1 or a8 for +20.
+    TestPosition::new(
+        "-OOOOO-X --OXOOXX -XXOXXOX X-XXOOXX OXOXOOXX OOXOXXO- O-OXOO-O -OOOOO--",
+        Color::Black, 0x0100000000000040, 20,
+    ),
+    // #25: 12 empties, black plays g1 or a8 for +34.
+    TestPosition::new(
+        "--X--X-X ---XOOXO XXXXXXOO XXXOXOXO XXXXOOOO -XOXXOOO OOOOOOO- --OXXXXX",
+        Color::Black, 0x0100000000000040, 34,
+    ),
+    // #26: 12 empties, black plays d7 for -18.
+    TestPosition::new(
+        "-OOOOOO- -XXXXOX- XXXOOXOO XXXXXOOO XXOXOXOX XXXOXOXX --O-OX-- -OOOOO--",
+        Color::Black, 0x0008000000000000, -18,
+    ),
+    // #27: 12 empties, black plays b8 for -22.
+    TestPosition::new(
+        "--OOXO-- OOOXXXX- OOOXXXXX -XOOXOOX XXXOOOOX XXXXOOXX --OOOO-X --OOOOO-",
+        Color::Black, 0x0200000000000000, -22,
+    ),
+    // #28: 12 empties, black plays b2, b4 or b6 for -48.
+    TestPosition::new(
+        "-XXXXXXO X-OOOXOO OOOXOOOO O-XXOOOO OOOOOXXO --XXXXXO ---XXX-O ---XOOOO",
+        Color::Black, 0x0000020002000200, -48,
+    ),
+    // #29: 12 empties, black plays g8 for +22.
+    TestPosition::new(
+        "--OOOOOO --OOOX-- XXOOOOOO OXXOOOOO OOXXOOOO XOOXXOOO --OXXOOO --XXOO--",
+        Color::Black, 0x4000000000000000, 22,
+    ),
+    // #30: 12 empties, black plays h1 for -18.
+    TestPosition::new(
+        "--OOOOO- X-XXXOO- XXXXXOXX XOXOOOXX XXXXOXXX XXXXXXXX --XXXX-- --XXXXX-",
+        Color::Black, 0x0000000000000080, -18,
+    ),
+    // #31: 13 empties, white plays c2 or e8 for -24.
+    TestPosition::new(
+        "---XXXXX O--XXXX- OXXXXXOO OXXXXOOO OXXXO-OO OOXOXXOO O-OXXX-O -XXX-O--",
+        Color::White, 0x1000000000000400, -24,
+    ),
+    // #32: 13 empties, white plays a7 for 0.
+    TestPosition::new(
+        "-XXXXXXX O-XOXX-- OOOXOXX- OOOXOOXX OXXOOOOO XXXXXOO- --XXOOO- --XXOX--",
+        Color::White, 0x0001000000000000, 0,
+    ),
+    // #33: 13 empties, white plays b1 for -6.
+    TestPosition::new(
+        "---XXX-- X-XXXO-- XXXXOXOX XOXXXOXX -OOXOXOX OOXOXXOX --OXXXXX -OOOOO-X",
+        Color::White, 0x0000000000000002, -6,
+    ),
+    // #34: 13 empties, white plays b1 or a7 for +42.
+    TestPosition::new(
+        "--OO-X-O -XXXXXOO OXXXOOXO OXXXOOXO OXOOXOXO OXOOOXXO -XX-OOX- -X-O-O--",
+        Color::White, 0x0001000000000002, 42,
+    ),
+    // #35: 13 empties, white plays a4 for -34.
+    TestPosition::new(
+        "--OOOOO- --OOXOOO OOOOOOOO -OOOOOXO XXOXOXXX XXXXXXOO O-XOX--- -XXXXX--",
+        Color::White, 0x0000000001000000, -34,
+    ),
+    // #36: 13 empties, white plays h8 for +36.
+    TestPosition::new(
+        "OXXXXXX- XXXXXX-- XXOOXXXX -XOOOOOO -XXOOXOX -XXXOOXX --XOOX-X -OOO-X--",
+        Color::White, 0x8000000000000000, 36,
+    ),
+    // #37: 13 empties, white plays b2 or h6 for 0.
+    TestPosition::new(
+        "--OXO-X- O-XXOOO- OOXXXOXX OXOXOXX- OXXOXXXO OXOOOOX- -XXXOO-X --OOOOO-",
+        Color::White, 0x0000800000000200, 0,
+    ),
+    // #38: 13 empties, white plays h7 for +48.
+    TestPosition::new(
+        "-XXXX-O- X-XXXX-- XXXXXOXO XXXOOXXX OOOXOOXX OXXOXXOX --XXOX-- --XXXXX-",
+        Color::White, 0x0080000000000000, 48,
+    ),
+    // #39: 13 empties, white plays g1 for +2.
+    TestPosition::new(
+        "-OOXXX-- X-XXXX-- XXXOXXXO XOXXOOOX XOXXXXXO XOOOOXXO XOOOOOX- --O--O--",
+        Color::White, 0x0000000000000040, 2,
+    ),
+    // #40: 13 empties, white plays g7 for -6.
+    TestPosition::new(
+        "-OOOOO-- OOOXXX-- OOOOXXXX OXOOOXOX OXXXOXXX OOXXXXXX --XXXO-- --OXXO--",
+        Color::White, 0x0040000000000000, -6,
+    ),
+    // #41: 14 empties, black plays c2 for -26.
+    TestPosition::new(
+        "--O-XXX- ---XXX-O XXXOXOOO XXXXXOXO --XXXOXO -OOOOXXO --OOOXXO -OOOOOOO",
+        Color::Black, 0x0000000000000400, -26,
+    ),
+    // #42: 14 empties, black plays c8 for +32.
+    TestPosition::new(
+        "-OOOOOO- O-OOOO-- OOXOXOXX OXOOXOOX OOXOXOOO -OOOOOOO X-OOXO-- ---OX-O-",
+        Color::Black, 0x0400000000000000, 32,
+    ),
+    // #43: 14 empties, black plays b1 for -32.
+    TestPosition::new(
+        "O-OOOO-- OOXXXX-- OXXXOXXO OXXOOOXO OXOOOXOO OXOXOX-O OOX-XX-- O---OX--",
+        Color::Black, 0x0000000000000002, -32,
+    ),
+    // #44: 14 empties, black plays c7 for -2.
+    TestPosition::new(
+        "-XXXXX-- O-XOOX-O OOOXOXOO OXOXXOXO OOXXOXXO OOOXXXOO ---OXX-- --XXXX--",
+        Color::Black, 0x0004000000000000, -2,
+    ),
+    // #45: 14 empties, black plays d8 for +40.
+    TestPosition::new(
+        "X-OOOOO- OXXXXO-- OOXXOXOO OOOXXOXO OOXOXOOX OOXOOO-O --OOOO-- --O-XO--",
+        Color::Black, 0x0800000000000000, 40,
+    ),
+    // #46: 14 empties, black plays b6 for +20.
+    TestPosition::new(
+        "--XXXX-- --OXXX-X --OXXXXX XXOXXXXX XXOXOXOX X-XOOOXX XXOOOO-- XOOOOO--",
+        Color::Black, 0x0000020000000000, 20,
+    ),
+    // #47: 14 empties, black plays d8 for +16.
+    TestPosition::new(
+        "-XXXXX-- X-XXOX-O XOOXXXOO XXOXXOOO XOXOOXOO XXOOXXX- X-O-XO-X ----OOO-",
+        Color::Black, 0x0800000000000000, 16,
+    ),
+    // #48: 14 empties, black plays a1, h1 or b6 for +2.
+    TestPosition::new(
+        "--OXXO-- OOOOOOOO XOOXXOOO XOOXOOOO XXOXOOXO X-XXOXOO --OXXX-- --O-XX--",
+        Color::Black, 0x0000020000000081, 2,
+    ),
+    // #49: 14 empties, black plays g1 for -32.
+    TestPosition::new(
+        "-XXXXX-- X-XOOO-- XXXXXOOO XXXXXXOO -XXXXOOO O-XXXXOO ---OOXXO --OOOOO-",
+        Color::Black, 0x0000000000000040, -32,
+    ),
+    // #50: 14 empties, black plays g1 for -48.
+    TestPosition::new(
+        "-OOOO--- --XXOO-- XXXOXOOO XXXXOXOO X-XOOXOO XXXXOXXO --OOOO-O --OOOOOO",
+        Color::Black, 0x0000000000000040, -48,
+    ),
+    // #51: 15 empties, white plays c8 for +24.
+    TestPosition::new(
+        "XXXXXXXO -XXXXXXO --XXXXXO XXXXXOXO OOOXXOXO XXXXXXXO --X-X-XO --------",
+        Color::White, 0x0400000000000000, 24,
+    ),
+    // #52: 15 empties, white plays h6 or d7 for +32.
+    TestPosition::new(
+        "OOOOOOOO OXXOOXXO OOXXOXXO OOOXOXXO XXOXXXOO -XXXOOX- --X-O--X --------",
+        Color::White, 0x0008800000000000, 32,
+    ),
+    // #53: 15 empties, white plays a6 for +8.
+    TestPosition::new(
+        "XXXXXX-- --XXOXXX -XXXXXXX -OOXXXOX --OOXOOX -XXXOOXX --XXOO-- -OOOOOO-",
+        Color::White, 0x0000010000000000, 8,
+    ),
+    // #54: 15 empties, white plays e8 for +14.
+    TestPosition::new(
+        "--XXXXXX OXXXXXX- OXXXXXXX OXXOOOOO OOXOXOXO OOOOOXX- O-XOX--- -----X--",
+        Color::White, 0x1000000000000000, 14,
+    ),
+    // #55: 15 empties, white plays g2 for +14.
+    TestPosition::new(
+        "O-XXXXO- XXXXXO-- XXXOOXOO XXXXOXOO XXXXXXOO XXXXXXXO -----OXX -----O-X",
+        Color::White, 0x0000000000004000, 14,
+    ),
+    // #56: 15 empties, white plays d1 or a8 for +22.
+    TestPosition::new(
+        "----OOO- ---XOO-O XXXXOOOO XOOOOOOO XOXXXXOO XXXOOXOO XXXXXXX- --O-XX--",
+        Color::White, 0x0100000000000008, 22,
+    ),
+    // #57: 15 empties, white plays h5 for +16.
+    TestPosition::new(
+        "-XXXXX-- --XOOX-- OXXXXOOO OXXOXOXO OOXOOXX- OOOOXXXX O-XXOX-- --XXOX--",
+        Color::White, 0x0000008000000000, 16,
+    ),
+    // #58: 15 empties, white plays a3 or b3 for -54.
+    TestPosition::new(
+        "--OOOOOX ---OOO-X --XOOXOX X-OOXOXX XXOXOOXX XOXXXX-O O-OXXO-- XXXXXO--",
+        Color::White, 0x0000000000030000, -54,
+    ),
+    // #59: 15 empties, white plays g7 for +2.
+    TestPosition::new(
+        "--X-OOO- X-XXXO-- XOXXOX-X XXOOOOX- XOOOOXXO XXOOOXOO --OOOO-O --OOOOO-",
+        Color::White, 0x0040000000000000, 2,
+    ),
+    // #60: 15 empties, white plays c2, c3 or b6 for -2.
+    TestPosition::new(
+        "--XXXXXX ---XXOXX -O-XOXOX -OOOXOXX --OXOXXX --XOOOXX --XXOXOX -XXXXXXO",
+        Color::White, 0x0000020000040400, -2,
+    ),
+    // #61: 16 empties, black plays h1 for -8.
+    TestPosition::new(
+        "OOOOOOO- -OXXX-OO OOXXOXOO OXXOXXOO X--XXOOO ---XOOXO ---OXX-X --OOOO--",
+        Color::Black, 0x0000000000000080, -8,
+    ),
+    // #62: 16 empties, black plays h4 for +56.
+    TestPosition::new(
+        "-XXXXX-- --OOXX-O X-OXXOOX XXXXOOO- XXXOXOOO XOOOOOOO --OOOO-O -OO--O--",
+        Color::Black, 0x0000000080000000, 56,
+    ),
+    // #63: 16 empties, black plays g5 for -4.
+    TestPosition::new(
+        "O-OOOO-- -OXXXO-- OOOOOOO- OOXOXOX- OOOXOO-- OOXOOOX- -XXXOOX- -OOOOO--",
+        Color::Black, 0x0000004000000000, -4,
+    ),
+    // #64: 16 empties, black plays d1 or a7 for +14.
+    TestPosition::new(
+        "-----X-- ---XOX-X XXXXOOXX XXXOOXOX XXOXOXXX XOOOOXXX -OOOOO-X --OOOOO-",
+        Color::Black, 0x0001000000000008, 14,
+    ),
+    // #65: 16 empties, black plays b1 for +30.
+    TestPosition::new(
+        "X-OOOX-- X-OOOO-- XOOOOO-- XXOOOO-- XXOOOOO- XOXOOO-- XXOOOOO- X-XXXXX-",
+        Color::Black, 0x0000000000000002, 30,
+    ),
+    // #66: 16 empties, black plays h5 for +4.
+    TestPosition::new(
+        "-XOOOO-- -XXOO--- OXOXOOOX OXOOXOOO OXOOOXX- OOOOX-XX O-OOXX-- --OOOO--",
+        Color::Black, 0x0000008000000000, 4,
+    ),
+    // #67: 16 empties, black plays h2 for +26.
+    TestPosition::new(
+        "-XXXXXXX --XXXOX- -OOOOXOO --OOOOOO --XOXXOX --XXOXOX --OOOOXX -OOO-X-X",
+        Color::Black, 0x0000000000008000, 26,
+    ),
+    // #68: 16 empties, black plays e2 for -30.
+    TestPosition::new(
+        "--OOX--- --OO---- OXXOOO-- OXXOOO-O OXOOXOOO OXOXXOXO OOXOOX-O OXXXXXX-",
+        Color::Black, 0x0000000000001000, -30,
+    ),
+    // #69: 16 empties, black plays e7 for -8.
+    TestPosition::new(
+        "--OOOOO- --OXXO-- -XXOOOXX XXXOXXOX XXXXXXXO OOXOXOXX --XX-X-- --XXXXX-",
+        Color::Black, 0x0010000000000000, -8,
+    ),
+    // #70: 16 empties, black plays h6 for -12.
+    TestPosition::new(
+        "--X--O-- ---XOO-- O-OOXOXX OOOXOXXX OOXOXOXX OXXXOXO- O-XOXO-O OOOOOOO-",
+        Color::Black, 0x0000800000000000, -12,
+    ),
+    // #71: 17 empties, white plays b7 for -22.
+    TestPosition::new(
+        "-----X-- O-OOXX-- O-OOXXXX OXXXOXXX -XXXOOXO XXOXXOOO --XXXXOO --XXXXX-",
+        Color::White, 0x0002000000000000, -22,
+    ),
+    // #72: 17 empties, white plays g1 for -42.
+    TestPosition::new(
+        "XXXXXO-X -OXOOOXX -OXOXXOX OOXOXXXX -OOOOOXX O-OOOOOX ---O-O-X -------X",
+        Color::White, 0x0000000000000040, -42,
+    ),
+    // #73: 17 empties, white plays b2 for +22.
+    TestPosition::new(
+        "----XXX- --OOOX-X XXXXXXXX XXXOOOOX XXXOOOOX XXXOOX-X X-OOXX-- ---OXX--",
+        Color::White, 0x0000000000000200, 22,
+    ),
+    // #74: 17 empties, white plays g2 for +4.
+    TestPosition::new(
+        "--XXXXX- --OXXX-- O-OXXXX- OO-XOXX- OOOOOOOO OOOXXXXX --XOOX-- -XXXXXX-",
+        Color::White, 0x0000000000004000, 4,
+    ),
+    // #75: 17 empties, white plays f2 for -16.
+    TestPosition::new(
+        "--X-OO-- --XXO--- -XXXXXXX -OXOXXXO OOOXOOXO -O-OXOXO --OOOXXO -OOOOOXO",
+        Color::White, 0x0000000000002000, -16,
+    ),
+    // #76: 17 empties, white plays h6 for -34.
+    TestPosition::new(
+        "--OOOO-- --OOOO-O OOOXXO-O -XXXOOOO XXXXXXXX XXXOOOX- X-XOOO-- -XXX-O--",
+        Color::White, 0x0000800000000000, -34,
+    ),
+    // #77: 17 empties, white plays b2 for +42.
+    TestPosition::new(
+        "OOOOOOO- O-XXXXXX OXOXXOXX OOXOOXX- OOOOOXX- OOOOXX-- O-O-XX-- ---X----",
+        Color::White, 0x0000000000000200, 42,
+    ),
+    // #78: 17 empties, white plays f2 for 0.
+    TestPosition::new(
+        "--OX---- --XXX--O XXOXXO-O XOOXOOOO XOOOXOOO XOXOXOOO X-OOOX-- --XXXXX-",
+        Color::White, 0x0000000000002000, 0,
+    ),
+    // #79: 17 empties, white plays g1 for +2.
+    TestPosition::new(
+        "XXXXXX-O --OOXXOO XOOXXOXO OOXXXOOO OOOOXXOO --OOXOOO -----X-- -----OOO",
+        Color::White, 0x0000000000000040, 2,
+    ),
+    // #80: 17 empties, white plays g2, f7 or g7 for -22.
+    TestPosition::new(
+        "-XXXXXXX --XXXX-X --OXXXXX --OXXXXX OOXXXXOX OOOXXOXX ---XX--X -OOO-X--",
+        Color::White, 0x0060000000004000, -22,
+    ),
+    // #81: 18 empties, black plays h2 or h6 for +16.
+    TestPosition::new(
+        "--OOOO-- --OOOO-- OXOOXOOX XXOOOXX- OXXXXOX- OXXXXXO- --XXOO-O -XXX-O--",
+        Color::Black, 0x0000800000008000, 16,
+    ),
+    // #82: 18 empties, black plays g3 for +34.
+    TestPosition::new(
+        "--O-XXX- --OOOX-O XXXOXO-O XXOXOOOO XXOXOOOO XXOO-OOO X-OO---- -OOOO---",
+        Color::Black, 0x0000000000400000, 34,
+    ),
+    // #83: 18 empties, black plays h2 for +40.
+    TestPosition::new(
+        "XXXX---- O-X-XO-- OOOXXXOX XOXOXOXX XXOXXOXX XOOOOXOX X-OOO--- X----O--",
+        Color::Black, 0x0000000000008000, 40,
+    ),
+    // #84: 18 empties, black plays b6 for +8.
+    TestPosition::new(
+        "--XXXXXX --XXXO-- -XXXOOO- -OXOOOO- -XOOXOOO X-OXOXXX --XOXX-- -XXXXXX-",
+        Color::Black, 0x0000020000000000, 8,
+    ),
+    // #85: 18 empties, black plays g1 for +12.
+    TestPosition::new(
+        "-OOO---- -OOOOO-- XXOOOOOO XXXOOOOX XXXXOOOX XXXXXOOO --XX-O-- -XXX----",
+        Color::Black, 0x0000000000000040, 12,
+    ),
+    // #86: 18 empties, black plays b2 for -36.
+    TestPosition::new(
+        "--OOOOO- O--XXO-- OOXXXXXX OXOOXX-- OXXXXXX- OXXXXX-- OXXXX--- OOOOX---",
+        Color::Black, 0x0000000000000200, -36,
+    ),
+    // #87: 18 empties, black plays b2 or g8 for -24.
+    TestPosition::new(
+        "-OOOOOO- --OXX--- XXXXXX-- OOXXXX-- OOOXXX-- OOXXXXX- --XXOOX- OOOOOO-X",
+        Color::Black, 0x4000000000000200, -24,
+    ),
+    // #88: 18 empties, black plays b7 for +56.
+    TestPosition::new(
+        "X------- XX-O---- XOXOOOX- XOXXOOO- XOOOXOOX XOXXXOOO X-XXXOO- XXXXXX--",
+        Color::Black, 0x0002000000000000, 56,
+    ),
+    // #89: 18 empties, black plays b7 for -18.
+    TestPosition::new(
+        "--OOOOO- --O-X--- OOOXXXXX XOOOXOXX -XOXXXXX -XOXXXXX --OXXX-X --OOOO--",
+        Color::Black, 0x0002000000000000, -18,
+    ),
+    // #90: 18 empties, black plays b3 for -8.
+    TestPosition::new(
+        "-XXXOX-- OXXOO--- O-XXOOO- OOXXOOO- OXXXOOX- OOOXO--X O-OOXO-- -XXXXX--",
+        Color::Black, 0x0000000000020000, -8,
+    ),
+    // #91: 19 empties, white plays b7 for -4.
+    TestPosition::new(
+        "--OOOO-- XXXX---- XXXXXO-- XXOXXXX- XOXOOX-- XXXOX-O- X-OXOO-- XXXXXXX-",
+        Color::White, 0x0002000000000000, -4,
+    ),
+    // #92: 19 empties, white plays e7 for +6.
+    TestPosition::new(
+        "-O-XXX-- --OXXX-O XXXOXXOO -XXXOOXO OXOXOXXO OOXOXXOO O--O-X-- --O-X---",
+        Color::White, 0x0010000000000000, 6,
+    ),
+    // #93: 19 empties, white plays h7 or d8 for +14.
+    TestPosition::new(
+        "-OOOXOOO -O-XXXOO XXXXXOOO -OOOXOOO -OOOXXOO O--OXXXX ---XO--- --X--O--",
+        Color::White, 0x0880000000000000, 14,
+    ),
+    // #94: 19 empties, white plays b2 for -54.
+    TestPosition::new(
+        "-XXXXXXX X-XXXO-- XXXXXO-- XXOOXO-- XXXOXOO- XXOXXO-- XOOXXO-- --O---O-",
+        Color::White, 0x0000000000000200, -54,
+    ),
+    // #95: 19 empties, white plays a5 or b8 for +40.
+    TestPosition::new(
+        "--OOXO-- --OOOO-- OXXXXXXX XXOXXOXX -XOOOXXX OXXOXXX- --XXX--- --XXXO--",
+        Color::White, 0x0200000100000000, 40,
+    ),
+    // #96: 20 empties, black plays d1 or f8 for -4.
+    TestPosition::new(
+        "-------- --OXXX-X XXOOXOXX -XOXOXOX OOOXOOOX OOOXXOXX O-OO-O-- -OOOO---",
+        Color::Black, 0x2000000000000008, -4,
+    ),
+    // #97: 20 empties, black plays b8 for +30.
+    TestPosition::new(
+        "-OOOOO-- --OOXO-- OOOXXO-- XXOOOOO- XXOOXO-- OXOXOOOO --XXXX-- --OXXX--",
+        Color::Black, 0x0200000000000000, 30,
+    ),
+    // #98: 20 empties, black plays c8 for +2.
+    TestPosition::new(
+        "--XO---- --O----O OOXOXXOO -OXXOXXO XXOXXXXX XXXOXOXX X-XOOX-- ---OXXX-",
+        Color::Black, 0x0400000000000000, 2,
+    ),
+    // #99: 20 empties, black plays b1 for +20.
+    TestPosition::new(
+        "--OOXX-- --OXXO-- O-XXXOOO -OXOXOOO -XOXOXOO -OOXOOOO --OOXX-- --OOOO--",
+        Color::Black, 0x0000000000000002, 20,
+    ),
+    // #100: 20 empties, black plays b2, g4 or b7 for -32.
+    TestPosition::new(
+        "OOOOOX-- O-OOOX-- OOOXXX-- OXOXXO-- OXOXOX-- XOOOXXX- --OXXX-- --OOOO--",
+        Color::Black, 0x0002000040000200, -32,
+    ),
+];
+
+//#################################################################################################
+//
+//                                       SUITE TEST
+//
+//#################################################################################################
+
+/*
+ * Checks the smallest positions of the suite against the endgame solver, the others taking too
+ * long to solve for a test.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endgame::*;
+
+    #[test]
+    fn solutions() {
+        for pos in positions().iter().filter(|pos| pos.oth.empties() <= 11) {
+            let mut moves: BitBoard = pos.oth.gen_moves(pos.color);
+            let mut best_moves: BitBoard = 0;
+
+            assert_eq!(solve(pos.oth, pos.color), pos.score as i32);
+            while moves != 0 {
+                let mv: BitBoard = moves.pop_lsb();
+                let score: i32 = -solve(pos.oth.make_move(pos.color, mv), pos.color.invert());
+                if score == pos.score as i32 {
+                    best_moves |= mv;
+                }
+            }
+            assert_eq!(best_moves, pos.best_moves);
+        }
+    }
+}
@@ -0,0 +1,440 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::types::*;
+use crate::othello::*;
+use crate::game::*;
+use crate::book::*;
+use crate::record::{day_number, date, today};
+use crate::light::*;
+use crate::endgame::*;
+use crate::player::next_random;
+use crate::config::*;
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                        CARD TYPE
+//
+//#################################################################################################
+
+/*
+ * The ease of a new card, by which the interval between reviews grows after each success, and
+ * the lowest it can fall to, as in the SM-2 algorithm.
+ */
+const INITIAL_EASE: f64 = 2.5;
+const MIN_EASE: f64 = 1.3;
+
+/*
+ * A position of a book line the user is quizzed on, given by the line of moves leading to it,
+ * with its schedule: the number of reviews in a row he got right, the interval in days until
+ * the next one, the ease and the date it is due.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Card {
+    pub line: String,
+    pub repetitions: u32,
+    pub interval: u32,
+    pub ease: f64,
+    pub due: String,
+}
+
+impl Card {
+    /*
+     * Creates a card for the position at the end of the line, due right away.
+     */
+    pub fn new(line: &str) -> Card {
+        Card {
+            line: line.to_string(),
+            repetitions: 0,
+            interval: 0,
+            ease: INITIAL_EASE,
+            due: today(),
+        }
+    }
+
+    /*
+     * Schedules the next review after one of the given quality, from 0 for a blackout to 5
+     * for a perfect answer, made on the given day counted from 1970-01-01, following SM-2:
+     * the card comes back the next day when the answer wasn't good enough, at least 3, and
+     * otherwise after 1 day, 6 days, and then an interval growing by the ease each time. The
+     * ease drops with poor answers.
+     */
+    pub fn review(&mut self, quality: u8, day: i64) {
+        let quality: u8 = quality.min(5);
+        if quality < 3 {
+            self.repetitions = 0;
+            self.interval = 1;
+        } else {
+            self.repetitions += 1;
+            self.interval = match self.repetitions {
+                1 => 1,
+                2 => 6,
+                _ => (self.interval as f64 * self.ease).round() as u32,
+            };
+        }
+
+        let miss: f64 = (5 - quality) as f64;
+        self.ease = (self.ease + 0.1 - miss * (0.08 + miss * 0.02)).max(MIN_EASE);
+        self.due = date(day + self.interval as i64);
+    }
+}
+
+/*
+ * Returns the quality of an answer, 5 for the book move found quickly, 4 for one found after
+ * a while, and 1 for a wrong move.
+ */
+pub fn grade(correct: bool, seconds: f64) -> u8 {
+    match (correct, seconds) {
+        (true, seconds) if seconds <= 10.0 => 5,
+        (true, _) => 4,
+        (false, _) => 1,
+    }
+}
+
+//#################################################################################################
+//
+//                                       TRAINER TYPE
+//
+//#################################################################################################
+
+/*
+ * An opening trainer, quizzing the user on the lines of a book: it shows him the positions of
+ * the lines where he is to move and asks for the best book move, scheduling each position
+ * again with spaced repetition, sooner when he got it wrong and later and later as he keeps
+ * getting it right. The cards are kept in a small text file in the config directory, one per
+ * line.
+ */
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Trainer {
+    pub cards: Vec<Card>,
+}
+
+impl Trainer {
+    /*
+     * Returns the path of the trainer file, trainer.txt next to the config file.
+     */
+    pub fn default_path() -> Option<PathBuf> {
+        Config::default_path().map(|path| path.with_file_name("trainer.txt"))
+    }
+
+    /*
+     * Reads the trainer at its default path, or returns an empty one if there is none.
+     */
+    pub fn load() -> Result<Trainer, OthelloError> {
+        match Trainer::default_path() {
+            Some(path) if path.exists() => Trainer::from_file(path),
+            _ => Ok(Trainer::default()),
+        }
+    }
+
+    /*
+     * Reads the trainer file at the given path.
+     */
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Trainer, OthelloError> {
+        Trainer::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /*
+     * Writes the trainer to the given file, creating its directory if needed.
+     */
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), OthelloError> {
+        if let Some(dir) = path.as_ref().parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, self.to_text())?;
+        Ok(())
+    }
+
+    /*
+     * Reads a trainer written by to_text: one card per line, with its line of moves, the
+     * number of good reviews in a row, the interval, the ease and the due date, separated by
+     * spaces. Empty lines and # comments are ignored.
+     */
+    pub fn parse(text: &str) -> Result<Trainer, OthelloError> {
+        let mut trainer: Trainer = Trainer::default();
+
+        for (i, line) in text.lines().enumerate() {
+            let line: &str = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let invalid = || parse_error(format!("Invalid trainer card at line {}.", i + 1));
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [moves, repetitions, interval, ease, due] = fields.as_slice() else {
+                return Err(invalid());
+            };
+            parse_line(moves)?;
+            trainer.cards.push(Card {
+                line: moves.to_string(),
+                repetitions: repetitions.parse().map_err(|_| invalid())?,
+                interval: interval.parse().map_err(|_| invalid())?,
+                ease: ease.parse().ok().filter(|&ease| ease >= MIN_EASE).ok_or_else(invalid)?,
+                due: due.to_string(),
+            });
+        }
+
+        Ok(trainer)
+    }
+
+    /*
+     * Writes the trainer in the format read by parse.
+     */
+    pub fn to_text(&self) -> String {
+        let mut text: String = String::from("# line repetitions interval ease due\n");
+        for card in &self.cards {
+            text += &format!("{} {} {} {:.2} {}\n", card.line, card.repetitions, card.interval,
+                card.ease, card.due);
+        }
+        text
+    }
+
+    /*
+     * Adds a card for each position of the lines of the book, up to the given number of
+     * moves, where the given player is to move and the book has a move for him, unless the
+     * trainer already has it. Returns the number of cards added.
+     */
+    pub fn add_lines(&mut self, book: &Book, color: Color, depth: usize) -> usize {
+        let mut added: usize = 0;
+        for line in book_lines(book, color, depth) {
+            if self.cards.iter().all(|card| card.line != line) {
+                self.cards.push(Card::new(&line));
+                added += 1;
+            }
+        }
+        added
+    }
+
+    /*
+     * Returns the indices of the cards due on the given date, in the format of the records,
+     * the most overdue first.
+     */
+    pub fn due(&self, on: &str) -> Vec<usize> {
+        let mut due: Vec<usize> = (0..self.cards.len())
+            .filter(|&i| self.cards[i].due.as_str() <= on)
+            .collect();
+        due.sort_by(|&a, &b| self.cards[a].due.cmp(&self.cards[b].due));
+        due
+    }
+
+    /*
+     * Returns the indices of the cards due today, see due.
+     */
+    pub fn due_today(&self) -> Vec<usize> {
+        self.due(&today())
+    }
+
+    /*
+     * Records the answer of the user to the card of the given index, made today, see
+     * Card::review.
+     */
+    pub fn review(&mut self, index: usize, quality: u8) {
+        self.cards[index].review(quality, day_number());
+    }
+}
+
+/*
+ * Returns the lines of the book, up to the given number of moves, leading to positions where
+ * the given player is to move and the book has a move for him. Along them, he always plays
+ * the best book move, while all the book moves of his opponent are followed. The lines all
+ * start with f5, the other first moves being the same by symmetry, and a position reached
+ * again by transposition or symmetry is left out.
+ */
+pub fn book_lines(book: &Book, color: Color, depth: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut seen: HashSet<(Othello, Color)> = HashSet::new();
+    let mut stack: Vec<Game> = Game::from_line("f5").into_iter().collect();
+
+    while let Some(game) = stack.pop() {
+        let moves: Vec<(BitBoard, BookEntry)> = book.moves(game.board(), game.color());
+        if game.history().len() >= depth || moves.is_empty()
+            || !seen.insert((game.board().canonical().0, game.color()))
+        {
+            continue;
+        }
+        let followed: &[(BitBoard, BookEntry)] = if game.color() == color {
+            lines.push(game.history().iter().map(|mv| mv.square_name()).collect());
+            &moves[..1]
+        } else {
+            &moves
+        };
+        for &(mv, _) in followed.iter().rev() {
+            let mut next: Game = game.clone();
+            if next.play(mv).is_ok() {
+                stack.push(next);
+            }
+        }
+    }
+    lines
+}
+
+/*
+ * Returns the best book moves of the position at the end of the line, those with the best
+ * score, any of which is a right answer.
+ */
+pub fn book_moves(book: &Book, line: &str) -> Result<BitBoard, OthelloError> {
+    let game: Game = Game::from_line(line)?;
+    let moves: Vec<(BitBoard, BookEntry)> = book.moves(game.board(), game.color());
+    Ok(moves.iter()
+        .filter(|&&(_, entry)| Some(entry.score) == moves.first().map(|(_, best)| best.score))
+        .fold(0, |acc, &(mv, _)| acc | mv))
+}
+
+//#################################################################################################
+//
+//                                   COUNTING EXERCISES
+//
+//#################################################################################################
+
+/*
+ * The most empty squares counting exercises can have, the solver checking the answers
+ * instantly below.
+ */
+pub const MAX_COUNTING_EMPTIES: u8 = 16;
+
+/*
+ * A near-final position to count out: the player to move, his final disc differential with
+ * perfect play, as found by the solver, and the moves reaching it.
+ */
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Exercise {
+    pub oth: Othello,
+    pub color: Color,
+    pub score: i32,
+    pub best: BitBoard,
+}
+
+/*
+ * An endless stream of counting exercises, positions with the given number of empty squares
+ * reached by plausible games: each move is the best one of a shallow light search, or a random
+ * one a quarter of the time so that the games differ. Positions where the player to move has
+ * to pass are skipped.
+ */
+pub struct Exercises {
+    rng: u64,
+    empties: u8,
+}
+
+impl Exercises {
+    /*
+     * Creates the stream, from the given seed, of positions with the given number of empty
+     * squares, at least 1 and at most MAX_COUNTING_EMPTIES.
+     */
+    pub fn new(seed: u64, empties: u8) -> Exercises {
+        Exercises {
+            rng: (seed ^ 0x9E3779B97F4A7C15).max(1),
+            empties: empties.clamp(1, MAX_COUNTING_EMPTIES),
+        }
+    }
+
+    /*
+     * Plays a game until the number of empty squares is reached, and returns its position
+     * and the player to move, or None if the game ended before.
+     */
+    fn play(&mut self) -> Option<(Othello, Color)> {
+        let (mut oth, mut color): (Othello, Color) = (Othello::new(), Color::Black);
+        while oth.empties() > self.empties {
+            let mut moves: BitBoard = oth.gen_moves(color);
+            if moves == 0 {
+                if oth.gen_moves(!color) == 0 {
+                    return None;
+                }
+                color = !color;
+                continue;
+            }
+            let mv: BitBoard = if next_random(&mut self.rng).is_multiple_of(4) {
+                for _ in 0..next_random(&mut self.rng) % moves.pop_cnt() as u64 {
+                    moves.pop_lsb();
+                }
+                moves & moves.wrapping_neg()
+            } else {
+                light_search(oth, color, 2).0
+            };
+            oth = oth.make_move(color, mv);
+            color = !color;
+        }
+        Some((oth, color)).filter(|&(oth, color)| oth.gen_moves(color) != 0)
+    }
+}
+
+impl Iterator for Exercises {
+    type Item = Exercise;
+
+    fn next(&mut self) -> Option<Exercise> {
+        let (oth, color): (Othello, Color) = loop {
+            if let Some(position) = self.play() {
+                break position;
+            }
+        };
+
+        let mut moves: BitBoard = oth.gen_moves(color);
+        let mut scores: Vec<(BitBoard, i32)> = Vec::new();
+        while moves != 0 {
+            let mv: BitBoard = moves.pop_lsb();
+            scores.push((mv, -solve(oth.make_move(color, mv), !color)));
+        }
+        let score: i32 = scores.iter().map(|&(_, score)| score).max().unwrap_or(0);
+        let best: BitBoard = scores.iter()
+            .filter(|&&(_, s)| s == score)
+            .fold(0, |acc, &(mv, _)| acc | mv);
+        Some(Exercise { oth, color, score, best, })
+    }
+}
+
+//#################################################################################################
+//
+//                                      TRAINER TEST
+//
+//#################################################################################################
+
+/*
+ * Checks the intervals of the scheduler, the lines found in a small book, that the trainer
+ * survives a round trip through its text format, and that counting exercises are solved right.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trainer() {
+        let mut card: Card = Card::new("f5");
+        let intervals: Vec<u32> = [5, 5, 4, 1, 5].iter().map(|&quality| {
+            card.review(quality, 0);
+            card.interval
+        }).collect();
+        assert_eq!(intervals, [1, 6, 16, 1, 1]);
+        assert!(card.ease < INITIAL_EASE);
+        assert_eq!(card.due, "1970.01.02");
+
+        // White answers f5 with d6, Black goes on with c3 or c5, and White answers c3 with d3.
+        let entry = |line: &str, score: i32| {
+            let game: Game = Game::from_line(line).unwrap();
+            (game.board(), game.color(), BookEntry { score, depth: 10, })
+        };
+        let book: Book = Book::from_entries([
+            entry("f5d6", -2), entry("f5f6", 3), entry("f5d6c3", -1), entry("f5d6c5", -1),
+            entry("f5d6c3d3", -1),
+        ]);
+        assert_eq!(book_lines(&book, Color::White, 10), ["f5", "f5d6c3"]);
+        assert_eq!(book_lines(&book, Color::Black, 10), ["f5d6"]);
+        assert_eq!(book_moves(&book, "f5").unwrap(), 1 << 43);
+        assert_eq!(book_moves(&book, "f5d6").unwrap().pop_cnt(), 2);
+
+        let mut trainer: Trainer = Trainer::default();
+        assert_eq!(trainer.add_lines(&book, Color::White, 10), 2);
+        assert_eq!(trainer.add_lines(&book, Color::White, 10), 0);
+        trainer.cards[1].review(5, 0);
+        assert_eq!(trainer.due("1970.01.01"), Vec::<usize>::new());
+        assert_eq!(trainer.due("1970.01.02"), [1]);
+        assert_eq!(Trainer::parse(&trainer.to_text()).unwrap(), trainer);
+        assert!(Trainer::parse("f5 1 1 0.5 1970.01.02").is_err());
+
+        for exercise in Exercises::new(1, 8).take(4) {
+            assert_eq!(exercise.oth.empties(), 8);
+            assert_eq!(exercise.score, solve(exercise.oth, exercise.color));
+            let moves: BitBoard = exercise.oth.gen_moves(exercise.color);
+            assert!(exercise.best != 0 && exercise.best & !moves == 0);
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use std::path::{Path, PathBuf};
+
+use crate::alphabeta::*;
+use crate::arena::*;
+use crate::config::*;
+use crate::player::next_random;
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                       TUNER TYPE
+//
+//#################################################################################################
+
+/*
+ * The tuned constants of SearchParams, with the bounds they are kept within and the size of
+ * the perturbations tried at first, in their own units. The tuner works on the constants
+ * divided by their perturbation, so that all move at the same pace.
+ */
+const PARAMS: [(&str, f64, f64, f64); 7] = [
+    ("reduction_depth", 1.0, 8.0, 1.0),
+    ("reduction_index", 1.0, 10.0, 1.0),
+    ("reduction_scale", 0.0, 100.0, 5.0),
+    ("pass_depth", 3.0, 10.0, 1.0),
+    ("pass_reduction", 0.0, 2.0, 0.5),
+    ("pass_margin", 0.0, 200.0, 10.0),
+    ("pass_mobility", 1.0, 16.0, 1.0),
+];
+
+/*
+ * The gains of SPSA: the step of the nth iteration is STEP / (n + 1 + STABILITY)^0.602 and its
+ * perturbation the size of PARAMS divided by (n + 1)^0.101, the decays advised by Spall.
+ */
+const STEP: f64 = 2.0;
+const STABILITY: f64 = 10.0;
+
+/*
+ * The random plies opening each pair of games of an iteration.
+ */
+const OPENING_PLIES: usize = 8;
+
+/*
+ * A tuner of the constants of the search by SPSA, simultaneous perturbation stochastic
+ * approximation: each iteration perturbs all the constants at once in random directions, plays
+ * games between the AIs with the constants moved one way and the other, and moves the
+ * constants towards the side that won, by how much it won. Games are too noisy for one
+ * iteration to tell much, but the steps add up over hundreds. The state of the tuner, the
+ * iteration, the seed and the current values of the constants, is kept in a small text file
+ * next to the config, so that a long tuning can be stopped and resumed.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Tuner {
+    pub iteration: u32,
+    pub seed: u64,
+    pub values: [f64; PARAMS.len()],
+}
+
+impl Default for Tuner {
+    fn default() -> Tuner {
+        let params: SearchParams = SearchParams::default();
+        Tuner {
+            iteration: 0,
+            seed: 1,
+            values: [
+                params.reduction_depth as f64, params.reduction_index as f64,
+                params.reduction_scale as f64, params.pass_depth as f64,
+                params.pass_reduction as f64, params.pass_margin as f64,
+                params.pass_mobility as f64,
+            ],
+        }
+    }
+}
+
+impl Tuner {
+    /*
+     * Returns the path of the tuning file, tuning.txt next to the config file.
+     */
+    pub fn default_path() -> Option<PathBuf> {
+        Config::default_path().map(|path| path.with_file_name("tuning.txt"))
+    }
+
+    /*
+     * Reads the tuning at its default path, or starts one from the default constants if there
+     * is none.
+     */
+    pub fn load() -> Result<Tuner, OthelloError> {
+        match Tuner::default_path() {
+            Some(path) if path.exists() => Tuner::from_file(path),
+            _ => Ok(Tuner::default()),
+        }
+    }
+
+    /*
+     * Reads the tuning file at the given path.
+     */
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Tuner, OthelloError> {
+        Tuner::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /*
+     * Writes the tuning to the given file, creating its directory if needed.
+     */
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), OthelloError> {
+        if let Some(dir) = path.as_ref().parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, self.to_text())?;
+        Ok(())
+    }
+
+    /*
+     * Reads a tuning written by to_text: one name and value per line, separated by spaces, the
+     * iteration, the seed and the constants, those missing keeping their default values. Empty
+     * lines and # comments are ignored.
+     */
+    pub fn parse(text: &str) -> Result<Tuner, OthelloError> {
+        let mut tuner: Tuner = Tuner::default();
+
+        for (i, line) in text.lines().enumerate() {
+            let line: &str = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let invalid = || parse_error(format!("Invalid tuning state at line {}.", i + 1));
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [name, value] = fields.as_slice() else {
+                return Err(invalid());
+            };
+            match *name {
+                "iteration" => tuner.iteration = value.parse().map_err(|_| invalid())?,
+                "seed" => tuner.seed = value.parse().map_err(|_| invalid())?,
+                _ => {
+                    let index: usize = PARAMS.iter().position(|param| param.0 == *name)
+                        .ok_or_else(invalid)?;
+                    tuner.values[index] = value.parse().map_err(|_| invalid())?;
+                },
+            }
+        }
+
+        Ok(tuner)
+    }
+
+    /*
+     * Writes the tuning in the format read by parse.
+     */
+    pub fn to_text(&self) -> String {
+        let mut text: String = String::from("# SPSA tuning of the search constants\n");
+        text += &format!("iteration {}\nseed {}\n", self.iteration, self.seed);
+        for (param, value) in PARAMS.iter().zip(self.values) {
+            text += &format!("{} {:.3}\n", param.0, value);
+        }
+        text
+    }
+
+    /*
+     * Returns the constants of the search with the current values, rounded.
+     */
+    pub fn params(&self) -> SearchParams {
+        params(&self.values)
+    }
+
+    /*
+     * Runs one iteration, playing the given number of games, rounded up to an even number,
+     * between the alpha-beta AIs of the config with the constants perturbed both ways. Returns
+     * the score of the AI with the constants perturbed the first way.
+     */
+    pub fn step(&mut self, config: &Config, games: u32) -> Result<f64, OthelloError> {
+        let k: f64 = self.iteration as f64;
+        let step: f64 = STEP / (k + 1.0 + STABILITY).powf(0.602);
+        let perturbation: f64 = 1.0 / (k + 1.0).powf(0.101);
+        let signs: Vec<f64> = (0..PARAMS.len())
+            .map(|_| if next_random(&mut self.seed) & 1 == 0 { 1.0 } else { -1.0 })
+            .collect();
+
+        let perturbed = |sign: f64| -> [f64; PARAMS.len()] {
+            let mut values: [f64; PARAMS.len()] = self.values;
+            for (i, value) in values.iter_mut().enumerate() {
+                *value += sign * perturbation * signs[i] * PARAMS[i].3;
+            }
+            values
+        };
+        let mut plus: AlphaBetaPlayer =
+            config.player()?.with_search_params(params(&perturbed(1.0)));
+        let mut minus: AlphaBetaPlayer =
+            config.player()?.with_search_params(params(&perturbed(-1.0)));
+        let arena: Arena = Arena::new(games.div_ceil(2) * 2)
+            .with_random_openings(OPENING_PLIES, next_random(&mut self.seed));
+        let score: f64 = arena.run(&mut plus, &mut minus)?.score();
+
+        for (i, value) in self.values.iter_mut().enumerate() {
+            let (_, min, max, size) = PARAMS[i];
+            let gradient: f64 = (2.0 * score - 1.0) / (2.0 * perturbation * signs[i]);
+            *value = (*value + step * gradient * size).clamp(min, max);
+        }
+        self.iteration += 1;
+        Ok(score)
+    }
+}
+
+/*
+ * Returns the constants of the search with the given values, rounded and kept within their
+ * bounds.
+ */
+fn params(values: &[f64; PARAMS.len()]) -> SearchParams {
+    let value = |i: usize| values[i].clamp(PARAMS[i].1, PARAMS[i].2).round();
+    SearchParams {
+        reduction_depth: value(0) as u8,
+        reduction_index: value(1) as u8,
+        reduction_scale: value(2) as u8,
+        pass_depth: value(3) as u8,
+        pass_reduction: value(4) as u8,
+        pass_margin: value(5) as i32,
+        pass_mobility: value(6) as u8,
+    }
+}
+
+//#################################################################################################
+//
+//                                       TUNER TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that the tuning starts from the default constants, that its state is written and read
+ * back, and that an iteration keeps the constants within their bounds.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tuning() {
+        let mut tuner: Tuner = Tuner::default();
+        assert_eq!(tuner.params(), SearchParams::default());
+        assert!(Tuner::parse("pass_margin 55.5\nseed 7").unwrap().params().pass_margin == 56);
+        assert!(Tuner::parse("aspiration 3").is_err());
+
+        let config: Config = Config { depth: 2, ..Config::default() };
+        let score: f64 = tuner.step(&config, 2).unwrap();
+        assert!((0.0..=1.0).contains(&score));
+        assert_eq!(tuner.iteration, 1);
+        assert!(tuner.values.iter().zip(PARAMS).all(|(&value, (_, min, max, _))| {
+            (min..=max).contains(&value)
+        }));
+        assert!(Tuner::parse(&tuner.to_text()).unwrap().params() == tuner.params());
+    }
+}
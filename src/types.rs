@@ -1,4 +1,4 @@
-use crate::othello::*;
+use crate::coord::*;
 
 //#################################################################################################
 //
@@ -15,6 +15,8 @@ pub trait BitBoardTrait {
     fn pop_lsb(&mut self) -> BitBoard;
     fn pop_cnt(self) -> u8;
     fn contains(self, x: u8, y: u8) -> bool;
+    #[cfg(feature = "std")]
+    fn square_name(self) -> String;
 }
 
 impl BitBoardTrait for BitBoard {
@@ -43,6 +45,23 @@ impl BitBoardTrait for BitBoard {
     fn contains(self, x: u8, y: u8) -> bool {
         1u64 << (x + 8 * y) & self != 0
     }
+
+    /*
+     * Returns the name of the square of a single-bit BitBoard, of the form "[a-h][1-8]", in
+     * the default coordinates convention.
+     */
+    #[cfg(feature = "std")]
+    fn square_name(self) -> String {
+        Coord::from_bitboard(self).name()
+    }
+}
+
+/*
+ * Parses a square name of the form "[a-h][1-8]", case insensitive and in the default coordinates
+ * convention, into a single-bit BitBoard.
+ */
+pub fn parse_square(name: &str) -> Option<BitBoard> {
+    Coord::parse(name).map(Coord::to_bitboard)
 }
 
 //#################################################################################################
@@ -52,24 +71,55 @@ impl BitBoardTrait for BitBoard {
 //#################################################################################################
 
 /*
- * The Color type, an enum that can either be White or Black.
+ * The Color type, an enum that can either be White or Black. It is displayed as its name, and
+ * !color is the opponent of color.
  */
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum Color {
     Black, White,
 }
 
 impl Color {
+    /*
+     * Both colors, Black first.
+     */
+    pub const ALL: [Color; 2] = [Color::Black, Color::White];
+
     /*
      * Matches White to Black and Black to White.
      */
     #[inline(always)]
-    pub fn invert(self) -> Color {
+    pub const fn invert(self) -> Color {
         match self {
             Color::Black => Color::White,
             Color::White => Color::Black,
         }
     }
+
+    /*
+     * Iterates over both colors, Black first.
+     */
+    pub fn iter() -> impl Iterator<Item = Color> {
+        Color::ALL.iter().copied()
+    }
+}
+
+impl core::ops::Not for Color {
+    type Output = Color;
+
+    #[inline(always)]
+    fn not(self) -> Color {
+        self.invert()
+    }
+}
+
+impl core::fmt::Display for Color {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(match self {
+            Color::Black => "Black",
+            Color::White => "White",
+        })
+    }
 }
 
 //#################################################################################################
@@ -80,26 +130,100 @@ impl Color {
 
 /*
  * The Square type, an enum that contains one of the three possible states a square can be in
- * a game of Othello.
+ * a game of Othello. It converts to and from the characters of the ASCII pictures of boards,
+ * see Othello::from_ascii.
  */
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Square {
     Empty, Black, White,
 }
 
+impl Square {
+    /*
+     * Returns the character of the square: 'X' for black, 'O' for white and '-' if empty.
+     */
+    pub const fn to_char(self) -> char {
+        match self {
+            Square::Empty => '-',
+            Square::Black => 'X',
+            Square::White => 'O',
+        }
+    }
+
+    /*
+     * Returns the square of the given character, '.' being empty too, or None if it isn't
+     * one of a square.
+     */
+    pub const fn from_char(c: char) -> Option<Square> {
+        match c {
+            '-' | '.' => Some(Square::Empty),
+            'X' => Some(Square::Black),
+            'O' => Some(Square::White),
+            _ => None,
+        }
+    }
+}
+
+impl From<Color> for Square {
+    fn from(color: Color) -> Square {
+        match color {
+            Color::Black => Square::Black,
+            Color::White => Square::White,
+        }
+    }
+}
+
+impl From<Square> for char {
+    fn from(square: Square) -> char {
+        square.to_char()
+    }
+}
+
+impl core::convert::TryFrom<char> for Square {
+    type Error = char;
+
+    /*
+     * Converts a character to its square, returning the character if it isn't one.
+     */
+    fn try_from(c: char) -> Result<Square, char> {
+        Square::from_char(c).ok_or(c)
+    }
+}
+
 //#################################################################################################
 //
-//                                    PLAYER TRAIT
+//                                    MOVE ERROR TYPE
 //
 //#################################################################################################
 
 /*
- * A trait representing a player by it's means of choosing a move.
+ * The reasons why a move can be rejected, precise enough to explain the rules to beginners.
+ * IllegalPass is a pass, an empty move, attempted by a player who has a move to make.
  */
-pub trait Player {
-    fn chose_move(&self, oth: Othello, moves: BitBoard, color: Color) -> BitBoard;
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MoveError {
+    OutOfBounds, Occupied, NoFlips, WrongTurn, GameOver, IllegalPass,
 }
 
+impl core::fmt::Display for MoveError {
+    /*
+     * Explains why the move was rejected.
+     */
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(match self {
+            MoveError::OutOfBounds => "This square is outside of the board.",
+            MoveError::Occupied => "This square is already occupied.",
+            MoveError::NoFlips => "A move must flip at least one of the opponent's disks.",
+            MoveError::WrongTurn => "It is not this player's turn.",
+            MoveError::GameOver => "The game is already over.",
+            MoveError::IllegalPass => "A player can only pass when he has no legal move.",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MoveError {}
+
 //#################################################################################################
 //
 //                                    SCORE TYPE
@@ -128,4 +252,43 @@ impl Score {
             Color::White => self.1,
         }
     }
+
+    /*
+     * Returns the color of the player with the most disks, or None if it is a draw.
+     */
+    pub fn winner(&self) -> Option<Color> {
+        match self.0.cmp(&self.1) {
+            core::cmp::Ordering::Greater => Some(Color::Black),
+            core::cmp::Ordering::Less => Some(Color::White),
+            core::cmp::Ordering::Equal => None,
+        }
+    }
+}
+
+//#################################################################################################
+//
+//                                       TYPES TEST
+//
+//#################################################################################################
+
+/*
+ * Checks the helpers of colors and squares.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::convert::TryFrom;
+
+    #[test]
+    fn colors_and_squares() {
+        assert_eq!(Color::iter().collect::<Vec<Color>>(), Color::ALL.to_vec());
+        assert_eq!(!Color::Black, Color::White);
+        assert_eq!(format!("{} {}", Color::Black, !Color::Black), "Black White");
+
+        for square in [Square::Empty, Square::Black, Square::White] {
+            assert_eq!(Square::try_from(char::from(square)), Ok(square));
+        }
+        assert_eq!(Square::from(Color::White), Square::White);
+        assert_eq!(Square::try_from('?'), Err('?'));
+    }
 }
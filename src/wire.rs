@@ -0,0 +1,249 @@
+use crate::types::*;
+use crate::othello::*;
+#[cfg(feature = "std")]
+use crate::game::*;
+
+//#################################################################################################
+//
+//                                      WIRE ENCODING
+//
+//#################################################################################################
+
+/*
+ * Two compact binary encodings of a position, the board and the player to move, to send it over
+ * the network or store it in savegames:
+ *   - The plain one takes 17 bytes: black's BitBoard, then white's, both in little-endian, and
+ *     the player to move, 0 for black and 1 for white.
+ *   - The packed one takes 13 bytes: the squares are the digits of a number in base 3, from a1
+ *     to h8, 0 for an empty square, 1 for a black disk and 2 for a white one. That number, times
+ *     2, plus the player to move, fits in 103 bits and is stored in little-endian.
+ * Decoding checks that the position is well-formed, but not that it can be reached in a game.
+ */
+pub const ENCODED_SIZE: usize = 17;
+pub const PACKED_SIZE: usize = 13;
+
+/*
+ * 3 to the power of 64, the number of boards that can be packed.
+ */
+const BOARDS: u128 = 3u128.pow(64);
+
+/*
+ * Encodes a position in the plain form.
+ */
+pub fn encode(oth: Othello, color: Color) -> [u8; ENCODED_SIZE] {
+    let mut bytes: [u8; ENCODED_SIZE] = [0; ENCODED_SIZE];
+    bytes[0..8].copy_from_slice(&oth.get_bitboard(Color::Black).to_le_bytes());
+    bytes[8..16].copy_from_slice(&oth.get_bitboard(Color::White).to_le_bytes());
+    bytes[16] = color as u8;
+    bytes
+}
+
+/*
+ * Decodes a position in the plain form. Returns None if the slice has the wrong size, if both
+ * players have a disk on the same square or if the player to move is invalid.
+ */
+pub fn decode(bytes: &[u8]) -> Option<(Othello, Color)> {
+    if bytes.len() != ENCODED_SIZE {
+        return None;
+    }
+
+    let mut word: [u8; 8] = [0; 8];
+    word.copy_from_slice(&bytes[0..8]);
+    let black: BitBoard = u64::from_le_bytes(word);
+    word.copy_from_slice(&bytes[8..16]);
+    let white: BitBoard = u64::from_le_bytes(word);
+
+    if black & white != 0 {
+        return None;
+    }
+
+    Some((Othello::create(black, white), color_from_bit(bytes[16] as u128)?))
+}
+
+/*
+ * Encodes a position in the packed form.
+ */
+pub fn encode_packed(oth: Othello, color: Color) -> [u8; PACKED_SIZE] {
+    let black: BitBoard = oth.get_bitboard(Color::Black);
+    let white: BitBoard = oth.get_bitboard(Color::White);
+    let mut value: u128 = 0;
+
+    for square in (0..64).rev() {
+        let digit: u128 = if black >> square & 1 != 0 {
+            1
+        } else if white >> square & 1 != 0 {
+            2
+        } else {
+            0
+        };
+        value = value * 3 + digit;
+    }
+    value = value * 2 + color as u128;
+
+    let mut bytes: [u8; PACKED_SIZE] = [0; PACKED_SIZE];
+    bytes.copy_from_slice(&value.to_le_bytes()[..PACKED_SIZE]);
+    bytes
+}
+
+/*
+ * Decodes a position in the packed form. Returns None if the slice has the wrong size or if the
+ * number it holds is too big to be a position.
+ */
+pub fn decode_packed(bytes: &[u8]) -> Option<(Othello, Color)> {
+    if bytes.len() != PACKED_SIZE {
+        return None;
+    }
+
+    let mut word: [u8; 16] = [0; 16];
+    word[..PACKED_SIZE].copy_from_slice(bytes);
+    let mut value: u128 = u128::from_le_bytes(word);
+
+    if value >= 2 * BOARDS {
+        return None;
+    }
+
+    let color: Color = color_from_bit(value % 2)?;
+    value /= 2;
+
+    let (mut black, mut white): (BitBoard, BitBoard) = (0, 0);
+    for square in 0..64 {
+        match value % 3 {
+            1 => black |= 1u64 << square,
+            2 => white |= 1u64 << square,
+            _ => (),
+        }
+        value /= 3;
+    }
+
+    Some((Othello::create(black, white), color))
+}
+
+/*
+ * Returns the player to move encoded by the given number, if valid.
+ */
+fn color_from_bit(bit: u128) -> Option<Color> {
+    match bit {
+        0 => Some(Color::Black),
+        1 => Some(Color::White),
+        _ => None,
+    }
+}
+
+//#################################################################################################
+//
+//                                     GAME ENCODING
+//
+//#################################################################################################
+
+/*
+ * The byte standing for a pass in encoded games.
+ */
+pub const PASS: u8 = 64;
+
+/*
+ * Encodes a move as a byte: the index of its square, from 0 for a1 to 63 for h8, or PASS.
+ */
+pub const fn move_to_byte(mv: BitBoard) -> u8 {
+    if mv == 0 { PASS } else { mv.trailing_zeros() as u8 }
+}
+
+/*
+ * Decodes a move encoded by move_to_byte, or returns None if the byte is invalid.
+ */
+pub const fn byte_to_move(byte: u8) -> Option<BitBoard> {
+    match byte {
+        PASS => Some(0),
+        0..=63 => Some(1u64 << byte),
+        _ => None,
+    }
+}
+
+/*
+ * Encodes a game from the starting position as one byte per ply, passes included, which is
+ * canonical: two games with the same moves have the same encoding. A full game takes about 60
+ * bytes.
+ */
+#[cfg(feature = "std")]
+pub fn encode_game(game: &Game) -> Vec<u8> {
+    game.history().iter().map(|&mv| move_to_byte(mv)).collect()
+}
+
+/*
+ * Decodes a game encoded by encode_game, from the starting position. Returns None if a byte is
+ * invalid, if a move is illegal, if a pass is missing or isn't forced, or if there are plies
+ * after the end of the game.
+ */
+#[cfg(feature = "std")]
+pub fn decode_game(bytes: &[u8]) -> Option<Game> {
+    let mut game: Game = Game::new();
+
+    for (ply, &byte) in bytes.iter().enumerate() {
+        let mv: BitBoard = byte_to_move(byte)?;
+        if game.history().len() > ply {
+            if mv != 0 {
+                return None;
+            }
+        } else {
+            game.play(mv).ok()?;
+        }
+    }
+
+    if game.history().len() != bytes.len() {
+        return None;
+    }
+
+    Some(game)
+}
+
+//#################################################################################################
+//
+//                                       WIRE TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that the positions of the test suite survive a round trip through both encodings, and
+ * so do games.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsuite::*;
+
+    #[test]
+    fn round_trip() {
+        for position in positions() {
+            let decoded = decode(&encode(position.oth, position.color)).unwrap();
+            assert!(decoded == (position.oth, position.color));
+            let decoded = decode_packed(&encode_packed(position.oth, position.color)).unwrap();
+            assert!(decoded == (position.oth, position.color));
+        }
+
+        let full: Othello = Othello::create(0, !0);
+        assert!(decode_packed(&encode_packed(full, Color::White)) == Some((full, Color::White)));
+        assert!(decode_packed(&[0xFF; PACKED_SIZE]).is_none());
+        assert!(decode(&[0xFF; ENCODED_SIZE]).is_none());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn games() {
+        let mut game: Game = Game::new();
+        while !game.is_over() {
+            let mut moves: BitBoard = game.moves();
+            for _ in 0..game.history().len() % 3 {
+                if moves.pop_cnt() > 1 {
+                    moves.pop_lsb();
+                }
+            }
+            game.play(moves & moves.wrapping_neg()).unwrap();
+        }
+
+        let bytes: Vec<u8> = encode_game(&game);
+        assert_eq!(decode_game(&bytes).unwrap().history(), game.history());
+        assert!(decode_game(&bytes[..bytes.len() - 1]).is_some());
+        assert!(decode_game(&[bytes.clone(), vec![PASS]].concat()).is_none());
+        assert!(decode_game(&[37, 37]).is_none());
+        assert!(decode_game(&[PASS]).is_none());
+    }
+}
@@ -0,0 +1,167 @@
+use crate::types::*;
+use crate::game::*;
+use crate::record::*;
+use crate::error::*;
+
+//#################################################################################################
+//
+//                                      WTHOR FORMAT
+//
+//#################################################################################################
+
+/*
+ * The WTHOR format is the binary format of the game database of the French Othello federation.
+ * Every file starts with a 16 bytes header, containing among other things the number of records
+ * it holds: the number of games as a little-endian u32 at offset 4, or the number of names as a
+ * little-endian u16 at offset 8, and the year the games were played at offset 10.
+ *
+ * In a games file (.wtb), each game takes 68 bytes: the ids of the tournament, of the black
+ * player and of the white player, as little-endian u16s, the final number of black disks, the
+ * theoretical score and 60 moves. A move is stored as 10 * row + column, both in 1..=8, and 0
+ * once the game is over. Passes are not recorded.
+ *
+ * In a players file (WTHOR.JOU), each name takes 20 bytes, padded with zeros.
+ */
+const HEADER_SIZE: usize = 16;
+const GAME_SIZE: usize = 68;
+const NAME_SIZE: usize = 20;
+
+/*
+ * Reads the names of the players from the content of a players file.
+ */
+pub fn parse_players(bytes: &[u8]) -> Result<Vec<String>, OthelloError> {
+    let count: usize = read_u16(bytes, 8)? as usize;
+
+    (0..count).map(|i| {
+        let start: usize = HEADER_SIZE + i * NAME_SIZE;
+        let name: &[u8] = bytes
+            .get(start..start + NAME_SIZE)
+            .ok_or_else(|| parse_error("Truncated players file."))?;
+        let len: usize = name.iter().position(|&byte| byte == 0).unwrap_or(NAME_SIZE);
+        // Names are in Latin-1, whose characters are the first 256 code points.
+        Ok(name[..len].iter().map(|&byte| byte as char).collect::<String>().trim().to_string())
+    }).collect()
+}
+
+/*
+ * Reads the games from the content of a games file. The names of the players are looked up in
+ * the given list, read from the players file, or replaced by their ids if missing. The year of
+ * the games is used as their date.
+ */
+pub fn parse_games(bytes: &[u8], players: &[String]) -> Result<Vec<GameRecord>, OthelloError> {
+    let count: usize = read_u32(bytes, 4)? as usize;
+    let year: u16 = read_u16(bytes, 10)?;
+
+    if !matches!(bytes.get(12), Some(0) | Some(8)) {
+        return Err(parse_error("Only games played on 8x8 boards are supported."));
+    }
+
+    let name = |id: u16| players.get(id as usize).cloned().unwrap_or_else(|| format!("#{}", id));
+
+    (0..count).map(|i| {
+        let start: usize = HEADER_SIZE + i * GAME_SIZE;
+        let game: &[u8] = bytes
+            .get(start..start + GAME_SIZE)
+            .ok_or_else(|| parse_error("Truncated games file."))?;
+
+        let mut moves: Vec<BitBoard> = Vec::with_capacity(60);
+        for &byte in game[8..].iter().take_while(|&&byte| byte != 0) {
+            let (row, col) = (byte / 10, byte % 10);
+            if !(1..=8).contains(&row) || !(1..=8).contains(&col) {
+                return Err(parse_error(format!("Invalid move {} in game {}.", byte, i + 1)));
+            }
+            moves.push(1u64 << ((col - 1) + 8 * (row - 1)));
+        }
+
+        let winner: Option<Color> = match game[6] {
+            0..=31 => Some(Color::White),
+            32 => None,
+            _ => Some(Color::Black),
+        };
+
+        Ok(GameRecord {
+            black: name(read_u16(game, 2)?),
+            white: name(read_u16(game, 4)?),
+            date: year.to_string(),
+            outcome: Some(Outcome { winner, reason: Reason::Finished, }),
+            handicap: 0,
+            moves,
+            stats: Vec::new(),
+        })
+    }).collect()
+}
+
+/*
+ * Reads a little-endian u16 at the given offset.
+ */
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, OthelloError> {
+    match bytes.get(offset..offset + 2) {
+        Some(&[a, b]) => Ok(u16::from_le_bytes([a, b])),
+        _ => Err(parse_error("Truncated WTHOR file.")),
+    }
+}
+
+/*
+ * Reads a little-endian u32 at the given offset.
+ */
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, OthelloError> {
+    match bytes.get(offset..offset + 4) {
+        Some(&[a, b, c, d]) => Ok(u32::from_le_bytes([a, b, c, d])),
+        _ => Err(parse_error("Truncated WTHOR file.")),
+    }
+}
+
+//#################################################################################################
+//
+//                                       WTHOR TEST
+//
+//#################################################################################################
+
+/*
+ * Checks that a small database is read with its players and moves, and that truncated or
+ * invalid files are rejected.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn database() {
+        let mut players: Vec<u8> = vec![0; HEADER_SIZE];
+        players[8] = 2;
+        for name in ["Alice", "Bob"] {
+            let mut field: [u8; NAME_SIZE] = [0; NAME_SIZE];
+            field[..name.len()].copy_from_slice(name.as_bytes());
+            players.extend_from_slice(&field);
+        }
+        let names: Vec<String> = parse_players(&players).unwrap();
+        assert_eq!(names, ["Alice", "Bob"]);
+
+        // One game of 2001 between Bob and Alice, f5 d6 c3, won by Black 40 to 24.
+        let mut games: Vec<u8> = vec![0; HEADER_SIZE + GAME_SIZE];
+        games[4] = 1;
+        games[10..12].copy_from_slice(&2001u16.to_le_bytes());
+        games[12] = 8;
+        games[HEADER_SIZE + 2] = 1;
+        games[HEADER_SIZE + 6] = 40;
+        games[HEADER_SIZE + 8..HEADER_SIZE + 11].copy_from_slice(&[56, 64, 33]);
+        let records: Vec<GameRecord> = parse_games(&games, &names).unwrap();
+        assert_eq!(records.len(), 1);
+        let record: &GameRecord = &records[0];
+        assert_eq!((record.black.as_str(), record.white.as_str()), ("Bob", "Alice"));
+        assert_eq!(record.date, "2001");
+        assert_eq!(record.moves, [1 << 37, 1 << 43, 1 << 18]);
+        assert!(record.outcome.is_some_and(|outcome| outcome.winner == Some(Color::Black)));
+        assert_eq!(parse_games(&games, &[]).unwrap()[0].black, "#1");
+
+        assert!(parse_players(&players[..players.len() - 1]).is_err());
+        assert!(parse_players(&players[..9]).is_err());
+        assert!(parse_games(&games[..games.len() - 1], &names).is_err());
+        assert!(parse_games(&games[..6], &names).is_err());
+        let mut invalid: Vec<u8> = games.clone();
+        invalid[HEADER_SIZE + 11] = 90;
+        assert!(parse_games(&invalid, &names).is_err());
+        invalid[12] = 10;
+        assert!(parse_games(&invalid, &names).is_err());
+    }
+}